@@ -0,0 +1,96 @@
+use std::{
+    process::{Command, ExitCode, Stdio},
+    time::{Duration, Instant},
+};
+
+/// The programs that the engines are timed on, by name. Each of them runs long enough that starting
+/// the interpreter up (and loading the prelude) is only a small part of it.
+const PROGRAMS: [(&str, &str); 4] = [
+    (
+        "fib",
+        "let fib = fn(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } }; fib(27)",
+    ),
+    (
+        "tail calls",
+        "let count = fn(n, acc) { if (n == 0) { acc } else { count(n - 1, acc + 1) } }; count(1000000, 0)",
+    ),
+    (
+        "for loop",
+        "for (i in 0..1000000) { let y = i * 2; y }",
+    ),
+    (
+        "closures",
+        "fn run(n) {
+            let step = fn(k) { k + n };
+            let go = fn(i, acc) { if (i == 0) { acc } else { go(i - 1, step(acc)) } };
+            go(300000, 0)
+        }
+        run(1)",
+    ),
+];
+
+/// How many times each program is run on each engine. The fastest run is the one that counts, as the
+/// others were slowed down by whatever else the machine was doing.
+const RUNS: usize = 3;
+
+/// Times the programs on the evaluator and on the VM, and fails if the VM isn't faster at all of them.
+/// Run with `cargo bench`.
+fn main() -> ExitCode {
+    let mut slower = Vec::new();
+
+    println!(
+        "{:<12} {:>10} {:>10} {:>8}",
+        "program", "eval", "vm", "vm/eval"
+    );
+
+    for (name, program) in PROGRAMS {
+        let eval = time("eval", program);
+        let vm = time("vm", program);
+        let ratio = vm.as_secs_f64() / eval.as_secs_f64();
+
+        println!(
+            "{:<12} {:>9.3}s {:>9.3}s {:>8.2}",
+            name,
+            eval.as_secs_f64(),
+            vm.as_secs_f64(),
+            ratio
+        );
+
+        if vm >= eval {
+            slower.push(name);
+        }
+    }
+
+    if !slower.is_empty() {
+        eprintln!("the vm is slower than eval at: {}", slower.join(", "));
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Runs `program` on `engine` `RUNS` times, returning how long the fastest run took.
+fn time(engine: &str, program: &str) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+
+            let status = Command::new(env!("CARGO_BIN_EXE_monkey-rs"))
+                .args(["--engine", engine, "eval", "-e", program])
+                .stdout(Stdio::null())
+                .status()
+                .expect("the interpreter should start");
+
+            assert!(
+                status.success(),
+                "{} failed on {} with {}",
+                program,
+                engine,
+                status
+            );
+
+            start.elapsed()
+        })
+        .min()
+        .expect("programs are run at least once")
+}
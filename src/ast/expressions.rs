@@ -1,9 +1,13 @@
 use super::{
     operators::{InfixOperator, PrefixOperator},
+    patterns::Pattern,
     statements::BlockStatement,
     Node,
 };
-use crate::token::Token;
+use crate::{
+    symbol::Symbol,
+    token::{Position, Token},
+};
 use std::fmt::Display;
 
 /// An expression is a piece of code that evaluates to a value. For example, `5 + 5` is an expression
@@ -24,6 +28,9 @@ pub enum Expression {
     /// The `BooleanLiteral` struct represents a boolean literal in the Monkey language.
     Boolean(BooleanLiteral),
 
+    /// The `StringLiteral` struct represents a string literal in the Monkey language.
+    String(StringLiteral),
+
     /// The `FunctionLiteral` struct represents a function literal in the Monkey language.
     Function(FunctionLiteral),
 
@@ -38,19 +45,93 @@ pub enum Expression {
 
     /// The `Call` struct represents a function call in the Monkey language.
     Call(CallExpression),
+
+    /// The `ArrayLiteral` struct represents an array literal in the Monkey language.
+    Array(ArrayLiteral),
+
+    /// The `Index` struct represents an index expression in the Monkey language.
+    Index(IndexExpression),
+
+    /// The `Member` struct represents a member access expression in the Monkey language.
+    Member(MemberExpression),
+
+    /// The `Spread` struct represents a spread argument in the Monkey language.
+    Spread(SpreadExpression),
+
+    /// The `HashLiteral` struct represents a hash literal in the Monkey language.
+    Hash(HashLiteral),
+
+    /// The `RecordLiteral` struct represents a record construction in the Monkey language.
+    Record(RecordLiteral),
+
+    /// The `Match` struct represents a `match` expression in the Monkey language.
+    Match(MatchExpression),
+
+    /// The `MacroLiteral` struct represents a macro literal in the Monkey language.
+    Macro(MacroLiteral),
+
+    /// The `TryExpression` struct represents a `try` expression in the Monkey language.
+    Try(TryExpression),
+
+    /// The `ForExpression` struct represents a `for` loop in the Monkey language.
+    For(ForExpression),
+}
+
+impl Expression {
+    /// Returns the token that the expression starts with (or, for infix, call and index expressions,
+    /// the token of the operator).
+    pub fn token(&self) -> &Token {
+        match self {
+            Self::Identifier(identifier) => &identifier.token,
+            Self::Integer(integer) => &integer.token,
+            Self::Boolean(boolean) => &boolean.token,
+            Self::String(string) => &string.token,
+            Self::Function(function) => &function.token,
+            Self::Prefix(prefix) => &prefix.token,
+            Self::Infix(infix) => &infix.token,
+            Self::If(if_expression) => &if_expression.token,
+            Self::Call(call) => &call.token,
+            Self::Array(array) => &array.token,
+            Self::Index(index) => &index.token,
+            Self::Member(member) => &member.token,
+            Self::Spread(spread) => &spread.token,
+            Self::Hash(hash) => &hash.token,
+            Self::Record(record) => &record.token,
+            Self::Match(match_expression) => &match_expression.token,
+            Self::Macro(macro_literal) => &macro_literal.token,
+            Self::Try(try_expression) => &try_expression.token,
+            Self::For(for_expression) => &for_expression.token,
+        }
+    }
+
+    /// Returns the position of the expression's token in the source code.
+    pub fn position(&self) -> Position {
+        self.token().position
+    }
 }
 
 impl Node for Expression {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         match self {
             Self::Identifier(identifier) => identifier.token_literal(),
             Self::Integer(integer) => integer.token_literal(),
             Self::Boolean(boolean) => boolean.token_literal(),
+            Self::String(string) => string.token_literal(),
             Self::Function(function) => function.token_literal(),
             Self::Prefix(prefix) => prefix.token_literal(),
             Self::Infix(infix) => infix.token_literal(),
             Self::If(if_expression) => if_expression.token_literal(),
             Self::Call(call) => call.token_literal(),
+            Self::Array(array) => array.token_literal(),
+            Self::Index(index) => index.token_literal(),
+            Self::Member(member) => member.token_literal(),
+            Self::Spread(spread) => spread.token_literal(),
+            Self::Hash(hash) => hash.token_literal(),
+            Self::Record(record) => record.token_literal(),
+            Self::Match(match_expression) => match_expression.token_literal(),
+            Self::Macro(macro_literal) => macro_literal.token_literal(),
+            Self::Try(try_expression) => try_expression.token_literal(),
+            Self::For(for_expression) => for_expression.token_literal(),
         }
     }
 }
@@ -63,12 +144,30 @@ pub struct Identifier {
     /// The `token` field is the token that the identifier represents.
     pub token: Token,
 
-    /// The `value` field is the literal value of the identifier.
-    pub value: String,
+    /// The `value` field is the literal value of the identifier, which is the same symbol as the
+    /// token's.
+    pub value: Symbol,
+
+    /// The `slots` field is where the resolver found the variable that the identifier refers to: the
+    /// slots of the scopes that bind its name, innermost first. A name that isn't bound in any of them
+    /// is a global, which is looked up by name. This is `None` for an identifier that hasn't been
+    /// resolved (see `resolver::resolve`), which is looked up by name too.
+    pub slots: Option<Vec<Slot>>,
+}
+
+/// The `Slot` struct is where a variable is kept: the number of scopes out from the current one that
+/// the scope it is in is, and its index in that scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slot {
+    /// The `depth` field is how many scopes out the variable's scope is.
+    pub depth: usize,
+
+    /// The `index` field is the index of the variable in its scope.
+    pub index: usize,
 }
 
 impl Node for Identifier {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -84,7 +183,7 @@ pub struct IntegerLiteral {
 }
 
 impl Node for IntegerLiteral {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -100,7 +199,23 @@ pub struct BooleanLiteral {
 }
 
 impl Node for BooleanLiteral {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `StringLiteral` struct represents a string literal in the Monkey language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringLiteral {
+    /// The `token` field is the token that the string literal represents.
+    pub token: Token,
+
+    /// The `value` field is the literal value of the string literal, without the quotes.
+    pub value: Symbol,
+}
+
+impl Node for StringLiteral {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -113,19 +228,114 @@ pub struct FunctionLiteral {
     /// The `token` field is the token that the function literal represents.
     pub token: Token,
 
+    /// The `name` field is the name of the function, if it has one. Function literals are anonymous
+    /// on their own, but they get named when they are declared with `fn name(...) { ... }`, or when
+    /// they are directly bound with `let name = fn(...) { ... };`. This is used so that runtime errors
+    /// can say which function they happened in.
+    pub name: Option<Symbol>,
+
     /// The `parameters` field is the parameters of the function literal.
-    pub parameters: Vec<Identifier>,
+    pub parameters: Vec<Parameter>,
 
     /// The `body` field is the body of the function literal.
     pub body: BlockStatement,
+
+    /// The `generator` field is whether the function is a generator, which it is if its body has a
+    /// `yield` statement in it (not counting any functions nested inside of it). Calling a generator
+    /// doesn't run its body, but returns an iterator that runs it up to each `yield` in turn.
+    pub generator: bool,
+
+    /// The `form` field is how the function literal is written, which only matters for writing it back
+    /// out, e.g. when formatting it.
+    pub form: FunctionForm,
+}
+
+/// How a function literal is written in the source code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionForm {
+    /// With `fn` and a block for its body, e.g. `fn(x) { x + 1 }`, including function declarations.
+    Fn,
+
+    /// As a short lambda with its parameters between bars, e.g. `|x| x + 1`.
+    Bar,
+
+    /// As a short lambda with a single parameter before a `=>`, e.g. `x => x + 1`.
+    Arrow,
+}
+
+impl FunctionForm {
+    /// Returns the name of the form.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fn => "fn",
+            Self::Bar => "bar",
+            Self::Arrow => "arrow",
+        }
+    }
 }
 
 impl Node for FunctionLiteral {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
 
+/// The `Parameter` enum represents a single parameter of a function literal. Parameters can either be
+/// required (`x`), have a default value that is used when no argument is given for them (`y = 10`), or
+/// be a rest parameter that collects all of the remaining arguments into an array (`...rest`). Required
+/// parameters always come before parameters with default values, and the rest parameter is always
+/// last, which the parser makes sure of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Parameter {
+    /// A parameter that must be given an argument, e.g. `x`.
+    Required(Identifier),
+
+    /// A parameter with a default value, e.g. `y = 10`. The default value is evaluated every time the
+    /// function is called without an argument for the parameter, in the function's environment, so it
+    /// can refer to the parameters before it.
+    Default {
+        /// The name of the parameter.
+        name: Identifier,
+
+        /// The expression that is evaluated if no argument is given.
+        value: Box<Expression>,
+    },
+
+    /// A parameter that collects every argument that is left over into an array, e.g. `...rest`.
+    Rest {
+        /// The `...` token.
+        token: Token,
+
+        /// The name of the parameter.
+        name: Identifier,
+    },
+}
+
+impl Parameter {
+    /// Returns the identifier that the parameter binds.
+    pub fn name(&self) -> &Identifier {
+        match self {
+            Self::Required(name) | Self::Default { name, .. } | Self::Rest { name, .. } => name,
+        }
+    }
+
+    /// Returns the identifier that the parameter binds, mutably.
+    pub fn name_mut(&mut self) -> &mut Identifier {
+        match self {
+            Self::Required(name) | Self::Default { name, .. } | Self::Rest { name, .. } => name,
+        }
+    }
+}
+
+impl Node for Parameter {
+    fn token_literal(&self) -> Symbol {
+        match self {
+            Self::Required(name) | Self::Default { name, .. } => name.token_literal(),
+            Self::Rest { token, .. } => token.literal.clone(),
+        }
+    }
+}
+
 /// The `PrefixExpression` struct represents a prefix expression in the Monkey language. For example,
 /// the expression `-5` is a prefix expression with the operator `-` and the right-hand side being the
 /// integer literal `5`.
@@ -142,7 +352,7 @@ pub struct PrefixExpression {
 }
 
 impl Node for PrefixExpression {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -166,7 +376,7 @@ pub struct InfixExpression {
 }
 
 impl Node for InfixExpression {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -175,6 +385,12 @@ impl Node for InfixExpression {
 /// expression `if (x < y) { x } else { y }` is an `if` expression with the condition being the
 /// infix expression `x < y`, the consequence being the block statement `{ x }`, and the alternative
 /// being the block statement `{ y }`.
+///
+/// An `if` expression can also have any number of `else if` branches between the consequence and
+/// the alternative, e.g. `if (a) { x } else if (b) { y } else { z }`. Instead of nesting another
+/// `IfExpression` inside of the alternative block (which is what the braces-only syntax would force
+/// you to write), these are stored in order in the `else_ifs` field, so that the chain can be walked
+/// front-to-back without having to decode nested blocks.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IfExpression {
     /// The `token` field is the token that the `if` expression represents.
@@ -186,12 +402,104 @@ pub struct IfExpression {
     /// The `consequence` field is the consequence of the `if` expression.
     pub consequence: BlockStatement,
 
-    /// The `alternative` field is the alternative of the `if` expression.
+    /// The `else_ifs` field is the `else if` branches of the `if` expression, in the order that
+    /// they appear in the source. These are checked (in order) only if `condition` is falsy.
+    pub else_ifs: Vec<ElseIfBranch>,
+
+    /// The `alternative` field is the alternative of the `if` expression. This is the final `else`
+    /// block, which is evaluated only if none of the conditions are truthy.
     pub alternative: Option<BlockStatement>,
 }
 
 impl Node for IfExpression {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `TryExpression` struct represents a `try` expression in the Monkey language. For example, in
+/// `try { risky() } catch (e) { 0 } finally { cleanup() }`, an error raised while evaluating the body
+/// is caught and bound to `e` while evaluating the `catch` block, and the `finally` block is always
+/// evaluated afterwards, however the body or `catch` block was left. At least one of the `catch` and
+/// `finally` blocks has to be given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryExpression {
+    /// The `token` field is the `try` token.
+    pub token: Token,
+
+    /// The `body` field is the block that errors are caught in.
+    pub body: BlockStatement,
+
+    /// The `catch` field is the `catch` clause, if there is one.
+    pub catch: Option<Box<CatchClause>>,
+
+    /// The `finally` field is the `finally` block, if there is one.
+    pub finally: Option<BlockStatement>,
+}
+
+impl Node for TryExpression {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `ForExpression` struct represents a `for` loop in the Monkey language. For example, in
+/// `for ([key, value] in pairs) { f(key, value) }`, the body is evaluated once for each value that
+/// `pairs` iterates over, with the value destructured by the pattern `[key, value]`. A `for` loop
+/// evaluates to `null`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForExpression {
+    /// The `token` field is the `for` token.
+    pub token: Token,
+
+    /// The `pattern` field is the pattern that each value is bound to.
+    pub pattern: Box<Pattern>,
+
+    /// The `iterable` field is the expression whose value is iterated over.
+    pub iterable: Box<Expression>,
+
+    /// The `body` field is the block that is evaluated for each value.
+    pub body: BlockStatement,
+}
+
+impl Node for ForExpression {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `CatchClause` struct represents the `catch` clause of a `TryExpression`. For example, in
+/// `catch (e) { e.message }`, the parameter is the identifier `e`, and the body is the block
+/// statement `{ e.message }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatchClause {
+    /// The `token` field is the `catch` token.
+    pub token: Token,
+
+    /// The `parameter` field is the identifier that the caught error is bound to.
+    pub parameter: Identifier,
+
+    /// The `body` field is the block that is evaluated when an error is caught.
+    pub body: BlockStatement,
+}
+
+/// The `ElseIfBranch` struct represents a single `else if` branch of an `IfExpression`. For example,
+/// in the expression `if (a) { x } else if (b) { y }`, the `else if` branch has the condition being
+/// the identifier `b`, and the consequence being the block statement `{ y }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElseIfBranch {
+    /// The `token` field is the `if` token that follows the `else` token of the branch.
+    pub token: Token,
+
+    /// The `condition` field is the condition of the `else if` branch.
+    pub condition: Box<Expression>,
+
+    /// The `consequence` field is the consequence of the `else if` branch.
+    pub consequence: BlockStatement,
+}
+
+impl Node for ElseIfBranch {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -211,10 +519,193 @@ pub struct CallExpression {
 
     /// The `arguments` field is the arguments of the function call.
     pub arguments: Vec<Expression>,
+
+    /// The `piped` field is whether the call is written as a pipe, e.g. `x |> f(y)` rather than
+    /// `f(x, y)`, in which case its first argument is the value on the left of the `|>`.
+    pub piped: bool,
 }
 
 impl Node for CallExpression {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `ArrayLiteral` struct represents an array literal in the Monkey language. For example, the
+/// expression `[1, 2 * 2, ...xs]` is an array literal with the elements being the integer literal `1`,
+/// the infix expression `2 * 2`, and the spread of the identifier `xs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayLiteral {
+    /// The `token` field is the `[` token.
+    pub token: Token,
+
+    /// The `elements` field is the elements of the array literal.
+    pub elements: Vec<Expression>,
+}
+
+impl Node for ArrayLiteral {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `IndexExpression` struct represents an index expression in the Monkey language. For example,
+/// the expression `xs[1 + 1]` is an index expression with the left-hand side being the identifier
+/// `xs`, and the index being the infix expression `1 + 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexExpression {
+    /// The `token` field is the `[` token.
+    pub token: Token,
+
+    /// The `left` field is the expression that is being indexed into.
+    pub left: Box<Expression>,
+
+    /// The `index` field is the index.
+    pub index: Box<Expression>,
+}
+
+impl Node for IndexExpression {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `MemberExpression` struct represents a member access expression in the Monkey language. For
+/// example, the expression `math.add` is a member access with the object being the identifier `math`,
+/// and the member being the identifier `add`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberExpression {
+    /// The `token` field is the `.` token.
+    pub token: Token,
+
+    /// The `object` field is the expression whose member is being accessed.
+    pub object: Box<Expression>,
+
+    /// The `member` field is the name of the member that is being accessed.
+    pub member: Identifier,
+}
+
+impl Node for MemberExpression {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `RecordLiteral` struct represents the construction of a record in the Monkey language. For
+/// example, the expression `Point { x: 1, y: 2 }` constructs a record of the struct `Point`, with the
+/// field `x` set to `1` and the field `y` set to `2`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordLiteral {
+    /// The `token` field is the `{` token.
+    pub token: Token,
+
+    /// The `name` field is the name of the struct that is being constructed.
+    pub name: Identifier,
+
+    /// The `fields` field is the fields of the record, in the order that they were written.
+    pub fields: Vec<(Identifier, Expression)>,
+}
+
+impl Node for RecordLiteral {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `SpreadExpression` struct represents a spread in the Monkey language. For example, in the call
+/// `add(...xs)`, the argument `...xs` spreads the elements of the array `xs` out into separate
+/// arguments. Spreads are only allowed as call arguments and array literal elements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpreadExpression {
+    /// The `token` field is the `...` token.
+    pub token: Token,
+
+    /// The `value` field is the expression that is being spread.
+    pub value: Box<Expression>,
+}
+
+impl Node for SpreadExpression {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `HashLiteral` struct represents a hash literal in the Monkey language. For example, the
+/// expression `{"one": 1, "two": 1 + 1}` is a hash literal with the key-value pairs being the string
+/// literal `"one"` and the integer literal `1`, and the string literal `"two"` and the infix
+/// expression `1 + 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashLiteral {
+    /// The `token` field is the `{` token.
+    pub token: Token,
+
+    /// The `pairs` field is the key-value pairs of the hash literal, in the order that they appear
+    /// in the source.
+    pub pairs: Vec<(Expression, Expression)>,
+}
+
+impl Node for HashLiteral {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `MatchExpression` struct represents a `match` expression in the Monkey language. For example,
+/// the expression `match (x) { 0 => "zero", n if n < 0 => "negative", _ => "positive" }` matches the
+/// value of `x` against the pattern of each arm in order, and evaluates to the body of the first arm
+/// whose pattern matches and whose guard (if it has one) is truthy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchExpression {
+    /// The `token` field is the `match` token.
+    pub token: Token,
+
+    /// The `subject` field is the expression whose value is matched against the arms.
+    pub subject: Box<Expression>,
+
+    /// The `arms` field is the arms of the `match` expression, in the order that they are checked.
+    pub arms: Vec<MatchArm>,
+}
+
+impl Node for MatchExpression {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `MatchArm` struct represents a single arm of a `MatchExpression`. For example, in the arm
+/// `[x, ...rest] if x > 0 => x`, the pattern is `[x, ...rest]`, the guard is the infix expression
+/// `x > 0`, and the body is the identifier `x`. The guard and body can use the pattern's bindings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchArm {
+    /// The `pattern` field is the pattern that the value is matched against.
+    pub pattern: Pattern,
+
+    /// The `guard` field is the condition after `if`, which also has to be truthy for the arm to match.
+    pub guard: Option<Box<Expression>>,
+
+    /// The `body` field is the expression that the `match` evaluates to if this arm matches.
+    pub body: Box<Expression>,
+}
+
+/// The `MacroLiteral` struct represents a macro literal in the Monkey language. For example, the
+/// expression `macro(a, b) { quote(unquote(b) - unquote(a)) }` is a macro literal with the parameters
+/// `a` and `b`. Macros look like functions, but they are called on the unevaluated AST of their
+/// arguments, and return the AST (as a quote) that the call is replaced with before the program is
+/// evaluated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroLiteral {
+    /// The `token` field is the `macro` token.
+    pub token: Token,
+
+    /// The `parameters` field is the parameters of the macro literal.
+    pub parameters: Vec<Parameter>,
+
+    /// The `body` field is the body of the macro literal.
+    pub body: BlockStatement,
+}
+
+impl Node for MacroLiteral {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -225,11 +716,22 @@ impl Display for Expression {
             Self::Identifier(identifier) => write!(f, "{}", identifier),
             Self::Integer(integer) => write!(f, "{}", integer),
             Self::Boolean(boolean) => write!(f, "{}", boolean),
+            Self::String(string) => write!(f, "{}", string),
             Self::Function(function) => write!(f, "{}", function),
             Self::Prefix(prefix) => write!(f, "{}", prefix),
             Self::Infix(infix) => write!(f, "{}", infix),
             Self::If(if_expression) => write!(f, "{}", if_expression),
             Self::Call(call) => write!(f, "{}", call),
+            Self::Array(array) => write!(f, "{}", array),
+            Self::Index(index) => write!(f, "{}", index),
+            Self::Member(member) => write!(f, "{}", member),
+            Self::Spread(spread) => write!(f, "{}", spread),
+            Self::Hash(hash) => write!(f, "{}", hash),
+            Self::Record(record) => write!(f, "{}", record),
+            Self::Match(match_expression) => write!(f, "{}", match_expression),
+            Self::Macro(macro_literal) => write!(f, "{}", macro_literal),
+            Self::Try(try_expression) => write!(f, "{}", try_expression),
+            Self::For(for_expression) => write!(f, "{}", for_expression),
         }
     }
 }
@@ -252,6 +754,12 @@ impl Display for BooleanLiteral {
     }
 }
 
+impl Display for StringLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\"", self.value)
+    }
+}
+
 impl Display for FunctionLiteral {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.token_literal())?;
@@ -260,7 +768,7 @@ impl Display for FunctionLiteral {
             "({})",
             self.parameters
                 .iter()
-                .map(Identifier::to_string)
+                .map(Parameter::to_string)
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
@@ -269,6 +777,16 @@ impl Display for FunctionLiteral {
     }
 }
 
+impl Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Required(name) => write!(f, "{}", name),
+            Self::Default { name, value } => write!(f, "{} = {}", name, value),
+            Self::Rest { name, .. } => write!(f, "...{}", name),
+        }
+    }
+}
+
 impl Display for PrefixExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "({}{})", self.operator, self.right)
@@ -284,6 +802,9 @@ impl Display for InfixExpression {
 impl Display for IfExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "if {} {}", self.condition, self.consequence)?;
+        for else_if in &self.else_ifs {
+            write!(f, " {}", else_if)?;
+        }
         if let Some(alternative) = &self.alternative {
             write!(f, " else {}", alternative)?;
         }
@@ -291,6 +812,41 @@ impl Display for IfExpression {
     }
 }
 
+impl Display for TryExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "try {}", self.body)?;
+        if let Some(catch) = &self.catch {
+            write!(f, " {}", catch)?;
+        }
+        if let Some(finally) = &self.finally {
+            write!(f, " finally {}", finally)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ForExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "for ({} in {}) {}",
+            self.pattern, self.iterable, self.body
+        )
+    }
+}
+
+impl Display for CatchClause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "catch ({}) {}", self.parameter, self.body)
+    }
+}
+
+impl Display for ElseIfBranch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "else if {} {}", self.condition, self.consequence)
+    }
+}
+
 impl Display for CallExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}(", self.function)?;
@@ -306,3 +862,105 @@ impl Display for CallExpression {
         write!(f, ")")
     }
 }
+
+impl Display for ArrayLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}]",
+            self.elements
+                .iter()
+                .map(Expression::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl Display for IndexExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}[{}])", self.left, self.index)
+    }
+}
+
+impl Display for MemberExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}.{})", self.object, self.member)
+    }
+}
+
+impl Display for RecordLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {{ {} }}",
+            self.name,
+            self.fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl Display for SpreadExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "...{}", self.value)
+    }
+}
+
+impl Display for HashLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{{}}}",
+            self.pairs
+                .iter()
+                .map(|(key, value)| format!("{}: {}", key, value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl Display for MatchExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "match ({}) {{ {} }}",
+            self.subject,
+            self.arms
+                .iter()
+                .map(MatchArm::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl Display for MatchArm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pattern)?;
+        if let Some(guard) = &self.guard {
+            write!(f, " if {}", guard)?;
+        }
+        write!(f, " => {}", self.body)
+    }
+}
+
+impl Display for MacroLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}({}) {}",
+            self.token_literal(),
+            self.parameters
+                .iter()
+                .map(Parameter::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.body
+        )
+    }
+}
@@ -1,9 +1,12 @@
 pub mod expressions;
+pub mod modify;
 pub mod operators;
+pub mod patterns;
 pub mod statements;
 mod tests;
 
 use self::statements::Statement;
+use crate::symbol::Symbol;
 use core::fmt::Debug;
 use std::fmt::Display;
 
@@ -11,7 +14,7 @@ use std::fmt::Display;
 /// returns the literal value of the token that the node represents. This is used for debugging.
 pub trait Node: Debug + Display {
     /// Returns the literal value of the token that the node represents. This is used for debugging.
-    fn token_literal(&self) -> String;
+    fn token_literal(&self) -> Symbol;
 }
 
 /// The `Program` struct represents the root node of the AST. It contains a list of statements.
@@ -23,11 +26,11 @@ pub struct Program {
 
 impl Node for Program {
     /// Returns the literal value of the token that the node represents. This is used for debugging.
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         if !self.statements.is_empty() {
             self.statements[0].token_literal()
         } else {
-            "".to_string()
+            Symbol::default()
         }
     }
 }
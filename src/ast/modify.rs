@@ -0,0 +1,418 @@
+use super::{
+    expressions::{
+        ArrayLiteral, CallExpression, CatchClause, ElseIfBranch, Expression, ForExpression,
+        FunctionLiteral, HashLiteral, IfExpression, IndexExpression, InfixExpression, MacroLiteral,
+        MatchArm, MatchExpression, MemberExpression, Parameter, PrefixExpression, RecordLiteral,
+        SpreadExpression, TryExpression,
+    },
+    statements::{
+        BlockStatement, ExportStatement, ExpressionStatement, FunctionStatement, LetStatement,
+        ReturnStatement, Statement, ThrowStatement, YieldStatement,
+    },
+    Program,
+};
+
+// A generic walker that rebuilds an AST, passing every expression in it through a `modifier`
+// function. The children of an expression are modified before the expression itself, so the
+// modifier always sees an expression whose children have already been modified. The modifier can
+// fail, in which case the walk stops and the error is returned. This is what macro expansion and
+// `quote`/`unquote` are built on, but it works for any AST-to-AST transformation.
+//
+// Patterns are left alone, as replacing the literals in them with arbitrary expressions wouldn't
+// make a valid pattern.
+
+/// How little stack is left when `modify_expression` grows the stack. This leaves the modifier room
+/// to run, which for macro expansion means evaluating the macro's body.
+const STACK_RED_ZONE: usize = 1024 * 1024;
+
+/// How much stack `modify_expression` grows the stack by at a time.
+const STACK_GROWTH: usize = 8 * 1024 * 1024;
+
+/// Modifies every expression in `program` with `modifier`.
+pub fn modify_program<E, F>(program: Program, modifier: &mut F) -> Result<Program, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    Ok(Program {
+        statements: modify_statements(program.statements, modifier)?,
+    })
+}
+
+/// Modifies every expression in `statement` with `modifier`.
+pub fn modify_statement<E, F>(statement: Statement, modifier: &mut F) -> Result<Statement, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    Ok(match statement {
+        Statement::Let(LetStatement {
+            token,
+            pattern,
+            value,
+        }) => Statement::Let(LetStatement {
+            token,
+            pattern,
+            value: modify_boxed(value, modifier)?,
+        }),
+
+        Statement::Return(ReturnStatement {
+            token,
+            return_value,
+        }) => Statement::Return(ReturnStatement {
+            token,
+            return_value: modify_boxed(return_value, modifier)?,
+        }),
+
+        Statement::Throw(ThrowStatement { token, value }) => Statement::Throw(ThrowStatement {
+            token,
+            value: modify_boxed(value, modifier)?,
+        }),
+
+        Statement::Yield(YieldStatement { token, value }) => Statement::Yield(YieldStatement {
+            token,
+            value: modify_boxed(value, modifier)?,
+        }),
+
+        Statement::Expression(ExpressionStatement { token, expression }) => {
+            Statement::Expression(ExpressionStatement {
+                token,
+                expression: modify_expression(expression, modifier)?,
+            })
+        }
+
+        Statement::Function(FunctionStatement {
+            token,
+            name,
+            function,
+        }) => Statement::Function(FunctionStatement {
+            token,
+            name,
+            function: modify_function(function, modifier)?,
+        }),
+
+        Statement::Import(import_statement) => Statement::Import(import_statement),
+
+        Statement::Struct(struct_statement) => Statement::Struct(struct_statement),
+
+        Statement::Export(ExportStatement { token, declaration }) => {
+            Statement::Export(ExportStatement {
+                token,
+                declaration: Box::new(modify_statement(*declaration, modifier)?),
+            })
+        }
+    })
+}
+
+/// Modifies every expression in `expression` (including `expression` itself) with `modifier`.
+pub fn modify_expression<E, F>(expression: Expression, modifier: &mut F) -> Result<Expression, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    // The walk recurses once for each level of the tree, and the trees that macros build aren't
+    // bounded by the parser's nesting limit, so the stack is grown on the heap when it runs low.
+    stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || {
+        modify_nested(expression, modifier)
+    })
+}
+
+/// Modifies the expressions nested in `expression`, and then `expression` itself, with `modifier`.
+fn modify_nested<E, F>(expression: Expression, modifier: &mut F) -> Result<Expression, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    let expression = match expression {
+        Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::Boolean(_)
+        | Expression::String(_) => expression,
+
+        Expression::Function(function) => {
+            Expression::Function(modify_function(function, modifier)?)
+        }
+
+        Expression::Macro(MacroLiteral {
+            token,
+            parameters,
+            body,
+        }) => Expression::Macro(MacroLiteral {
+            token,
+            parameters: modify_parameters(parameters, modifier)?,
+            body: modify_block(body, modifier)?,
+        }),
+
+        Expression::Prefix(PrefixExpression {
+            token,
+            operator,
+            right,
+        }) => Expression::Prefix(PrefixExpression {
+            token,
+            operator,
+            right: modify_boxed(right, modifier)?,
+        }),
+
+        Expression::Infix(InfixExpression {
+            token,
+            left,
+            operator,
+            right,
+        }) => Expression::Infix(InfixExpression {
+            token,
+            left: modify_boxed(left, modifier)?,
+            operator,
+            right: modify_boxed(right, modifier)?,
+        }),
+
+        Expression::If(IfExpression {
+            token,
+            condition,
+            consequence,
+            else_ifs,
+            alternative,
+        }) => Expression::If(IfExpression {
+            token,
+            condition: modify_boxed(condition, modifier)?,
+            consequence: modify_block(consequence, modifier)?,
+            else_ifs: else_ifs
+                .into_iter()
+                .map(
+                    |ElseIfBranch {
+                         token,
+                         condition,
+                         consequence,
+                     }| {
+                        Ok(ElseIfBranch {
+                            token,
+                            condition: modify_boxed(condition, modifier)?,
+                            consequence: modify_block(consequence, modifier)?,
+                        })
+                    },
+                )
+                .collect::<Result<_, _>>()?,
+            alternative: alternative
+                .map(|alternative| modify_block(alternative, modifier))
+                .transpose()?,
+        }),
+
+        Expression::Try(TryExpression {
+            token,
+            body,
+            catch,
+            finally,
+        }) => Expression::Try(TryExpression {
+            token,
+            body: modify_block(body, modifier)?,
+            catch: catch
+                .map(|catch| {
+                    let CatchClause {
+                        token,
+                        parameter,
+                        body,
+                    } = *catch;
+
+                    Ok(Box::new(CatchClause {
+                        token,
+                        parameter,
+                        body: modify_block(body, modifier)?,
+                    }))
+                })
+                .transpose()?,
+            finally: finally
+                .map(|finally| modify_block(finally, modifier))
+                .transpose()?,
+        }),
+
+        Expression::For(ForExpression {
+            token,
+            pattern,
+            iterable,
+            body,
+        }) => Expression::For(ForExpression {
+            token,
+            pattern,
+            iterable: modify_boxed(iterable, modifier)?,
+            body: modify_block(body, modifier)?,
+        }),
+
+        Expression::Call(CallExpression {
+            token,
+            function,
+            arguments,
+            piped,
+        }) => Expression::Call(CallExpression {
+            token,
+            function: modify_boxed(function, modifier)?,
+            arguments: modify_expressions(arguments, modifier)?,
+            piped,
+        }),
+
+        Expression::Array(ArrayLiteral { token, elements }) => Expression::Array(ArrayLiteral {
+            token,
+            elements: modify_expressions(elements, modifier)?,
+        }),
+
+        Expression::Member(MemberExpression {
+            token,
+            object,
+            member,
+        }) => Expression::Member(MemberExpression {
+            token,
+            object: modify_boxed(object, modifier)?,
+            member,
+        }),
+
+        Expression::Index(IndexExpression { token, left, index }) => {
+            Expression::Index(IndexExpression {
+                token,
+                left: modify_boxed(left, modifier)?,
+                index: modify_boxed(index, modifier)?,
+            })
+        }
+
+        Expression::Spread(SpreadExpression { token, value }) => {
+            Expression::Spread(SpreadExpression {
+                token,
+                value: modify_boxed(value, modifier)?,
+            })
+        }
+
+        Expression::Hash(HashLiteral { token, pairs }) => Expression::Hash(HashLiteral {
+            token,
+            pairs: pairs
+                .into_iter()
+                .map(|(key, value)| {
+                    Ok((
+                        modify_expression(key, modifier)?,
+                        modify_expression(value, modifier)?,
+                    ))
+                })
+                .collect::<Result<_, _>>()?,
+        }),
+
+        Expression::Record(RecordLiteral {
+            token,
+            name,
+            fields,
+        }) => Expression::Record(RecordLiteral {
+            token,
+            name,
+            fields: fields
+                .into_iter()
+                .map(|(field, value)| Ok((field, modify_expression(value, modifier)?)))
+                .collect::<Result<_, _>>()?,
+        }),
+
+        Expression::Match(MatchExpression {
+            token,
+            subject,
+            arms,
+        }) => Expression::Match(MatchExpression {
+            token,
+            subject: modify_boxed(subject, modifier)?,
+            arms: arms
+                .into_iter()
+                .map(
+                    |MatchArm {
+                         pattern,
+                         guard,
+                         body,
+                     }| {
+                        Ok(MatchArm {
+                            pattern,
+                            guard: guard
+                                .map(|guard| modify_boxed(guard, modifier))
+                                .transpose()?,
+                            body: modify_boxed(body, modifier)?,
+                        })
+                    },
+                )
+                .collect::<Result<_, _>>()?,
+        }),
+    };
+
+    modifier(expression)
+}
+
+/// Modifies every expression in `block` with `modifier`.
+pub fn modify_block<E, F>(block: BlockStatement, modifier: &mut F) -> Result<BlockStatement, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    Ok(BlockStatement {
+        token: block.token,
+        statements: modify_statements(block.statements, modifier)?,
+    })
+}
+
+/// Modifies every expression in the default values and body of `function` with `modifier`.
+fn modify_function<E, F>(function: FunctionLiteral, modifier: &mut F) -> Result<FunctionLiteral, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    Ok(FunctionLiteral {
+        token: function.token,
+        name: function.name,
+        parameters: modify_parameters(function.parameters, modifier)?,
+        body: modify_block(function.body, modifier)?,
+        generator: function.generator,
+        form: function.form,
+    })
+}
+
+/// Modifies the default values of `parameters` with `modifier`.
+fn modify_parameters<E, F>(
+    parameters: Vec<Parameter>,
+    modifier: &mut F,
+) -> Result<Vec<Parameter>, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    parameters
+        .into_iter()
+        .map(|parameter| match parameter {
+            Parameter::Default { name, value } => Ok(Parameter::Default {
+                name,
+                value: modify_boxed(value, modifier)?,
+            }),
+            parameter => Ok(parameter),
+        })
+        .collect()
+}
+
+/// Modifies each of `statements` with `modifier`.
+fn modify_statements<E, F>(
+    statements: Vec<Statement>,
+    modifier: &mut F,
+) -> Result<Vec<Statement>, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    statements
+        .into_iter()
+        .map(|statement| modify_statement(statement, modifier))
+        .collect()
+}
+
+/// Modifies each of `expressions` with `modifier`.
+fn modify_expressions<E, F>(
+    expressions: Vec<Expression>,
+    modifier: &mut F,
+) -> Result<Vec<Expression>, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    expressions
+        .into_iter()
+        .map(|expression| modify_expression(expression, modifier))
+        .collect()
+}
+
+/// Modifies a boxed expression with `modifier`, keeping it in the same box.
+fn modify_boxed<E, F>(
+    mut expression: Box<Expression>,
+    modifier: &mut F,
+) -> Result<Box<Expression>, E>
+where
+    F: FnMut(Expression) -> Result<Expression, E>,
+{
+    *expression = modify_expression(*expression, modifier)?;
+    Ok(expression)
+}
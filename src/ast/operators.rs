@@ -1,5 +1,5 @@
 use super::Node;
-use crate::token::Token;
+use crate::{symbol::Symbol, token::Token};
 use std::fmt::Display;
 
 /// a `PrefixOperator` is a token that can be used in an expression. `Operator`s are used to build
@@ -21,7 +21,7 @@ impl PrefixOperator {
 }
 
 impl Node for PrefixOperator {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -69,7 +69,7 @@ impl InfixOperator {
 }
 
 impl Node for InfixOperator {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -99,6 +99,8 @@ pub enum InfixOperatorType {
     Lt,
     /// `>`
     Gt,
+    /// `..`
+    Range,
 }
 
 impl Display for InfixOperatorType {
@@ -112,6 +114,7 @@ impl Display for InfixOperatorType {
             Self::NotEq => write!(f, "!="),
             Self::Lt => write!(f, "<"),
             Self::Gt => write!(f, ">"),
+            Self::Range => write!(f, ".."),
         }
     }
 }
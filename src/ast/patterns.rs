@@ -0,0 +1,220 @@
+use super::{
+    expressions::{Expression, Identifier, PrefixExpression},
+    operators::{PrefixOperator, PrefixOperatorType},
+    Node,
+};
+use crate::{
+    symbol::Symbol,
+    token::{Position, Token},
+};
+use std::fmt::Display;
+
+/// A `Pattern` describes the shape of a value, and binds the parts of the value to identifiers. This
+/// is used on the left-hand side of `let` statements to destructure arrays and hashes, e.g. in
+/// `let [a, b] = [1, 2];`, `[a, b]` is a pattern that binds `a` to `1` and `b` to `2`, and in the
+/// arms of `match` expressions. Patterns can be nested inside of each other, e.g.
+/// `let {"point": [x, y]} = shape;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    /// A plain identifier, which binds the whole value, e.g. `x`.
+    Identifier(Identifier),
+
+    /// The wildcard `_`, which matches any value without binding it. The token is the `_` token.
+    Wildcard(Token),
+
+    /// A literal value, which only matches values equal to it, e.g. `1`, `-1`, `"foo"` or `true`.
+    Literal(Expression),
+
+    /// The `ArrayPattern` struct represents an array pattern in the Monkey language.
+    Array(ArrayPattern),
+
+    /// The `HashPattern` struct represents a hash pattern in the Monkey language.
+    Hash(HashPattern),
+}
+
+impl Pattern {
+    /// Returns the position of the pattern's first token in the source code.
+    pub fn position(&self) -> Position {
+        match self {
+            Self::Identifier(identifier) => identifier.token.position,
+            Self::Wildcard(token) => token.position,
+            Self::Literal(literal) => literal.position(),
+            Self::Array(array) => array.token.position,
+            Self::Hash(hash) => hash.token.position,
+        }
+    }
+
+    /// Returns the identifiers that the pattern binds, in the order that they appear in.
+    pub fn identifiers(&self) -> Vec<&Identifier> {
+        match self {
+            Self::Identifier(identifier) => vec![identifier],
+            Self::Wildcard(_) | Self::Literal(_) => vec![],
+            Self::Array(array) => array
+                .elements
+                .iter()
+                .flat_map(Pattern::identifiers)
+                .chain(&array.rest)
+                .collect(),
+            Self::Hash(hash) => hash
+                .pairs
+                .iter()
+                .flat_map(|(_, pattern)| pattern.identifiers())
+                .collect(),
+        }
+    }
+
+    /// Returns whether every value that `other` matches is also matched by this pattern, ignoring any
+    /// guards. This is used to find `match` arms that can never match because an earlier arm already
+    /// matches everything they would. This errs on the side of `false`, e.g. hash keys that aren't
+    /// literals are never considered to be the same key.
+    pub fn covers(&self, other: &Pattern) -> bool {
+        match (self, other) {
+            (Self::Identifier(_) | Self::Wildcard(_), _) => true,
+
+            (Self::Literal(literal), Self::Literal(other)) => literal == other,
+
+            (Self::Array(array), Self::Array(other)) => {
+                let prefix_covered = other.elements.len() >= array.elements.len()
+                    && array
+                        .elements
+                        .iter()
+                        .zip(&other.elements)
+                        .all(|(element, other)| element.covers(other));
+
+                // Without a rest element, this only matches arrays of exactly its length, so the other
+                // pattern has to as well.
+                match array.rest {
+                    Some(_) => prefix_covered,
+                    None => {
+                        prefix_covered
+                            && other.rest.is_none()
+                            && other.elements.len() == array.elements.len()
+                    }
+                }
+            }
+
+            (Self::Hash(hash), Self::Hash(other)) => hash.pairs.iter().all(|(key, pattern)| {
+                is_literal(key)
+                    && other
+                        .pairs
+                        .iter()
+                        .any(|(other_key, other)| key == other_key && pattern.covers(other))
+            }),
+
+            _ => false,
+        }
+    }
+}
+
+/// Returns whether `expression` is a literal that is allowed in a pattern (and so always evaluates to
+/// the same value).
+pub fn is_literal(expression: &Expression) -> bool {
+    match expression {
+        Expression::Integer(_) | Expression::String(_) | Expression::Boolean(_) => true,
+        Expression::Prefix(PrefixExpression {
+            operator:
+                PrefixOperator {
+                    r#type: PrefixOperatorType::Neg,
+                    ..
+                },
+            right,
+            ..
+        }) => matches!(**right, Expression::Integer(_)),
+        _ => false,
+    }
+}
+
+impl Node for Pattern {
+    fn token_literal(&self) -> Symbol {
+        match self {
+            Self::Identifier(identifier) => identifier.token_literal(),
+            Self::Wildcard(token) => token.literal.clone(),
+            Self::Literal(literal) => literal.token_literal(),
+            Self::Array(array) => array.token_literal(),
+            Self::Hash(hash) => hash.token_literal(),
+        }
+    }
+}
+
+/// The `ArrayPattern` struct represents an array pattern in the Monkey language. For example, the
+/// pattern `[a, b, ...rest]` matches an array with at least two elements, binding the first element
+/// to `a`, the second to `b`, and an array of the remaining elements to `rest`. Without a rest
+/// element, the array has to have exactly as many elements as the pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrayPattern {
+    /// The `token` field is the `[` token.
+    pub token: Token,
+
+    /// The `elements` field is the patterns that the elements of the array are matched against.
+    pub elements: Vec<Pattern>,
+
+    /// The `rest` field is the identifier that the remaining elements are bound to, if there is one.
+    pub rest: Option<Identifier>,
+}
+
+impl Node for ArrayPattern {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `HashPattern` struct represents a hash pattern in the Monkey language. For example, the
+/// pattern `{"name": n, "age": a}` matches a hash that has both a `"name"` and an `"age"` key,
+/// binding the values to `n` and `a` respectively. Any other keys in the hash are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashPattern {
+    /// The `token` field is the `{` token.
+    pub token: Token,
+
+    /// The `pairs` field is the keys to look up in the hash, and the patterns that their values are
+    /// matched against.
+    pub pairs: Vec<(Expression, Pattern)>,
+}
+
+impl Node for HashPattern {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Identifier(identifier) => write!(f, "{}", identifier),
+            Self::Wildcard(token) => write!(f, "{}", token.literal),
+            Self::Literal(literal) => write!(f, "{}", literal),
+            Self::Array(array) => write!(f, "{}", array),
+            Self::Hash(hash) => write!(f, "{}", hash),
+        }
+    }
+}
+
+impl Display for ArrayPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut elements = self
+            .elements
+            .iter()
+            .map(Pattern::to_string)
+            .collect::<Vec<_>>();
+
+        if let Some(rest) = &self.rest {
+            elements.push(format!("...{}", rest));
+        }
+
+        write!(f, "[{}]", elements.join(", "))
+    }
+}
+
+impl Display for HashPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{{}}}",
+            self.pairs
+                .iter()
+                .map(|(key, pattern)| format!("{}: {}", key, pattern))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
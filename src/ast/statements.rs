@@ -1,10 +1,11 @@
 use std::fmt::Display;
 
 use super::{
-    expressions::{Expression, Identifier},
+    expressions::{Expression, FunctionLiteral, Identifier, Parameter, StringLiteral},
+    patterns::Pattern,
     Node,
 };
-use crate::token::Token;
+use crate::{symbol::Symbol, token::Token};
 
 /// The `Statement` enum represents a statement in the Monkey language. Note that while in the original
 /// implementation, the `Statement` trait was implemented by the `LetStatement` struct, I have chosen to
@@ -20,35 +21,62 @@ pub enum Statement {
 
     /// The `ExpressionStatement` struct represents an expression statement in the Monkey language.
     Expression(ExpressionStatement),
+
+    /// The `FunctionStatement` struct represents a named function declaration in the Monkey language.
+    Function(FunctionStatement),
+
+    /// The `ImportStatement` struct represents an `import` statement in the Monkey language.
+    Import(ImportStatement),
+
+    /// The `ExportStatement` struct represents an `export` declaration in the Monkey language.
+    Export(ExportStatement),
+
+    /// The `StructStatement` struct represents a `struct` declaration in the Monkey language.
+    Struct(StructStatement),
+
+    /// The `ThrowStatement` struct represents a `throw` statement in the Monkey language.
+    Throw(ThrowStatement),
+
+    /// The `YieldStatement` struct represents a `yield` statement in the Monkey language.
+    Yield(YieldStatement),
 }
 
 impl Node for Statement {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         match self {
             Self::Let(let_statement) => let_statement.token_literal(),
             Self::Return(return_statement) => return_statement.token_literal(),
             Self::Expression(expression_statement) => expression_statement.token_literal(),
+            Self::Function(function_statement) => function_statement.token_literal(),
+            Self::Import(import_statement) => import_statement.token_literal(),
+            Self::Export(export_statement) => export_statement.token_literal(),
+            Self::Struct(struct_statement) => struct_statement.token_literal(),
+            Self::Throw(throw_statement) => throw_statement.token_literal(),
+            Self::Yield(yield_statement) => yield_statement.token_literal(),
         }
     }
 }
 
 /// The `LetStatement` struct represents a `let` statement in the Monkey language. It contains a
-/// `token` field, which is the `let` token, a `name` field, which is the identifier that is being
-/// assigned to, and a `value` field, which is the expression that is being assigned to the identifier.
+/// `token` field, which is the `let` token, a `pattern` field, which is the pattern that is being
+/// assigned to, and a `value` field, which is the expression that is being assigned to the pattern.
+/// In the original implementation, you could only assign to a single identifier, but here you can
+/// also destructure arrays and hashes, e.g. `let [a, b] = [1, 2];`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LetStatement {
     /// The `token` field is the `let` token.
     pub token: Token,
 
-    /// The `name` field is the identifier that is being assigned to.
-    pub name: Identifier,
+    /// The `pattern` field is the pattern that is being assigned to. Usually, this is just an
+    /// identifier.
+    pub pattern: Pattern,
 
-    /// The `value` field is the expression that is being assigned to the identifier.
+    /// The `value` field is the expression that is being assigned to the pattern.
     pub value: Box<Expression>,
 }
 
 impl Node for LetStatement {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -66,7 +94,44 @@ pub struct ReturnStatement {
 }
 
 impl Node for ReturnStatement {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `YieldStatement` struct represents a `yield` statement in the Monkey language. It contains a
+/// `token` field, which is the `yield` token, and a `value` field, which is the expression whose value
+/// is yielded. A function with a `yield` in it is a generator, which is paused at each `yield` until
+/// the next value is asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YieldStatement {
+    /// The `token` field is the `yield` token.
+    pub token: Token,
+
+    /// The `value` field is the expression whose value is yielded.
+    pub value: Box<Expression>,
+}
+
+impl Node for YieldStatement {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `ThrowStatement` struct represents a `throw` statement in the Monkey language. It contains a
+/// `token` field, which is the `throw` token, and a `value` field, which is the expression whose value
+/// is thrown. For example, `throw "not found";` raises an error that can be caught by a `try`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThrowStatement {
+    /// The `token` field is the `throw` token.
+    pub token: Token,
+
+    /// The `value` field is the expression whose value is thrown.
+    pub value: Box<Expression>,
+}
+
+impl Node for ThrowStatement {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -84,7 +149,105 @@ pub struct ExpressionStatement {
 }
 
 impl Node for ExpressionStatement {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `FunctionStatement` struct represents a named function declaration in the Monkey language. For
+/// example, `fn add(x, y) { x + y }` declares a function named `add`. This is mostly equivalent to
+/// `let add = fn(x, y) { x + y };`, except that declarations are hoisted to the top of the program or
+/// block that they are declared in, so they can be called before they are declared. This allows for
+/// mutually recursive functions to be declared in any order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionStatement {
+    /// The `token` field is the `fn` token.
+    pub token: Token,
+
+    /// The `name` field is the identifier that the function is declared as.
+    pub name: Identifier,
+
+    /// The `function` field is the function that is being declared. Its `name` field is always set
+    /// to the name of the declaration.
+    pub function: FunctionLiteral,
+}
+
+impl Node for FunctionStatement {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `ImportStatement` struct represents an `import` statement in the Monkey language. For example,
+/// `import "lib/math.monkey" as math;` evaluates the module at the path `lib/math.monkey` (relative to
+/// the file that is importing it), and binds a module object with its exports to `math`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportStatement {
+    /// The `token` field is the `import` token.
+    pub token: Token,
+
+    /// The `path` field is the path of the module that is being imported.
+    pub path: StringLiteral,
+
+    /// The `alias` field is the identifier that the module object is bound to.
+    pub alias: Identifier,
+}
+
+impl Node for ImportStatement {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `ExportStatement` struct represents an `export` declaration in the Monkey language. For
+/// example, `export let pi = 3;` or `export fn add(x, y) { x + y }`. The bindings made by the
+/// declaration can be accessed through the module object of modules that import this one. Exports
+/// are only allowed at the top level of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportStatement {
+    /// The `token` field is the `export` token.
+    pub token: Token,
+
+    /// The `declaration` field is the `let` statement, function declaration or struct declaration
+    /// that is being exported.
+    pub declaration: Box<Statement>,
+}
+
+impl ExportStatement {
+    /// Returns the identifiers of the bindings that are exported.
+    pub fn names(&self) -> Vec<&Identifier> {
+        match self.declaration.as_ref() {
+            Statement::Let(let_statement) => let_statement.pattern.identifiers(),
+            Statement::Function(function_statement) => vec![&function_statement.name],
+            Statement::Struct(struct_statement) => vec![&struct_statement.name],
+            _ => vec![],
+        }
+    }
+}
+
+impl Node for ExportStatement {
+    fn token_literal(&self) -> Symbol {
+        self.token.literal.clone()
+    }
+}
+
+/// The `StructStatement` struct represents a `struct` declaration in the Monkey language. For example,
+/// `struct Point { x, y }` declares a struct named `Point` with the fields `x` and `y`. Records of the
+/// struct can then be constructed with `Point { x: 1, y: 2 }`, and their fields accessed with `.`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructStatement {
+    /// The `token` field is the `struct` token.
+    pub token: Token,
+
+    /// The `name` field is the identifier that the struct is declared as.
+    pub name: Identifier,
+
+    /// The `fields` field is the names of the fields of the struct, in the order they were declared.
+    pub fields: Vec<Identifier>,
+}
+
+impl Node for StructStatement {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -102,7 +265,7 @@ pub struct BlockStatement {
 }
 
 impl Node for BlockStatement {
-    fn token_literal(&self) -> String {
+    fn token_literal(&self) -> Symbol {
         self.token.literal.clone()
     }
 }
@@ -124,6 +287,12 @@ impl Display for Statement {
             Self::Expression(expression_statement) => {
                 write!(f, "{}", expression_statement)
             }
+            Self::Function(function_statement) => write!(f, "{}", function_statement),
+            Self::Import(import_statement) => write!(f, "{}", import_statement),
+            Self::Export(export_statement) => write!(f, "{}", export_statement),
+            Self::Struct(struct_statement) => write!(f, "{}", struct_statement),
+            Self::Throw(throw_statement) => write!(f, "{}", throw_statement),
+            Self::Yield(yield_statement) => write!(f, "{}", yield_statement),
         }
     }
 }
@@ -134,7 +303,7 @@ impl Display for LetStatement {
             f,
             "{} {} = {};",
             self.token_literal(),
-            self.name,
+            self.pattern,
             self.value
         )
     }
@@ -146,8 +315,72 @@ impl Display for ReturnStatement {
     }
 }
 
+impl Display for ThrowStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {};", self.token_literal(), self.value)
+    }
+}
+
+impl Display for YieldStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {};", self.token_literal(), self.value)
+    }
+}
+
 impl Display for ExpressionStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.expression)
     }
 }
+
+impl Display for FunctionStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}({}) {}",
+            self.token_literal(),
+            self.name,
+            self.function
+                .parameters
+                .iter()
+                .map(Parameter::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.function.body
+        )
+    }
+}
+
+impl Display for ImportStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} as {};",
+            self.token_literal(),
+            self.path,
+            self.alias
+        )
+    }
+}
+
+impl Display for ExportStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.token_literal(), self.declaration)
+    }
+}
+
+impl Display for StructStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {{ {} }}",
+            self.token_literal(),
+            self.name,
+            self.fields
+                .iter()
+                .map(Identifier::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
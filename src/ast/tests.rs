@@ -1,9 +1,15 @@
 #![cfg(test)]
 
-use crate::token::{Token, TokenType};
+use crate::{
+    lexer::Lexer,
+    parser::Parser,
+    token::{Position, Token, TokenType},
+};
 
 use super::{
-    expressions::{Expression, Identifier},
+    expressions::{Expression, Identifier, IntegerLiteral},
+    modify::modify_program,
+    patterns::Pattern,
     statements::{LetStatement, Statement},
     Program,
 };
@@ -14,24 +20,105 @@ fn test_formatting() {
         statements: vec![Statement::Let(LetStatement {
             token: Token {
                 r#type: TokenType::Let,
-                literal: "let".to_string(),
+                literal: "let".into(),
+                position: Position::new(1, 1),
             },
-            name: Identifier {
+            pattern: Pattern::Identifier(Identifier {
                 token: Token {
                     r#type: TokenType::Ident,
-                    literal: "myVar".to_string(),
+                    literal: "myVar".into(),
+                    position: Position::new(1, 5),
                 },
-                value: "myVar".to_string(),
-            },
+                value: "myVar".into(),
+                slots: None,
+            }),
             value: Box::new(Expression::Identifier(Identifier {
                 token: Token {
                     r#type: TokenType::Ident,
-                    literal: "anotherVar".to_string(),
+                    literal: "anotherVar".into(),
+                    position: Position::new(1, 13),
                 },
-                value: "anotherVar".to_string(),
+                value: "anotherVar".into(),
+                slots: None,
             })),
         })],
     };
 
     assert_eq!(ast.to_string(), "let myVar = anotherVar;".to_string());
 }
+
+/// Parses the input source code
+fn parse(input: &str) -> Program {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    parser.parse_program().unwrap()
+}
+
+/// Tests that the modify walker reaches every expression in the AST.
+#[test]
+fn test_modify() {
+    // Turns every `1` into a `2`.
+    let mut turn_one_into_two = |expression: Expression| -> Result<Expression, ()> {
+        match expression {
+            Expression::Integer(IntegerLiteral { token, value: 1 }) => {
+                Ok(Expression::Integer(IntegerLiteral { token, value: 2 }))
+            }
+            expression => Ok(expression),
+        }
+    };
+
+    let tests = vec![
+        ("1", "2"),
+        ("1 + 2", "(2 + 2)"),
+        ("-1", "(-2)"),
+        ("x[1]", "(x[2])"),
+        ("[1, ...1]", "[2, ...2]"),
+        ("{1: 1}", "{2: 2}"),
+        ("f(1, 1)", "f(2, 2)"),
+        (
+            "if (1) { 1 } else if (1) { 1 } else { 1 }",
+            "if 2 2 else if 2 2 else 2",
+        ),
+        ("return 1;", "return 2;"),
+        ("let x = 1;", "let x = 2;"),
+        ("let [a, 1] = 1;", "let [a, 1] = 2;"),
+        ("fn(x = 1) { 1 }", "fn(x = 2) 2"),
+        ("fn f(x = 1) { 1 }", "fn f(x = 2) 2"),
+        ("macro(x = 1) { 1 }", "macro(x = 2) 2"),
+        ("match (1) { 1 if 1 => 1 }", "match (2) { 1 if 2 => 2 }"),
+    ];
+
+    for (input, expected) in tests {
+        let program = modify_program(parse(input), &mut turn_one_into_two).unwrap();
+
+        assert_eq!(program.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests that the children of an expression are modified before the expression itself, and that
+/// an error from the modifier stops the walk.
+#[test]
+fn test_modify_order_and_errors() {
+    let mut seen = Vec::new();
+
+    modify_program(parse("f(1 + 2, -3)"), &mut |expression: Expression| {
+        seen.push(expression.to_string());
+        Ok::<_, ()>(expression)
+    })
+    .unwrap();
+
+    assert_eq!(
+        seen,
+        vec!["f", "1", "2", "(1 + 2)", "3", "(-3)", "f((1 + 2), (-3))"]
+    );
+
+    let result = modify_program(
+        parse("1 + 2"),
+        &mut |expression: Expression| match expression {
+            Expression::Integer(IntegerLiteral { value: 2, .. }) => Err("found a 2"),
+            expression => Ok(expression),
+        },
+    );
+
+    assert_eq!(result, Err("found a 2"));
+}
@@ -0,0 +1,755 @@
+pub mod tests;
+
+use crate::{
+    ast::Program,
+    dump::{dump_program, dump_tokens, Format},
+    evaluator::modules::normalize,
+    formatter::{self, Config},
+    lexer::Lexer,
+    linter::{self, config::CONFIG_FILE},
+    object::{array::Array, string::Str, Object},
+    optimizer::OptLevel,
+    parser::{Limits, Parser},
+    repl::{self, Engine, EngineKind},
+    symbol::Symbol,
+};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    slice,
+};
+use whoami::username;
+
+/// How to use the `monkey-rs` command, which is printed for `--help` and after usage errors.
+pub const USAGE: &str = "\
+Usage: monkey-rs [options] [command]
+
+Commands:
+  run <file> [args...]      Runs a script, with the arguments after it in `args`
+  eval -e <code> [args...]  Runs code, and prints its value
+  repl                      Starts the REPL (the default)
+  check <file>...           Checks that files parse, without running them
+  fmt [file...]             Formats files in place, or code from stdin to stdout
+  lint <file>...            Lints files, with the rules turned on in monkey.toml
+
+Options:
+  --engine <eval|vm>        The engine to run code on (default: eval)
+  --opt-level <0|1|2>       How much to optimize code before running it (default: 0)
+  --max-parse-depth <n>     How deeply code can be nested when it is parsed (default: 1000)
+  --max-chain <n>           How many operators, calls and indexes can wrap an expression when it is
+                            parsed (default: 5000)
+  --max-depth <n>           How deeply expressions can be nested while they are evaluated, which on
+                            the vm is only while macros are expanded (default: 10000)
+  --max-call-depth <n>      How many function calls can be running at once (default: 1000)
+  --dump-tokens             Prints the tokens of the code instead of running it
+  --dump-ast[=<format>]     Prints the syntax tree of the code instead of running it, as a tree (the
+                            default), an S-expression (sexpr) or JSON (json)
+  --check                   With fmt, lists the files that aren't formatted instead of formatting
+                            them, and fails if there are any
+  --indent <n>              With fmt, the number of spaces to indent by (default: 4)
+  --width <n>               With fmt, the width to keep lines within (default: 80)
+  --config <file>           With lint, the config to use instead of monkey.toml
+  -h, --help                Prints this help
+";
+
+/// The name that the arguments of a script are bound to, as an array of strings.
+pub const ARGS: &str = "args";
+
+/// How running a command went, which is what the process exits with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Status {
+    /// Everything went fine.
+    Success = 0,
+
+    /// The program failed while running, e.g. with an uncaught exception.
+    RuntimeError = 1,
+
+    /// The program couldn't be parsed, so it didn't run at all.
+    ParseError = 2,
+
+    /// A check found something that needs fixing, e.g. a file that `fmt --check` would reformat, or
+    /// anything that `lint` finds.
+    CheckFailed = 3,
+
+    /// The command was used wrong, e.g. with an unknown option.
+    Usage = 64,
+
+    /// A file couldn't be read, or the output couldn't be written.
+    IoError = 74,
+}
+
+impl From<Status> for ExitCode {
+    fn from(status: Status) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
+
+/// A command that `monkey-rs` can run.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Command {
+    /// Runs the script at `path`, with `args` bound to the arguments given after it.
+    Run { path: PathBuf, args: Vec<String> },
+
+    /// Runs `code` and prints its value, with `args` bound to the arguments given after it.
+    Eval { code: String, args: Vec<String> },
+
+    /// Starts the REPL.
+    #[default]
+    Repl,
+
+    /// Parses the files at `paths`, without running them.
+    Check { paths: Vec<PathBuf> },
+
+    /// Formats the files at `paths` in place as `config` says, or only reports the ones that aren't
+    /// formatted if `check` is set. Without any paths, code is formatted from stdin to stdout.
+    Fmt {
+        paths: Vec<PathBuf>,
+        check: bool,
+        config: Config,
+    },
+
+    /// Lints the files at `paths`, with the rules turned on in the config at `config`, or in
+    /// `monkey.toml` in the current directory if there is one.
+    Lint {
+        paths: Vec<PathBuf>,
+        config: Option<PathBuf>,
+    },
+
+    /// Prints how to use `monkey-rs`.
+    Help,
+}
+
+/// What to print instead of running code, to see how it is lexed or parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dump {
+    /// The tokens that the code is lexed into.
+    Tokens,
+
+    /// The syntax tree that the code is parsed into, in the given format.
+    Ast(Format),
+}
+
+/// The command that `monkey-rs` was asked to run, and how to run it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Options {
+    /// The command to run.
+    pub command: Command,
+
+    /// The kind of engine to run code on.
+    pub engine: EngineKind,
+
+    /// How much to optimize code before running it.
+    pub opt_level: OptLevel,
+
+    /// What to print instead of running the code of `run`, `eval` or `check`, if anything.
+    pub dump: Option<Dump>,
+
+    /// The limits that code is parsed with.
+    pub parse_limits: Limits,
+
+    /// The deepest that expressions can be nested while they are evaluated, if not the engine's
+    /// default.
+    pub max_depth: Option<usize>,
+
+    /// The most function calls that can be running at once, if not the engine's default.
+    pub max_call_depth: Option<usize>,
+}
+
+impl Options {
+    /// Parses the command line arguments of `monkey-rs`, not counting the name of the program itself.
+    ///
+    /// Options can come anywhere before the script (or code) that is run, and everything after it is
+    /// an argument to the script, even if it looks like an option. Without a command, the REPL is
+    /// started.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut options = Options::default();
+        let mut command = None;
+        let mut code = None;
+        let mut paths = Vec::new();
+        let mut check = false;
+        let mut config = Config::default();
+        let mut lint_config = None;
+
+        // The options of `fmt` and `lint` can come anywhere too, but only go with that command.
+        let mut fmt_option = None;
+        let mut lint_option = None;
+
+        while let Some(arg) = args.next() {
+            if arg.starts_with('-') && arg != "-" {
+                let (flag, inline) = match arg.split_once('=') {
+                    Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                    None => (arg, None),
+                };
+
+                let mut value = || {
+                    inline
+                        .clone()
+                        .or_else(|| args.next())
+                        .ok_or_else(|| format!("missing value for {}", flag))
+                };
+
+                match flag.as_str() {
+                    "--engine" => options.engine = value()?.parse()?,
+                    "--opt-level" => options.opt_level = value()?.parse()?,
+                    "--max-parse-depth" => {
+                        options.parse_limits.max_depth = number(&flag, value()?)?
+                    }
+                    "--max-chain" => options.parse_limits.max_chain = number(&flag, value()?)?,
+                    "--max-depth" => options.max_depth = Some(number(&flag, value()?)?),
+                    "--max-call-depth" => options.max_call_depth = Some(number(&flag, value()?)?),
+                    "--dump-tokens" if inline.is_none() => options.dump = Some(Dump::Tokens),
+                    "--dump-tokens" => return Err(format!("unexpected value for {}", flag)),
+                    "--dump-ast" => {
+                        // The format is optional, so it can only be given with an `=`.
+                        let format = inline
+                            .as_deref()
+                            .map_or(Ok(Format::default()), str::parse)?;
+                        options.dump = Some(Dump::Ast(format));
+                    }
+                    "-e" if command == Some("eval") => code = Some(value()?),
+                    "--check" if inline.is_none() => {
+                        check = true;
+                        fmt_option.get_or_insert(flag);
+                    }
+                    "--check" => return Err(format!("unexpected value for {}", flag)),
+                    "--indent" => {
+                        config.indent = number(&flag, value()?)?;
+                        fmt_option.get_or_insert(flag);
+                    }
+                    "--width" => {
+                        config.width = number(&flag, value()?)?;
+                        fmt_option.get_or_insert(flag);
+                    }
+                    "--config" => {
+                        lint_config = Some(PathBuf::from(value()?));
+                        lint_option.get_or_insert(flag);
+                    }
+                    "-h" | "--help" => command = Some("help"),
+                    _ => return Err(format!("unknown option: {}", flag)),
+                }
+
+                // The code to evaluate is the last thing before its arguments.
+                if code.is_some() {
+                    break;
+                }
+
+                continue;
+            }
+
+            match command {
+                None => {
+                    command = Some(match arg.as_str() {
+                        "run" => "run",
+                        "eval" => "eval",
+                        "repl" => "repl",
+                        "check" => "check",
+                        "fmt" => "fmt",
+                        "lint" => "lint",
+                        _ => return Err(format!("unknown command: {}", arg)),
+                    })
+                }
+
+                // The script is the last thing before its arguments.
+                Some("run") => {
+                    paths.push(PathBuf::from(arg));
+                    break;
+                }
+
+                Some("check" | "fmt" | "lint") => paths.push(PathBuf::from(arg)),
+
+                Some("eval") => return Err("expected -e <code> before any arguments".to_string()),
+
+                Some(_) => return Err(format!("unexpected argument: {}", arg)),
+            }
+        }
+
+        let args = args.collect::<Vec<_>>();
+
+        options.command = match command {
+            Some("help") => Command::Help,
+
+            Some("run") => Command::Run {
+                path: paths.pop().ok_or("expected a file to run")?,
+                args,
+            },
+
+            Some("eval") => Command::Eval {
+                code: code.ok_or("expected -e <code>")?,
+                args,
+            },
+
+            Some("check") if paths.is_empty() => return Err("expected a file to check".to_string()),
+            Some("check") => Command::Check { paths },
+
+            Some("fmt") => Command::Fmt {
+                paths,
+                check,
+                config,
+            },
+
+            Some("lint") if paths.is_empty() => return Err("expected a file to lint".to_string()),
+            Some("lint") => Command::Lint {
+                paths,
+                config: lint_config,
+            },
+
+            _ => Command::Repl,
+        };
+
+        if let Some(flag) = fmt_option.filter(|_| !matches!(options.command, Command::Fmt { .. })) {
+            return Err(format!("{} can only be used with fmt", flag));
+        }
+
+        if let Some(flag) = lint_option.filter(|_| !matches!(options.command, Command::Lint { .. }))
+        {
+            return Err(format!("{} can only be used with lint", flag));
+        }
+
+        if options.dump.is_some() {
+            match options.command {
+                Command::Repl => {
+                    return Err(
+                        "--dump-tokens and --dump-ast need a file or code to dump".to_string()
+                    )
+                }
+                Command::Fmt { .. } => {
+                    return Err("--dump-tokens and --dump-ast can't be used with fmt".to_string())
+                }
+                Command::Lint { .. } => {
+                    return Err("--dump-tokens and --dump-ast can't be used with lint".to_string())
+                }
+                _ => {}
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Parses the `value` of `flag` as a number.
+fn number(flag: &str, value: String) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("expected a number for {}, got {:?}", flag, value))
+}
+
+/// Runs the command in `options`, reading input (for the REPL) from `inp`, writing output to `out` and
+/// errors to `err`, and returns how it went.
+pub fn execute<I: Read, O: Write, E: Write>(
+    options: Options,
+    inp: I,
+    mut out: O,
+    mut err: E,
+) -> Status {
+    let limits = options.parse_limits;
+
+    let result = match (&options.command, options.dump) {
+        (Command::Run { path, .. }, Some(dump)) => {
+            dump_files(dump, slice::from_ref(path), limits, &mut out, &mut err)
+        }
+
+        (Command::Eval { code, .. }, Some(dump)) => {
+            dump_source(dump, code, "<eval>", limits, &mut out, &mut err)
+        }
+
+        (Command::Check { paths }, Some(dump)) => {
+            dump_files(dump, paths, limits, &mut out, &mut err)
+        }
+
+        (Command::Run { path, args }, None) => run_file(&options, path, args, &mut err),
+
+        (Command::Eval { code, args }, None) => {
+            run_code(&options, code, "<eval>", None, args, &mut err).and_then(|(status, value)| {
+                if let Some(value) = value {
+                    writeln!(out, "{}", value)?;
+                }
+
+                Ok(status)
+            })
+        }
+
+        (Command::Repl, _) => start_repl(&options, inp, &mut out),
+
+        (Command::Check { paths }, None) => check(paths, limits, &mut err),
+
+        (
+            Command::Fmt {
+                paths,
+                check,
+                config,
+            },
+            _,
+        ) => fmt(paths, *check, config, inp, &mut out, &mut err),
+
+        (Command::Lint { paths, config }, _) => {
+            lint(paths, config.as_deref(), limits, &mut out, &mut err)
+        }
+
+        (Command::Help, _) => out.write_all(USAGE.as_bytes()).map(|_| Status::Success),
+    };
+
+    result.unwrap_or_else(|error| {
+        // There might be nowhere left to report the error to, in which case there is nothing to do.
+        let _ = writeln!(err, "error: {}", error);
+        Status::IoError
+    })
+}
+
+/// Runs the script at `path`, reporting errors to `err`.
+fn run_file<E: Write>(
+    options: &Options,
+    path: &Path,
+    args: &[String],
+    err: &mut E,
+) -> io::Result<Status> {
+    let Some(source) = read(path, err)? else {
+        return Ok(Status::IoError);
+    };
+
+    let name = path.display().to_string();
+    let (status, _) = run_code(options, &source, &name, Some(path), args, err)?;
+
+    Ok(status)
+}
+
+/// Runs `source` on a new engine, with `args` bound to `ARGS`, and returns its value. Errors and
+/// warnings are reported to `err`, as being in `name` (or in the imported module that an error
+/// happened in). Relative imports are resolved against `file`, if the source is from one.
+fn run_code<E: Write>(
+    options: &Options,
+    source: &str,
+    name: &str,
+    file: Option<&Path>,
+    args: &[String],
+    err: &mut E,
+) -> io::Result<(Status, Option<Object>)> {
+    let Some(program) = parse(source, name, options.parse_limits, err)? else {
+        return Ok((Status::ParseError, None));
+    };
+
+    let mut engine = engine(options, args);
+
+    if let Some(file) = file {
+        engine.set_file(file);
+    }
+
+    let result = engine.run(program);
+
+    for warning in engine.warnings() {
+        writeln!(err, "{}: warning: {}", name, warning)?;
+    }
+
+    match result {
+        Ok(value) => Ok((Status::Success, value)),
+        Err(error) => {
+            // Errors in imported modules are reported as being in the module that they happened in.
+            match &error.file {
+                Some(module) if file.map(normalize).as_deref() != Some(module) => {
+                    writeln!(err, "{}: error: {}", module.display(), error)?
+                }
+                _ => writeln!(err, "{}: error: {}", name, error)?,
+            }
+
+            Ok((Status::RuntimeError, None))
+        }
+    }
+}
+
+/// Greets the user, and starts the REPL.
+fn start_repl<I: Read, O: Write>(options: &Options, inp: I, out: &mut O) -> io::Result<Status> {
+    writeln!(
+        out,
+        "Hello, {}! This is the Monkey programming language!",
+        username()
+    )?;
+    writeln!(out, "Feel free to type in commands")?;
+
+    repl::start(engine(options, &[]), inp, out)?;
+
+    Ok(Status::Success)
+}
+
+/// Parses the files at `paths` with `limits`, reporting their errors and warnings to `err`.
+fn check<E: Write>(paths: &[PathBuf], limits: Limits, err: &mut E) -> io::Result<Status> {
+    let mut status = Status::Success;
+
+    for path in paths {
+        let Some(source) = read(path, err)? else {
+            status = Status::IoError;
+            continue;
+        };
+
+        if parse(&source, &path.display().to_string(), limits, err)?.is_none()
+            && status == Status::Success
+        {
+            status = Status::ParseError;
+        }
+    }
+
+    Ok(status)
+}
+
+/// Formats the files at `paths` in place, or only lists the ones that aren't formatted to `out` if
+/// `check` is set. Without any paths, the code from `inp` is formatted to `out` instead. Errors are
+/// reported to `err`, and files with errors are left as they are.
+fn fmt<I: Read, O: Write, E: Write>(
+    paths: &[PathBuf],
+    check: bool,
+    config: &Config,
+    mut inp: I,
+    out: &mut O,
+    err: &mut E,
+) -> io::Result<Status> {
+    if paths.is_empty() {
+        let mut source = String::new();
+        inp.read_to_string(&mut source)?;
+
+        let Some(formatted) = format_source(&source, "<stdin>", config, err)? else {
+            return Ok(Status::ParseError);
+        };
+
+        if !check {
+            out.write_all(formatted.as_bytes())?;
+        } else if formatted != source {
+            writeln!(out, "would reformat <stdin>")?;
+            return Ok(Status::CheckFailed);
+        }
+
+        return Ok(Status::Success);
+    }
+
+    let mut status = Status::Success;
+
+    for path in paths {
+        let Some(source) = read(path, err)? else {
+            status = Status::IoError;
+            continue;
+        };
+
+        let formatted = match format_source(&source, &path.display().to_string(), config, err)? {
+            Some(formatted) if formatted == source => continue,
+            Some(formatted) => formatted,
+            None => {
+                if status == Status::Success {
+                    status = Status::ParseError;
+                }
+
+                continue;
+            }
+        };
+
+        if check {
+            writeln!(out, "would reformat {}", path.display())?;
+
+            if status == Status::Success {
+                status = Status::CheckFailed;
+            }
+        } else if let Err(error) = fs::write(path, formatted) {
+            writeln!(err, "error: couldn't write {}: {}", path.display(), error)?;
+            status = Status::IoError;
+        }
+    }
+
+    Ok(status)
+}
+
+/// Formats `source` as `config` says, reporting its errors to `err` as being in `name`. Returns `None`
+/// if there were any errors.
+fn format_source<E: Write>(
+    source: &str,
+    name: &str,
+    config: &Config,
+    err: &mut E,
+) -> io::Result<Option<String>> {
+    match formatter::format(source, config) {
+        Ok(formatted) => Ok(Some(formatted)),
+        Err(errors) => {
+            for error in errors {
+                writeln!(err, "{}: error: {}", name, error)?;
+            }
+
+            Ok(None)
+        }
+    }
+}
+
+/// Lints the files at `paths`, which are parsed with `limits`, listing what is found to `out` and
+/// reporting errors to `err`. The rules are turned on as the config at `config` says, or as
+/// `monkey.toml` in the current directory says if there is one.
+fn lint<O: Write, E: Write>(
+    paths: &[PathBuf],
+    config: Option<&Path>,
+    limits: Limits,
+    out: &mut O,
+    err: &mut E,
+) -> io::Result<Status> {
+    let (config, required) = match config {
+        Some(config) => (config, true),
+        None => (Path::new(CONFIG_FILE), false),
+    };
+
+    let config = if required || config.exists() {
+        let Some(source) = read(config, err)? else {
+            return Ok(Status::IoError);
+        };
+
+        match linter::Config::parse(&source) {
+            Ok(config) => config,
+            Err(error) => {
+                writeln!(err, "{}: error: {}", config.display(), error)?;
+                return Ok(Status::Usage);
+            }
+        }
+    } else {
+        linter::Config::default()
+    };
+
+    let mut status = Status::Success;
+
+    for path in paths {
+        let Some(source) = read(path, err)? else {
+            status = Status::IoError;
+            continue;
+        };
+
+        let Some(program) = parse(&source, &path.display().to_string(), limits, err)? else {
+            if status == Status::Success {
+                status = Status::ParseError;
+            }
+
+            continue;
+        };
+
+        for finding in linter::lint_program(&program, &source, &config) {
+            writeln!(out, "{}:{}", path.display(), finding)?;
+
+            if let Some(fix) = finding.fix {
+                writeln!(out, "  fix: {}", fix.message)?;
+            }
+
+            if status == Status::Success {
+                status = Status::CheckFailed;
+            }
+        }
+    }
+
+    Ok(status)
+}
+
+/// Prints `dump` of the files at `paths` to `out`, reporting errors to `err`. If there is more than
+/// one file, each dump starts with a line with the path of its file.
+fn dump_files<O: Write, E: Write>(
+    dump: Dump,
+    paths: &[PathBuf],
+    limits: Limits,
+    out: &mut O,
+    err: &mut E,
+) -> io::Result<Status> {
+    let mut status = Status::Success;
+
+    for path in paths {
+        let Some(source) = read(path, err)? else {
+            status = Status::IoError;
+            continue;
+        };
+
+        if paths.len() > 1 {
+            writeln!(out, "==> {} <==", path.display())?;
+        }
+
+        let dumped = dump_source(dump, &source, &path.display().to_string(), limits, out, err)?;
+
+        if dumped != Status::Success && status == Status::Success {
+            status = dumped;
+        }
+    }
+
+    Ok(status)
+}
+
+/// Prints `dump` of `source` to `out`, parsing it with `limits`. Parse errors are reported to `err` as
+/// being in `name`, and nothing is printed for source that doesn't parse.
+fn dump_source<O: Write, E: Write>(
+    dump: Dump,
+    source: &str,
+    name: &str,
+    limits: Limits,
+    out: &mut O,
+    err: &mut E,
+) -> io::Result<Status> {
+    match dump {
+        Dump::Tokens => out.write_all(dump_tokens(source).as_bytes())?,
+        Dump::Ast(format) => match parse(source, name, limits, err)? {
+            Some(program) => out.write_all(dump_program(&program, format).as_bytes())?,
+            None => return Ok(Status::ParseError),
+        },
+    }
+
+    Ok(Status::Success)
+}
+
+/// Reads the file at `path`. If it can't be read, that is reported to `err`, and `None` is returned.
+fn read<E: Write>(path: &Path, err: &mut E) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(source) => Ok(Some(source)),
+        Err(error) => {
+            writeln!(err, "error: couldn't read {}: {}", path.display(), error)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Parses `source` with `limits`, reporting its errors and warnings to `err` as being in `name`.
+/// Returns `None` if there were any errors.
+fn parse<E: Write>(
+    source: &str,
+    name: &str,
+    limits: Limits,
+    err: &mut E,
+) -> io::Result<Option<Program>> {
+    let mut parser = Parser::new(Lexer::new(source));
+    parser.set_limits(limits);
+    let parsed = parser.parse_program();
+
+    for warning in parser.warnings() {
+        writeln!(err, "{}: warning: {}", name, warning)?;
+    }
+
+    match parsed {
+        Ok(program) => Ok(Some(program)),
+        Err(errors) => {
+            for error in errors {
+                writeln!(err, "{}: error: {}", name, error)?;
+            }
+
+            Ok(None)
+        }
+    }
+}
+
+/// Returns a new engine to run code on as `options` say, with `args` bound to `ARGS`.
+fn engine(options: &Options, args: &[String]) -> Engine {
+    let mut engine = Engine::new(options.engine);
+    engine.set_opt_level(options.opt_level);
+    engine.set_parse_limits(options.parse_limits);
+
+    if let Some(max_depth) = options.max_depth {
+        engine.set_max_depth(max_depth);
+    }
+
+    if let Some(max_call_depth) = options.max_call_depth {
+        engine.set_max_call_depth(max_call_depth);
+    }
+
+    let args = args
+        .iter()
+        .map(|arg| Object::String(Str::new(arg)))
+        .collect();
+
+    engine.define(Symbol::intern(ARGS), Object::Array(Array::new(args)));
+    engine
+}
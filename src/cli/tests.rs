@@ -0,0 +1,682 @@
+#![cfg(test)]
+
+use super::{execute, Command, Dump, Options, Status};
+use crate::{
+    dump::Format, formatter::Config, optimizer::OptLevel, parser::Limits, repl::EngineKind,
+};
+use std::{
+    env, fs, io,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process, thread,
+};
+
+/// Parses `args`, which are split on spaces
+fn parse(args: &str) -> Result<Options, String> {
+    Options::parse(args.split_whitespace().map(str::to_string))
+}
+
+/// Returns a `Vec<String>` of `args`
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// Tests parsing the command line arguments
+#[test]
+fn test_parse_options() {
+    let run = |path: &str, args: &[&str]| Command::Run {
+        path: PathBuf::from(path),
+        args: strings(args),
+    };
+
+    let tests = vec![
+        ("", Command::Repl),
+        ("repl", Command::Repl),
+        ("run a.monkey", run("a.monkey", &[])),
+        (
+            "run a.monkey x --y -e z",
+            run("a.monkey", &["x", "--y", "-e", "z"]),
+        ),
+        (
+            "eval -e 1 2 3",
+            Command::Eval {
+                code: "1".to_string(),
+                args: strings(&["2", "3"]),
+            },
+        ),
+        (
+            "eval -e=x",
+            Command::Eval {
+                code: "x".to_string(),
+                args: Vec::new(),
+            },
+        ),
+        (
+            "check a.monkey b.monkey",
+            Command::Check {
+                paths: vec![PathBuf::from("a.monkey"), PathBuf::from("b.monkey")],
+            },
+        ),
+        (
+            "fmt",
+            Command::Fmt {
+                paths: Vec::new(),
+                check: false,
+                config: Config::default(),
+            },
+        ),
+        (
+            "fmt --check --indent 2 --width=100 a.monkey",
+            Command::Fmt {
+                paths: vec![PathBuf::from("a.monkey")],
+                check: true,
+                config: Config {
+                    indent: 2,
+                    width: 100,
+                },
+            },
+        ),
+        // The options of fmt can come before the command too.
+        (
+            "--width 60 --check fmt",
+            Command::Fmt {
+                paths: Vec::new(),
+                check: true,
+                config: Config {
+                    width: 60,
+                    ..Config::default()
+                },
+            },
+        ),
+        (
+            "lint a.monkey",
+            Command::Lint {
+                paths: vec![PathBuf::from("a.monkey")],
+                config: None,
+            },
+        ),
+        (
+            "lint --config lint.toml a.monkey b.monkey",
+            Command::Lint {
+                paths: vec![PathBuf::from("a.monkey"), PathBuf::from("b.monkey")],
+                config: Some(PathBuf::from("lint.toml")),
+            },
+        ),
+        ("--help", Command::Help),
+        ("run -h", Command::Help),
+    ];
+
+    for (args, expected) in tests {
+        assert_eq!(
+            parse(args).map(|options| options.command),
+            Ok(expected),
+            "args: {}",
+            args
+        );
+    }
+
+    // Options can come before or after the command.
+    for args in [
+        "--engine vm --opt-level 2 run a.monkey",
+        "run --engine=vm --opt-level=2 a.monkey",
+    ] {
+        let options = parse(args).unwrap();
+
+        assert_eq!(options.engine, EngineKind::Vm, "args: {}", args);
+        assert_eq!(options.opt_level, OptLevel::Full, "args: {}", args);
+    }
+
+    let options =
+        parse("eval --max-parse-depth 10 --max-chain=20 --max-depth 30 --max-call-depth 40 -e 1")
+            .unwrap();
+
+    assert_eq!(
+        options.parse_limits,
+        Limits {
+            max_depth: 10,
+            max_chain: 20
+        }
+    );
+    assert_eq!(options.max_depth, Some(30));
+    assert_eq!(options.max_call_depth, Some(40));
+
+    let options = parse("run a.monkey").unwrap();
+
+    assert_eq!(options.parse_limits, Limits::default());
+    assert_eq!((options.max_depth, options.max_call_depth), (None, None));
+
+    let dumps = vec![
+        ("run a.monkey", None),
+        ("--dump-tokens run a.monkey", Some(Dump::Tokens)),
+        ("--dump-ast check a.monkey", Some(Dump::Ast(Format::Tree))),
+        ("eval --dump-ast=sexpr -e 1", Some(Dump::Ast(Format::Sexpr))),
+        (
+            "--dump-ast=json run a.monkey",
+            Some(Dump::Ast(Format::Json)),
+        ),
+    ];
+
+    for (args, expected) in dumps {
+        assert_eq!(parse(args).unwrap().dump, expected, "args: {}", args);
+    }
+
+    let errors = vec![
+        ("frob", "unknown command: frob"),
+        ("--frob", "unknown option: --frob"),
+        ("-e 1", "unknown option: -e"),
+        ("run", "expected a file to run"),
+        ("check", "expected a file to check"),
+        ("eval", "expected -e <code>"),
+        ("eval x -e 1", "expected -e <code> before any arguments"),
+        ("eval -e", "missing value for -e"),
+        ("repl x", "unexpected argument: x"),
+        ("check --check a", "--check can only be used with fmt"),
+        ("--width 60 run a", "--width can only be used with fmt"),
+        ("fmt --check=yes", "unexpected value for --check"),
+        ("lint", "expected a file to lint"),
+        ("fmt --config x", "--config can only be used with lint"),
+        (
+            "fmt --indent x",
+            "expected a number for --indent, got \"x\"",
+        ),
+        (
+            "--dump-ast fmt a",
+            "--dump-tokens and --dump-ast can't be used with fmt",
+        ),
+        (
+            "lint --dump-tokens a",
+            "--dump-tokens and --dump-ast can't be used with lint",
+        ),
+        (
+            "--dump-tokens=json run a",
+            "unexpected value for --dump-tokens",
+        ),
+        (
+            "--dump-ast=xml run a",
+            "unknown format: \"xml\", expected tree, sexpr or json",
+        ),
+        (
+            "--dump-ast",
+            "--dump-tokens and --dump-ast need a file or code to dump",
+        ),
+        (
+            "--max-chain -1 run a",
+            "expected a number for --max-chain, got \"-1\"",
+        ),
+        (
+            "--engine jit",
+            "unknown engine: \"jit\", expected eval or vm",
+        ),
+    ];
+
+    for (args, expected) in errors {
+        assert_eq!(parse(args), Err(expected.to_string()), "args: {}", args);
+    }
+}
+
+/// Runs the command in `args`, and returns its status, output and errors
+fn execute_args(args: &[&str]) -> (Status, String, String) {
+    let options = Options::parse(strings(args)).unwrap();
+    let mut out = Vec::new();
+    let mut err = Vec::new();
+
+    let status = execute(options, io::empty(), &mut out, &mut err);
+
+    (
+        status,
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+    )
+}
+
+/// A script file in a temporary directory of its own, which is removed along with everything in it
+/// when the script is dropped, whether or not the test passes
+struct Script {
+    path: PathBuf,
+}
+
+impl Deref for Script {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for Script {
+    fn drop(&mut self) {
+        if let Some(directory) = self.path.parent() {
+            fs::remove_dir_all(directory).ok();
+        }
+    }
+}
+
+/// Writes `source` to a file in a temporary directory of its own, and returns it
+fn script(name: &str, source: &str) -> Script {
+    let directory = env::temp_dir().join(format!("monkey-cli-{}-{}", process::id(), name));
+    fs::create_dir_all(&directory).unwrap();
+
+    let path = directory.join(format!("{}.monkey", name));
+    fs::write(&path, source).unwrap();
+
+    Script { path }
+}
+
+/// Tests the output and exit status of evaluating code
+#[test]
+fn test_execute_eval() {
+    let tests = vec![
+        (vec!["eval", "-e", "1 + 2"], Status::Success, "3\n", ""),
+        (vec!["eval", "-e", "let x = 1;"], Status::Success, "", ""),
+        (vec!["eval", "-e", "args", "a", "b"], Status::Success, "[a, b]\n", ""),
+        (
+            vec!["--engine", "vm", "eval", "-e", "args", "-x"],
+            Status::Success,
+            "[-x]\n",
+            "",
+        ),
+        (
+            vec!["eval", "-e", "1 +"],
+            Status::ParseError,
+            "",
+            "<eval>: error: 1:4: no prefix parse function for EOF found\n",
+        ),
+        (
+            vec!["eval", "-e", "throw 1"],
+            Status::RuntimeError,
+            "",
+            "<eval>: error: 1:1: 1\n",
+        ),
+        (
+            vec!["--engine=vm", "eval", "-e", "x"],
+            Status::RuntimeError,
+            "",
+            "<eval>: warning: 1:1: identifier not found: x\n<eval>: error: 1:1: identifier not found: x\n",
+        ),
+        // Errors in the prelude's code are reported as being in the prelude.
+        (
+            vec!["eval", "-e", "map(1, fn(x) { x })"],
+            Status::RuntimeError,
+            "",
+            "<prelude>: error: 3:19: cannot iterate over INTEGER\n    in mapped\n    in collect\n    in map\n",
+        ),
+        (
+            vec!["--engine=vm", "eval", "-e", "map(1, fn(x) { x })"],
+            Status::RuntimeError,
+            "",
+            "<prelude>: error: 3:19: cannot iterate over INTEGER\n    in mapped\n    in collect\n    in map\n",
+        ),
+        (
+            vec!["--dump-ast=sexpr", "eval", "-e", "x", "a"],
+            Status::Success,
+            "(ExpressionStatement :expression (Identifier :name \"x\"))\n",
+            "",
+        ),
+        (
+            vec!["--dump-tokens", "eval", "-e", "1 +"],
+            Status::Success,
+            "1:1      INT        \"1\"\n1:3      +          \"+\"\n1:4      EOF        \"\"\n",
+            "",
+        ),
+        (
+            vec!["--dump-ast", "eval", "-e", "1 +"],
+            Status::ParseError,
+            "",
+            "<eval>: error: 1:4: no prefix parse function for EOF found\n",
+        ),
+    ];
+
+    for (args, status, out, err) in tests {
+        assert_eq!(
+            execute_args(&args),
+            (status, out.to_string(), err.to_string()),
+            "args: {:?}",
+            args
+        );
+    }
+}
+
+/// Tests that long chains of operators and indexes are parse errors on both engines, instead of
+/// overflowing the stack in one of the passes after parsing, that flat code well within the limits
+/// still runs, and that the limits can be changed with flags
+#[test]
+fn test_execute_long_chains() {
+    // Like `main`, run on a big stack, which chains as long as the default limit need.
+    thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(execute_long_chains)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+fn execute_long_chains() {
+    let additions = format!("1{}", " + 1".repeat(20_000));
+    let indexes = format!("let x = [0]; x{}", "[0]".repeat(30_000));
+    let strings = format!("\"a\"{}", " + \"a\"".repeat(149));
+    let pipes = format!("let f = fn(x) {{ x + 1 }}; 0{}", " |> f".repeat(1_000));
+
+    for engine in ["eval", "vm"] {
+        for source in [&additions, &indexes] {
+            let (status, out, err) = execute_args(&["--engine", engine, "eval", "-e", source]);
+
+            assert_eq!(
+                (status, out.as_str()),
+                (Status::ParseError, ""),
+                "engine: {}",
+                engine
+            );
+            assert!(
+                err.starts_with("<eval>: error: 1:")
+                    && err.contains(": maximum chain length of 5000 exceeded\n"),
+                "engine: {}, err: {}",
+                engine,
+                err
+            );
+        }
+
+        let tests = vec![
+            (format!("1{}", " + 1".repeat(1_000)), "1001\n".to_string()),
+            (strings.clone(), format!("{}\n", "a".repeat(150))),
+            (pipes.clone(), "1000\n".to_string()),
+        ];
+
+        for (source, expected) in tests {
+            assert_eq!(
+                execute_args(&["--engine", engine, "eval", "-e", &source]),
+                (Status::Success, expected, String::new()),
+                "engine: {}",
+                engine
+            );
+        }
+
+        let tests = vec![
+            (
+                vec!["--max-chain", "3", "eval", "-e", "1 + 1 + 1 + 1 + 1"],
+                "<eval>: error: 1:15: maximum chain length of 3 exceeded\n",
+            ),
+            (
+                vec!["--max-parse-depth", "3", "eval", "-e", "((((1))))"],
+                "<eval>: error: 1:4: maximum nesting depth of 3 exceeded\n",
+            ),
+        ];
+
+        for (args, expected) in tests {
+            let (status, out, err) = execute_args(&[&["--engine", engine][..], &args[..]].concat());
+
+            assert_eq!(
+                (status, out.as_str()),
+                (Status::ParseError, ""),
+                "engine: {}, args: {:?}",
+                engine,
+                args
+            );
+            assert!(
+                err.starts_with(expected),
+                "engine: {}, args: {:?}, err: {}",
+                engine,
+                args,
+                err
+            );
+        }
+
+        assert_eq!(
+            execute_args(&[
+                "--engine",
+                engine,
+                "--max-chain",
+                "30000",
+                "--max-depth",
+                "30000",
+                "eval",
+                "-e",
+                &additions
+            ]),
+            (Status::Success, "20001\n".to_string(), String::new()),
+            "engine: {}",
+            engine
+        );
+    }
+
+    let source = "fn f(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } } f(100)";
+
+    for engine in ["eval", "vm"] {
+        let (status, _, err) = execute_args(&[
+            "--engine",
+            engine,
+            "--max-call-depth",
+            "50",
+            "eval",
+            "-e",
+            source,
+        ]);
+
+        assert_eq!(status, Status::RuntimeError, "engine: {}", engine);
+        assert!(
+            err.contains("maximum call depth of 50 exceeded"),
+            "engine: {}, err: {}",
+            engine,
+            err
+        );
+    }
+}
+
+/// Tests running and checking script files
+#[test]
+fn test_execute_files() {
+    let main = script(
+        "main",
+        "#!/usr/bin/env monkey\nimport \"./lib.monkey\" as lib;\nlib.greet(args[0])",
+    );
+    let lib = main.with_file_name("lib.monkey");
+    fs::write(&lib, "export fn greet(name) { \"hi \" + name }").unwrap();
+
+    let broken = script("broken", "let x = ;");
+    let missing = main.with_file_name("missing.monkey");
+
+    let main = main.to_str().unwrap();
+    let broken = broken.to_str().unwrap();
+    let missing = missing.to_str().unwrap();
+
+    for engine in ["eval", "vm"] {
+        let (status, _, err) = execute_args(&["--engine", engine, "run", main, "bob"]);
+        assert_eq!((status, err.as_str()), (Status::Success, ""));
+
+        let (status, _, err) = execute_args(&["--engine", engine, "run", main]);
+        assert_eq!(status, Status::RuntimeError);
+        assert!(err.contains("type mismatch: STRING + NULL"), "err: {}", err);
+
+        // The error happened in the imported module, so it is reported as being in there.
+        assert!(
+            err.starts_with(&format!("{}: error: 1:", lib.display())),
+            "err: {}",
+            err
+        );
+    }
+
+    assert_eq!(
+        execute_args(&["check", main, lib.to_str().unwrap()]),
+        (Status::Success, String::new(), String::new())
+    );
+
+    let (status, _, err) = execute_args(&["check", main, broken]);
+    assert_eq!(status, Status::ParseError);
+    assert!(
+        err.starts_with(&format!("{}: error: ", broken)),
+        "err: {}",
+        err
+    );
+
+    let (status, _, err) = execute_args(&["run", broken]);
+    assert_eq!(status, Status::ParseError);
+    assert!(
+        err.starts_with(&format!("{}: error: ", broken)),
+        "err: {}",
+        err
+    );
+
+    // Dumping a script doesn't run it, so it doesn't matter that it has no arguments.
+    let (status, out, err) = execute_args(&["--dump-ast", "run", main]);
+    assert_eq!((status, err.as_str()), (Status::Success, ""));
+    assert!(out.starts_with("Program\n"), "out: {}", out);
+
+    let (status, out, _) = execute_args(&["--dump-ast=sexpr", "check", main, broken]);
+    assert_eq!(status, Status::ParseError);
+    assert!(
+        out.starts_with(&format!("==> {} <==\n(ImportStatement", main)),
+        "out: {}",
+        out
+    );
+    assert!(
+        out.ends_with(&format!("==> {} <==\n", broken)),
+        "out: {}",
+        out
+    );
+
+    for command in ["run", "check"] {
+        let (status, _, err) = execute_args(&[command, missing]);
+        assert_eq!(status, Status::IoError);
+        assert!(
+            err.starts_with(&format!("error: couldn't read {}: ", missing)),
+            "err: {}",
+            err
+        );
+    }
+}
+
+/// Tests formatting files in place, checking them, and formatting stdin
+#[test]
+fn test_execute_fmt() {
+    let messy = script("messy", "let x=1\n;fn f(a){a}");
+    let tidy = script("tidy", "let x = 1;\n");
+    let broken = script("broken-fmt", "let x = ;");
+
+    let messy = messy.to_str().unwrap();
+    let tidy = tidy.to_str().unwrap();
+    let broken = broken.to_str().unwrap();
+
+    let (status, out, err) = execute_args(&["fmt", "--check", messy, tidy]);
+    assert_eq!(
+        (status, out, err),
+        (
+            Status::CheckFailed,
+            format!("would reformat {}\n", messy),
+            String::new()
+        )
+    );
+
+    assert_eq!(
+        execute_args(&["fmt", messy, tidy]),
+        (Status::Success, String::new(), String::new())
+    );
+    assert_eq!(
+        fs::read_to_string(messy).unwrap(),
+        "let x = 1;\nfn f(a) { a }\n"
+    );
+
+    assert_eq!(
+        execute_args(&["fmt", "--check", messy, tidy]),
+        (Status::Success, String::new(), String::new())
+    );
+
+    let (status, _, err) = execute_args(&["fmt", broken]);
+    assert_eq!(status, Status::ParseError);
+    assert!(
+        err.starts_with(&format!("{}: error: ", broken)),
+        "err: {}",
+        err
+    );
+    assert_eq!(fs::read_to_string(broken).unwrap(), "let x = ;");
+
+    let stdin = |args: &[&str], input: &str| {
+        let options = Options::parse(strings(args)).unwrap();
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+
+        let status = execute(options, input.as_bytes(), &mut out, &mut err);
+
+        (status, String::from_utf8(out).unwrap())
+    };
+
+    assert_eq!(
+        stdin(&["fmt", "--indent", "2"], "fn f(){let x=1;x}"),
+        (
+            Status::Success,
+            "fn f() {\n  let x = 1;\n  x\n}\n".to_string()
+        )
+    );
+    assert_eq!(
+        stdin(&["fmt", "--check"], "x"),
+        (Status::CheckFailed, "would reformat <stdin>\n".to_string())
+    );
+    assert_eq!(
+        stdin(&["fmt", "--check"], "x;\n"),
+        (Status::Success, String::new())
+    );
+}
+
+/// Tests the output and exit status of linting files, with and without a config
+#[test]
+fn test_execute_lint() {
+    let messy = script("messy-lint", "let x = 1;\nfn f(a) { !!a }\nputs(f(2));\n");
+    let tidy = script("tidy-lint", "let x = 1;\nputs(x);\n");
+    let broken = script("broken-lint", "let x = ;");
+    let config = script("config-lint", "[lint]\ndouble-negation = false\n");
+    let bad_config = script("bad-config-lint", "[lint]\nfrob = true\n");
+
+    let messy = messy.to_str().unwrap();
+    let tidy = tidy.to_str().unwrap();
+    let broken = broken.to_str().unwrap();
+    let config = config.to_str().unwrap();
+    let bad_config = bad_config.to_str().unwrap();
+
+    assert_eq!(
+        execute_args(&["lint", messy, tidy]),
+        (
+            Status::CheckFailed,
+            format!(
+                "{0}:1:5: x is never used [unused-variable]\n  \
+                 fix: rename it to _x if it is meant to be unused\n\
+                 {0}:2:11: double negation [double-negation]\n  \
+                 fix: replace it with a if that is already a boolean\n",
+                messy
+            ),
+            String::new()
+        )
+    );
+
+    assert_eq!(
+        execute_args(&["lint", "--config", config, messy]),
+        (
+            Status::CheckFailed,
+            format!(
+                "{}:1:5: x is never used [unused-variable]\n  \
+                 fix: rename it to _x if it is meant to be unused\n",
+                messy
+            ),
+            String::new()
+        )
+    );
+
+    assert_eq!(
+        execute_args(&["lint", tidy]),
+        (Status::Success, String::new(), String::new())
+    );
+
+    let (status, _, err) = execute_args(&["lint", broken]);
+    assert_eq!(status, Status::ParseError);
+    assert!(
+        err.starts_with(&format!("{}: error: ", broken)),
+        "err: {}",
+        err
+    );
+
+    assert_eq!(
+        execute_args(&["lint", "--config", bad_config, tidy]),
+        (
+            Status::Usage,
+            String::new(),
+            format!("{}: error: line 2: unknown rule: \"frob\"\n", bad_config)
+        )
+    );
+}
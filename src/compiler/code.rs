@@ -0,0 +1,362 @@
+use crate::{
+    ast::{
+        expressions::{Expression, FunctionLiteral, Identifier},
+        operators::{InfixOperatorType, PrefixOperatorType},
+    },
+    evaluator::errors::Error,
+    object::Object,
+    symbol::Symbol,
+    token::Position,
+};
+use std::{fmt::Display, rc::Rc};
+
+/// A single instruction of bytecode. Operands are kept inline, and refer to the constant pool of the
+/// `CompiledFunction` that the instruction is in by index. Jump targets are indexes into the function's
+/// instructions.
+///
+/// Instructions work on the value stack of the frame that they run in. Unless said otherwise, they pop
+/// their operands off of the stack, and push their result onto it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// Pushes the object constant at the index.
+    Constant(usize),
+
+    /// Pushes `null`.
+    Null,
+
+    /// Pushes `true`.
+    True,
+
+    /// Pushes `false`.
+    False,
+
+    /// Pops the top value, throwing it away.
+    Pop,
+
+    /// Pushes a copy of the top value.
+    Dup,
+
+    /// Pops the value just below the top one, leaving the top one where it is.
+    Nip,
+
+    /// Pushes the value of local `index` of the frame. If the local hasn't been bound yet, the variable
+    /// is looked up as the fallback constant at `fallback` says instead.
+    GetLocal { index: usize, fallback: usize },
+
+    /// Pushes the value of the variable `index` that the closure being run captured. If it hasn't been
+    /// bound yet, it is looked up like an unbound `GetLocal`.
+    GetFree { index: usize, fallback: usize },
+
+    /// Pops the top value, binding local `index` of the frame to it.
+    SetLocal(usize),
+
+    /// Jumps to the target if local `index` of the frame has already been bound, without touching the
+    /// stack. This is for skipping the default values of parameters that were given.
+    JumpIfBound { index: usize, target: usize },
+
+    /// Pushes the value of the global with the name constant at the index, looking it up in the global
+    /// environment (and the prelude), and then in the builtins.
+    GetGlobal(usize),
+
+    /// Pops the top value, binding the global with the name constant at the index to it.
+    SetGlobal(usize),
+
+    /// Applies a prefix operator.
+    Prefix(PrefixOperatorType),
+
+    /// Applies an infix operator, with the right operand on top of the left one.
+    Infix(InfixOperatorType),
+
+    /// Jumps to the target.
+    Jump(usize),
+
+    /// Pops the top value, jumping to the target if it is falsy.
+    JumpIfFalse(usize),
+
+    /// Pops the given number of values into an array, the first of them being the deepest.
+    List(usize),
+
+    /// Appends the top value to the array below it.
+    Append,
+
+    /// Appends the elements of the array on top to the array below it, erroring if the top value isn't
+    /// an array. This is how spreads are evaluated.
+    Extend,
+
+    /// Counts the array on top against the memory budget, leaving it where it is. This is done once an
+    /// array literal has been built.
+    Allocate,
+
+    /// Errors if the top value can't be used as a hash key, leaving it where it is.
+    HashKey,
+
+    /// Pops the given number of key-value pairs into a hash, the first pair being the deepest.
+    Hash(usize),
+
+    /// Indexes the value below the top one with the top one.
+    Index,
+
+    /// Looks up the member with the identifier constant at the index on the top value.
+    Member(usize),
+
+    /// Creates a closure of the function constant at the index, capturing the variables that the
+    /// function uses from the functions that it is in (see `CompiledFunction::captures`).
+    Closure(usize),
+
+    /// Calls the function below its arguments. If `spread` is set, the arguments are in an array on
+    /// top of the function instead of being on the stack themselves.
+    Call { arguments: usize, spread: bool },
+
+    /// Calls a function like `Call`, but in place of the current function, as it is in tail position.
+    TailCall { arguments: usize, spread: bool },
+
+    /// Leaves the current frame, returning the top value. Any `finally` blocks that are being returned
+    /// through are run first.
+    Return,
+
+    /// Leaves the current frame, which is a program, without a value.
+    End,
+
+    /// Turns the current call to a generator function, whose arguments have just been bound, into a
+    /// generator, and returns it. The generator carries on from the next instruction once resumed.
+    Generate,
+
+    /// Pauses the current generator, handing out the top value.
+    Yield,
+
+    /// Raises the top value as an error.
+    Throw,
+
+    /// Raises the error constant at the index. This is for code that the compiler already knows is
+    /// wrong, but which is only an error if it is run.
+    Fail(usize),
+
+    /// Starts a `try`. Errors are caught by the `catch` target, and the `finally` target is run on the
+    /// way out of the `try`, however it is left.
+    Try {
+        catch: Option<usize>,
+        finally: Option<usize>,
+    },
+
+    /// Leaves the innermost `try` normally.
+    EndTry,
+
+    /// Enters a `finally` block after the `try` was left normally.
+    EnterFinally,
+
+    /// Carries on however the `try` was left once its `finally` block is done, by carrying on with the
+    /// next instruction, raising the error that was pending, or carrying on with the `return`.
+    EndFinally,
+
+    /// Unbinds the locals from `start` up to `end`, which are the slots of a scope that is being entered
+    /// (and of the scopes inside of it), so that each time the scope is entered, its variables are new
+    /// ones. Any closures that captured the old ones keep them.
+    EnterScope { start: usize, end: usize },
+
+    /// Turns the top value into an iterator, erroring if it can't be iterated over.
+    Iterate,
+
+    /// Pushes the next value of the iterator on top, or pops the iterator and jumps to the target if
+    /// it is done.
+    Next(usize),
+
+    /// Checks that the top value is an array with `elements` elements, or at least that many if the
+    /// pattern has a `rest`, leaving it where it is. If it isn't, either the value is popped and the
+    /// jump is made to `fail`, or there isn't a `fail` target and it is an error.
+    DestructureArray {
+        elements: usize,
+        rest: bool,
+        fail: Option<usize>,
+    },
+
+    /// Pushes the element at the index of the array on top, leaving the array where it is.
+    Element(usize),
+
+    /// Pushes an array of the elements from the index onwards of the array on top, leaving the array
+    /// where it is.
+    Rest(usize),
+
+    /// Checks that the top value is a hash, leaving it where it is. If it isn't, it is handled like a
+    /// failed `DestructureArray`.
+    DestructureHash { fail: Option<usize> },
+
+    /// Pushes the value of the key on top in the hash below it, leaving the hash where it is. If the
+    /// hash doesn't have the key, either the key is popped and the jump is made to `fail`, or it is an
+    /// error, which shows the key with the expression constant at `key`.
+    Key { key: usize, fail: Option<usize> },
+
+    /// Pops the top value (a pattern's literal) and the value below it, checking that they are equal.
+    /// If they aren't, either the jump is made to `fail`, or it is an error, which shows the literal with
+    /// the expression constant at `literal`.
+    Literal { literal: usize, fail: Option<usize> },
+
+    /// Errors that no arm of a `match` matches the value on top.
+    NoMatch,
+
+    /// Errors if the top value isn't a struct, leaving it where it is. The record literal's name is the
+    /// identifier constant at the index.
+    Struct(usize),
+
+    /// Errors if the struct `offset` values below the top one has no field with the identifier
+    /// constant at `field`.
+    Field { field: usize, offset: usize },
+
+    /// Pops the values of a record literal's fields, whose names are the names constant at the index,
+    /// and the struct below them, into a record.
+    Record(usize),
+
+    /// Imports the module at the path constant at the index, pushing its module object.
+    Import(usize),
+
+    /// Replaces the calls to `unquote` in the expression constant at `quote` with the given number of
+    /// values, the first of them being the deepest, and pushes the quote.
+    Quote { quote: usize, unquotes: usize },
+}
+
+/// A constant in the constant pool of a `CompiledFunction`.
+#[derive(Debug, Clone)]
+pub enum Constant {
+    /// A value, e.g. an integer, string or struct.
+    Object(Object),
+
+    /// A name, e.g. of a global.
+    Name(Symbol),
+
+    /// An identifier, for errors that point at it.
+    Identifier(Identifier),
+
+    /// The names of the fields of a record literal, in the order that they were written in.
+    Names(Vec<Symbol>),
+
+    /// An expression, e.g. the argument of a `quote`.
+    Expression(Expression),
+
+    /// An error, for `Op::Fail`.
+    Error(Error),
+
+    /// A function, for `Op::Closure`.
+    Function(Rc<CompiledFunction>),
+
+    /// What to look a variable up as if its slot hasn't been bound yet, for `Op::GetLocal` and
+    /// `Op::GetFree`.
+    Fallback(Fallback),
+}
+
+/// Where a variable is kept, in the frame that uses it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variable {
+    /// A local of the frame, by index.
+    Local(usize),
+
+    /// A variable that the closure being run captured, by index.
+    Free(usize),
+}
+
+/// What a variable is looked up as when its slot hasn't been bound yet, which is the same as the
+/// evaluator does (see `Environment::lookup`). A name can have a slot in more than one of the scopes
+/// around its use, and until the innermost of them is bound, the name refers to the next one out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fallback {
+    /// The other slots that the name has, from the innermost to the outermost.
+    pub variables: Vec<Variable>,
+
+    /// The name, which is looked up as a global when none of its slots are bound.
+    pub name: Symbol,
+}
+
+/// A function (or program) that has been compiled into bytecode.
+#[derive(Debug, Clone)]
+pub struct CompiledFunction {
+    /// The literal that the function was compiled from, or `None` for a program.
+    pub literal: Option<Rc<FunctionLiteral>>,
+
+    /// The function's instructions.
+    pub instructions: Vec<Op>,
+
+    /// The position in the source code of each instruction, for the errors that it raises.
+    pub positions: Vec<Option<Position>>,
+
+    /// The constants that the instructions refer to.
+    pub constants: Vec<Constant>,
+
+    /// How many locals a frame of the function has. The first of them are its parameters.
+    pub locals: usize,
+
+    /// The variables that a closure of the function captures from the frame that creates it. The
+    /// function refers to them as `Variable::Free`, by their index in here.
+    pub captures: Vec<Variable>,
+}
+
+impl CompiledFunction {
+    /// Returns the name of the function, or `<anonymous>` if it does not have one.
+    pub fn name(&self) -> &str {
+        self.literal
+            .as_ref()
+            .and_then(|literal| literal.name.as_deref())
+            .unwrap_or("<anonymous>")
+    }
+
+    /// Returns the object constant at `index`.
+    pub(crate) fn object(&self, index: usize) -> &Object {
+        match &self.constants[index] {
+            Constant::Object(object) => object,
+            constant => unreachable!("constant {} is not an object: {:?}", index, constant),
+        }
+    }
+}
+
+/// Generates accessors for the other kinds of constant, like `CompiledFunction::object`.
+macro_rules! constant_accessors {
+    ($($name:ident: $variant:ident -> $type:ty),* $(,)?) => {
+        impl CompiledFunction {
+            $(
+                #[doc = concat!("Returns the ", stringify!($name), " constant at `index`.")]
+                pub(crate) fn $name(&self, index: usize) -> &$type {
+                    match &self.constants[index] {
+                        Constant::$variant(value) => value,
+                        constant => unreachable!(
+                            "constant {} is not a {}: {:?}",
+                            index,
+                            stringify!($name),
+                            constant
+                        ),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+constant_accessors! {
+    symbol: Name -> Symbol,
+    identifier: Identifier -> Identifier,
+    names: Names -> Vec<Symbol>,
+    expression: Expression -> Expression,
+    error: Error -> Error,
+    function: Function -> Rc<CompiledFunction>,
+    fallback: Fallback -> Fallback,
+}
+
+impl Display for CompiledFunction {
+    /// Disassembles the function, listing its instructions, followed by those of the functions that it
+    /// creates closures of.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}:",
+            self.literal.as_ref().map_or("<program>", |_| self.name())
+        )?;
+
+        for (index, op) in self.instructions.iter().enumerate() {
+            writeln!(f, "{:04} {:?}", index, op)?;
+        }
+
+        for constant in &self.constants {
+            if let Constant::Function(function) = constant {
+                write!(f, "\n{}", function)?;
+            }
+        }
+
+        Ok(())
+    }
+}
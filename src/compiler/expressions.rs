@@ -0,0 +1,453 @@
+use super::{
+    code::{Constant, Op},
+    Compiler, FunctionState,
+};
+use crate::{
+    ast::{
+        expressions::{
+            CallExpression, Expression, ForExpression, FunctionLiteral, IfExpression, Parameter,
+            RecordLiteral, TryExpression,
+        },
+        modify::modify_expression,
+        operators::{InfixOperator, PrefixOperator},
+    },
+    evaluator::{
+        errors::{Error, ErrorKind},
+        expressions::is_quote,
+        macros::{is_unquote, unexpected_macro},
+    },
+    object::{integer::Integer, string::Str, Object},
+    resolver::{declare, declare_expression, declare_statements},
+};
+use std::{convert::Infallible, mem, rc::Rc};
+
+impl Compiler {
+    /// Compiles an `Expression`, which leaves its value on the stack. The instructions emitted for it
+    /// raise their errors at its position, unless they are for an expression nested inside of it.
+    pub(crate) fn compile_expression(&mut self, expression: &Expression) {
+        self.at(Some(expression.position()), |compiler| {
+            compiler.compile_expression_inner(expression)
+        });
+    }
+
+    /// Compiles an `Expression` that is in tail position, like `Evaluator::eval_tail_expression`. A call
+    /// is made as a tail call, and the branches of `if` and `match` expressions are in tail position too.
+    pub(crate) fn compile_tail_expression(&mut self, expression: &Expression) {
+        self.at(Some(expression.position()), |compiler| match expression {
+            Expression::Call(call) if !is_quote(call) => compiler.compile_call(call, true),
+            Expression::If(if_expression) => compiler.compile_if(if_expression, true, false),
+            Expression::Match(match_expression) => {
+                compiler.compile_match(match_expression, true, false)
+            }
+            expression => compiler.compile_expression_inner(expression),
+        });
+    }
+
+    /// Compiles an `Expression`, at the current position.
+    fn compile_expression_inner(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Integer(integer) => {
+                let integer = self.constant(Constant::Object(Object::Integer(Integer::new(
+                    integer.value,
+                ))));
+                self.emit(Op::Constant(integer));
+            }
+
+            Expression::Boolean(boolean) => {
+                self.emit(if boolean.value { Op::True } else { Op::False });
+            }
+
+            Expression::String(string) => {
+                let string = self.constant(Constant::Object(Object::String(Str::new(string.value.clone()))));
+                self.emit(Op::Constant(string));
+            }
+
+            Expression::Identifier(identifier) => self.emit_get(identifier.value.clone()),
+
+            Expression::Prefix(prefix) => {
+                let PrefixOperator {
+                    r#type: operator, ..
+                } = prefix.operator;
+
+                self.compile_expression(&prefix.right);
+                self.emit(Op::Prefix(operator));
+            }
+
+            Expression::Infix(infix) => {
+                let InfixOperator {
+                    r#type: operator, ..
+                } = infix.operator;
+
+                self.compile_expression(&infix.left);
+                self.compile_expression(&infix.right);
+                self.emit(Op::Infix(operator));
+            }
+
+            Expression::If(if_expression) => self.compile_if(if_expression, false, false),
+
+            Expression::Try(try_expression) => self.compile_try(try_expression, false),
+
+            Expression::For(for_expression) => self.compile_for(for_expression, false),
+
+            Expression::Function(function) => self.compile_function(function),
+
+            Expression::Call(call) if is_quote(call) => self.compile_quote(call),
+
+            Expression::Call(call) => self.compile_call(call, false),
+
+            Expression::Array(array) => {
+                if let Some(elements) = self.compile_list(&array.elements) {
+                    self.emit(Op::List(elements));
+                }
+
+                self.emit(Op::Allocate);
+            }
+
+            Expression::Index(index) => {
+                self.compile_expression(&index.left);
+                self.compile_expression(&index.index);
+                self.emit(Op::Index);
+            }
+
+            Expression::Member(member) => {
+                self.compile_expression(&member.object);
+
+                let member = self.constant(Constant::Identifier(member.member.clone()));
+                self.emit(Op::Member(member));
+            }
+
+            Expression::Hash(hash) => {
+                for (key, value) in &hash.pairs {
+                    self.compile_expression(key);
+                    self.at(Some(key.position()), |compiler| compiler.emit(Op::HashKey));
+                    self.compile_expression(value);
+                }
+
+                self.emit(Op::Hash(hash.pairs.len()));
+            }
+
+            Expression::Record(record) => self.compile_record(record),
+
+            Expression::Match(match_expression) => {
+                self.compile_match(match_expression, false, false)
+            }
+
+            Expression::Macro(macro_literal) => self.emit_fail(unexpected_macro(macro_literal)),
+
+            // Spreads are handled by `compile_list`, as they evaluate to multiple values, so they can't
+            // be compiled on their own.
+            Expression::Spread(spread) => self.emit_fail(Error::new(
+                ErrorKind::Syntax,
+                format!(
+                    "unexpected spread: {}, spreads are only allowed in call arguments and array literals",
+                    spread
+                ),
+            )),
+        }
+    }
+
+    /// Compiles a list of expressions (call arguments or array literal elements), from left to right.
+    /// Returns how many values are left on the stack, or `None` if the list has spreads in it, in which
+    /// case its values are left in an array instead.
+    fn compile_list(&mut self, expressions: &[Expression]) -> Option<usize> {
+        if !expressions
+            .iter()
+            .any(|expression| matches!(expression, Expression::Spread(_)))
+        {
+            for expression in expressions {
+                self.compile_expression(expression);
+            }
+
+            return Some(expressions.len());
+        }
+
+        self.emit(Op::List(0));
+
+        for expression in expressions {
+            match expression {
+                Expression::Spread(spread) => {
+                    self.compile_expression(&spread.value);
+                    self.emit(Op::Extend);
+                }
+                expression => {
+                    self.compile_expression(expression);
+                    self.emit(Op::Append);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Compiles an `IfExpression`, which leaves the value of the chosen block (or `null`) on the stack.
+    /// If `tail` is set, the blocks are in tail position, and if `yields` is set, they are blocks that a
+    /// generator can pause in.
+    pub(crate) fn compile_if(&mut self, if_expression: &IfExpression, tail: bool, yields: bool) {
+        let branches = [(&if_expression.condition, &if_expression.consequence)]
+            .into_iter()
+            .chain(
+                if_expression
+                    .else_ifs
+                    .iter()
+                    .map(|else_if| (&else_if.condition, &else_if.consequence)),
+            );
+
+        let mut ends = Vec::new();
+
+        for (condition, consequence) in branches {
+            self.compile_expression(condition);
+            let skip = self.emit(Op::JumpIfFalse(0));
+
+            self.compile_block(&consequence.statements, tail, yields);
+            ends.push(self.emit(Op::Jump(0)));
+
+            self.patch_here(skip);
+        }
+
+        match &if_expression.alternative {
+            Some(alternative) => self.compile_block(&alternative.statements, tail, yields),
+            None => {
+                self.emit(Op::Null);
+            }
+        }
+
+        for end in ends {
+            self.patch_here(end);
+        }
+    }
+
+    /// Compiles a `TryExpression`, like `Evaluator::eval_try`. The body is run with a handler that
+    /// catches its errors. After the body (or `catch` block) is left normally, the `finally` block is
+    /// entered, and the handler enters it too when the `try` is left by an error or a `return`. If
+    /// `yields` is set, the blocks are ones that a generator can pause in.
+    pub(crate) fn compile_try(&mut self, try_expression: &TryExpression, yields: bool) {
+        // Nothing in a `try` is in tail position, as the `catch` and `finally` blocks have to be able to
+        // run after it.
+        let tail_returns = mem::replace(&mut self.function().tail_returns, false);
+
+        let start = self.emit(Op::Try {
+            catch: None,
+            finally: None,
+        });
+
+        self.compile_block(&try_expression.body.statements, false, yields);
+        self.emit(Op::EndTry);
+
+        let catch = try_expression.catch.as_ref().map(|catch| {
+            let skip = self.emit(Op::Jump(0));
+            let target = self.next();
+
+            let mut names = vec![catch.parameter.value.clone()];
+            declare_statements(&mut names, &catch.body.statements);
+
+            // The error is on the stack when the handler jumps here.
+            self.begin_scope(names);
+            self.emit_set(catch.parameter.value.clone());
+            self.compile_block(&catch.body.statements, false, yields);
+            self.end_scope();
+
+            // An error in the `catch` block still has to run the `finally` block, which the handler is
+            // kept around for.
+            if try_expression.finally.is_some() {
+                self.emit(Op::EndTry);
+            }
+
+            self.patch_here(skip);
+            target
+        });
+
+        let finally = try_expression.finally.as_ref().map(|finally| {
+            self.emit(Op::EnterFinally);
+            let target = self.next();
+
+            self.compile_block(&finally.statements, false, yields);
+            self.emit(Op::Pop);
+            self.emit(Op::EndFinally);
+            target
+        });
+
+        self.function().instructions[start] = Op::Try { catch, finally };
+        self.function().tail_returns = tail_returns;
+    }
+
+    /// Compiles a `ForExpression`, like `Evaluator::eval_for`. Each value is bound in a new scope, which
+    /// the body is run in. If `yields` is set, the body is a block that a generator can pause in.
+    pub(crate) fn compile_for(&mut self, for_expression: &ForExpression, yields: bool) {
+        self.compile_expression(&for_expression.iterable);
+        self.at(Some(for_expression.iterable.position()), |compiler| {
+            compiler.emit(Op::Iterate)
+        });
+
+        let start = self.next();
+        let next = self.emit(Op::Next(0));
+
+        let mut names = Vec::new();
+
+        for identifier in for_expression.pattern.identifiers() {
+            declare(&mut names, identifier.value.clone());
+        }
+
+        declare_statements(&mut names, &for_expression.body.statements);
+
+        self.begin_scope(names);
+        self.compile_bind(&for_expression.pattern);
+        self.compile_block(&for_expression.body.statements, false, yields);
+        self.emit(Op::Pop);
+        self.end_scope();
+
+        self.emit(Op::Jump(start));
+        self.patch_here(next);
+        self.emit(Op::Null);
+    }
+
+    /// Compiles a `FunctionLiteral` into its own `CompiledFunction`, and emits the instruction that
+    /// creates a closure of it.
+    ///
+    /// The arguments are bound to the parameters' slots by the call, and the function starts by filling
+    /// in the default values of the parameters that weren't given. A generator then stops, returning
+    /// itself, and runs the rest when it is resumed.
+    pub(crate) fn compile_function(&mut self, literal: &FunctionLiteral) {
+        let mut names = literal
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name().value.clone())
+            .collect::<Vec<_>>();
+
+        for parameter in &literal.parameters {
+            if let Parameter::Default { value, .. } = parameter {
+                declare_expression(&mut names, value);
+            }
+        }
+
+        declare_statements(&mut names, &literal.body.statements);
+
+        self.functions.push(FunctionState::new(
+            Some(Rc::new(literal.clone())),
+            Some(names),
+            !literal.generator,
+        ));
+
+        for (index, parameter) in literal.parameters.iter().enumerate() {
+            if let Parameter::Default { value, .. } = parameter {
+                let skip = self.emit(Op::JumpIfBound { index, target: 0 });
+                self.compile_expression(value);
+                self.emit(Op::SetLocal(index));
+                self.patch_here(skip);
+            }
+        }
+
+        if literal.generator {
+            self.emit(Op::Generate);
+        }
+
+        self.compile_block(
+            &literal.body.statements,
+            !literal.generator,
+            literal.generator,
+        );
+
+        // A generator only returns a value with a `return`, not by reaching the end of its body.
+        if literal.generator {
+            self.emit(Op::Pop);
+            self.emit(Op::Null);
+        }
+
+        self.emit(Op::Return);
+
+        let function = self
+            .functions
+            .pop()
+            .expect("the function is being compiled")
+            .finish();
+
+        let function = self.constant(Constant::Function(Rc::new(function)));
+        self.emit(Op::Closure(function));
+    }
+
+    /// Compiles a `CallExpression`. The function is evaluated first, then the arguments from left to
+    /// right. If `tail` is set, the call is made as a tail call.
+    fn compile_call(&mut self, call: &CallExpression, tail: bool) {
+        self.compile_expression(&call.function);
+
+        let (arguments, spread) = match self.compile_list(&call.arguments) {
+            Some(arguments) => (arguments, false),
+            None => (0, true),
+        };
+
+        self.emit(match tail {
+            true => Op::TailCall { arguments, spread },
+            false => Op::Call { arguments, spread },
+        });
+    }
+
+    /// Compiles a `RecordLiteral`, like `Evaluator::eval_record`.
+    fn compile_record(&mut self, record: &RecordLiteral) {
+        let position = Some(record.name.token.position);
+
+        self.compile_expression(&Expression::Identifier(record.name.clone()));
+
+        let name = self.constant(Constant::Identifier(record.name.clone()));
+        self.at(position, |compiler| compiler.emit(Op::Struct(name)));
+
+        for (offset, (field, value)) in record.fields.iter().enumerate() {
+            let field = self.constant(Constant::Identifier(field.clone()));
+            self.emit(Op::Field { field, offset });
+            self.compile_expression(value);
+        }
+
+        let names = self.constant(Constant::Names(
+            record
+                .fields
+                .iter()
+                .map(|(field, _)| field.value.clone())
+                .collect(),
+        ));
+        self.at(position, |compiler| compiler.emit(Op::Record(names)));
+    }
+
+    /// Compiles a call to `quote`, like `Evaluator::eval_quote`. The arguments of the calls to `unquote`
+    /// in the quoted expression are compiled, in the order that they are replaced in, so that their values
+    /// can be put into the quote when it is created.
+    fn compile_quote(&mut self, call: &CallExpression) {
+        let [argument] = call.arguments.as_slice() else {
+            return self.emit_fail(Error::new(
+                ErrorKind::Argument,
+                format!(
+                    "wrong number of arguments: want=1, got={}",
+                    call.arguments.len()
+                ),
+            ));
+        };
+
+        let mut unquotes = Vec::new();
+
+        let Ok(_) = modify_expression(argument.clone(), &mut |expression| {
+            if let Expression::Call(call) = &expression {
+                if is_unquote(call) {
+                    unquotes.push(call.clone());
+                }
+            }
+
+            Ok::<_, Infallible>(expression)
+        });
+
+        for unquote in &unquotes {
+            match unquote.arguments.as_slice() {
+                [argument] => self.compile_expression(argument),
+                arguments => self.emit_fail(
+                    Error::new(
+                        ErrorKind::Argument,
+                        format!("wrong number of arguments: want=1, got={}", arguments.len()),
+                    )
+                    .at(unquote.token.position),
+                ),
+            }
+        }
+
+        let quote = self.constant(Constant::Expression(argument.clone()));
+        self.emit(Op::Quote {
+            quote,
+            unquotes: unquotes.len(),
+        });
+    }
+}
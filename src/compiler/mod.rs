@@ -0,0 +1,320 @@
+pub mod code;
+mod expressions;
+mod patterns;
+mod statements;
+pub mod tests;
+
+use self::code::{CompiledFunction, Constant, Fallback, Op, Variable};
+use crate::{
+    ast::{expressions::FunctionLiteral, Program},
+    symbol::Symbol,
+    token::Position,
+};
+use std::rc::Rc;
+
+/// Compiles a `Program` into bytecode for the `Vm` to run.
+///
+/// Names bound at the top level of the program are globals, which are looked up by name in the
+/// environment that the program is run in, so that programs run one after the other (like the lines of
+/// the REPL) can see each other's bindings. Every other scope gets a slot for each name bound in it,
+/// which is a local of the frame that the scope is in, and which the compiler resolves references to
+/// ahead of time. A function refers to the slots of the functions around it as free variables, which
+/// its closures capture.
+pub fn compile(program: &Program) -> CompiledFunction {
+    let mut compiler = Compiler::new();
+    compiler.compile_program(program);
+
+    compiler
+        .functions
+        .pop()
+        .expect("the program is being compiled")
+        .finish()
+}
+
+/// The bytecode compiler. It keeps track of the functions that are being compiled, as function literals
+/// are compiled in the middle of the function that they are in.
+pub struct Compiler {
+    /// The functions that are being compiled, from the program to the innermost function literal.
+    functions: Vec<FunctionState>,
+}
+
+/// A function that is being compiled.
+struct FunctionState {
+    /// The literal that the function is being compiled from, or `None` for a program.
+    literal: Option<Rc<FunctionLiteral>>,
+
+    /// The instructions that have been emitted so far.
+    instructions: Vec<Op>,
+
+    /// The position of each instruction.
+    positions: Vec<Option<Position>>,
+
+    /// The constants that the instructions refer to.
+    constants: Vec<Constant>,
+
+    /// The scopes that the code being compiled is in, from the function's own scope (which a program
+    /// doesn't have) to the innermost one.
+    scopes: Vec<ScopeState>,
+
+    /// How many locals the function's scopes have been given so far.
+    locals: usize,
+
+    /// The variables that the function captures from the frame that creates its closures.
+    captures: Vec<Variable>,
+
+    /// The position of the innermost expression that is being compiled, which the instructions that are
+    /// emitted for it raise their errors at.
+    position: Option<Position>,
+
+    /// Whether a `return` is in tail position, like `Evaluator::tail_returns`.
+    tail_returns: bool,
+}
+
+/// A scope that is being compiled.
+struct ScopeState {
+    /// The names of the scope's slots, with the local that each of them is.
+    slots: Vec<(Symbol, usize)>,
+
+    /// The index of the scope's `Op::EnterScope`, whose end is filled in once the scope is done. A
+    /// function's own scope isn't entered, as its locals start out unbound.
+    enter: Option<usize>,
+}
+
+impl Compiler {
+    /// Creates a new `Compiler` for a program.
+    fn new() -> Self {
+        Self {
+            functions: vec![FunctionState::new(None, None, false)],
+        }
+    }
+
+    /// Returns the function that is currently being compiled.
+    fn function(&mut self) -> &mut FunctionState {
+        self.functions
+            .last_mut()
+            .expect("a function is being compiled")
+    }
+
+    /// Emits an instruction at the current position, returning its index.
+    fn emit(&mut self, op: Op) -> usize {
+        let function = self.function();
+        let position = function.position;
+
+        function.instructions.push(op);
+        function.positions.push(position);
+        function.instructions.len() - 1
+    }
+
+    /// Returns the index of the next instruction to be emitted.
+    fn next(&mut self) -> usize {
+        self.function().instructions.len()
+    }
+
+    /// Sets the target of the jump (or the `fail` target of the pattern instruction) at `index`.
+    fn patch(&mut self, index: usize, to: usize) {
+        match &mut self.function().instructions[index] {
+            Op::Jump(target)
+            | Op::JumpIfFalse(target)
+            | Op::Next(target)
+            | Op::JumpIfBound { target, .. } => *target = to,
+
+            Op::DestructureArray { fail, .. }
+            | Op::DestructureHash { fail }
+            | Op::Key { fail, .. }
+            | Op::Literal { fail, .. } => *fail = Some(to),
+
+            op => unreachable!("{:?} doesn't jump", op),
+        }
+    }
+
+    /// Sets the target of the jump at `index` to the next instruction to be emitted.
+    fn patch_here(&mut self, index: usize) {
+        let next = self.next();
+        self.patch(index, next);
+    }
+
+    /// Adds a constant to the current function, returning its index.
+    fn constant(&mut self, constant: Constant) -> usize {
+        let constants = &mut self.function().constants;
+
+        constants.push(constant);
+        constants.len() - 1
+    }
+
+    /// Runs `compile` with `position` as the current position, so that the instructions it emits raise
+    /// their errors there (unless it is `None`, which keeps the current position).
+    fn at<T>(&mut self, position: Option<Position>, compile: impl FnOnce(&mut Self) -> T) -> T {
+        let Some(position) = position else {
+            return compile(self);
+        };
+
+        let previous = self.function().position.replace(position);
+        let result = compile(self);
+        self.function().position = previous;
+
+        result
+    }
+
+    /// Starts a new scope with slots for `names`, emitting the instruction that enters it. The scope
+    /// has to be ended with `end_scope` once the code in it has been compiled. Nothing has to be done
+    /// to leave a scope, as the code outside of it doesn't refer to its slots.
+    fn begin_scope(&mut self, names: Vec<Symbol>) {
+        let start = self.function().locals;
+        let enter = self.emit(Op::EnterScope { start, end: start });
+
+        self.function().push_scope(names, Some(enter));
+    }
+
+    /// Ends the innermost scope, filling in the locals that entering it unbinds.
+    fn end_scope(&mut self) {
+        let function = self.function();
+        let scope = function.scopes.pop().expect("a scope has been begun");
+        let enter = scope.enter.expect("function scopes aren't ended");
+
+        let Op::EnterScope { start, .. } = function.instructions[enter] else {
+            unreachable!("scopes are entered with Op::EnterScope");
+        };
+
+        function.instructions[enter] = Op::EnterScope {
+            start,
+            end: function.locals,
+        };
+    }
+
+    /// Finds the slots that `name` can refer to from the function at `level`, from the innermost to
+    /// the outermost, like the resolver does (see `Resolver::resolve_use`). The slots of the functions
+    /// around it are captured by it (and by the functions in between) as free variables.
+    fn resolve(&mut self, level: usize, name: &Symbol) -> Vec<Variable> {
+        let mut variables = self.functions[level]
+            .scopes
+            .iter()
+            .rev()
+            .filter_map(|scope| scope.slot(name))
+            .map(Variable::Local)
+            .collect::<Vec<_>>();
+
+        if let Some(outer) = level.checked_sub(1) {
+            for variable in self.resolve(outer, name) {
+                let captures = &mut self.functions[level].captures;
+
+                let index = match captures.iter().position(|capture| *capture == variable) {
+                    Some(index) => index,
+                    None => {
+                        captures.push(variable);
+                        captures.len() - 1
+                    }
+                };
+
+                variables.push(Variable::Free(index));
+            }
+        }
+
+        variables
+    }
+
+    /// Emits the instruction that pushes the value of `name`, from the innermost of its slots, falling
+    /// back on the others and then the global with the name while that slot isn't bound.
+    fn emit_get(&mut self, name: Symbol) {
+        let level = self.functions.len() - 1;
+        let mut variables = self.resolve(level, &name).into_iter();
+
+        let Some(variable) = variables.next() else {
+            let name = self.constant(Constant::Name(name));
+            self.emit(Op::GetGlobal(name));
+            return;
+        };
+
+        let fallback = self.constant(Constant::Fallback(Fallback {
+            variables: variables.collect(),
+            name,
+        }));
+
+        self.emit(match variable {
+            Variable::Local(index) => Op::GetLocal { index, fallback },
+            Variable::Free(index) => Op::GetFree { index, fallback },
+        });
+    }
+
+    /// Emits the instruction that binds `name` to the top value in the current scope, adding a slot for
+    /// it if it doesn't have one yet.
+    fn emit_set(&mut self, name: Symbol) {
+        let function = self.function();
+
+        let Some(scope) = function.scopes.last_mut() else {
+            let name = self.constant(Constant::Name(name));
+            self.emit(Op::SetGlobal(name));
+            return;
+        };
+
+        let index = match scope.slot(&name) {
+            Some(index) => index,
+            None => {
+                scope.slots.push((name, function.locals));
+                function.locals += 1;
+                function.locals - 1
+            }
+        };
+
+        self.emit(Op::SetLocal(index));
+    }
+}
+
+impl FunctionState {
+    /// Creates the state for compiling a function from `literal`, whose own scope has slots for `names`,
+    /// or for compiling a program if there is no literal.
+    fn new(
+        literal: Option<Rc<FunctionLiteral>>,
+        names: Option<Vec<Symbol>>,
+        tail_returns: bool,
+    ) -> Self {
+        let mut function = Self {
+            literal,
+            instructions: Vec::new(),
+            positions: Vec::new(),
+            constants: Vec::new(),
+            scopes: Vec::new(),
+            locals: 0,
+            captures: Vec::new(),
+            position: None,
+            tail_returns,
+        };
+
+        if let Some(names) = names {
+            function.push_scope(names, None);
+        }
+
+        function
+    }
+
+    /// Starts a new scope with slots for `names`, which are the next locals of the function.
+    fn push_scope(&mut self, names: Vec<Symbol>, enter: Option<usize>) {
+        let start = self.locals;
+        self.locals += names.len();
+
+        self.scopes.push(ScopeState {
+            slots: names.into_iter().zip(start..).collect(),
+            enter,
+        });
+    }
+
+    /// Finishes compiling the function.
+    fn finish(self) -> CompiledFunction {
+        CompiledFunction {
+            literal: self.literal,
+            instructions: self.instructions,
+            positions: self.positions,
+            constants: self.constants,
+            locals: self.locals,
+            captures: self.captures,
+        }
+    }
+}
+
+impl ScopeState {
+    /// Returns the local that is the slot for `name` in this scope, if it has one.
+    fn slot(&self, name: &Symbol) -> Option<usize> {
+        self.slots
+            .iter()
+            .find_map(|(slot, index)| (slot == name).then_some(*index))
+    }
+}
@@ -0,0 +1,169 @@
+use super::{
+    code::{Constant, Op},
+    Compiler,
+};
+use crate::{
+    ast::{expressions::MatchExpression, patterns::Pattern},
+    resolver::{declare, declare_expression},
+};
+
+/// The instructions that jump out of a pattern when it doesn't match, together with how many of the
+/// arrays and hashes that contain the part of the value being matched are left on the stack when they
+/// do.
+type Fails = Vec<(usize, usize)>;
+
+impl Compiler {
+    /// Compiles the binding of the top value to `pattern` in the current scope, like
+    /// `Evaluator::bind_pattern`. A value that doesn't match is an error.
+    pub(crate) fn compile_bind(&mut self, pattern: &Pattern) {
+        self.compile_pattern(pattern, None, 0);
+    }
+
+    /// Compiles the matching of the top value against `pattern`, binding its parts as it goes. If
+    /// `fails` is given, the instructions that jump out when the value doesn't match are added to it, to
+    /// be patched by the caller, and otherwise they are errors. `depth` is the number of arrays and
+    /// hashes containing the value that are on the stack below it.
+    fn compile_pattern(&mut self, pattern: &Pattern, mut fails: Option<&mut Fails>, depth: usize) {
+        self.at(Some(pattern.position()), |compiler| match pattern {
+            Pattern::Identifier(identifier) => compiler.emit_set(identifier.value.clone()),
+
+            Pattern::Wildcard(_) => {
+                compiler.emit(Op::Pop);
+            }
+
+            Pattern::Literal(literal) => {
+                compiler.compile_expression(literal);
+
+                let literal = compiler.constant(Constant::Expression(literal.clone()));
+                let index = compiler.emit(Op::Literal {
+                    literal,
+                    fail: None,
+                });
+
+                if let Some(fails) = fails {
+                    fails.push((index, depth));
+                }
+            }
+
+            Pattern::Array(array) => {
+                let index = compiler.emit(Op::DestructureArray {
+                    elements: array.elements.len(),
+                    rest: array.rest.is_some(),
+                    fail: None,
+                });
+
+                if let Some(fails) = fails.as_deref_mut() {
+                    fails.push((index, depth));
+                }
+
+                for (index, element) in array.elements.iter().enumerate() {
+                    compiler.emit(Op::Element(index));
+                    compiler.compile_pattern(element, fails.as_deref_mut(), depth + 1);
+                }
+
+                if let Some(rest) = &array.rest {
+                    compiler.emit(Op::Rest(array.elements.len()));
+                    compiler.emit_set(rest.value.clone());
+                }
+
+                compiler.emit(Op::Pop);
+            }
+
+            Pattern::Hash(hash) => {
+                let index = compiler.emit(Op::DestructureHash { fail: None });
+
+                if let Some(fails) = fails.as_deref_mut() {
+                    fails.push((index, depth));
+                }
+
+                for (key, pattern) in &hash.pairs {
+                    compiler.compile_expression(key);
+
+                    let constant = compiler.constant(Constant::Expression(key.clone()));
+                    let index = compiler.at(Some(key.position()), |compiler| {
+                        compiler.emit(Op::HashKey);
+                        compiler.emit(Op::Key {
+                            key: constant,
+                            fail: None,
+                        })
+                    });
+
+                    if let Some(fails) = fails.as_deref_mut() {
+                        fails.push((index, depth + 1));
+                    }
+
+                    compiler.compile_pattern(pattern, fails.as_deref_mut(), depth + 1);
+                }
+
+                compiler.emit(Op::Pop);
+            }
+        });
+    }
+
+    /// Compiles a `MatchExpression`, like `Evaluator::eval_match`. Each arm matches a copy of the subject
+    /// in a new scope, which its pattern binds in and its guard and body are run in. When the pattern or
+    /// guard doesn't match, whatever the pattern left on the stack is popped, and the next arm is tried.
+    /// If `tail` is set, the bodies are in tail position, and if `yields` is set, they are evaluated like
+    /// the expressions of statements in a block that a generator can pause in.
+    pub(crate) fn compile_match(
+        &mut self,
+        match_expression: &MatchExpression,
+        tail: bool,
+        yields: bool,
+    ) {
+        self.compile_expression(&match_expression.subject);
+
+        let mut ends = Vec::new();
+
+        for arm in &match_expression.arms {
+            let mut names = Vec::new();
+
+            for identifier in arm.pattern.identifiers() {
+                declare(&mut names, identifier.value.clone());
+            }
+
+            if let Some(guard) = &arm.guard {
+                declare_expression(&mut names, guard);
+            }
+
+            declare_expression(&mut names, &arm.body);
+
+            self.begin_scope(names);
+            self.emit(Op::Dup);
+
+            let mut fails = Fails::new();
+            self.compile_pattern(&arm.pattern, Some(&mut fails), 0);
+
+            if let Some(guard) = &arm.guard {
+                self.compile_expression(guard);
+                fails.push((self.emit(Op::JumpIfFalse(0)), 0));
+            }
+
+            self.compile_expression_statement(&arm.body, tail, yields);
+
+            self.emit(Op::Nip);
+            ends.push(self.emit(Op::Jump(0)));
+
+            // A failed match jumps into the right place in a run of pops, so that everything it left on
+            // the stack is popped before the next arm is tried.
+            let most = fails.iter().map(|&(_, depth)| depth).max().unwrap_or(0);
+            let start = self.next();
+
+            for _ in 0..most {
+                self.emit(Op::Pop);
+            }
+
+            for (index, depth) in fails {
+                self.patch(index, start + (most - depth));
+            }
+
+            self.end_scope();
+        }
+
+        self.emit(Op::NoMatch);
+
+        for end in ends {
+            self.patch_here(end);
+        }
+    }
+}
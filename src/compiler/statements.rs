@@ -0,0 +1,211 @@
+use super::{
+    code::{Constant, Op},
+    Compiler,
+};
+use crate::{
+    ast::{expressions::Expression, statements::Statement, Program},
+    evaluator::errors::{Error, ErrorKind},
+    object::{structs::Struct, Object},
+};
+
+impl Compiler {
+    /// Compiles the statements of a `Program`. The program returns the value of its last statement, if
+    /// that is an expression statement, and ends without a value otherwise.
+    pub(crate) fn compile_program(&mut self, program: &Program) {
+        self.hoist_declarations(&program.statements);
+
+        // Exports are only allowed at the top level, where they are compiled like the declaration that
+        // they export.
+        let statements = program
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::Export(export) => export.declaration.as_ref(),
+                statement => statement,
+            })
+            .collect::<Vec<_>>();
+
+        for (index, statement) in statements.iter().enumerate() {
+            let last = index == statements.len() - 1;
+
+            match statement {
+                Statement::Expression(expression) => {
+                    self.compile_expression(&expression.expression);
+                    self.emit(if last { Op::Return } else { Op::Pop });
+                }
+                statement => self.compile_statement(statement, false),
+            }
+        }
+
+        if !matches!(statements.last(), Some(Statement::Expression(_))) {
+            self.emit(Op::End);
+        }
+    }
+
+    /// Compiles the statements of a block, which leave the value of the block on the stack: the value
+    /// of its last statement if that is an expression statement, and `null` otherwise. If `tail` is set,
+    /// the last statement is in tail position. If `yields` is set, the block is one that a generator can
+    /// pause in.
+    pub(crate) fn compile_block(&mut self, statements: &[Statement], tail: bool, yields: bool) {
+        self.hoist_declarations(statements);
+
+        for (index, statement) in statements.iter().enumerate() {
+            let last = index == statements.len() - 1;
+
+            match statement {
+                Statement::Expression(expression) => {
+                    self.compile_expression_statement(&expression.expression, last && tail, yields);
+
+                    if !last {
+                        self.emit(Op::Pop);
+                    }
+                }
+                statement => {
+                    self.compile_statement(statement, yields);
+
+                    if last {
+                        self.emit(Op::Null);
+                    }
+                }
+            }
+        }
+
+        if statements.is_empty() {
+            self.emit(Op::Null);
+        }
+    }
+
+    /// Compiles the expression of an expression statement. In a block that a generator can pause in,
+    /// the blocks of `if`, `for` and `try` expressions, and the arms of `match` expressions, can pause
+    /// the generator too.
+    pub(crate) fn compile_expression_statement(
+        &mut self,
+        expression: &Expression,
+        tail: bool,
+        yields: bool,
+    ) {
+        self.at(Some(expression.position()), |compiler| match expression {
+            Expression::If(if_expression) if yields => {
+                compiler.compile_if(if_expression, false, true)
+            }
+            Expression::For(for_expression) if yields => compiler.compile_for(for_expression, true),
+            Expression::Try(try_expression) if yields => compiler.compile_try(try_expression, true),
+            Expression::Match(match_expression) if yields => {
+                compiler.compile_match(match_expression, false, true)
+            }
+            expression if tail => compiler.compile_tail_expression(expression),
+            expression => compiler.compile_expression(expression),
+        });
+    }
+
+    /// Compiles a single `Statement` other than an expression statement, which doesn't leave anything on
+    /// the stack.
+    fn compile_statement(&mut self, statement: &Statement, yields: bool) {
+        match statement {
+            Statement::Expression(expression) => {
+                self.compile_expression(&expression.expression);
+                self.emit(Op::Pop);
+            }
+
+            Statement::Let(let_statement) => {
+                self.compile_expression(&let_statement.value);
+                self.compile_bind(&let_statement.pattern);
+            }
+
+            Statement::Return(return_statement) => {
+                if self.function().tail_returns {
+                    self.compile_tail_expression(&return_statement.return_value);
+                } else {
+                    self.compile_expression(&return_statement.return_value);
+                }
+
+                self.emit(Op::Return);
+            }
+
+            Statement::Throw(throw) => {
+                self.compile_expression(&throw.value);
+                self.at(Some(throw.token.position), |compiler| {
+                    compiler.emit(Op::Throw)
+                });
+            }
+
+            Statement::Yield(yield_statement) if yields => {
+                self.compile_expression(&yield_statement.value);
+                self.emit(Op::Yield);
+            }
+
+            Statement::Yield(yield_statement) => self.emit_fail(
+                Error::new(
+                    ErrorKind::Syntax,
+                    "yield is only allowed in a generator's body, and the if, for, try and match statements in it",
+                )
+                .at(yield_statement.token.position),
+            ),
+
+            // Function and struct declarations were already bound when the enclosing program or block
+            // was entered (see `hoist_declarations`), so there is nothing left to do here.
+            Statement::Function(_) | Statement::Struct(_) => {}
+
+            Statement::Import(import) => {
+                let path = self.constant(Constant::Name(import.path.value.clone()));
+
+                self.at(Some(import.token.position), |compiler| {
+                    compiler.emit(Op::Import(path))
+                });
+                self.emit_set(import.alias.value.clone());
+            }
+
+            // Top-level exports are handled by `compile_program`, so an export here is inside of a block.
+            // The parser doesn't allow that, but a program might not have come from the parser.
+            Statement::Export(export) => self.emit_fail(
+                Error::new(
+                    ErrorKind::Syntax,
+                    "export is only allowed at the top level of a module",
+                )
+                .at(export.token.position),
+            ),
+        }
+    }
+
+    /// Emits an instruction that raises `error` when it is run.
+    pub(crate) fn emit_fail(&mut self, error: Error) {
+        let error = self.constant(Constant::Error(error));
+        self.emit(Op::Fail(error));
+    }
+
+    /// Binds every function and struct declared in `statements` in the current scope, before any of the
+    /// statements are run, like `Evaluator::hoist_declarations`.
+    fn hoist_declarations(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            let statement = match statement {
+                Statement::Export(export) => export.declaration.as_ref(),
+                statement => statement,
+            };
+
+            match statement {
+                Statement::Function(declaration) => {
+                    self.compile_function(&declaration.function);
+                    self.emit_set(declaration.name.value.clone());
+                }
+
+                Statement::Struct(declaration) => {
+                    let fields = declaration
+                        .fields
+                        .iter()
+                        .map(|field| field.value.clone())
+                        .collect();
+
+                    let declared = self.constant(Constant::Object(Object::Struct(Struct::new(
+                        declaration.name.value.clone(),
+                        fields,
+                    ))));
+
+                    self.emit(Op::Constant(declared));
+                    self.emit_set(declaration.name.value.clone());
+                }
+
+                _ => {}
+            }
+        }
+    }
+}
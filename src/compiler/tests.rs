@@ -0,0 +1,148 @@
+#![cfg(test)]
+
+use super::{
+    code::{CompiledFunction, Constant, Op, Variable},
+    compile,
+};
+use crate::{ast::operators::InfixOperatorType, lexer::Lexer, parser::Parser};
+
+/// Parses and compiles the input source code
+fn compile_input(input: &str) -> CompiledFunction {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    compile(&parser.parse_program().unwrap())
+}
+
+/// Tests the instructions that programs compile to
+#[test]
+fn test_compile_programs() {
+    let tests = vec![
+        (
+            "1 + 2",
+            vec![
+                Op::Constant(0),
+                Op::Constant(1),
+                Op::Infix(InfixOperatorType::Add),
+                Op::Return,
+            ],
+        ),
+        (
+            "let x = 1; x",
+            vec![
+                Op::Constant(0),
+                Op::SetGlobal(1),
+                Op::GetGlobal(2),
+                Op::Return,
+            ],
+        ),
+        (
+            "if (true) { 1 } else { 2 }",
+            vec![
+                Op::True,
+                Op::JumpIfFalse(4),
+                Op::Constant(0),
+                Op::Jump(5),
+                Op::Constant(1),
+                Op::Return,
+            ],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let compiled = compile_input(input);
+
+        assert_eq!(compiled.instructions, expected, "input: {}", input);
+        assert_eq!(
+            compiled.positions.len(),
+            compiled.instructions.len(),
+            "input: {}",
+            input
+        );
+    }
+}
+
+/// Tests that the locals of functions are compiled to slots, and the functions to closures
+#[test]
+fn test_compile_functions() {
+    let compiled = compile_input("fn(a) { let b = a; b }");
+
+    assert_eq!(compiled.instructions, vec![Op::Closure(0), Op::Return]);
+
+    let function = function_constant(&compiled, 0);
+
+    assert_eq!(
+        function.instructions,
+        vec![
+            Op::GetLocal {
+                index: 0,
+                fallback: 0
+            },
+            Op::SetLocal(1),
+            Op::GetLocal {
+                index: 1,
+                fallback: 1
+            },
+            Op::Return,
+        ]
+    );
+    assert_eq!(function.locals, 2);
+    assert_eq!(function.captures, vec![]);
+
+    assert_eq!(
+        compiled.to_string(),
+        "<program>:\n0000 Closure(0)\n0001 Return\n\n<anonymous>:\n0000 GetLocal { index: 0, fallback: 0 }\n0001 SetLocal(1)\n0002 GetLocal { index: 1, fallback: 1 }\n0003 Return\n"
+    );
+}
+
+/// Tests that closures only capture the variables that they use from the functions around them, and
+/// that the scopes in a function are more of its locals
+#[test]
+fn test_compile_captures() {
+    let compiled = compile_input("fn(a, b) { for (x in b) { fn() { fn() { a + x } } } }");
+    let outer = function_constant(&compiled, 0);
+
+    assert_eq!(outer.locals, 3);
+    assert!(outer
+        .instructions
+        .contains(&Op::EnterScope { start: 2, end: 3 }));
+
+    let middle = outer
+        .constants
+        .iter()
+        .find_map(|constant| match constant {
+            Constant::Function(function) => Some(function),
+            _ => None,
+        })
+        .expect("the outer function has a function constant");
+
+    assert_eq!(middle.locals, 0);
+    assert_eq!(
+        middle.captures,
+        vec![Variable::Local(0), Variable::Local(2)]
+    );
+
+    let inner = function_constant(middle, 0);
+
+    assert_eq!(inner.captures, vec![Variable::Free(0), Variable::Free(1)]);
+    assert_eq!(
+        inner.instructions[..2],
+        [
+            Op::GetFree {
+                index: 0,
+                fallback: 0
+            },
+            Op::GetFree {
+                index: 1,
+                fallback: 1
+            },
+        ]
+    );
+}
+
+/// Returns the function constant at `index` of `function`
+fn function_constant(function: &CompiledFunction, index: usize) -> &CompiledFunction {
+    match &function.constants[index] {
+        Constant::Function(function) => function,
+        constant => panic!("Constant is not a function, found {:?}", constant),
+    }
+}
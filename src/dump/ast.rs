@@ -0,0 +1,372 @@
+use super::Tree;
+use crate::ast::{
+    expressions::{
+        ArrayLiteral, BooleanLiteral, CallExpression, CatchClause, ElseIfBranch, Expression,
+        ForExpression, FunctionLiteral, HashLiteral, Identifier, IfExpression, IndexExpression,
+        InfixExpression, IntegerLiteral, MacroLiteral, MatchArm, MatchExpression, MemberExpression,
+        Parameter, PrefixExpression, RecordLiteral, SpreadExpression, StringLiteral, TryExpression,
+    },
+    patterns::{ArrayPattern, HashPattern, Pattern},
+    statements::{
+        BlockStatement, ExportStatement, ExpressionStatement, FunctionStatement, ImportStatement,
+        LetStatement, ReturnStatement, Statement, StructStatement, ThrowStatement, YieldStatement,
+    },
+    Program,
+};
+
+/// Implemented by the nodes of the AST, so that they can be dumped.
+pub trait ToTree {
+    /// Returns the node as a `Tree`, with every part of it.
+    fn to_tree(&self) -> Tree;
+}
+
+impl<T: ToTree + ?Sized> ToTree for Box<T> {
+    fn to_tree(&self) -> Tree {
+        self.as_ref().to_tree()
+    }
+}
+
+/// The key and value of a hash literal or hash pattern, or the name and value of a record field.
+impl<K: ToTree, V: ToTree> ToTree for (K, V) {
+    fn to_tree(&self) -> Tree {
+        let key = self.0.to_tree();
+
+        Tree::new("Pair", key.position)
+            .node("key", &self.0)
+            .node("value", &self.1)
+    }
+}
+
+impl ToTree for Program {
+    fn to_tree(&self) -> Tree {
+        Tree::new("Program", None).nodes("statements", &self.statements)
+    }
+}
+
+impl ToTree for Statement {
+    fn to_tree(&self) -> Tree {
+        match self {
+            Self::Let(let_statement) => let_statement.to_tree(),
+            Self::Return(return_statement) => return_statement.to_tree(),
+            Self::Expression(expression_statement) => expression_statement.to_tree(),
+            Self::Function(function_statement) => function_statement.to_tree(),
+            Self::Import(import_statement) => import_statement.to_tree(),
+            Self::Export(export_statement) => export_statement.to_tree(),
+            Self::Struct(struct_statement) => struct_statement.to_tree(),
+            Self::Throw(throw_statement) => throw_statement.to_tree(),
+            Self::Yield(yield_statement) => yield_statement.to_tree(),
+        }
+    }
+}
+
+impl ToTree for LetStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("LetStatement", Some(self.token.position))
+            .node("pattern", &self.pattern)
+            .node("value", &self.value)
+    }
+}
+
+impl ToTree for ReturnStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("ReturnStatement", Some(self.token.position)).node("value", &self.return_value)
+    }
+}
+
+impl ToTree for YieldStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("YieldStatement", Some(self.token.position)).node("value", &self.value)
+    }
+}
+
+impl ToTree for ThrowStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("ThrowStatement", Some(self.token.position)).node("value", &self.value)
+    }
+}
+
+impl ToTree for ExpressionStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("ExpressionStatement", Some(self.token.position))
+            .node("expression", &self.expression)
+    }
+}
+
+impl ToTree for FunctionStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("FunctionStatement", Some(self.token.position))
+            .node("name", &self.name)
+            .node("function", &self.function)
+    }
+}
+
+impl ToTree for ImportStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("ImportStatement", Some(self.token.position))
+            .node("path", &self.path)
+            .node("alias", &self.alias)
+    }
+}
+
+impl ToTree for ExportStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("ExportStatement", Some(self.token.position))
+            .node("declaration", &self.declaration)
+    }
+}
+
+impl ToTree for StructStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("StructStatement", Some(self.token.position))
+            .node("name", &self.name)
+            .nodes("fields", &self.fields)
+    }
+}
+
+impl ToTree for BlockStatement {
+    fn to_tree(&self) -> Tree {
+        Tree::new("BlockStatement", Some(self.token.position)).nodes("statements", &self.statements)
+    }
+}
+
+impl ToTree for Expression {
+    fn to_tree(&self) -> Tree {
+        match self {
+            Self::Identifier(identifier) => identifier.to_tree(),
+            Self::Integer(integer) => integer.to_tree(),
+            Self::Boolean(boolean) => boolean.to_tree(),
+            Self::String(string) => string.to_tree(),
+            Self::Function(function) => function.to_tree(),
+            Self::Prefix(prefix) => prefix.to_tree(),
+            Self::Infix(infix) => infix.to_tree(),
+            Self::If(if_expression) => if_expression.to_tree(),
+            Self::Call(call) => call.to_tree(),
+            Self::Array(array) => array.to_tree(),
+            Self::Index(index) => index.to_tree(),
+            Self::Member(member) => member.to_tree(),
+            Self::Spread(spread) => spread.to_tree(),
+            Self::Hash(hash) => hash.to_tree(),
+            Self::Record(record) => record.to_tree(),
+            Self::Match(match_expression) => match_expression.to_tree(),
+            Self::Macro(macro_literal) => macro_literal.to_tree(),
+            Self::Try(try_expression) => try_expression.to_tree(),
+            Self::For(for_expression) => for_expression.to_tree(),
+        }
+    }
+}
+
+impl ToTree for Identifier {
+    fn to_tree(&self) -> Tree {
+        Tree::new("Identifier", Some(self.token.position)).value("name", self.value.clone())
+    }
+}
+
+impl ToTree for IntegerLiteral {
+    fn to_tree(&self) -> Tree {
+        Tree::new("IntegerLiteral", Some(self.token.position)).value("value", self.value)
+    }
+}
+
+impl ToTree for BooleanLiteral {
+    fn to_tree(&self) -> Tree {
+        Tree::new("BooleanLiteral", Some(self.token.position)).value("value", self.value)
+    }
+}
+
+impl ToTree for StringLiteral {
+    fn to_tree(&self) -> Tree {
+        Tree::new("StringLiteral", Some(self.token.position)).value("value", self.value.clone())
+    }
+}
+
+impl ToTree for FunctionLiteral {
+    fn to_tree(&self) -> Tree {
+        Tree::new("FunctionLiteral", Some(self.token.position))
+            .value("name", self.name.clone())
+            .value("generator", self.generator)
+            .value("form", self.form.name())
+            .nodes("parameters", &self.parameters)
+            .node("body", &self.body)
+    }
+}
+
+impl ToTree for Parameter {
+    fn to_tree(&self) -> Tree {
+        match self {
+            Self::Required(name) => {
+                Tree::new("RequiredParameter", Some(name.token.position)).node("name", name)
+            }
+            Self::Default { name, value } => {
+                Tree::new("DefaultParameter", Some(name.token.position))
+                    .node("name", name)
+                    .node("value", value)
+            }
+            Self::Rest { token, name } => {
+                Tree::new("RestParameter", Some(token.position)).node("name", name)
+            }
+        }
+    }
+}
+
+impl ToTree for PrefixExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("PrefixExpression", Some(self.token.position))
+            .value("operator", self.operator.r#type.to_string().as_str())
+            .node("right", &self.right)
+    }
+}
+
+impl ToTree for InfixExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("InfixExpression", Some(self.token.position))
+            .value("operator", self.operator.r#type.to_string().as_str())
+            .node("left", &self.left)
+            .node("right", &self.right)
+    }
+}
+
+impl ToTree for IfExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("IfExpression", Some(self.token.position))
+            .node("condition", &self.condition)
+            .node("consequence", &self.consequence)
+            .nodes("else_ifs", &self.else_ifs)
+            .optional("alternative", self.alternative.as_ref())
+    }
+}
+
+impl ToTree for ElseIfBranch {
+    fn to_tree(&self) -> Tree {
+        Tree::new("ElseIfBranch", Some(self.token.position))
+            .node("condition", &self.condition)
+            .node("consequence", &self.consequence)
+    }
+}
+
+impl ToTree for TryExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("TryExpression", Some(self.token.position))
+            .node("body", &self.body)
+            .optional("catch", self.catch.as_ref())
+            .optional("finally", self.finally.as_ref())
+    }
+}
+
+impl ToTree for CatchClause {
+    fn to_tree(&self) -> Tree {
+        Tree::new("CatchClause", Some(self.token.position))
+            .node("parameter", &self.parameter)
+            .node("body", &self.body)
+    }
+}
+
+impl ToTree for ForExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("ForExpression", Some(self.token.position))
+            .node("pattern", &self.pattern)
+            .node("iterable", &self.iterable)
+            .node("body", &self.body)
+    }
+}
+
+impl ToTree for CallExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("CallExpression", Some(self.token.position))
+            .value("piped", self.piped)
+            .node("function", &self.function)
+            .nodes("arguments", &self.arguments)
+    }
+}
+
+impl ToTree for ArrayLiteral {
+    fn to_tree(&self) -> Tree {
+        Tree::new("ArrayLiteral", Some(self.token.position)).nodes("elements", &self.elements)
+    }
+}
+
+impl ToTree for IndexExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("IndexExpression", Some(self.token.position))
+            .node("left", &self.left)
+            .node("index", &self.index)
+    }
+}
+
+impl ToTree for MemberExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("MemberExpression", Some(self.token.position))
+            .node("object", &self.object)
+            .node("member", &self.member)
+    }
+}
+
+impl ToTree for RecordLiteral {
+    fn to_tree(&self) -> Tree {
+        Tree::new("RecordLiteral", Some(self.token.position))
+            .node("name", &self.name)
+            .nodes("fields", &self.fields)
+    }
+}
+
+impl ToTree for SpreadExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("SpreadExpression", Some(self.token.position)).node("value", &self.value)
+    }
+}
+
+impl ToTree for HashLiteral {
+    fn to_tree(&self) -> Tree {
+        Tree::new("HashLiteral", Some(self.token.position)).nodes("pairs", &self.pairs)
+    }
+}
+
+impl ToTree for MatchExpression {
+    fn to_tree(&self) -> Tree {
+        Tree::new("MatchExpression", Some(self.token.position))
+            .node("subject", &self.subject)
+            .nodes("arms", &self.arms)
+    }
+}
+
+impl ToTree for MatchArm {
+    fn to_tree(&self) -> Tree {
+        Tree::new("MatchArm", Some(self.pattern.position()))
+            .node("pattern", &self.pattern)
+            .optional("guard", self.guard.as_ref())
+            .node("body", &self.body)
+    }
+}
+
+impl ToTree for MacroLiteral {
+    fn to_tree(&self) -> Tree {
+        Tree::new("MacroLiteral", Some(self.token.position))
+            .nodes("parameters", &self.parameters)
+            .node("body", &self.body)
+    }
+}
+
+impl ToTree for Pattern {
+    fn to_tree(&self) -> Tree {
+        match self {
+            Self::Identifier(identifier) => identifier.to_tree(),
+            Self::Wildcard(token) => Tree::new("WildcardPattern", Some(token.position)),
+            Self::Literal(literal) => literal.to_tree(),
+            Self::Array(array) => array.to_tree(),
+            Self::Hash(hash) => hash.to_tree(),
+        }
+    }
+}
+
+impl ToTree for ArrayPattern {
+    fn to_tree(&self) -> Tree {
+        Tree::new("ArrayPattern", Some(self.token.position))
+            .nodes("elements", &self.elements)
+            .optional("rest", self.rest.as_ref())
+    }
+}
+
+impl ToTree for HashPattern {
+    fn to_tree(&self) -> Tree {
+        Tree::new("HashPattern", Some(self.token.position)).nodes("pairs", &self.pairs)
+    }
+}
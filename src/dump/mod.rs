@@ -0,0 +1,378 @@
+pub mod ast;
+pub mod tests;
+
+use self::ast::ToTree;
+use crate::{
+    ast::Program,
+    lexer::Lexer,
+    symbol::Symbol,
+    token::{Position, TokenType},
+};
+use std::{fmt::Write, str::FromStr};
+
+/// The forms that a syntax tree can be dumped in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// An indented tree, with a line for each node and where it is in the source code. This is the
+    /// easiest to read.
+    #[default]
+    Tree,
+
+    /// An S-expression, without positions, so that the same code always dumps the same way wherever
+    /// it is written. This is the most compact, and is good for comparing the shapes of trees.
+    Sexpr,
+
+    /// JSON, for other tools to read.
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "tree" => Ok(Format::Tree),
+            "sexpr" => Ok(Format::Sexpr),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "unknown format: {:?}, expected tree, sexpr or json",
+                name
+            )),
+        }
+    }
+}
+
+/// A node of a syntax tree, in a form that can be dumped. Unlike the `Display` impls of the AST, this
+/// keeps every part of the node, and where it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tree {
+    /// The kind of node, which is the name of its type in the AST, e.g. `InfixExpression`.
+    pub kind: &'static str,
+
+    /// Where the node is in the source code, if it is anywhere in particular.
+    pub position: Option<Position>,
+
+    /// The parts of the node, in the order that they are written in.
+    pub fields: Vec<(&'static str, Value)>,
+}
+
+/// The value of a field of a `Tree`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// Another node.
+    Tree(Tree),
+
+    /// Any number of nodes, e.g. the arguments of a call.
+    List(Vec<Value>),
+
+    /// An integer, e.g. the value of an integer literal.
+    Integer(i64),
+
+    /// A boolean, e.g. the value of a boolean literal.
+    Boolean(bool),
+
+    /// A string, e.g. a name or an operator.
+    String(String),
+
+    /// An optional part that isn't there, e.g. the `else` of an `if` without one.
+    Null,
+}
+
+impl Value {
+    /// Returns whether the value is written on the same line as its node in the tree format.
+    fn is_scalar(&self) -> bool {
+        !matches!(self, Value::Tree(_) | Value::List(_))
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<Symbol> for Value {
+    fn from(value: Symbol) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl Tree {
+    /// Returns a new node of the given kind, without any fields.
+    pub fn new(kind: &'static str, position: Option<Position>) -> Self {
+        Self {
+            kind,
+            position,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Adds a field with a scalar value, such as a name.
+    pub fn value<V: Into<Value>>(mut self, name: &'static str, value: V) -> Self {
+        self.fields.push((name, value.into()));
+        self
+    }
+
+    /// Adds a field with another node.
+    pub fn node<T: ToTree + ?Sized>(mut self, name: &'static str, node: &T) -> Self {
+        self.fields.push((name, Value::Tree(node.to_tree())));
+        self
+    }
+
+    /// Adds a field with a node that might not be there.
+    pub fn optional<T: ToTree>(mut self, name: &'static str, node: Option<&T>) -> Self {
+        let value = node.map_or(Value::Null, |node| Value::Tree(node.to_tree()));
+        self.fields.push((name, value));
+        self
+    }
+
+    /// Adds a field with a list of nodes.
+    pub fn nodes<'a, T: ToTree + 'a, I: IntoIterator<Item = &'a T>>(
+        mut self,
+        name: &'static str,
+        nodes: I,
+    ) -> Self {
+        let nodes = nodes
+            .into_iter()
+            .map(|node| Value::Tree(node.to_tree()))
+            .collect();
+
+        self.fields.push((name, Value::List(nodes)));
+        self
+    }
+
+    /// Renders the tree in the given format. Every format ends with a newline.
+    pub fn render(&self, format: Format) -> String {
+        let mut out = String::new();
+
+        match format {
+            Format::Tree => write_tree(&mut out, self, 0),
+            Format::Sexpr => {
+                write_sexpr(&mut out, &Value::Tree(self.clone()));
+                out.push('\n');
+            }
+            Format::Json => {
+                write_json(&mut out, &Value::Tree(self.clone()), 0);
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+}
+
+/// Dumps the tokens that `input` is lexed into, up to and including the end of the input, with a line
+/// for each token of where it is, its type and its literal.
+pub fn dump_tokens(input: &str) -> String {
+    let mut lexer = Lexer::new(input);
+    let mut out = String::new();
+
+    loop {
+        let token = lexer.next_token();
+
+        // Writing to a `String` can't fail.
+        let _ = writeln!(
+            out,
+            "{:<8} {:<10} {}",
+            token.position.to_string(),
+            token.r#type.to_string(),
+            quote(&token.literal)
+        );
+
+        if token.r#type == TokenType::EOF {
+            return out;
+        }
+    }
+}
+
+/// Dumps `program` in the given format. In the S-expression format, each statement is on a line of
+/// its own.
+pub fn dump_program(program: &Program, format: Format) -> String {
+    match format {
+        Format::Sexpr => program
+            .statements
+            .iter()
+            .map(|statement| statement.to_tree().render(format))
+            .collect(),
+        _ => program.to_tree().render(format),
+    }
+}
+
+/// Writes `tree` as an indented tree, `depth` levels deep. The scalar fields of a node are written on
+/// its line, and the other fields on the lines after it, one level deeper.
+fn write_tree(out: &mut String, tree: &Tree, depth: usize) {
+    out.push_str(tree.kind);
+
+    if let Some(position) = tree.position {
+        let _ = write!(out, " {}", position);
+    }
+
+    for (name, value) in tree.fields.iter().filter(|(_, value)| value.is_scalar()) {
+        let _ = write!(out, " {}=", name);
+        write_scalar(out, value);
+    }
+
+    out.push('\n');
+
+    let indent = "  ".repeat(depth + 1);
+
+    for (name, value) in tree.fields.iter().filter(|(_, value)| !value.is_scalar()) {
+        let _ = write!(out, "{}{}:", indent, name);
+
+        match value {
+            Value::Tree(tree) => {
+                out.push(' ');
+                write_tree(out, tree, depth + 1);
+            }
+            Value::List(values) if values.is_empty() => out.push_str(" []\n"),
+            Value::List(values) => {
+                out.push('\n');
+
+                for value in values {
+                    let _ = write!(out, "{}  - ", indent);
+
+                    match value {
+                        Value::Tree(tree) => write_tree(out, tree, depth + 2),
+                        _ => {
+                            write_scalar(out, value);
+                            out.push('\n');
+                        }
+                    }
+                }
+            }
+            _ => unreachable!("scalars are written on the line of their node"),
+        }
+    }
+}
+
+/// Writes `value` as an S-expression. Nodes are written as `(Kind :field value ...)`, and lists as
+/// `[value ...]`.
+fn write_sexpr(out: &mut String, value: &Value) {
+    match value {
+        Value::Tree(tree) => {
+            out.push('(');
+            out.push_str(tree.kind);
+
+            for (name, value) in &tree.fields {
+                let _ = write!(out, " :{} ", name);
+                write_sexpr(out, value);
+            }
+
+            out.push(')');
+        }
+        Value::List(values) => {
+            out.push('[');
+
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+
+                write_sexpr(out, value);
+            }
+
+            out.push(']');
+        }
+        _ => write_scalar(out, value),
+    }
+}
+
+/// Writes `value` as JSON, indented as if it were `depth` levels deep. Nodes are objects with their
+/// `"kind"`, their `"line"` and `"column"` (if they have a position), and their fields.
+fn write_json(out: &mut String, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth + 1);
+
+    match value {
+        Value::Tree(tree) => {
+            let _ = write!(out, "{{\n{}\"kind\": {}", indent, quote(tree.kind));
+
+            if let Some(position) = tree.position {
+                let _ = write!(
+                    out,
+                    ",\n{}\"line\": {},\n{}\"column\": {}",
+                    indent, position.line, indent, position.column
+                );
+            }
+
+            for (name, value) in &tree.fields {
+                let _ = write!(out, ",\n{}{}: ", indent, quote(name));
+                write_json(out, value, depth + 1);
+            }
+
+            let _ = write!(out, "\n{}}}", "  ".repeat(depth));
+        }
+        Value::List(values) if values.is_empty() => out.push_str("[]"),
+        Value::List(values) => {
+            out.push('[');
+
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+
+                let _ = write!(out, "\n{}", indent);
+                write_json(out, value, depth + 1);
+            }
+
+            let _ = write!(out, "\n{}]", "  ".repeat(depth));
+        }
+        _ => write_scalar(out, value),
+    }
+}
+
+/// Writes a scalar value, the same way in every format.
+fn write_scalar(out: &mut String, value: &Value) {
+    match value {
+        Value::Integer(integer) => {
+            let _ = write!(out, "{}", integer);
+        }
+        Value::Boolean(boolean) => {
+            let _ = write!(out, "{}", boolean);
+        }
+        Value::String(string) => out.push_str(&quote(string)),
+        Value::Null => out.push_str("null"),
+        Value::Tree(_) | Value::List(_) => unreachable!("only scalars are written as scalars"),
+    }
+}
+
+/// Quotes `string` as a JSON string, escaping quotes, backslashes and control characters, so that
+/// every format can be read back unambiguously.
+fn quote(string: &str) -> String {
+    let mut quoted = String::with_capacity(string.len() + 2);
+    quoted.push('"');
+
+    for ch in string.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", ch as u32);
+            }
+            ch => quoted.push(ch),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,211 @@
+#![cfg(test)]
+
+use super::{dump_program, dump_tokens, Format};
+use crate::{lexer::Lexer, parser::Parser};
+
+/// Parses `input`, and dumps it in the given format
+fn dump(input: &str, format: Format) -> String {
+    let program = Parser::new(Lexer::new(input))
+        .parse_program()
+        .unwrap_or_else(|errors| panic!("couldn't parse {:?}: {:?}", input, errors));
+
+    dump_program(&program, format)
+}
+
+/// Tests dumping the tokens of some code
+#[test]
+fn test_dump_tokens() {
+    let input = "let s = \"a\tb\";\n  x |> f(...xs)";
+
+    let expected = r#"1:1      LET        "let"
+1:5      IDENT      "s"
+1:7      =          "="
+1:9      STRING     "a\tb"
+1:14     ;          ";"
+2:3      IDENT      "x"
+2:5      |>         "|>"
+2:8      IDENT      "f"
+2:9      (          "("
+2:10     ...        "..."
+2:13     IDENT      "xs"
+2:15     )          ")"
+2:16     EOF        ""
+"#;
+
+    assert_eq!(dump_tokens(input), expected);
+}
+
+/// Tests that S-expression dumps show how expressions are grouped, which `Display` does with
+/// parentheses that can't be told apart from the ones in the code
+#[test]
+fn test_dump_sexpr() {
+    let tests = vec![
+        (
+            "1 + 2 * 3",
+            r#"(ExpressionStatement :expression (InfixExpression :operator "+" :left (IntegerLiteral :value 1) :right (InfixExpression :operator "*" :left (IntegerLiteral :value 2) :right (IntegerLiteral :value 3))))"#,
+        ),
+        (
+            "(1 + 2) * 3",
+            r#"(ExpressionStatement :expression (InfixExpression :operator "*" :left (InfixExpression :operator "+" :left (IntegerLiteral :value 1) :right (IntegerLiteral :value 2)) :right (IntegerLiteral :value 3)))"#,
+        ),
+        (
+            "-a[0]",
+            r#"(ExpressionStatement :expression (PrefixExpression :operator "-" :right (IndexExpression :left (Identifier :name "a") :index (IntegerLiteral :value 0))))"#,
+        ),
+        (
+            "!f(x, ...y)",
+            r#"(ExpressionStatement :expression (PrefixExpression :operator "!" :right (CallExpression :piped false :function (Identifier :name "f") :arguments [(Identifier :name "x") (SpreadExpression :value (Identifier :name "y"))])))"#,
+        ),
+        (
+            "let [a, ...b] = {\"k\": m.v};",
+            r#"(LetStatement :pattern (ArrayPattern :elements [(Identifier :name "a")] :rest (Identifier :name "b")) :value (HashLiteral :pairs [(Pair :key (StringLiteral :value "k") :value (MemberExpression :object (Identifier :name "m") :member (Identifier :name "v")))]))"#,
+        ),
+        (
+            "fn f(x, y = 1) { return x; }",
+            r#"(FunctionStatement :name (Identifier :name "f") :function (FunctionLiteral :name "f" :generator false :form "fn" :parameters [(RequiredParameter :name (Identifier :name "x")) (DefaultParameter :name (Identifier :name "y") :value (IntegerLiteral :value 1))] :body (BlockStatement :statements [(ReturnStatement :value (Identifier :name "x"))])))"#,
+        ),
+        (
+            "if (a) { 1 } else if (b) { 2 }",
+            r#"(ExpressionStatement :expression (IfExpression :condition (Identifier :name "a") :consequence (BlockStatement :statements [(ExpressionStatement :expression (IntegerLiteral :value 1))]) :else_ifs [(ElseIfBranch :condition (Identifier :name "b") :consequence (BlockStatement :statements [(ExpressionStatement :expression (IntegerLiteral :value 2))]))] :alternative null))"#,
+        ),
+        (
+            "match (x) { _ if true => 1 }",
+            r#"(ExpressionStatement :expression (MatchExpression :subject (Identifier :name "x") :arms [(MatchArm :pattern (WildcardPattern) :guard (BooleanLiteral :value true) :body (IntegerLiteral :value 1))]))"#,
+        ),
+        (
+            "1; 2",
+            "(ExpressionStatement :expression (IntegerLiteral :value 1))\n(ExpressionStatement :expression (IntegerLiteral :value 2))",
+        ),
+        ("", ""),
+    ];
+
+    for (input, expected) in tests {
+        let expected = if expected.is_empty() {
+            String::new()
+        } else {
+            format!("{}\n", expected)
+        };
+
+        assert_eq!(dump(input, Format::Sexpr), expected, "input: {}", input);
+    }
+}
+
+/// Tests dumping a program as an indented tree
+#[test]
+fn test_dump_tree() {
+    let input = "let x = f(1, \"a\");\ntry { x } catch (e) {}";
+
+    let expected = r#"Program
+  statements:
+    - LetStatement 1:1
+      pattern: Identifier 1:5 name="x"
+      value: CallExpression 1:10 piped=false
+        function: Identifier 1:9 name="f"
+        arguments:
+          - IntegerLiteral 1:11 value=1
+          - StringLiteral 1:14 value="a"
+    - ExpressionStatement 2:1
+      expression: TryExpression 2:1 finally=null
+        body: BlockStatement 2:5
+          statements:
+            - ExpressionStatement 2:7
+              expression: Identifier 2:7 name="x"
+        catch: CatchClause 2:11
+          parameter: Identifier 2:18 name="e"
+          body: BlockStatement 2:21
+            statements: []
+"#;
+
+    assert_eq!(dump(input, Format::Tree), expected);
+}
+
+/// Tests dumping a program as JSON
+#[test]
+fn test_dump_json() {
+    let input = "struct P { x }\nP { x: \"\\\" }";
+
+    let expected = r#"{
+  "kind": "Program",
+  "statements": [
+    {
+      "kind": "StructStatement",
+      "line": 1,
+      "column": 1,
+      "name": {
+        "kind": "Identifier",
+        "line": 1,
+        "column": 8,
+        "name": "P"
+      },
+      "fields": [
+        {
+          "kind": "Identifier",
+          "line": 1,
+          "column": 12,
+          "name": "x"
+        }
+      ]
+    },
+    {
+      "kind": "ExpressionStatement",
+      "line": 2,
+      "column": 1,
+      "expression": {
+        "kind": "RecordLiteral",
+        "line": 2,
+        "column": 3,
+        "name": {
+          "kind": "Identifier",
+          "line": 2,
+          "column": 1,
+          "name": "P"
+        },
+        "fields": [
+          {
+            "kind": "Pair",
+            "line": 2,
+            "column": 5,
+            "key": {
+              "kind": "Identifier",
+              "line": 2,
+              "column": 5,
+              "name": "x"
+            },
+            "value": {
+              "kind": "StringLiteral",
+              "line": 2,
+              "column": 8,
+              "value": "\\"
+            }
+          }
+        ]
+      }
+    }
+  ]
+}
+"#;
+
+    assert_eq!(dump(input, Format::Json), expected);
+    assert_eq!(
+        dump("", Format::Json),
+        "{\n  \"kind\": \"Program\",\n  \"statements\": []\n}\n"
+    );
+}
+
+/// Tests parsing the names of the formats
+#[test]
+fn test_format_names() {
+    let tests = vec![
+        ("tree", Ok(Format::Tree)),
+        ("sexpr", Ok(Format::Sexpr)),
+        ("json", Ok(Format::Json)),
+        (
+            "xml",
+            Err("unknown format: \"xml\", expected tree, sexpr or json".to_string()),
+        ),
+    ];
+
+    for (name, expected) in tests {
+        assert_eq!(name.parse::<Format>(), expected, "name: {}", name);
+    }
+}
@@ -0,0 +1,160 @@
+use super::{
+    errors::{Error, ErrorKind},
+    EvalResult, Evaluator,
+};
+use crate::object::{hash::HashPair, Object};
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
+
+/// How many steps are taken between checks of the deadline, as getting the current time is much
+/// slower than taking a step.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// The resources that an `Evaluator` is allowed to use, for running untrusted code. Each resource is
+/// unlimited when it is `None`, which is the default.
+///
+/// A budget is set with `Evaluator::set_budget`, and everything that is evaluated after that uses it up,
+/// including imported modules and macro expansion. Once any of it runs out, evaluation stops with a
+/// `BudgetExceeded` error, which can't be caught by a `try`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Budget {
+    /// How many steps evaluation is allowed to take. A step is the evaluation of a single expression.
+    pub fuel: Option<u64>,
+
+    /// The time by which evaluation has to have finished.
+    pub deadline: Option<Instant>,
+
+    /// Roughly how many bytes evaluation is allowed to allocate for strings, arrays and hashes. This
+    /// counts every allocation, even of values that have since been dropped.
+    pub memory: Option<usize>,
+}
+
+impl Budget {
+    /// Creates a `Budget` with no limits.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Limits how many steps evaluation is allowed to take.
+    pub fn with_fuel(self, fuel: u64) -> Self {
+        Self {
+            fuel: Some(fuel),
+            ..self
+        }
+    }
+
+    /// Limits evaluation to finishing by `deadline`.
+    pub fn with_deadline(self, deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+
+    /// Limits evaluation to finishing within `timeout` from now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Limits roughly how many bytes evaluation is allowed to allocate.
+    pub fn with_memory(self, memory: usize) -> Self {
+        Self {
+            memory: Some(memory),
+            ..self
+        }
+    }
+}
+
+/// Public API for budgets.
+impl Evaluator {
+    /// Sets the budget that evaluation is allowed to use from now on, replacing whatever was left of the
+    /// previous one. To give each call to `eval` its own budget, set one before each call.
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+        self.steps = 0;
+    }
+
+    /// Returns what is left of the budget, e.g. to bill for what evaluation used. Once the fuel or time
+    /// has run out, any further evaluation fails until a new budget is set.
+    pub fn remaining_budget(&self) -> Budget {
+        self.budget
+    }
+}
+
+impl Budget {
+    /// Takes a step of evaluation, which is the `steps`th since the budget was set, erroring instead if
+    /// the budget has no fuel or time left.
+    #[inline]
+    pub(crate) fn step(&mut self, steps: u64) -> EvalResult<()> {
+        if let Some(fuel) = &mut self.fuel {
+            *fuel = fuel.checked_sub(1).ok_or_else(|| budget_exceeded("fuel"))?;
+        }
+
+        if let Some(deadline) = self.deadline {
+            if steps.is_multiple_of(DEADLINE_CHECK_INTERVAL) && Instant::now() >= deadline {
+                return Err(budget_exceeded("time"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Uses up `bytes` of the memory budget, erroring instead if there isn't that much left.
+    pub(crate) fn allocate(&mut self, bytes: usize) -> EvalResult<()> {
+        if let Some(memory) = &mut self.memory {
+            *memory = memory
+                .checked_sub(bytes)
+                .ok_or_else(|| budget_exceeded("memory"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Uses up the memory budget for a string of `length` bytes.
+    pub(crate) fn allocate_string(&mut self, length: usize) -> EvalResult<()> {
+        self.allocate(length)
+    }
+
+    /// Uses up the memory budget for an array of `length` elements.
+    pub(crate) fn allocate_array(&mut self, length: usize) -> EvalResult<()> {
+        self.allocate(length.saturating_mul(mem::size_of::<Object>()))
+    }
+
+    /// Uses up the memory budget for a hash of `length` pairs.
+    pub(crate) fn allocate_hash(&mut self, length: usize) -> EvalResult<()> {
+        self.allocate(length.saturating_mul(mem::size_of::<HashPair>()))
+    }
+}
+
+impl Evaluator {
+    /// Takes a step of evaluation, erroring instead if the budget has no fuel or time left.
+    pub(crate) fn step(&mut self) -> EvalResult<()> {
+        self.steps += 1;
+        self.budget.step(self.steps)
+    }
+
+    /// Uses up the memory budget for a string of `length` bytes.
+    pub(crate) fn allocate_string(&mut self, length: usize) -> EvalResult<()> {
+        self.budget.allocate_string(length)
+    }
+
+    /// Uses up the memory budget for an array of `length` elements.
+    pub(crate) fn allocate_array(&mut self, length: usize) -> EvalResult<()> {
+        self.budget.allocate_array(length)
+    }
+
+    /// Uses up the memory budget for a hash of `length` pairs.
+    pub(crate) fn allocate_hash(&mut self, length: usize) -> EvalResult<()> {
+        self.budget.allocate_hash(length)
+    }
+}
+
+/// The error for running out of the `resource` part of the budget.
+fn budget_exceeded(resource: &str) -> Error {
+    Error::new(
+        ErrorKind::BudgetExceeded,
+        format!("{} budget exceeded", resource),
+    )
+}
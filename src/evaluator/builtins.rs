@@ -0,0 +1,252 @@
+use super::{
+    errors::{Error, ErrorKind},
+    iterators::iterate,
+    EvalResult, Evaluator,
+};
+use crate::{
+    gc,
+    object::{
+        array::Array,
+        boolean::Boolean,
+        builtin::{Builtin, BuiltinFunction, Runtime},
+        hash::{Hash, HashKey, HashPair},
+        integer::Integer,
+        iterator::Iter,
+        null::Null,
+        string::Str,
+        Object, Objective,
+    },
+};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+/// The builtins, which are functions that are written in Rust, as they can't be written in Monkey:
+///
+/// - `iter(iterable)`, an iterator over the values of an array, string, hash, range or iterator.
+/// - `next(iterator)`, the next value of an iterator, as a hash of the `"value"` and whether the
+///   iterator is `"done"`. Once an iterator is done, the value is `null`, apart from the first time
+///   that `next` finds a generator done, when it is the value that the generator returned.
+/// - `collect(iterable)`, an array of the values of an iterable.
+/// - `sort_by(iterable, key)`, an array of the values of an iterable, stably sorted by the keys that
+///   `key` returns for them, which have to be all integers or all strings.
+/// - `puts(values...)`, which prints each of the values on a line of its own to standard output, so
+///   that scripts can show what they did, and returns `null`.
+/// - `gc()`, which collects the garbage (see `gc::collect`), and returns a hash of how many objects it
+///   `"freed"`, how many are still `"live"`, and how many `"collections"` there have been so far.
+const BUILTINS: &[(&str, BuiltinFunction)] = &[
+    ("iter", iter),
+    ("next", next),
+    ("collect", collect),
+    ("sort_by", sort_by),
+    ("puts", puts),
+    ("gc", gc),
+];
+
+/// Looks up the builtin with the given name. Builtins are looked up after every environment, so
+/// anything that code binds shadows them.
+pub(crate) fn get_builtin(name: &str) -> Option<Builtin> {
+    BUILTINS
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(name, function)| Builtin::new(name, *function))
+}
+
+impl Evaluator {
+    /// Calls `builtin` with the given `arguments`. Like with functions, the builtin's name is added to
+    /// the stack trace of any error that happens inside of it.
+    pub(crate) fn call_builtin(
+        &mut self,
+        builtin: Builtin,
+        arguments: Vec<Object>,
+    ) -> EvalResult<Object> {
+        (builtin.function)(self, arguments).map_err(|mut error| {
+            error.trace.push(builtin.name.to_string());
+            error
+        })
+    }
+}
+
+impl Runtime for Evaluator {
+    fn next_value(&mut self, iterator: &Iter) -> EvalResult<Option<Object>> {
+        Evaluator::next_value(self, iterator)
+    }
+
+    fn allocate_array(&mut self, length: usize) -> EvalResult<()> {
+        Evaluator::allocate_array(self, length)
+    }
+
+    fn allocate_hash(&mut self, length: usize) -> EvalResult<()> {
+        Evaluator::allocate_hash(self, length)
+    }
+
+    fn call(&mut self, function: Object, arguments: Vec<Object>) -> EvalResult<Object> {
+        self.call_value(function, arguments)
+    }
+}
+
+/// The `iter(iterable)` builtin.
+fn iter(_: &mut dyn Runtime, arguments: Vec<Object>) -> EvalResult<Object> {
+    let [iterable] = expect_arguments(arguments)?;
+
+    Ok(Object::Iterator(iterate(iterable)?))
+}
+
+/// The `next(iterator)` builtin.
+fn next(runtime: &mut dyn Runtime, arguments: Vec<Object>) -> EvalResult<Object> {
+    let [iterator] = expect_arguments(arguments)?;
+
+    let Object::Iterator(iterator) = iterator else {
+        return Err(Error::new(
+            ErrorKind::Type,
+            format!(
+                "argument to next must be ITERATOR, got {}",
+                iterator.object_type()
+            ),
+        ));
+    };
+
+    let value = runtime.next_value(&iterator)?;
+    let done = Object::Boolean(Boolean::new(value.is_none()));
+
+    // The first time that a generator is found to be done, the value is what it returned.
+    let value = value
+        .or_else(|| iterator.state.borrow_mut().take_returned())
+        .unwrap_or(Object::Null(Null));
+
+    string_hash(runtime, [("value", value), ("done", done)])
+}
+
+/// The `collect(iterable)` builtin.
+fn collect(runtime: &mut dyn Runtime, arguments: Vec<Object>) -> EvalResult<Object> {
+    let [iterable] = expect_arguments(arguments)?;
+
+    let iterator = iterate(iterable)?;
+    let mut elements = Vec::new();
+
+    while let Some(value) = runtime.next_value(&iterator)? {
+        runtime.allocate_array(1)?;
+        elements.push(value);
+    }
+
+    Ok(Object::Array(Array::new(elements)))
+}
+
+/// The `sort_by(iterable, key)` builtin.
+fn sort_by(runtime: &mut dyn Runtime, arguments: Vec<Object>) -> EvalResult<Object> {
+    let [iterable, key] = expect_arguments(arguments)?;
+
+    let iterator = iterate(iterable)?;
+    let mut keyed = Vec::new();
+
+    while let Some(value) = runtime.next_value(&iterator)? {
+        runtime.allocate_array(1)?;
+        keyed.push((runtime.call(key.clone(), vec![value.clone()])?, value));
+    }
+
+    // Checking the keys up front means that comparing them can't fail halfway through the sort.
+    if let Some((first, _)) = keyed.first() {
+        let first = first.object_type();
+
+        for (key, _) in &keyed {
+            if !matches!(key, Object::Integer(_) | Object::String(_)) {
+                return Err(Error::new(
+                    ErrorKind::Type,
+                    format!(
+                        "keys of sort_by must be INTEGER or STRING, got {}",
+                        key.object_type()
+                    ),
+                ));
+            }
+
+            if key.object_type() != first {
+                return Err(Error::new(
+                    ErrorKind::Type,
+                    format!(
+                        "keys of sort_by must all have the same type, got {} and {}",
+                        first,
+                        key.object_type()
+                    ),
+                ));
+            }
+        }
+    }
+
+    // `sort_by` is a stable sort, so values with the same key keep their order.
+    keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+        (Object::Integer(a), Object::Integer(b)) => a.value.cmp(&b.value),
+        (Object::String(a), Object::String(b)) => a.value.cmp(&b.value),
+        _ => unreachable!("the keys have been checked"),
+    });
+
+    Ok(Object::Array(Array::new(
+        keyed.into_iter().map(|(_, value)| value).collect(),
+    )))
+}
+
+/// The `puts(values...)` builtin.
+fn puts(_: &mut dyn Runtime, arguments: Vec<Object>) -> EvalResult<Object> {
+    let mut stdout = io::stdout().lock();
+
+    for argument in arguments {
+        // Output that can't be written (e.g. to a closed pipe) is dropped, like a shell's `echo`.
+        let _ = writeln!(stdout, "{}", argument);
+    }
+
+    Ok(Object::Null(Null))
+}
+
+/// The `gc()` builtin.
+fn gc(runtime: &mut dyn Runtime, arguments: Vec<Object>) -> EvalResult<Object> {
+    let [] = expect_arguments(arguments)?;
+
+    let freed = gc::collect();
+    let stats = gc::stats();
+
+    let count = |count: u64| Object::Integer(Integer::new(count.try_into().unwrap_or(i64::MAX)));
+
+    string_hash(
+        runtime,
+        [
+            ("freed", count(freed as u64)),
+            ("live", count(stats.live as u64)),
+            ("collections", count(stats.collections)),
+        ],
+    )
+}
+
+/// Returns a hash of `pairs`, whose keys are strings.
+fn string_hash<const N: usize>(
+    runtime: &mut dyn Runtime,
+    pairs: [(&str, Object); N],
+) -> EvalResult<Object> {
+    let pairs = pairs
+        .into_iter()
+        .map(|(key, value)| {
+            (
+                HashKey::String(key.to_string()),
+                HashPair {
+                    key: Object::String(Str::new(key)),
+                    value,
+                },
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    runtime.allocate_hash(pairs.len())?;
+
+    Ok(Object::Hash(Hash::new(pairs)))
+}
+
+/// Checks that a builtin was called with exactly `N` arguments, and returns them.
+fn expect_arguments<const N: usize>(arguments: Vec<Object>) -> EvalResult<[Object; N]> {
+    let got = arguments.len();
+
+    arguments.try_into().map_err(|_| {
+        Error::new(
+            ErrorKind::Argument,
+            format!("wrong number of arguments: want={}, got={}", N, got),
+        )
+    })
+}
@@ -0,0 +1,213 @@
+use crate::{
+    gc::{Trace, Tracer},
+    object::Object,
+    token::Position,
+};
+use std::{fmt::Display, path::Path, rc::Rc};
+
+/// Custom error type for the evaluator. In the original implementation, runtime errors are an `Error`
+/// object that is passed around like any other value, and has to be checked for after evaluating just
+/// about anything. Here, like in the parser, errors are a separate type that is returned through a
+/// `Result`, so that the `?` operator can do all of the checking for us.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    /// What kind of error this is.
+    pub kind: ErrorKind,
+
+    /// The message describing what went wrong.
+    pub message: String,
+
+    /// Where in the source code the error happened, if we know.
+    pub position: Option<Position>,
+
+    /// The path of the file that the position is in, if we know. This is filled in as the error leaves
+    /// the function or module that it happened in, so it is missing for code that isn't from a file.
+    pub file: Option<Rc<Path>>,
+
+    /// The names of the functions that the error was raised in, innermost call first. This is filled
+    /// in as the error unwinds out of each function call, and makes up the stack trace.
+    pub trace: Vec<String>,
+
+    /// The value that was thrown, if the error was raised by a `throw` of something that isn't an error
+    /// object. A `catch` gives this value back through the error object's `value` field.
+    pub value: Option<Box<Object>>,
+}
+
+impl Error {
+    /// Creates a new `Error` of the given `kind` with the given `message`, and an empty stack trace.
+    pub fn new<S: ToString>(kind: ErrorKind, message: S) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+            position: None,
+            file: None,
+            trace: vec![],
+            value: None,
+        }
+    }
+
+    /// Sets where in the source code the error happened, unless that is already known. As errors are
+    /// given a position as they bubble up through the AST, this means that the position ends up being
+    /// that of the innermost node that the error happened in, which is the most precise one.
+    pub fn at(mut self, position: Position) -> Self {
+        self.position.get_or_insert(position);
+        self
+    }
+
+    /// Sets the file that the error's position is in to `file`, if the error has a position whose file
+    /// isn't already known. This is done as the error leaves the code from `file`, which is where the
+    /// innermost position that it was given is.
+    pub(crate) fn locate(&mut self, file: Option<&Rc<Path>>) {
+        if self.position.is_some() && self.file.is_none() {
+            self.file = file.cloned();
+        }
+    }
+
+    /// Creates the `Return` "error" for a `return` of `value`, which unwinds evaluation to the end of
+    /// the function (or program) that the `return` is in, from wherever in it the `return` is.
+    pub(crate) fn returning(value: Object) -> Self {
+        Self {
+            value: Some(Box::new(value)),
+            ..Self::new(ErrorKind::Return, "return outside of a function")
+        }
+    }
+
+    /// Returns the value of the `return` that this is unwinding for, or the error back if it isn't a
+    /// `Return`. This is what a function call (or program) does with the errors that leave its body.
+    pub(crate) fn returned(self) -> Result<Object, Self> {
+        match (self.kind, self.value) {
+            (ErrorKind::Return, Some(value)) => Ok(*value),
+            (kind, value) => Err(Self {
+                kind,
+                value,
+                ..self
+            }),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(position) = self.position {
+            write!(f, "{}: ", position)?;
+        }
+
+        write!(f, "{}", self.message)?;
+
+        // Runaway recursion makes for very long traces, so repeats of the same function are collapsed.
+        let mut frames = self.trace.iter().peekable();
+
+        while let Some(function) = frames.next() {
+            write!(f, "\n    in {}", function)?;
+
+            let mut repeats = 0;
+
+            while frames.next_if_eq(&function).is_some() {
+                repeats += 1;
+            }
+
+            if repeats > 0 {
+                write!(f, " (and {} more times)", repeats)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The kinds of errors that can happen while evaluating. The kind of an error that is caught by a
+/// `catch` is available as the `kind` field of the error object, so that code can decide which errors
+/// it can recover from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// An identifier was used that isn't bound to anything.
+    Name,
+
+    /// An operation was given a value of the wrong type.
+    Type,
+
+    /// An arithmetic operation overflowed or divided by zero.
+    Arithmetic,
+
+    /// A function was called with the wrong number of arguments.
+    Argument,
+
+    /// A value didn't match the pattern it was matched against.
+    Match,
+
+    /// A field or export was accessed that doesn't exist, or a record literal is missing a field.
+    Member,
+
+    /// A module couldn't be loaded, parsed or imported.
+    Module,
+
+    /// A macro was used wrongly.
+    Macro,
+
+    /// Something was used where it isn't allowed, e.g. a spread outside of a call or array literal.
+    Syntax,
+
+    /// The error was raised by a `throw`.
+    Thrown,
+
+    /// Calls or expressions were nested deeper than the evaluator allows.
+    Limit,
+
+    /// Evaluation ran out of its budget (see `budget::Budget`).
+    BudgetExceeded,
+
+    /// Evaluation was stopped through an `InterruptHandle`.
+    Interrupted,
+
+    /// Not an error, but a `return` unwinding to the end of the function that it is in (see
+    /// `Error::returning`). This never escapes a function call, and can't be caught.
+    Return,
+}
+
+impl ErrorKind {
+    /// Returns whether errors of this kind can be caught by a `try`. Running out of budget and being
+    /// interrupted can't be, as they are meant to stop evaluation altogether, and neither can a
+    /// `return`, which isn't an error.
+    pub fn is_catchable(&self) -> bool {
+        !self.is_fatal() && *self != ErrorKind::Return
+    }
+
+    /// Returns whether errors of this kind stop evaluation altogether, so that they aren't followed by
+    /// `finally` blocks either.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, ErrorKind::BudgetExceeded | ErrorKind::Interrupted)
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ErrorKind::Name => "name",
+                ErrorKind::Type => "type",
+                ErrorKind::Arithmetic => "arithmetic",
+                ErrorKind::Argument => "argument",
+                ErrorKind::Match => "match",
+                ErrorKind::Member => "member",
+                ErrorKind::Module => "module",
+                ErrorKind::Macro => "macro",
+                ErrorKind::Syntax => "syntax",
+                ErrorKind::Thrown => "thrown",
+                ErrorKind::Limit => "limit",
+                ErrorKind::BudgetExceeded => "budget exceeded",
+                ErrorKind::Interrupted => "interrupted",
+                ErrorKind::Return => "return",
+            }
+        )
+    }
+}
+
+impl Trace for Error {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(value) = &self.value {
+            value.trace(tracer);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use super::{
+    errors::{Error, ErrorKind},
+    EvalResult, Evaluator,
+};
+use crate::{
+    ast::{
+        expressions::{CatchClause, TryExpression},
+        statements::ThrowStatement,
+    },
+    gc,
+    object::{environment::Environment, error::ErrorValue, null::Null, Object},
+};
+use std::{mem, rc::Rc};
+
+impl Evaluator {
+    /// Evaluates a `ThrowStatement`, raising the error for its value (see `thrown`).
+    pub(crate) fn eval_throw(&mut self, throw: &ThrowStatement) -> EvalResult<Option<Object>> {
+        let value = self.eval_expression(&throw.value)?;
+
+        Err(thrown(value).at(throw.token.position))
+    }
+
+    /// Evaluates a `TryExpression`. If evaluating the body fails, the error is caught by the `catch`
+    /// clause (if there is one). The `finally` block is then evaluated however the body or `catch`
+    /// block was left, whether that was normally, by an error, or by a `return`. If the `finally` block
+    /// itself returns or fails, that takes the place of the result of the body or `catch` block.
+    ///
+    /// Errors that stop evaluation altogether (see `ErrorKind::is_fatal`) aren't followed by the
+    /// `finally` block either.
+    pub(crate) fn eval_try(&mut self, try_expression: &TryExpression) -> EvalResult<Object> {
+        // Nothing in a `try` is in tail position, as the `catch` and `finally` blocks have to be able
+        // to run after it.
+        let tail_returns = mem::replace(&mut self.tail_returns, false);
+        let result = self.eval_try_blocks(try_expression);
+        self.tail_returns = tail_returns;
+
+        result
+    }
+
+    /// Evaluates the blocks of a `TryExpression`.
+    fn eval_try_blocks(&mut self, try_expression: &TryExpression) -> EvalResult<Object> {
+        let result = match (self.eval_block(&try_expression.body), &try_expression.catch) {
+            (Err(error), Some(catch)) if error.kind.is_catchable() => self.eval_catch(catch, error),
+            (result, _) => result,
+        };
+
+        let result = match result {
+            Err(error) if error.kind.is_fatal() => return Err(error),
+            result => result,
+        };
+
+        if let Some(finally) = &try_expression.finally {
+            self.eval_block(finally)?;
+        }
+
+        Ok(result?.unwrap_or(Object::Null(Null)))
+    }
+
+    /// Evaluates the body of a `catch` clause, with the caught `error` bound to its parameter as an
+    /// error object.
+    fn eval_catch(&mut self, catch: &CatchClause, error: Error) -> EvalResult<Option<Object>> {
+        let mut env = Environment::new_enclosed(Rc::clone(&self.env));
+        env.bind(&catch.parameter, Object::Error(ErrorValue::new(error)));
+
+        // Swap in the clause's environment while evaluating the body, and make sure to swap the previous
+        // environment back in afterwards, whether or not that succeeded.
+        let previous = mem::replace(&mut self.env, gc::alloc(env));
+        let result = self.eval_block(&catch.body);
+        self.env = previous;
+
+        result
+    }
+}
+
+/// Returns the error that throwing `value` raises. Throwing an error object (e.g. one bound by a
+/// `catch`) raises the error that it holds again, so it keeps its original kind and position. Any other
+/// value is raised as a new error, with the value (or for strings, their contents) as the message.
+pub(crate) fn thrown(value: Object) -> Error {
+    match value {
+        Object::Error(error) => (*error.error).clone(),
+        value => {
+            let message = match &value {
+                Object::String(string) => string.value.clone(),
+                value => value.to_string(),
+            };
+
+            Error {
+                value: Some(Box::new(value)),
+                ..Error::new(ErrorKind::Thrown, message)
+            }
+        }
+    }
+}
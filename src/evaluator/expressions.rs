@@ -1,35 +1,519 @@
+use super::{
+    builtins::get_builtin,
+    errors::{Error, ErrorKind},
+    macros::unexpected_macro,
+    EvalResult, Evaluator,
+};
 use crate::{
     ast::{
-        expressions::{Expression, PrefixExpression},
-        operators::{PrefixOperator, PrefixOperatorType},
+        expressions::{
+            CallExpression, Expression, FunctionLiteral, HashLiteral, IfExpression,
+            IndexExpression, InfixExpression, Parameter, PrefixExpression,
+        },
+        operators::{InfixOperator, InfixOperatorType, PrefixOperator, PrefixOperatorType},
+        statements::BlockStatement,
+    },
+    gc,
+    object::{
+        array::Array,
+        boolean::Boolean,
+        environment::Environment,
+        function::Function,
+        hash::{Hash, HashKey, HashPair},
+        integer::Integer,
+        null::Null,
+        range::Range,
+        string::Str,
+        tail_call::TailCall,
+        Object, Objective,
     },
-    object::{boolean::Boolean, integer::Integer, null::Null, Object},
 };
+use std::{collections::BTreeMap, mem, rc::Rc};
+
+impl Evaluator {
+    /// Evaluates an `Expression`. If evaluating the expression fails, and the error doesn't know where
+    /// it happened yet, it happened at this expression.
+    pub(crate) fn eval_expression(&mut self, expression: &Expression) -> EvalResult<Object> {
+        self.nested(|evaluator| evaluator.eval_expression_inner(expression))
+            .map_err(|error| error.at(expression.position()))
+    }
+
+    /// Evaluates an `Expression` that is in tail position, i.e. whose value is the value of the function
+    /// that it is in. If it is a call, the function and arguments are evaluated, but instead of making
+    /// the call, a `TailCall` is returned for the enclosing function call to make (see `call_function`).
+    /// The branches of `if` and `match` expressions in tail position are in tail position too.
+    pub(crate) fn eval_tail_expression(&mut self, expression: &Expression) -> EvalResult<Object> {
+        self.nested(|evaluator| evaluator.eval_tail_expression_inner(expression))
+            .map_err(|error| error.at(expression.position()))
+    }
+
+    /// Evaluates an `Expression` in tail position, without attaching its position to any errors.
+    fn eval_tail_expression_inner(&mut self, expression: &Expression) -> EvalResult<Object> {
+        match expression {
+            Expression::Call(call) if !is_quote(call) => {
+                let function = self.eval_expression(&call.function)?;
+
+                let arguments = self.eval_expression_list(&call.arguments)?;
+
+                match function {
+                    Object::Function(function) => Ok(Object::TailCall(TailCall::new(
+                        function,
+                        arguments,
+                        call.token.position,
+                    ))),
 
-/// Evaluates an `Expression`.
-pub fn eval_expression(expression: Expression) -> Option<Object> {
-    match expression {
-        Expression::Integer(integer) => Some(Object::Integer(Integer::new(integer.value))),
+                    // Builtins don't evaluate any Monkey code of their own, so they are just called.
+                    Object::Builtin(builtin) => self.call_builtin(builtin, arguments),
 
-        Expression::Boolean(boolean) => Some(Object::Boolean(Boolean::new(boolean.value))),
+                    function => Err(not_a_function(&function)),
+                }
+            }
 
-        Expression::Prefix(PrefixExpression {
-            right,
-            operator: PrefixOperator {
-                r#type: operator, ..
-            },
-            ..
-        }) => {
-            let right = eval_expression(*right)?;
-            eval_prefix(operator, right)
+            Expression::If(if_expression) => self.eval_if(if_expression, true),
+
+            Expression::Match(match_expression) => self.eval_match(match_expression, true),
+
+            expression => self.eval_expression_inner(expression),
         }
+    }
+
+    /// Evaluates an `Expression`, without attaching its position to any errors.
+    fn eval_expression_inner(&mut self, expression: &Expression) -> EvalResult<Object> {
+        match expression {
+            Expression::Integer(integer) => Ok(Object::Integer(Integer::new(integer.value))),
+
+            Expression::Boolean(boolean) => Ok(Object::Boolean(Boolean::new(boolean.value))),
+
+            Expression::String(string) => {
+                self.allocate_string(string.value.len())?;
+                Ok(Object::String(Str::new(string.value.clone())))
+            }
+
+            Expression::Identifier(identifier) => self
+                .env
+                .borrow()
+                .lookup(identifier)
+                .or_else(|| get_builtin(&identifier.value).map(Object::Builtin))
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::Name,
+                        format!("identifier not found: {}", identifier.value),
+                    )
+                }),
+
+            Expression::Prefix(PrefixExpression {
+                right,
+                operator:
+                    PrefixOperator {
+                        r#type: operator, ..
+                    },
+                ..
+            }) => {
+                let right = self.eval_expression(right)?;
+                eval_prefix(*operator, right)
+            }
 
-        _ => todo!(),
+            Expression::Infix(InfixExpression {
+                left,
+                operator: InfixOperator {
+                    r#type: operator, ..
+                },
+                right,
+                ..
+            }) => {
+                let left = self.eval_expression(left)?;
+                let right = self.eval_expression(right)?;
+
+                // Concatenating two strings allocates a new one.
+                if let (Object::String(left), Object::String(right)) = (&left, &right) {
+                    if *operator == InfixOperatorType::Add {
+                        self.allocate_string(left.value.len() + right.value.len())?;
+                    }
+                }
+
+                eval_infix(*operator, left, right)
+            }
+
+            Expression::If(if_expression) => self.eval_if(if_expression, false),
+
+            Expression::Try(try_expression) => self.eval_try(try_expression),
+
+            Expression::For(for_expression) => self.eval_for(for_expression),
+
+            Expression::Function(function) => Ok(self.eval_function(function)),
+
+            Expression::Call(call) => self.eval_call(call),
+
+            Expression::Array(array) => {
+                let elements = self.eval_expression_list(&array.elements)?;
+                self.allocate_array(elements.len())?;
+                Ok(Object::Array(Array::new(elements)))
+            }
+
+            Expression::Index(index) => self.eval_index(index),
+
+            Expression::Member(member) => self.eval_member(member),
+
+            Expression::Hash(hash) => self.eval_hash(hash),
+
+            Expression::Record(record) => self.eval_record(record),
+
+            Expression::Match(match_expression) => self.eval_match(match_expression, false),
+
+            Expression::Macro(macro_literal) => Err(unexpected_macro(macro_literal)),
+
+            // Spreads are handled by `eval_expression_list`, as they evaluate to multiple values, so
+            // they can't be evaluated on their own.
+            Expression::Spread(spread) => Err(Error::new(ErrorKind::Syntax, format!(
+                "unexpected spread: {}, spreads are only allowed in call arguments and array literals",
+                spread
+            ))),
+        }
+    }
+
+    /// Evaluates a list of expressions (call arguments or array literal elements) from left to right.
+    /// Any spreads in the list have their array's elements inserted into the list in their place.
+    fn eval_expression_list(&mut self, expressions: &[Expression]) -> EvalResult<Vec<Object>> {
+        let mut objects = Vec::with_capacity(expressions.len());
+
+        for expression in expressions {
+            let Expression::Spread(spread) = expression else {
+                objects.push(self.eval_expression(expression)?);
+                continue;
+            };
+
+            match self.eval_expression(&spread.value)? {
+                Object::Array(array) => objects.extend(array.elements),
+                object => {
+                    return Err(Error::new(
+                        ErrorKind::Type,
+                        format!(
+                            "cannot spread {}, only arrays can be spread",
+                            object.object_type()
+                        ),
+                    ))
+                }
+            }
+        }
+
+        Ok(objects)
+    }
+
+    /// Evaluates a `HashLiteral`. The pairs are evaluated in order, keys before values.
+    fn eval_hash(&mut self, hash: &HashLiteral) -> EvalResult<Object> {
+        let mut pairs = BTreeMap::new();
+
+        for (key, value) in &hash.pairs {
+            let key_object = self.eval_expression(key)?;
+
+            let hash_key = HashKey::try_from(&key_object).map_err(|object_type| {
+                Error::new(
+                    ErrorKind::Type,
+                    format!("unusable as hash key: {}", object_type),
+                )
+                .at(key.position())
+            })?;
+
+            let value = self.eval_expression(value)?;
+
+            pairs.insert(
+                hash_key,
+                HashPair {
+                    key: key_object,
+                    value,
+                },
+            );
+        }
+
+        self.allocate_hash(pairs.len())?;
+
+        Ok(Object::Hash(Hash::new(pairs)))
+    }
+
+    /// Evaluates an `IndexExpression`.
+    fn eval_index(&mut self, index: &IndexExpression) -> EvalResult<Object> {
+        let left = self.eval_expression(&index.left)?;
+        let index = self.eval_expression(&index.index)?;
+
+        eval_index(left, index)
+    }
+
+    /// Evaluates an `IfExpression`. If none of its branches are chosen (see `choose_branch`), or the
+    /// chosen block doesn't produce a value, this evaluates to `null`. If `tail` is set, the chosen block
+    /// is in tail position.
+    fn eval_if(&mut self, if_expression: &IfExpression, tail: bool) -> EvalResult<Object> {
+        let result = match self.choose_branch(if_expression)? {
+            Some(block) if tail => self.eval_tail_block(block)?,
+            Some(block) => self.eval_block(block)?,
+            None => None,
+        };
+
+        Ok(result.unwrap_or(Object::Null(Null)))
+    }
+
+    /// Chooses the block of an `IfExpression` to evaluate. The conditions of the `if` and each `else if`
+    /// branch are checked in order, and the block of the first branch with a truthy condition is chosen.
+    /// If none of them are truthy, the `else` block is chosen instead, if there is one.
+    pub(crate) fn choose_branch<'a>(
+        &mut self,
+        if_expression: &'a IfExpression,
+    ) -> EvalResult<Option<&'a BlockStatement>> {
+        let branches = [(&if_expression.condition, &if_expression.consequence)]
+            .into_iter()
+            .chain(
+                if_expression
+                    .else_ifs
+                    .iter()
+                    .map(|else_if| (&else_if.condition, &else_if.consequence)),
+            );
+
+        for (condition, consequence) in branches {
+            if is_truthy(&self.eval_expression(condition)?) {
+                return Ok(Some(consequence));
+            }
+        }
+
+        Ok(if_expression.alternative.as_ref())
+    }
+
+    /// Evaluates a `FunctionLiteral`, creating a function that closes over the current environment.
+    fn eval_function(&mut self, function: &FunctionLiteral) -> Object {
+        Object::Function(Function::new(
+            Rc::new(function.clone()),
+            Rc::clone(&self.env),
+            self.file.clone(),
+        ))
+    }
+
+    /// Evaluates a `CallExpression`. The function is evaluated first, then the arguments from left to
+    /// right, and then the function is applied to the arguments.
+    fn eval_call(&mut self, call: &CallExpression) -> EvalResult<Object> {
+        // `quote` isn't a function, as it needs the AST of its argument rather than its value.
+        if is_quote(call) {
+            return self.eval_quote(call);
+        }
+
+        let function = self.eval_expression(&call.function)?;
+
+        let arguments = self.eval_expression_list(&call.arguments)?;
+
+        self.call_value(function, arguments)
+    }
+
+    /// Calls `function`, which has to be a function or builtin, with the given `arguments`.
+    pub(crate) fn call_value(
+        &mut self,
+        function: Object,
+        arguments: Vec<Object>,
+    ) -> EvalResult<Object> {
+        match function {
+            Object::Function(function) => self.call_function(function, arguments),
+            Object::Builtin(builtin) => self.call_builtin(builtin, arguments),
+            function => Err(not_a_function(&function)),
+        }
+    }
+
+    /// Calls `function` with the given `arguments`. The function's body is evaluated in a new
+    /// environment, which is enclosed by the environment the function was defined in, and has the
+    /// parameters bound to the arguments. If an error happens inside of the function, the function's
+    /// name is added to the error's stack trace.
+    ///
+    /// If the function returns a tail call, that call is made here in a loop, in place of the function
+    /// that made it, so that tail recursion runs in constant Rust stack space. As the calling function
+    /// is gone by then, only the function that was tail called ends up in the stack trace.
+    pub(crate) fn call_function(
+        &mut self,
+        function: Function,
+        arguments: Vec<Object>,
+    ) -> EvalResult<Object> {
+        if self.call_depth >= self.max_call_depth {
+            return Err(Error::new(
+                ErrorKind::Limit,
+                format!("maximum call depth of {} exceeded", self.max_call_depth),
+            ));
+        }
+
+        self.call_depth += 1;
+        let result = self.call_function_inner(function, arguments);
+        self.call_depth -= 1;
+
+        result
+    }
+
+    /// Calls `function` like `call_function`, without counting it towards the call depth.
+    fn call_function_inner(
+        &mut self,
+        mut function: Function,
+        mut arguments: Vec<Object>,
+    ) -> EvalResult<Object> {
+        let mut position = None;
+
+        loop {
+            let env = Environment::new_enclosed(Rc::clone(&function.env));
+
+            // Swap in the function's environment (and file) while binding the arguments and evaluating
+            // the body, and make sure to swap the caller's back in afterwards, whether or not that
+            // succeeded. Arguments are bound in the function's environment so that default values can
+            // refer to the parameters that come before them.
+            let previous = mem::replace(&mut self.env, gc::alloc(env));
+            let previous_file = mem::replace(&mut self.file, function.file.clone());
+            let tail_returns = mem::replace(&mut self.tail_returns, true);
+            let result = self
+                .bind_arguments(&function.literal.parameters, arguments)
+                .and_then(|_| match function.literal.generator {
+                    // Calling a generator only binds its arguments, the body is evaluated as the
+                    // generator is iterated over.
+                    true => Ok(Some(
+                        self.make_generator(function.clone(), Rc::clone(&self.env)),
+                    )),
+                    false => self.eval_tail_block(&function.literal.body),
+                });
+            self.env = previous;
+            self.file = previous_file;
+            self.tail_returns = tail_returns;
+
+            // A `return` only returns from the function it is in, so it stops unwinding here.
+            let object = match result.or_else(|error| error.returned().map(Some)) {
+                Ok(Some(object)) => object,
+                Ok(None) => Object::Null(Null),
+                Err(mut error) => {
+                    error.locate(function.file.as_ref());
+                    error.trace.push(function.name().to_string());
+
+                    // Errors in a tail call happened at the tail call, not at the original call.
+                    return Err(match position {
+                        Some(position) => error.at(position),
+                        None => error,
+                    });
+                }
+            };
+
+            let Object::TailCall(tail_call) = object else {
+                return Ok(object);
+            };
+
+            function = tail_call.function;
+            arguments = tail_call.arguments;
+            position = Some(tail_call.position);
+        }
+    }
+}
+
+/// Returns whether `call` is a call to `quote`.
+pub(crate) fn is_quote(call: &CallExpression) -> bool {
+    matches!(call.function.as_ref(), Expression::Identifier(name) if name.value == "quote")
+}
+
+/// Creates the error for calling `object`, which isn't a function.
+pub(crate) fn not_a_function(object: &Object) -> Error {
+    Error::new(
+        ErrorKind::Type,
+        format!("not a function: {}", object.object_type()),
+    )
+}
+
+impl Evaluator {
+    /// Binds `arguments` to `parameters` in the current environment. Required parameters and parameters
+    /// with default values are bound to the arguments in order, with default values being evaluated for
+    /// any that are missing. A rest parameter is bound to an array of whatever arguments are left over.
+    pub(crate) fn bind_arguments(
+        &mut self,
+        parameters: &[Parameter],
+        arguments: Vec<Object>,
+    ) -> EvalResult<()> {
+        check_arity(parameters, arguments.len())?;
+
+        let mut arguments = arguments.into_iter();
+
+        for parameter in parameters {
+            let value = match parameter {
+                Parameter::Required(_) => arguments.next().unwrap_or(Object::Null(Null)),
+
+                Parameter::Default { value, .. } => match arguments.next() {
+                    Some(argument) => argument,
+                    None => self.eval_expression(value)?,
+                },
+
+                Parameter::Rest { .. } => {
+                    let elements = arguments.by_ref().collect::<Vec<_>>();
+                    self.allocate_array(elements.len())?;
+                    Object::Array(Array::new(elements))
+                }
+            };
+
+            self.env.borrow_mut().bind(parameter.name(), value);
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks that `got` arguments are enough, and not too many, for `parameters`. With a rest parameter,
+/// there is no upper limit.
+pub(crate) fn check_arity(parameters: &[Parameter], got: usize) -> EvalResult<()> {
+    let required = parameters
+        .iter()
+        .filter(|parameter| matches!(parameter, Parameter::Required(_)))
+        .count();
+    let has_rest = matches!(parameters.last(), Some(Parameter::Rest { .. }));
+    let positional = parameters.len() - usize::from(has_rest);
+
+    if got < required || (!has_rest && got > positional) {
+        let want = if has_rest {
+            format!("at least {}", required)
+        } else if required == positional {
+            required.to_string()
+        } else {
+            format!("{} to {}", required, positional)
+        };
+
+        return Err(Error::new(
+            ErrorKind::Argument,
+            format!("wrong number of arguments: want={}, got={}", want, got),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns whether an object counts as `true` when used as a condition. Only `false` and `null` are
+/// falsy, everything else is truthy.
+pub(crate) fn is_truthy(object: &Object) -> bool {
+    !matches!(
+        object,
+        Object::Boolean(Boolean { value: false }) | Object::Null(_)
+    )
+}
+
+/// Indexes `left` with `index`. Indexing an array out of bounds, or a hash with a key that it doesn't
+/// have, evaluates to `null`.
+pub(crate) fn eval_index(left: Object, index: Object) -> EvalResult<Object> {
+    match (left, index) {
+        (Object::Array(array), Object::Integer(Integer { value })) => Ok(usize::try_from(value)
+            .ok()
+            .and_then(|value| array.elements.get(value).cloned())
+            .unwrap_or(Object::Null(Null))),
+
+        (Object::Hash(hash), index) => {
+            let key = HashKey::try_from(&index).map_err(|object_type| {
+                Error::new(
+                    ErrorKind::Type,
+                    format!("unusable as hash key: {}", object_type),
+                )
+            })?;
+
+            Ok(hash.get(&key).cloned().unwrap_or(Object::Null(Null)))
+        }
+
+        (left, _) => Err(Error::new(
+            ErrorKind::Type,
+            format!("index operator not supported: {}", left.object_type()),
+        )),
     }
 }
 
 /// Evaluates a `PrefixExpression`.
-fn eval_prefix(operator: PrefixOperatorType, right: Object) -> Option<Object> {
+pub(crate) fn eval_prefix(operator: PrefixOperatorType, right: Object) -> EvalResult<Object> {
     match operator {
         PrefixOperatorType::Bang => eval_op_bang(right),
 
@@ -39,22 +523,102 @@ fn eval_prefix(operator: PrefixOperatorType, right: Object) -> Option<Object> {
 
 /// Evaluates the `!` operator. This operator defaults to `false`, except for `null` and `false`, which
 /// it converts to `true`.
-fn eval_op_bang(right: Object) -> Option<Object> {
+fn eval_op_bang(right: Object) -> EvalResult<Object> {
+    Ok(Object::Boolean(Boolean::new(!is_truthy(&right))))
+}
+
+/// Evaluates the `-` operator. This operator only works on `Integer`s, and negates their value.
+fn eval_op_neg(right: Object) -> EvalResult<Object> {
     match right {
-        Object::Boolean(Boolean { value }) => Some(Object::Boolean(Boolean::new(!value))),
+        Object::Integer(Integer { value }) => value
+            .checked_neg()
+            .map(|value| Object::Integer(Integer::new(value)))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Arithmetic,
+                    format!("integer overflow: -{}", value),
+                )
+            }),
+
+        _ => Err(Error::new(
+            ErrorKind::Type,
+            format!("unknown operator: -{}", right.object_type()),
+        )),
+    }
+}
+
+/// Evaluates an `InfixExpression`.
+pub(crate) fn eval_infix(
+    operator: InfixOperatorType,
+    left: Object,
+    right: Object,
+) -> EvalResult<Object> {
+    match (left, right) {
+        (Object::Integer(left), Object::Integer(right)) => {
+            eval_integer_infix(operator, left.value, right.value)
+        }
+
+        (Object::String(left), Object::String(right)) if operator == InfixOperatorType::Add => {
+            Ok(Object::String(Str::new(left.value + &right.value)))
+        }
+
+        (left, right) if left.object_type() != right.object_type() => Err(Error::new(
+            ErrorKind::Type,
+            format!(
+                "type mismatch: {} {} {}",
+                left.object_type(),
+                operator,
+                right.object_type()
+            ),
+        )),
+
+        // Any other two values of the same type can be compared for equality.
+        (left, right) if operator == InfixOperatorType::Eq => {
+            Ok(Object::Boolean(Boolean::new(left == right)))
+        }
 
-        Object::Null(Null) => Some(Object::Boolean(Boolean::new(true))),
+        (left, right) if operator == InfixOperatorType::NotEq => {
+            Ok(Object::Boolean(Boolean::new(left != right)))
+        }
 
-        _ => Some(Object::Boolean(Boolean::new(false))),
+        (left, right) => Err(Error::new(
+            ErrorKind::Type,
+            format!(
+                "unknown operator: {} {} {}",
+                left.object_type(),
+                operator,
+                right.object_type()
+            ),
+        )),
     }
 }
 
-/// Evaluates the `-` operator. This operator converts the `right` object to an `Integer` and negates
-/// its value.
-fn eval_op_neg(right: Object) -> Option<Object> {
-    match right {
-        Object::Integer(Integer { value }) => Some(Object::Integer(Integer::new(-value))),
+/// Evaluates an `InfixExpression` where both sides are integers. Arithmetic is checked, so that
+/// overflowing or dividing by zero is a runtime error instead of a panic.
+fn eval_integer_infix(operator: InfixOperatorType, left: i64, right: i64) -> EvalResult<Object> {
+    let arithmetic = |result: Option<i64>| {
+        result
+            .map(|value| Object::Integer(Integer::new(value)))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Arithmetic,
+                    format!("integer overflow: {} {} {}", left, operator, right),
+                )
+            })
+    };
 
-        _ => Some(Object::Null(Null)),
+    match operator {
+        InfixOperatorType::Add => arithmetic(left.checked_add(right)),
+        InfixOperatorType::Sub => arithmetic(left.checked_sub(right)),
+        InfixOperatorType::Mul => arithmetic(left.checked_mul(right)),
+        InfixOperatorType::Div if right == 0 => {
+            Err(Error::new(ErrorKind::Arithmetic, "division by zero"))
+        }
+        InfixOperatorType::Div => arithmetic(left.checked_div(right)),
+        InfixOperatorType::Lt => Ok(Object::Boolean(Boolean::new(left < right))),
+        InfixOperatorType::Gt => Ok(Object::Boolean(Boolean::new(left > right))),
+        InfixOperatorType::Eq => Ok(Object::Boolean(Boolean::new(left == right))),
+        InfixOperatorType::NotEq => Ok(Object::Boolean(Boolean::new(left != right))),
+        InfixOperatorType::Range => Ok(Object::Range(Range::new(left, right))),
     }
 }
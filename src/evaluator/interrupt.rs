@@ -0,0 +1,81 @@
+use super::{
+    errors::{Error, ErrorKind},
+    EvalResult, Evaluator,
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A handle for interrupting an `Evaluator` from another thread, e.g. to stop a script that has been
+/// running for too long, or from a signal handler. Handles are cheap to clone, and every clone
+/// interrupts the same evaluator.
+#[derive(Clone, Debug, Default)]
+pub struct InterruptHandle {
+    /// Whether evaluation should be interrupted at the next step.
+    flag: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    /// Creates a new `InterruptHandle` that isn't interrupted.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Interrupts the evaluator. If it is evaluating something, that stops with an `Interrupted`
+    /// error at its next step. Otherwise, the next evaluation is interrupted as soon as it starts.
+    pub fn interrupt(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Takes back an interrupt that the evaluator hasn't acted on yet.
+    pub fn clear(&self) {
+        self.flag.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns whether there is an interrupt that the evaluator hasn't acted on yet.
+    pub fn is_interrupted(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+
+    /// Returns the flag that is set to interrupt the evaluator. This is for registering with APIs that
+    /// set a flag themselves, like signal handlers.
+    pub fn flag(&self) -> &Arc<AtomicBool> {
+        &self.flag
+    }
+
+    /// Errors if there is an interrupt, clearing it so that whatever was interrupted can be used again
+    /// afterwards.
+    #[inline]
+    pub(crate) fn check(&self) -> EvalResult<()> {
+        if self.is_interrupted() {
+            self.clear();
+
+            return Err(Error::new(ErrorKind::Interrupted, "interrupted"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Public API for interrupts.
+impl Evaluator {
+    /// Returns a handle that can interrupt this evaluator from another thread.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+}
+
+impl Evaluator {
+    /// Replaces the flag that interrupts this evaluator with the one behind `interrupt`, so that both
+    /// interrupt the same evaluation.
+    pub(crate) fn set_interrupt_handle(&mut self, interrupt: InterruptHandle) {
+        self.interrupt = interrupt;
+    }
+
+    /// Errors if evaluation has been interrupted, clearing the interrupt so that the evaluator can be
+    /// used again afterwards.
+    pub(crate) fn check_interrupt(&mut self) -> EvalResult<()> {
+        self.interrupt.check()
+    }
+}
@@ -0,0 +1,493 @@
+use super::{
+    errors::{Error, ErrorKind},
+    EvalResult, Evaluator,
+};
+use crate::{
+    ast::{
+        expressions::{Expression, ForExpression, TryExpression},
+        statements::{ExpressionStatement, Statement},
+    },
+    gc,
+    object::{
+        array::Array,
+        environment::Environment,
+        error::ErrorValue,
+        function::Function,
+        iterator::{Completion, Frame, Generator, Iter, IterState, TryStage},
+        null::Null,
+        string::Str,
+        Object, Objective,
+    },
+};
+use std::{cell::RefCell, mem, rc::Rc};
+
+impl Evaluator {
+    /// Evaluates a `ForExpression`. The body is evaluated once for each value of the iterable, in a new
+    /// environment that has the value bound to the loop's pattern, so that closures made in the body
+    /// each see their own value. A `return` in the body stops the loop, and unwinds out of it like it
+    /// does from any other block.
+    pub(crate) fn eval_for(&mut self, for_expression: &ForExpression) -> EvalResult<Object> {
+        let iterable = self.eval_expression(&for_expression.iterable)?;
+
+        let iterator =
+            iterate(iterable).map_err(|error| error.at(for_expression.iterable.position()))?;
+
+        while let Some(value) = self.next_value(&iterator)? {
+            let env = Environment::new_enclosed(Rc::clone(&self.env));
+
+            // Swap in the iteration's environment while binding the value and evaluating the body, and
+            // make sure to swap the previous environment back in afterwards, whether or not that
+            // succeeded.
+            let previous = mem::replace(&mut self.env, gc::alloc(env));
+            let result = self
+                .bind_pattern(&for_expression.pattern, value)
+                .and_then(|_| self.eval_block(&for_expression.body));
+            self.env = previous;
+
+            result?;
+        }
+
+        Ok(Object::Null(Null))
+    }
+
+    /// Takes the next value from `iterator`, or returns `None` if it is done. Taking a value counts as a
+    /// step of evaluation, so that even a loop with an empty body uses up the budget and can be
+    /// interrupted.
+    pub(crate) fn next_value(&mut self, iterator: &Iter) -> EvalResult<Option<Object>> {
+        self.check_interrupt()?;
+        self.step()?;
+
+        if let Some(value) = iterator.state.borrow_mut().next_known() {
+            return Ok(value);
+        }
+
+        // The generator is resumed once the iterator is no longer borrowed, as the generator might
+        // look at the iterator itself.
+        self.resume_generator(iterator)
+    }
+
+    /// Creates a generator for a call to the generator `function`, whose arguments have already been
+    /// bound in `env`. None of the body is evaluated until the first value is asked for.
+    pub(crate) fn make_generator(
+        &mut self,
+        function: Function,
+        env: Rc<RefCell<Environment>>,
+    ) -> Object {
+        Object::Iterator(Iter::new(IterState::Generator(Generator::new(
+            function, env,
+        ))))
+    }
+}
+
+impl Evaluator {
+    /// Resumes the generator that `iterator` is iterating over, evaluating its body up to the next
+    /// `yield`, and returning the yielded value. Once the body is left, by reaching its end, a `return`
+    /// or an error, the generator is done, and keeps the value that it returned. Resuming a generator
+    /// counts as a function call.
+    fn resume_generator(&mut self, iterator: &Iter) -> EvalResult<Option<Object>> {
+        let (function, mut frames) = {
+            let mut state = iterator.state.borrow_mut();
+
+            let IterState::Generator(generator) = &mut *state else {
+                unreachable!("only generators are resumed");
+            };
+
+            if generator.running {
+                return Err(Error::new(
+                    ErrorKind::Type,
+                    format!("generator {} is already running", generator.function.name()),
+                ));
+            }
+
+            generator.running = true;
+
+            (generator.function.clone(), mem::take(&mut generator.frames))
+        };
+
+        if self.call_depth >= self.max_call_depth {
+            return Err(Error::new(
+                ErrorKind::Limit,
+                format!("maximum call depth of {} exceeded", self.max_call_depth),
+            ));
+        }
+
+        // Like a function call, the generator's environments (and file) are swapped in while it runs,
+        // and the caller's are swapped back in afterwards. A `return` in a generator only stops it,
+        // so it is never a tail call.
+        self.call_depth += 1;
+        let previous = Rc::clone(&self.env);
+        let previous_file = mem::replace(&mut self.file, function.file.clone());
+        let tail_returns = mem::replace(&mut self.tail_returns, false);
+        let result = self.run_generator(&mut frames);
+        self.env = previous;
+        self.file = previous_file;
+        self.tail_returns = tail_returns;
+        self.call_depth -= 1;
+
+        let mut state = iterator.state.borrow_mut();
+
+        let IterState::Generator(generator) = &mut *state else {
+            unreachable!("only generators are resumed");
+        };
+
+        generator.running = false;
+
+        // A generator that was left for any reason other than a `yield` is done.
+        match result {
+            Ok(Leave::Yield(value)) => {
+                generator.frames = frames;
+                Ok(Some(value))
+            }
+
+            Ok(Leave::Return(value)) => {
+                generator.returned = Some(value);
+                Ok(None)
+            }
+
+            Err(mut error) => {
+                error.locate(function.file.as_ref());
+                error.trace.push(function.name().to_string());
+                Err(error)
+            }
+        }
+    }
+
+    /// Evaluates the statements in `frames` up to the next `yield`, or until the generator's body is
+    /// left.
+    ///
+    /// A `yield` can be directly in the generator's body, or in the blocks of the `if`, `for` and `try`
+    /// expressions, and the arms of the `match` expressions, that are statements in it (and so on), as
+    /// those are evaluated through `frames` too. Anything else is evaluated as usual, so a `yield`
+    /// anywhere else is an error.
+    fn run_generator(&mut self, frames: &mut Vec<Frame>) -> EvalResult<Leave> {
+        loop {
+            let leaving = match frames.last() {
+                None => return Ok(Leave::Return(Object::Null(Null))),
+
+                // A `try` is only on top of the frames once the block of it that was being evaluated
+                // is done.
+                Some(Frame::Try { .. }) => {
+                    let Some(Frame::Try {
+                        try_expression,
+                        stage,
+                        env,
+                    }) = frames.pop()
+                    else {
+                        unreachable!("the frame on top is a try");
+                    };
+
+                    match stage {
+                        TryStage::Body | TryStage::Catch => {
+                            enter_finally(frames, try_expression, env, Completion::Normal);
+                            continue;
+                        }
+                        TryStage::Finally(Completion::Normal) => continue,
+                        TryStage::Finally(Completion::Raise(error)) => Err(error),
+                        TryStage::Finally(Completion::Return(value)) => Ok(value),
+                    }
+                }
+
+                // A `return` from inside of an expression unwinds to here, where it leaves the body
+                // like any other `return`.
+                Some(_) => match self.step_generator(frames) {
+                    Ok(None) => continue,
+                    Ok(Some(Leave::Yield(value))) => return Ok(Leave::Yield(value)),
+                    Ok(Some(Leave::Return(value))) => Ok(value),
+                    Err(error) => error.returned(),
+                },
+            };
+
+            if let Some(value) = unwind_generator(frames, leaving)? {
+                return Ok(Leave::Return(value));
+            }
+        }
+    }
+
+    /// Evaluates the next step of the block or `for` loop on top of `frames`, returning how the
+    /// generator's body is left by it, if it is.
+    fn step_generator(&mut self, frames: &mut Vec<Frame>) -> EvalResult<Option<Leave>> {
+        match frames.last_mut() {
+            Some(Frame::Block { block, index, env }) => {
+                let block = Rc::clone(block);
+                let statement_index = *index;
+                *index += 1;
+                self.env = Rc::clone(env);
+
+                // Like any other block, declarations are hoisted when the block is entered.
+                if statement_index == 0 {
+                    self.hoist_declarations(&block.statements);
+                }
+
+                let Some(statement) = block.statements.get(statement_index) else {
+                    frames.pop();
+                    return Ok(None);
+                };
+
+                match statement {
+                    Statement::Yield(yield_statement) => self
+                        .eval_expression(&yield_statement.value)
+                        .map(|value| Some(Leave::Yield(value))),
+
+                    Statement::Return(return_statement) => self
+                        .eval_expression(&return_statement.return_value)
+                        .map(|value| Some(Leave::Return(value))),
+
+                    Statement::Expression(ExpressionStatement { expression, .. }) => {
+                        self.enter_statement(expression, frames)
+                    }
+
+                    statement => self.eval_statement(statement).map(|_| None),
+                }
+            }
+
+            Some(Frame::For {
+                iterator,
+                pattern,
+                body,
+                env,
+            }) => {
+                let (iterator, pattern, body) =
+                    (iterator.clone(), Rc::clone(pattern), Rc::clone(body));
+                let env = gc::alloc(Environment::new_enclosed(Rc::clone(env)));
+
+                let Some(value) = self.next_value(&iterator)? else {
+                    frames.pop();
+                    return Ok(None);
+                };
+
+                self.env = Rc::clone(&env);
+                self.bind_pattern(&pattern, value)?;
+
+                frames.push(Frame::Block {
+                    block: body,
+                    index: 0,
+                    env,
+                });
+
+                Ok(None)
+            }
+
+            Some(Frame::Try { .. }) | None => unreachable!("only blocks and for loops are stepped"),
+        }
+    }
+
+    /// Enters the expression of an expression statement in a generator's body. If it is an `if`, `for`,
+    /// `try` or `match`, the block or arm of it that is evaluated is pushed onto `frames`, so that the
+    /// generator can pause in it. Anything else is evaluated as usual.
+    fn enter_statement(
+        &mut self,
+        expression: &Expression,
+        frames: &mut Vec<Frame>,
+    ) -> EvalResult<Option<Leave>> {
+        match expression {
+            Expression::If(if_expression) => {
+                if let Some(block) = self.choose_branch(if_expression)? {
+                    frames.push(Frame::Block {
+                        block: Rc::new(block.clone()),
+                        index: 0,
+                        env: Rc::clone(&self.env),
+                    });
+                }
+            }
+
+            Expression::For(for_expression) => {
+                let iterable = self.eval_expression(&for_expression.iterable)?;
+
+                let iterator = iterate(iterable)
+                    .map_err(|error| error.at(for_expression.iterable.position()))?;
+
+                frames.push(Frame::For {
+                    iterator,
+                    pattern: Rc::new(*for_expression.pattern.clone()),
+                    body: Rc::new(for_expression.body.clone()),
+                    env: Rc::clone(&self.env),
+                });
+            }
+
+            // The `try` stays under its blocks, to handle how they are left.
+            Expression::Try(try_expression) => {
+                frames.push(Frame::Try {
+                    try_expression: Rc::new(try_expression.clone()),
+                    stage: TryStage::Body,
+                    env: Rc::clone(&self.env),
+                });
+                frames.push(Frame::Block {
+                    block: Rc::new(try_expression.body.clone()),
+                    index: 0,
+                    env: Rc::clone(&self.env),
+                });
+            }
+
+            // The arm's body is entered like a statement of its own, in the arm's environment.
+            Expression::Match(match_expression) => {
+                let (arm, env) = self
+                    .choose_arm(match_expression)
+                    .map_err(|error| error.at(expression.position()))?;
+                self.env = env;
+
+                return self.enter_statement(&arm.body, frames);
+            }
+
+            expression => {
+                self.eval_expression(expression)?;
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// How a generator's body is left by `Evaluator::run_generator`.
+enum Leave {
+    /// By a `yield`, with the yielded value. The generator carries on from there when it is resumed.
+    Yield(Object),
+
+    /// For good, with the value that the generator returned, which is `null` if it reached the end of
+    /// its body.
+    Return(Object),
+}
+
+/// Passes an error, or the value of a `return`, out through `frames`, popping them until a `try`
+/// takes it over, like `Evaluator::eval_try` would. Returns `None` if one does, and the returned
+/// value if the generator's body is left by a `return`.
+fn unwind_generator(
+    frames: &mut Vec<Frame>,
+    mut leaving: EvalResult<Object>,
+) -> EvalResult<Option<Object>> {
+    // Errors that stop evaluation altogether aren't followed by `finally` blocks either.
+    if let Err(error) = &leaving {
+        if error.kind.is_fatal() {
+            return leaving.map(Some);
+        }
+    }
+
+    while let Some(frame) = frames.pop() {
+        let Frame::Try {
+            try_expression,
+            stage,
+            env,
+        } = frame
+        else {
+            continue;
+        };
+
+        leaving = match (stage, leaving) {
+            // An error in the `try` block is caught by the `catch` clause, if there is one.
+            (TryStage::Body, Err(error)) if try_expression.catch.is_some() => {
+                enter_catch(frames, try_expression, env, error);
+                return Ok(None);
+            }
+
+            // Otherwise, the `finally` block is evaluated before the `try` is left.
+            (TryStage::Body | TryStage::Catch, leaving) if try_expression.finally.is_some() => {
+                let completion = match leaving {
+                    Ok(value) => Completion::Return(value),
+                    Err(error) => Completion::Raise(error),
+                };
+
+                enter_finally(frames, try_expression, env, completion);
+                return Ok(None);
+            }
+
+            // Otherwise, the `try` is left the same way. An error or `return` in the `finally` block
+            // takes the place of how the `try` was being left.
+            (_, leaving) => leaving,
+        };
+    }
+
+    leaving.map(Some)
+}
+
+/// Enters the `catch` block of `try_expression` (if it has one), with `error` bound to its parameter,
+/// pushing the `try` back onto `frames` under it, like `Evaluator::eval_catch`.
+fn enter_catch(
+    frames: &mut Vec<Frame>,
+    try_expression: Rc<TryExpression>,
+    env: Rc<RefCell<Environment>>,
+    error: Error,
+) {
+    let Some(catch) = &try_expression.catch else {
+        return;
+    };
+
+    let mut catch_env = Environment::new_enclosed(Rc::clone(&env));
+    catch_env.bind(&catch.parameter, Object::Error(ErrorValue::new(error)));
+    let block = Rc::new(catch.body.clone());
+
+    frames.push(Frame::Try {
+        try_expression,
+        stage: TryStage::Catch,
+        env,
+    });
+    frames.push(Frame::Block {
+        block,
+        index: 0,
+        env: gc::alloc(catch_env),
+    });
+}
+
+/// Enters the `finally` block of `try_expression` (if it has one), pushing the `try` back onto `frames`
+/// under it, so that the `try` is left as `completion` describes once the block is done.
+fn enter_finally(
+    frames: &mut Vec<Frame>,
+    try_expression: Rc<TryExpression>,
+    env: Rc<RefCell<Environment>>,
+    completion: Completion,
+) {
+    let Some(finally) = &try_expression.finally else {
+        return;
+    };
+
+    let block = Rc::new(finally.clone());
+
+    frames.push(Frame::Try {
+        try_expression,
+        stage: TryStage::Finally(completion),
+        env: Rc::clone(&env),
+    });
+    frames.push(Frame::Block {
+        block,
+        index: 0,
+        env,
+    });
+}
+
+/// Returns an iterator over the values of `object`. Arrays are iterated over by element, strings by
+/// character, hashes by `[key, value]` pairs (in the order that they are printed in), and ranges by
+/// integer. An iterator is iterated over by itself, so iterating over it again carries on from where
+/// it was left.
+pub(crate) fn iterate(object: Object) -> EvalResult<Iter> {
+    let values = match object {
+        Object::Iterator(iterator) => return Ok(iterator),
+
+        Object::Range(range) => {
+            return Ok(Iter::new(IterState::Range {
+                next: range.start,
+                end: range.end,
+            }))
+        }
+
+        Object::Array(array) => array.elements,
+
+        Object::String(string) => string
+            .value
+            .chars()
+            .map(|char| Object::String(Str::new(char)))
+            .collect(),
+
+        Object::Hash(hash) => hash
+            .pairs
+            .into_values()
+            .map(|pair| Object::Array(Array::new(vec![pair.key, pair.value])))
+            .collect(),
+
+        object => {
+            return Err(Error::new(
+                ErrorKind::Type,
+                format!("cannot iterate over {}", object.object_type()),
+            ))
+        }
+    };
+
+    Ok(Iter::new(IterState::Values(values.into_iter())))
+}
@@ -0,0 +1,236 @@
+use super::{
+    errors::{Error, ErrorKind},
+    EvalResult, Evaluator,
+};
+use crate::{
+    ast::{
+        expressions::{
+            BooleanLiteral, CallExpression, Expression, IntegerLiteral, MacroLiteral, StringLiteral,
+        },
+        modify::{modify_expression, modify_program},
+        patterns::Pattern,
+        statements::{LetStatement, Statement},
+        Program,
+    },
+    gc,
+    object::{environment::Environment, macros::Macro, quote::Quote, Object, Objective},
+    token::{Position, Token, TokenType},
+};
+use std::{mem, rc::Rc};
+
+/// Public API for macros.
+impl Evaluator {
+    /// Pulls the macro definitions out of `program`, and defines them in the evaluator's macro
+    /// environment. A macro definition is a top-level `let` statement that binds a macro literal to
+    /// a name, e.g. `let unless = macro(cond, then) { ... };`. Macros are kept in their own
+    /// environment, so they can't be seen by (or clash with) the program once it is evaluated.
+    pub fn define_macros(&mut self, program: &mut Program) {
+        let statements = mem::take(&mut program.statements);
+
+        for statement in statements {
+            if let Statement::Let(LetStatement {
+                pattern: Pattern::Identifier(name),
+                value,
+                ..
+            }) = &statement
+            {
+                if let Expression::Macro(literal) = value.as_ref() {
+                    let macro_object =
+                        Macro::new(Rc::new(literal.clone()), Rc::clone(&self.macro_env));
+
+                    self.macro_env
+                        .borrow_mut()
+                        .set(name.value.clone(), Object::Macro(macro_object));
+
+                    continue;
+                }
+            }
+
+            program.statements.push(statement);
+        }
+    }
+
+    /// Expands every call to a macro in `program`. The macro is called with its arguments quoted
+    /// (not evaluated), and the call is replaced with the quoted AST that the macro returns. Calls
+    /// inside the arguments are expanded before the call itself.
+    pub fn expand_macros(&mut self, program: Program) -> EvalResult<Program> {
+        // Most programs define no macros, and then there is nothing to expand.
+        if self.macro_env.borrow().is_empty() {
+            return Ok(program);
+        }
+
+        modify_program(program, &mut |expression| {
+            self.expand_macro_call(expression)
+        })
+    }
+}
+
+impl Evaluator {
+    /// If `expression` is a call to a defined macro, calls the macro and returns the expression it
+    /// expands to. Otherwise, `expression` is returned as-is.
+    fn expand_macro_call(&mut self, expression: Expression) -> EvalResult<Expression> {
+        let Expression::Call(call) = &expression else {
+            return Ok(expression);
+        };
+
+        let Expression::Identifier(name) = call.function.as_ref() else {
+            return Ok(expression);
+        };
+
+        let Some(Object::Macro(macro_object)) = self.macro_env.borrow().get(&name.value) else {
+            return Ok(expression);
+        };
+
+        let arguments = call
+            .arguments
+            .iter()
+            .map(|argument| Object::Quote(Quote::new(argument.clone())))
+            .collect();
+
+        let env = Environment::new_enclosed(Rc::clone(&macro_object.env));
+
+        // Swap in the macro's environment while binding the arguments and evaluating the body, and
+        // make sure to swap the previous environment back in afterwards, whether or not that succeeded.
+        let previous = mem::replace(&mut self.env, gc::alloc(env));
+        let result = self
+            .bind_arguments(&macro_object.literal.parameters, arguments)
+            .and_then(|_| self.eval_block(&macro_object.literal.body));
+        self.env = previous;
+
+        let result = result
+            .or_else(|error| error.returned().map(Some))
+            .map_err(|mut error| {
+                error.trace.push(name.value.to_string());
+                error.at(call.token.position)
+            })?;
+
+        match result {
+            Some(Object::Quote(quote)) => Ok(*quote.node),
+            Some(object) => Err(not_a_quote(&name.value, &object).at(call.token.position)),
+            None => Err(Error::new(
+                ErrorKind::Macro,
+                format!("macro {} must return a quote, got nothing", name.value),
+            )
+            .at(call.token.position)),
+        }
+    }
+
+    /// Evaluates a call to `quote`, which returns its argument as an (unevaluated) AST. Any calls to
+    /// `unquote` inside of the argument are evaluated, and replaced with the AST of their value.
+    pub(crate) fn eval_quote(&mut self, call: &CallExpression) -> EvalResult<Object> {
+        let [argument] = call.arguments.as_slice() else {
+            return Err(Error::new(
+                ErrorKind::Argument,
+                format!(
+                    "wrong number of arguments: want=1, got={}",
+                    call.arguments.len()
+                ),
+            ));
+        };
+
+        let node = modify_expression(argument.clone(), &mut |expression| {
+            self.eval_unquote(expression)
+        })?;
+
+        Ok(Object::Quote(Quote::new(node)))
+    }
+
+    /// If `expression` is a call to `unquote`, evaluates its argument and returns the AST of the
+    /// value. Otherwise, `expression` is returned as-is.
+    fn eval_unquote(&mut self, expression: Expression) -> EvalResult<Expression> {
+        let Expression::Call(call) = &expression else {
+            return Ok(expression);
+        };
+
+        if !is_unquote(call) {
+            return Ok(expression);
+        }
+
+        let [argument] = call.arguments.as_slice() else {
+            return Err(Error::new(
+                ErrorKind::Argument,
+                format!(
+                    "wrong number of arguments: want=1, got={}",
+                    call.arguments.len()
+                ),
+            )
+            .at(call.token.position));
+        };
+
+        let value = self.eval_expression(argument)?;
+
+        object_to_expression(value, call.token.position)
+    }
+}
+
+/// Returns whether `call` is a call to `unquote`.
+pub(crate) fn is_unquote(call: &CallExpression) -> bool {
+    matches!(call.function.as_ref(), Expression::Identifier(name) if name.value == "unquote")
+}
+
+/// Converts an object back into an AST, so that it can be put into a quote by `unquote`. The tokens
+/// of the AST are made up, at the `position` of the `unquote` call.
+pub(crate) fn object_to_expression(object: Object, position: Position) -> EvalResult<Expression> {
+    match object {
+        Object::Integer(integer) => Ok(Expression::Integer(IntegerLiteral {
+            token: Token::new(TokenType::Int, integer.value).at(position),
+            value: integer.value,
+        })),
+
+        Object::Boolean(boolean) => Ok(Expression::Boolean(BooleanLiteral {
+            token: Token::new(
+                if boolean.value {
+                    TokenType::True
+                } else {
+                    TokenType::False
+                },
+                boolean.value,
+            )
+            .at(position),
+            value: boolean.value,
+        })),
+
+        Object::String(string) => {
+            let token = Token::new(TokenType::String, &string.value).at(position);
+
+            Ok(Expression::String(StringLiteral {
+                value: token.literal.clone(),
+                token,
+            }))
+        }
+
+        Object::Quote(quote) => Ok(*quote.node),
+
+        object => Err(Error::new(
+            ErrorKind::Type,
+            format!(
+                "cannot unquote {}, only integers, booleans, strings and quotes can be unquoted",
+                object.object_type()
+            ),
+        )
+        .at(position)),
+    }
+}
+
+/// The error for a macro that returned something other than a quote.
+fn not_a_quote(name: &str, object: &Object) -> Error {
+    Error::new(
+        ErrorKind::Macro,
+        format!(
+            "macro {} must return a quote, got {}",
+            name,
+            object.object_type()
+        ),
+    )
+}
+
+/// The error for evaluating a macro literal that wasn't pulled out of the program by `define_macros`.
+pub(crate) fn unexpected_macro(literal: &MacroLiteral) -> Error {
+    Error::new(
+        ErrorKind::Syntax,
+        format!(
+            "unexpected macro literal: {}, macros can only be defined by top-level let statements",
+            literal
+        ),
+    )
+}
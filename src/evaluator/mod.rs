@@ -1,17 +1,327 @@
-mod expressions;
+pub mod budget;
+pub(crate) mod builtins;
+pub mod errors;
+pub(crate) mod exceptions;
+pub(crate) mod expressions;
+pub mod interrupt;
+pub(crate) mod iterators;
+pub(crate) mod macros;
+pub mod modules;
+mod patterns;
+pub mod prelude;
+pub(crate) mod records;
 mod statements;
 pub mod tests;
 
-use self::statements::eval_statement;
-use crate::{ast::Program, object::Object};
+use self::{
+    budget::Budget,
+    builtins::get_builtin,
+    errors::{Error, ErrorKind},
+    interrupt::InterruptHandle,
+    modules::{FileLoader, Loader},
+    prelude::load_prelude,
+};
+use crate::{
+    ast::{statements::Statement, Program},
+    gc,
+    object::{environment::Environment, module::Module, Object},
+    optimizer::{optimize_at, OptLevel},
+    parser::Limits,
+    resolver::resolve,
+    symbol::Symbol,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
-/// Evaluates a `Program`.
-pub fn eval(program: Program) -> Option<Object> {
-    let mut result = None;
+/// The maximum call depth that an `Evaluator` allows by default. See `Evaluator::set_max_call_depth`.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1_000;
 
-    for statement in program.statements {
-        result = eval_statement(statement);
+/// The maximum nesting depth that an `Evaluator` allows by default. See `Evaluator::set_max_depth`.
+pub const DEFAULT_MAX_DEPTH: usize = 10_000;
+
+/// How much of the thread's stack has to be left for evaluation to go another level deeper. This is
+/// more than evaluating a single level uses, even in a debug build, so that running out of stack is
+/// an error instead of a crash whatever the limits are.
+const STACK_RED_ZONE: usize = 256 * 1024;
+
+/// The `EvalResult` type is a shorthand for a `Result` type that returns an evaluator `Error`.
+pub type EvalResult<T> = Result<T, Error>;
+
+/// The tree-walking evaluator for the Monkey programming language. It keeps track of the environment
+/// that code is currently being evaluated in, so that bindings persist between calls to `eval` (which
+/// is what the REPL needs).
+pub struct Evaluator {
+    /// The environment that identifiers are currently being looked up in and bound to. This gets
+    /// swapped out for the function's environment while a function is being called.
+    env: Rc<RefCell<Environment>>,
+
+    /// The environment that the prelude was loaded into, if it was. Imported modules are evaluated in
+    /// an environment enclosed by this one, so that they can use the prelude too.
+    prelude: Option<Rc<RefCell<Environment>>>,
+
+    /// The environment that macros are defined in by `define_macros`. This is separate from `env`, as
+    /// macros only exist before the program is evaluated.
+    macro_env: Rc<RefCell<Environment>>,
+
+    /// The loader that the source code of imported modules is loaded with.
+    loader: Rc<dyn Loader>,
+
+    /// The modules that have already been imported, by their path, so that each module is only
+    /// evaluated once.
+    modules: HashMap<PathBuf, Module>,
+
+    /// The paths of the modules that are currently being loaded, from the outermost import to the
+    /// innermost. This is used to detect import cycles.
+    loading: Vec<PathBuf>,
+
+    /// The path of the file that is currently being evaluated, if there is one.
+    file: Option<Rc<Path>>,
+
+    /// Whether a `return` is currently in tail position, so that a call that it returns can be made as
+    /// a tail call. This is the case inside of a function body, but not inside of a `try`, as the
+    /// `catch` and `finally` blocks have to run after the call.
+    tail_returns: bool,
+
+    /// How many function calls are currently being evaluated.
+    call_depth: usize,
+
+    /// The most function calls that are allowed to be evaluated at once.
+    max_call_depth: usize,
+
+    /// How deeply nested the expression currently being evaluated is, counting the expressions in
+    /// every function that is being called.
+    depth: usize,
+
+    /// The deepest that expressions are allowed to be nested.
+    max_depth: usize,
+
+    /// What is left of the resources that evaluation is allowed to use.
+    budget: Budget,
+
+    /// How many steps have been taken since the budget was set.
+    steps: u64,
+
+    /// The flag that is set to interrupt evaluation.
+    interrupt: InterruptHandle,
+
+    /// The names that the last program that was evaluated uses before they are defined, or without
+    /// defining them at all, as found by the resolver.
+    warnings: Vec<Error>,
+
+    /// How much programs are optimized before they are evaluated.
+    opt_level: OptLevel,
+
+    /// The limits that imported modules are parsed with.
+    parse_limits: Limits,
+}
+
+/// Public API for the `Evaluator` struct.
+impl Evaluator {
+    /// Creates a new `Evaluator` with the prelude (see `prelude::PRELUDE`) loaded. Any names that
+    /// are defined by the code being evaluated shadow the prelude's names.
+    pub fn new() -> Self {
+        let prelude = load_prelude();
+
+        let mut evaluator =
+            Self::with_environment(gc::alloc(Environment::new_enclosed(Rc::clone(&prelude))));
+
+        evaluator.prelude = Some(prelude);
+        evaluator
+    }
+
+    /// Creates a new `Evaluator` with an empty global environment, without the prelude. This is for
+    /// embedders that want a minimal sandbox.
+    pub fn without_prelude() -> Self {
+        Self::with_environment(gc::alloc(Environment::new()))
+    }
+
+    /// Creates a new `Evaluator` that evaluates code in the given environment.
+    pub fn with_environment(env: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            env,
+            prelude: None,
+            macro_env: gc::alloc(Environment::new()),
+            loader: Rc::new(FileLoader),
+            modules: HashMap::new(),
+            loading: Vec::new(),
+            file: None,
+            tail_returns: false,
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            budget: Budget::unlimited(),
+            steps: 0,
+            interrupt: InterruptHandle::new(),
+            warnings: Vec::new(),
+            opt_level: OptLevel::None,
+            parse_limits: Limits::default(),
+        }
+    }
+
+    /// Sets the most function calls that are allowed to be evaluated at once, which defaults to
+    /// `DEFAULT_MAX_CALL_DEPTH`. Going deeper than this, e.g. with runaway recursion, is a runtime error
+    /// instead of a stack overflow. Tail calls don't count, as they don't use any more of the stack.
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /// Sets the deepest that expressions are allowed to be nested while evaluating, which defaults to
+    /// `DEFAULT_MAX_DEPTH`. This counts the expressions in every function that is being called, so it
+    /// bounds how much of the stack evaluation uses, even when every call is itself deeply nested.
+    ///
+    /// The evaluator recurses on the Rust stack, which can run out before either limit is reached, e.g.
+    /// on a thread with the default stack size of 2 MiB. Going deeper than the stack allows is a
+    /// `Limit` error too, so the limits only need to be lowered to make the errors the same on every
+    /// stack. `main` runs code on a 256 MiB stack, which the defaults fit in even in a debug build.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Sets how much programs are optimized before they are evaluated (see `optimizer::optimize_at`),
+    /// which defaults to not at all. This applies to the modules that they import too.
+    pub fn set_opt_level(&mut self, opt_level: OptLevel) {
+        self.opt_level = opt_level;
+    }
+
+    /// Sets the limits that imported modules are parsed with (see `Parser::set_limits`), which should be
+    /// the same as the ones that the program importing them was parsed with.
+    pub fn set_parse_limits(&mut self, limits: Limits) {
+        self.parse_limits = limits;
     }
 
-    result
+    /// Returns the limits that imported modules are parsed with.
+    pub fn parse_limits(&self) -> Limits {
+        self.parse_limits
+    }
+
+    /// Binds `name` to `value` in the global environment, so that the host can give programs values,
+    /// like the arguments of a script.
+    pub fn define(&mut self, name: Symbol, value: Object) {
+        self.env.borrow_mut().set(name, value);
+    }
+
+    /// Defines the macros in `program`, expands the calls to them, and then evaluates the expanded
+    /// program. This is what should be used to run code that might use macros.
+    pub fn run(&mut self, mut program: Program) -> EvalResult<Option<Object>> {
+        self.define_macros(&mut program);
+
+        let program = self.expand_macros(program)?;
+        let mut program = optimize_at(program, self.opt_level);
+        self.resolve(&mut program);
+
+        self.eval_resolved(&program)
+    }
+
+    /// Evaluates a `Program`, returning the value of the last statement (if it has one).
+    pub fn eval(&mut self, program: &Program) -> EvalResult<Option<Object>> {
+        let mut program = optimize_at(program.clone(), self.opt_level);
+        self.resolve(&mut program);
+
+        self.eval_resolved(&program)
+    }
+
+    /// Returns the names that the last program that was evaluated uses before they are defined, or
+    /// without defining them at all. These are found before the program is evaluated, but aren't errors,
+    /// as the code that uses them might never run.
+    pub fn warnings(&self) -> &[Error] {
+        &self.warnings
+    }
+}
+
+impl Evaluator {
+    /// Resolves the identifiers in `program` (see `resolver::resolve`), so that its variables are
+    /// looked up by slot, keeping the resolver's warnings.
+    fn resolve(&mut self, program: &mut Program) {
+        let env = Rc::clone(&self.env);
+
+        self.warnings = resolve(program, |name| {
+            env.borrow().get(name).is_some() || get_builtin(name).is_some()
+        });
+    }
+
+    /// Evaluates a `Program` whose identifiers have been resolved.
+    fn eval_resolved(&mut self, program: &Program) -> EvalResult<Option<Object>> {
+        // A program isn't a function body, so its `return`s aren't tail calls, even if the program is a
+        // module that is imported from inside of a function.
+        let tail_returns = mem::replace(&mut self.tail_returns, false);
+        let result = self.eval_program(program);
+        self.tail_returns = tail_returns;
+
+        result.map_err(|mut error| {
+            error.locate(self.file.as_ref());
+            error
+        })
+    }
+
+    /// Runs `eval` one level of nesting deeper than the current one as a step of evaluation, erroring
+    /// instead if that is deeper than the maximum nesting depth or than the stack has room for, the
+    /// budget has run out, or evaluation has been interrupted.
+    fn nested<T>(&mut self, eval: impl FnOnce(&mut Self) -> EvalResult<T>) -> EvalResult<T> {
+        self.check_interrupt()?;
+        self.step()?;
+
+        if self.depth >= self.max_depth {
+            return Err(Error::new(
+                ErrorKind::Limit,
+                format!("maximum nesting depth of {} exceeded", self.max_depth),
+            ));
+        }
+
+        // The remaining stack is unknown on some platforms, where only the limits above apply.
+        if stacker::remaining_stack().is_some_and(|remaining| remaining < STACK_RED_ZONE) {
+            return Err(Error::new(
+                ErrorKind::Limit,
+                "out of stack space for nesting any deeper",
+            ));
+        }
+
+        self.depth += 1;
+        let result = eval(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    /// Evaluates the statements of a `Program`, returning the value of the last statement (if it has
+    /// one).
+    fn eval_program(&mut self, program: &Program) -> EvalResult<Option<Object>> {
+        // Function declarations are hoisted, so that they can be called before they are declared.
+        self.hoist_declarations(&program.statements);
+
+        let mut result = None;
+
+        for statement in &program.statements {
+            // Exports are only allowed at the top level, where they are evaluated like the declaration
+            // that they export. The module's exports are collected once the whole module has run.
+            let statement = match statement {
+                Statement::Export(export) => export.declaration.as_ref(),
+                statement => statement,
+            };
+
+            // A top-level `return` stops the program, and its value is the program's value.
+            result = match self.eval_statement(statement) {
+                Ok(result) => result,
+                Err(error) => return error.returned().map(Some),
+            };
+        }
+
+        Ok(result)
+    }
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Evaluates a `Program` in a new, empty environment.
+pub fn eval(program: &Program) -> EvalResult<Option<Object>> {
+    Evaluator::new().eval(program)
 }
@@ -0,0 +1,303 @@
+use super::{
+    errors::{Error, ErrorKind},
+    records::unknown_field,
+    EvalResult, Evaluator,
+};
+use crate::{
+    ast::{
+        expressions::{Identifier, MemberExpression},
+        statements::{ImportStatement, Statement},
+        Program,
+    },
+    gc,
+    lexer::Lexer,
+    object::{environment::Environment, module::Module, Object, Objective},
+    parser::{Limits, Parser},
+    symbol::Symbol,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io, mem,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+};
+
+/// A `Loader` loads the source code of modules for `import` statements. By default, modules are
+/// loaded from the file system with a `FileLoader`, but an embedder can replace the loader (see
+/// `Evaluator::set_loader`), e.g. to serve modules from memory with a `MemoryLoader`.
+pub trait Loader {
+    /// Loads the source code of the module at `path`. The path has already been resolved against
+    /// the importing file, and normalized.
+    fn load(&self, path: &Path) -> io::Result<String>;
+}
+
+/// A `Loader` that loads modules from the file system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileLoader;
+
+impl Loader for FileLoader {
+    fn load(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+/// A `Loader` that serves modules from memory, by their path.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryLoader {
+    /// The source code of each module, by its path.
+    modules: HashMap<PathBuf, String>,
+}
+
+impl MemoryLoader {
+    /// Creates a new, empty `MemoryLoader`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a module with the given `path` and `source` code, replacing any module that was already
+    /// at that path.
+    pub fn insert<P: AsRef<Path>, S: ToString>(&mut self, path: P, source: S) {
+        self.modules
+            .insert(normalize(path.as_ref()), source.to_string());
+    }
+}
+
+impl Loader for MemoryLoader {
+    fn load(&self, path: &Path) -> io::Result<String> {
+        self.modules
+            .get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such module"))
+    }
+}
+
+/// Public API for modules.
+impl Evaluator {
+    /// Replaces the loader that is used to load the source code of imported modules.
+    pub fn set_loader<L: Loader + 'static>(&mut self, loader: L) {
+        self.loader = Rc::new(loader);
+    }
+
+    /// Sets the path of the file that is being evaluated, which relative imports are resolved
+    /// against. Without a file, imports are resolved against the current directory.
+    ///
+    /// The file counts as being loaded, like the modules that it imports while they are, so that a
+    /// module importing it back is an import cycle instead of running the file a second time.
+    pub fn set_file<P: AsRef<Path>>(&mut self, path: P) {
+        let path = normalize(path.as_ref());
+
+        self.file = Some(Rc::from(path.as_path()));
+        self.loading = vec![path];
+    }
+}
+
+impl Evaluator {
+    /// Evaluates an `ImportStatement`, binding the module object of the imported module to the
+    /// import's alias. The module is only evaluated the first time that it is imported.
+    pub(crate) fn eval_import(&mut self, import: &ImportStatement) -> EvalResult<()> {
+        let path = resolve_import(self.file.as_deref(), &import.path.value);
+
+        let module = self
+            .load_module(path)
+            .map_err(|error| error.at(import.token.position))?;
+
+        self.env
+            .borrow_mut()
+            .bind(&import.alias, Object::Module(module));
+
+        Ok(())
+    }
+
+    /// Returns the module at `path`, loading and evaluating it first if it hasn't been yet. The
+    /// module is evaluated in its own, new environment, and then its exports are collected from it.
+    fn load_module(&mut self, path: PathBuf) -> EvalResult<Module> {
+        if let Some(module) = self.modules.get(&path) {
+            return Ok(module.clone());
+        }
+
+        check_cycle(&self.loading, &path)?;
+
+        let program = parse_module(self.loader.as_ref(), &path, self.parse_limits)?;
+        let names = export_names(&program);
+
+        // Swap in a new environment (and macro environment, file and warnings) for the module, and make
+        // sure to swap the importer's back in afterwards, whether or not evaluating the module succeeded.
+        let env = gc::alloc(match &self.prelude {
+            Some(prelude) => Environment::new_enclosed(Rc::clone(prelude)),
+            None => Environment::new(),
+        });
+        let previous_env = mem::replace(&mut self.env, Rc::clone(&env));
+        let previous_macro_env = mem::replace(&mut self.macro_env, gc::alloc(Environment::new()));
+        let file = Rc::from(path.as_path());
+        let previous_file = self.file.replace(Rc::clone(&file));
+        let previous_warnings = mem::take(&mut self.warnings);
+        self.loading.push(path.clone());
+
+        let result = self.run(program);
+
+        self.loading.pop();
+        self.warnings = previous_warnings;
+        self.file = previous_file;
+        self.macro_env = previous_macro_env;
+        self.env = previous_env;
+
+        if let Err(mut error) = result {
+            error.locate(Some(&file));
+            error.trace.push(format!("module {}", path.display()));
+            return Err(error);
+        }
+
+        let exports = names
+            .into_iter()
+            .filter_map(|name| {
+                let value = env.borrow().get(&name)?;
+                Some((name, value))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let module = Module::new(path.display(), exports);
+
+        self.modules.insert(path, module.clone());
+
+        Ok(module)
+    }
+
+    /// Evaluates a `MemberExpression`, which looks up an export of a module, or a field of a record or
+    /// error object.
+    pub(crate) fn eval_member(&mut self, member: &MemberExpression) -> EvalResult<Object> {
+        let object = self.eval_expression(&member.object)?;
+
+        get_member(object, &member.member)
+    }
+}
+
+/// Looks up `member` on `object`, which is an export of a module, or a field of a record or error
+/// object.
+pub(crate) fn get_member(object: Object, member: &Identifier) -> EvalResult<Object> {
+    match object {
+        Object::Module(module) => module.get(&member.value).cloned().ok_or_else(|| {
+            Error::new(
+                ErrorKind::Member,
+                format!("module {} has no export named {}", module.path, member),
+            )
+        }),
+
+        Object::Record(record) => record
+            .get(&member.value)
+            .cloned()
+            .ok_or_else(|| unknown_field(&record.name, member)),
+
+        Object::Error(error) => error
+            .get(&member.value)
+            .ok_or_else(|| unknown_field("error", member)),
+
+        object => Err(Error::new(
+            ErrorKind::Type,
+            format!(
+                "cannot access member {} of {}",
+                member,
+                object.object_type()
+            ),
+        )),
+    }
+}
+
+/// Resolves the `path` of an imported module against the directory of `file` (the file that the import
+/// is in), or the current directory if there isn't one.
+pub(crate) fn resolve_import(file: Option<&Path>, path: &str) -> PathBuf {
+    let base = file.and_then(Path::parent).unwrap_or(Path::new(""));
+
+    normalize(&base.join(path))
+}
+
+/// Errors if the module at `path` is already being loaded further up the chain of imports in
+/// `loading`, as importing it again would never finish. The error shows the whole cycle.
+pub(crate) fn check_cycle(loading: &[PathBuf], path: &Path) -> EvalResult<()> {
+    let Some(start) = loading.iter().position(|loading| loading == path) else {
+        return Ok(());
+    };
+
+    let cycle = loading[start..]
+        .iter()
+        .map(PathBuf::as_path)
+        .chain([path])
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ");
+
+    Err(Error::new(
+        ErrorKind::Module,
+        format!("import cycle: {}", cycle),
+    ))
+}
+
+/// Loads the source code of the module at `path` with `loader`, and parses it with `limits`.
+pub(crate) fn parse_module(
+    loader: &dyn Loader,
+    path: &Path,
+    limits: Limits,
+) -> EvalResult<Program> {
+    let source = loader.load(path).map_err(|error| {
+        Error::new(
+            ErrorKind::Module,
+            format!("cannot load module {}: {}", path.display(), error),
+        )
+    })?;
+
+    let mut parser = Parser::new(Lexer::new(&source));
+    parser.set_limits(limits);
+
+    parser.parse_program().map_err(|errors| {
+        Error::new(
+            ErrorKind::Module,
+            format!(
+                "cannot parse module {}: {}",
+                path.display(),
+                errors
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )
+    })
+}
+
+/// Returns the names of the bindings that the top-level `export`s of `program` export.
+pub(crate) fn export_names(program: &Program) -> Vec<Symbol> {
+    program
+        .statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Export(export) => Some(export.names()),
+            _ => None,
+        })
+        .flatten()
+        .map(|name| name.value.clone())
+        .collect()
+}
+
+/// Normalizes `path` without touching the file system, by removing `.` components and resolving `..`
+/// components against the component before them. This makes sure that the same module always has
+/// the same path, no matter how it was imported.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                // We can't go above the root, and if we are already above the starting directory,
+                // we have to keep going up.
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
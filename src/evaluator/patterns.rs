@@ -0,0 +1,278 @@
+use super::{
+    errors::{Error, ErrorKind},
+    expressions::is_truthy,
+    EvalResult, Evaluator,
+};
+use crate::{
+    ast::{
+        expressions::{Identifier, MatchArm, MatchExpression},
+        patterns::{ArrayPattern, HashPattern, Pattern},
+    },
+    gc,
+    object::{array::Array, environment::Environment, hash::HashKey, Object, Objective},
+};
+use std::{cell::RefCell, mem, rc::Rc};
+
+impl Evaluator {
+    /// Binds the parts of `value` to the identifiers in `pattern`, in the current environment. If the
+    /// value doesn't have the shape that the pattern describes, an error is returned with the position
+    /// of the part of the pattern that didn't match.
+    pub(crate) fn bind_pattern(&mut self, pattern: &Pattern, value: Object) -> EvalResult<()> {
+        match pattern {
+            Pattern::Identifier(identifier) => {
+                self.env.borrow_mut().bind(identifier, value);
+                Ok(())
+            }
+
+            Pattern::Wildcard(_) => Ok(()),
+
+            Pattern::Literal(literal) => {
+                if self.eval_expression(literal)? == value {
+                    Ok(())
+                } else {
+                    Err(Error::new(
+                        ErrorKind::Match,
+                        format!("{} does not match the pattern {}", value, literal),
+                    )
+                    .at(literal.position()))
+                }
+            }
+
+            Pattern::Array(array) => self.bind_array_pattern(array, value),
+
+            Pattern::Hash(hash) => self.bind_hash_pattern(hash, value),
+        }
+    }
+
+    /// Binds the elements of an array to an array pattern. The array has to have exactly as many
+    /// elements as the pattern, unless the pattern has a rest element, in which case it has to have
+    /// at least as many.
+    fn bind_array_pattern(&mut self, pattern: &ArrayPattern, value: Object) -> EvalResult<()> {
+        let Object::Array(array) = value else {
+            return Err(Error::new(
+                ErrorKind::Type,
+                format!("cannot destructure {} as an array", value.object_type()),
+            )
+            .at(pattern.token.position));
+        };
+
+        let want = pattern.elements.len();
+        let got = array.elements.len();
+
+        if got < want {
+            let want = match pattern.rest {
+                Some(_) => format!("at least {}", want),
+                None => want.to_string(),
+            };
+
+            return Err(Error::new(
+                ErrorKind::Match,
+                format!(
+                    "not enough elements to destructure: want={}, got={}",
+                    want, got
+                ),
+            )
+            .at(pattern.token.position));
+        }
+
+        if got > want && pattern.rest.is_none() {
+            return Err(Error::new(
+                ErrorKind::Match,
+                format!(
+                    "too many elements to destructure: want={}, got={}",
+                    want, got
+                ),
+            )
+            .at(pattern.token.position));
+        }
+
+        let mut elements = array.elements.into_iter();
+
+        for (element, value) in pattern.elements.iter().zip(elements.by_ref()) {
+            self.bind_pattern(element, value)?;
+        }
+
+        if let Some(rest) = &pattern.rest {
+            let elements = elements.collect::<Vec<_>>();
+            self.allocate_array(elements.len())?;
+
+            self.env
+                .borrow_mut()
+                .bind(rest, Object::Array(Array::new(elements)));
+        }
+
+        Ok(())
+    }
+
+    /// Binds the values of a hash to a hash pattern. Every key in the pattern has to be in the hash,
+    /// but the hash can have other keys as well.
+    fn bind_hash_pattern(&mut self, pattern: &HashPattern, value: Object) -> EvalResult<()> {
+        let Object::Hash(hash) = value else {
+            return Err(Error::new(
+                ErrorKind::Type,
+                format!("cannot destructure {} as a hash", value.object_type()),
+            )
+            .at(pattern.token.position));
+        };
+
+        for (key, pattern) in &pattern.pairs {
+            let key_object = self.eval_expression(key)?;
+
+            let hash_key = HashKey::try_from(&key_object).map_err(|object_type| {
+                Error::new(
+                    ErrorKind::Type,
+                    format!("unusable as hash key: {}", object_type),
+                )
+                .at(key.position())
+            })?;
+
+            let Some(value) = hash.get(&hash_key) else {
+                return Err(
+                    Error::new(ErrorKind::Match, format!("missing key {} in hash", key))
+                        .at(key.position()),
+                );
+            };
+
+            self.bind_pattern(pattern, value.clone())?;
+        }
+
+        Ok(())
+    }
+
+    /// Evaluates a `MatchExpression`. The arm that `choose_arm` chooses is evaluated in the environment
+    /// with its pattern's bindings, so that the bindings don't leak out of the arm. If `tail` is set, the
+    /// arm's body is in tail position.
+    pub(crate) fn eval_match(
+        &mut self,
+        match_expression: &MatchExpression,
+        tail: bool,
+    ) -> EvalResult<Object> {
+        let (arm, env) = self.choose_arm(match_expression)?;
+
+        // Swap in the arm's environment while evaluating the body, and make sure to swap the previous
+        // environment back in afterwards, whether or not that succeeded.
+        let previous = mem::replace(&mut self.env, env);
+        let result = if tail {
+            self.eval_tail_expression(&arm.body)
+        } else {
+            self.eval_expression(&arm.body)
+        };
+        self.env = previous;
+
+        result
+    }
+
+    /// Chooses the arm of a `MatchExpression` to evaluate, returning it with a new environment that has
+    /// its pattern's bindings. The arms are checked in order, and the first arm whose pattern matches the
+    /// subject's value, and whose guard (if it has one) is truthy in that environment, is chosen. If no
+    /// arm matches, an error is returned.
+    pub(crate) fn choose_arm<'a>(
+        &mut self,
+        match_expression: &'a MatchExpression,
+    ) -> EvalResult<(&'a MatchArm, Rc<RefCell<Environment>>)> {
+        let value = self.eval_expression(&match_expression.subject)?;
+
+        for arm in &match_expression.arms {
+            let mut bindings = Vec::new();
+
+            if !self.match_pattern(&arm.pattern, &value, &mut bindings)? {
+                continue;
+            }
+
+            let mut env = Environment::new_enclosed(Rc::clone(&self.env));
+
+            for (identifier, value) in bindings {
+                env.bind(identifier, value);
+            }
+
+            let env = gc::alloc(env);
+
+            if let Some(guard) = &arm.guard {
+                let previous = mem::replace(&mut self.env, Rc::clone(&env));
+                let guard = self.eval_expression(guard);
+                self.env = previous;
+
+                if !is_truthy(&guard?) {
+                    continue;
+                }
+            }
+
+            return Ok((arm, env));
+        }
+
+        Err(Error::new(
+            ErrorKind::Match,
+            format!("no match arm matches {}", value),
+        ))
+    }
+
+    /// Checks whether `value` has the shape that `pattern` describes. Unlike `bind_pattern`, a value
+    /// that doesn't match isn't an error, and the bindings are collected into `bindings` instead of
+    /// being set in the environment, as they should only be set once the whole pattern has matched.
+    fn match_pattern<'a>(
+        &mut self,
+        pattern: &'a Pattern,
+        value: &Object,
+        bindings: &mut Vec<(&'a Identifier, Object)>,
+    ) -> EvalResult<bool> {
+        match (pattern, value) {
+            (Pattern::Identifier(identifier), value) => {
+                bindings.push((identifier, value.clone()));
+                Ok(true)
+            }
+
+            (Pattern::Wildcard(_), _) => Ok(true),
+
+            (Pattern::Literal(literal), value) => Ok(self.eval_expression(literal)? == *value),
+
+            (Pattern::Array(pattern), Object::Array(array)) => {
+                let want = pattern.elements.len();
+                let got = array.elements.len();
+
+                if got < want || (got > want && pattern.rest.is_none()) {
+                    return Ok(false);
+                }
+
+                for (element, value) in pattern.elements.iter().zip(&array.elements) {
+                    if !self.match_pattern(element, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                if let Some(rest) = &pattern.rest {
+                    let rest_elements = array.elements[want..].to_vec();
+                    self.allocate_array(rest_elements.len())?;
+                    bindings.push((rest, Object::Array(Array::new(rest_elements))));
+                }
+
+                Ok(true)
+            }
+
+            (Pattern::Hash(pattern), Object::Hash(hash)) => {
+                for (key, pattern) in &pattern.pairs {
+                    let key_object = self.eval_expression(key)?;
+
+                    let hash_key = HashKey::try_from(&key_object).map_err(|object_type| {
+                        Error::new(
+                            ErrorKind::Type,
+                            format!("unusable as hash key: {}", object_type),
+                        )
+                        .at(key.position())
+                    })?;
+
+                    let Some(value) = hash.get(&hash_key) else {
+                        return Ok(false);
+                    };
+
+                    if !self.match_pattern(pattern, value, bindings)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+
+            _ => Ok(false),
+        }
+    }
+}
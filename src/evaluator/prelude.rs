@@ -0,0 +1,33 @@
+use super::Evaluator;
+use crate::{lexer::Lexer, object::environment::Environment, parser::Parser};
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+/// The source code of the prelude, a set of higher-order helpers that are written in Monkey itself:
+///
+/// - `map(xs, f)`, `filter(xs, keep)` and `reduce(xs, initial, f)`.
+/// - `each(xs, f)`, which calls `f` on each element for its side effects, and returns `xs`.
+/// - `range(end)` and `range(start, end)`, the integers from `start` (or `0`) up to, but not
+///   including, `end`.
+/// - `zip(xs, ys)`, the pairs of elements at the same index, up to the length of the shorter array.
+/// - `contains(xs, value)`, whether `value` is an element of `xs`.
+/// - `join(xs, separator)`, the strings in `xs` joined together with `separator` in between.
+pub const PRELUDE: &str = include_str!("prelude.monkey");
+
+/// The file that errors in the prelude's code are reported as being in.
+pub(crate) const PRELUDE_FILE: &str = "<prelude>";
+
+/// Evaluates the prelude into a new environment. User code is evaluated in an environment that is
+/// enclosed by this one, so that user-defined names shadow the prelude's names instead of replacing
+/// them, which means that the prelude's functions keep working even if a user redefines e.g. `map`.
+pub(crate) fn load_prelude() -> Rc<RefCell<Environment>> {
+    let program = Parser::new(Lexer::new(PRELUDE))
+        .parse_program()
+        .expect("the prelude should parse");
+
+    let mut evaluator = Evaluator::without_prelude();
+    evaluator.file = Some(Rc::from(Path::new(PRELUDE_FILE)));
+
+    evaluator.run(program).expect("the prelude should evaluate");
+
+    evaluator.env
+}
@@ -0,0 +1,102 @@
+use super::{
+    errors::{Error, ErrorKind},
+    EvalResult, Evaluator,
+};
+use crate::{
+    ast::{
+        expressions::{Expression, Identifier, RecordLiteral},
+        statements::StructStatement,
+    },
+    object::{record::Record, structs::Struct, Object, Objective},
+    symbol::Symbol,
+};
+
+impl Evaluator {
+    /// Binds the struct declared by `declaration` in the current environment.
+    pub(crate) fn declare_struct(&mut self, declaration: &StructStatement) {
+        let fields = declaration
+            .fields
+            .iter()
+            .map(|field| field.value.clone())
+            .collect();
+
+        self.env.borrow_mut().bind(
+            &declaration.name,
+            Object::Struct(Struct::new(declaration.name.value.clone(), fields)),
+        );
+    }
+
+    /// Evaluates a `RecordLiteral`. The fields are evaluated in the order they were written, but are
+    /// stored in the order they were declared in the struct, so that records of the same struct always
+    /// look the same. Every field of the struct has to be given exactly once.
+    pub(crate) fn eval_record(&mut self, record: &RecordLiteral) -> EvalResult<Object> {
+        let declaration = match self
+            .eval_expression(&Expression::Identifier(record.name.clone()))?
+        {
+            Object::Struct(declaration) => declaration,
+            object => {
+                return Err(not_a_struct(&record.name.value, &object).at(record.name.token.position))
+            }
+        };
+
+        let mut values = Vec::with_capacity(record.fields.len());
+
+        for (field, value) in &record.fields {
+            if !declaration.fields.contains(&field.value) {
+                return Err(unknown_field(&declaration.name, field));
+            }
+
+            values.push((field.value.clone(), self.eval_expression(value)?));
+        }
+
+        build_record(&declaration, values).map_err(|error| error.at(record.name.token.position))
+    }
+}
+
+/// Builds a record of the struct `declaration` from the `values` of its fields, which can be in any
+/// order, but have to have every field of the struct.
+pub(crate) fn build_record(
+    declaration: &Struct,
+    values: Vec<(Symbol, Object)>,
+) -> EvalResult<Object> {
+    let fields = declaration
+        .fields
+        .iter()
+        .map(|name| {
+            let index = values
+                .iter()
+                .position(|(field, _)| field == name)
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::Member,
+                        format!("missing field {} in {} literal", name, declaration.name),
+                    )
+                })?;
+
+            Ok((name.clone(), values[index].1.clone()))
+        })
+        .collect::<EvalResult<_>>()?;
+
+    Ok(Object::Record(Record::new(
+        declaration.name.clone(),
+        fields,
+    )))
+}
+
+/// Creates the error for a record literal of `name`, which is bound to `object` rather than a struct.
+pub(crate) fn not_a_struct(name: &str, object: &Object) -> Error {
+    Error::new(
+        ErrorKind::Type,
+        format!("{} is not a struct, got {}", name, object.object_type()),
+    )
+}
+
+/// Creates the error for accessing (or giving a value to) the field `field` on a record of the struct
+/// `name`, which doesn't have a field with that name. The error happens at the field.
+pub(crate) fn unknown_field(name: &str, field: &Identifier) -> Error {
+    Error::new(
+        ErrorKind::Member,
+        format!("{} has no field {}", name, field),
+    )
+    .at(field.token.position)
+}
@@ -1,11 +1,133 @@
-use super::expressions::eval_expression;
-use crate::{ast::statements::Statement, object::Object};
+use super::{
+    errors::{Error, ErrorKind},
+    EvalResult, Evaluator,
+};
+use crate::{
+    ast::statements::{BlockStatement, Statement},
+    object::{function::Function, Object},
+};
+use std::rc::Rc;
 
-/// Evaluates a single `Statement`.
-pub fn eval_statement(statement: Statement) -> Option<Object> {
-    match statement {
-        Statement::Expression(expression) => eval_expression(expression.expression),
+impl Evaluator {
+    /// Evaluates a single `Statement`. Statements that don't produce a value (like `let`) return `None`.
+    pub(crate) fn eval_statement(&mut self, statement: &Statement) -> EvalResult<Option<Object>> {
+        match statement {
+            Statement::Expression(expression) => {
+                Ok(Some(self.eval_expression(&expression.expression)?))
+            }
 
-        _ => todo!(),
+            Statement::Let(let_statement) => {
+                let value = self.eval_expression(&let_statement.value)?;
+                self.bind_pattern(&let_statement.pattern, value)?;
+                Ok(None)
+            }
+
+            Statement::Return(return_statement) => {
+                let value = if self.tail_returns {
+                    self.eval_tail_expression(&return_statement.return_value)?
+                } else {
+                    self.eval_expression(&return_statement.return_value)?
+                };
+
+                Err(Error::returning(value))
+            }
+
+            Statement::Throw(throw) => self.eval_throw(throw),
+
+            // The statements of a generator's body are evaluated by `run_generator`, which handles the
+            // `yield`s that it can pause at, so a `yield` here is somewhere that it can't pause. The
+            // parser doesn't allow that, but a program might not have come from the parser.
+            Statement::Yield(yield_statement) => Err(Error::new(
+                ErrorKind::Syntax,
+                "yield is only allowed in a generator's body, and the if, for, try and match statements in it",
+            )
+            .at(yield_statement.token.position)),
+
+            // Function and struct declarations were already bound when the enclosing program or
+            // block was entered (see `hoist_declarations`), so there is nothing left to do here.
+            Statement::Function(_) | Statement::Struct(_) => Ok(None),
+
+            Statement::Import(import) => {
+                self.eval_import(import)?;
+                Ok(None)
+            }
+
+            // Top-level exports are handled by `eval`, so an export here is inside of a block. The parser
+            // doesn't allow that, but a program might not have come from the parser.
+            Statement::Export(export) => Err(Error::new(
+                ErrorKind::Syntax,
+                "export is only allowed at the top level of a module",
+            )
+            .at(export.token.position)),
+        }
+    }
+
+    /// Evaluates a `BlockStatement`, returning the value of the last statement (if it has one). A
+    /// `return` statement unwinds out of the block like an error does (see `Error::returning`), so that
+    /// the enclosing blocks and expressions stop evaluating as well.
+    pub(crate) fn eval_block(&mut self, block: &BlockStatement) -> EvalResult<Option<Object>> {
+        self.eval_block_statements(block, false)
+    }
+
+    /// Evaluates a `BlockStatement` that is in tail position, like `eval_block`. The block's last
+    /// statement is in tail position too, so if it is a call, it is made as a tail call.
+    pub(crate) fn eval_tail_block(&mut self, block: &BlockStatement) -> EvalResult<Option<Object>> {
+        self.eval_block_statements(block, true)
+    }
+
+    /// Evaluates the statements of a `BlockStatement`, with the last statement being evaluated in tail
+    /// position if `tail` is set.
+    fn eval_block_statements(
+        &mut self,
+        block: &BlockStatement,
+        tail: bool,
+    ) -> EvalResult<Option<Object>> {
+        self.hoist_declarations(&block.statements);
+
+        let mut result = None;
+
+        for (index, statement) in block.statements.iter().enumerate() {
+            result = match statement {
+                Statement::Expression(expression)
+                    if tail && index == block.statements.len() - 1 =>
+                {
+                    Some(self.eval_tail_expression(&expression.expression)?)
+                }
+                statement => self.eval_statement(statement)?,
+            };
+        }
+
+        Ok(result)
+    }
+
+    /// Binds every function and struct declared in `statements` in the current environment, before any
+    /// of the statements are evaluated. As all of the functions capture the same environment, they can
+    /// all see each other, so mutually recursive functions work no matter what order they are declared
+    /// in.
+    pub(crate) fn hoist_declarations(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            let statement = match statement {
+                Statement::Export(export) => export.declaration.as_ref(),
+                statement => statement,
+            };
+
+            match statement {
+                Statement::Function(declaration) => {
+                    let function = Function::new(
+                        Rc::new(declaration.function.clone()),
+                        Rc::clone(&self.env),
+                        self.file.clone(),
+                    );
+
+                    self.env
+                        .borrow_mut()
+                        .bind(&declaration.name, Object::Function(function));
+                }
+
+                Statement::Struct(declaration) => self.declare_struct(declaration),
+
+                _ => {}
+            }
+        }
     }
 }
@@ -1,8 +1,29 @@
 #![cfg(test)]
 
-use crate::{ast::Program, lexer::Lexer, object::Object, parser::Parser};
+use crate::{
+    ast::{expressions::Expression, statements::Statement, Program},
+    lexer::Lexer,
+    object::Object,
+    parser::Parser,
+    vm,
+};
+use std::{
+    cell::RefCell,
+    io,
+    path::Path,
+    path::PathBuf,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
+};
 
-use super::eval;
+use super::{
+    budget::Budget,
+    errors::{Error, ErrorKind},
+    eval,
+    modules::{Loader, MemoryLoader},
+    Evaluator,
+};
 
 /// Parses the input source code
 fn parse(input: &str) -> Program {
@@ -11,6 +32,48 @@ fn parse(input: &str) -> Program {
     parser.parse_program().unwrap()
 }
 
+/// Parses and evaluates the input source code, expecting it to succeed. The program is also run on
+/// the `Vm`, which has to give the same result.
+fn run(input: &str) -> Option<Object> {
+    let program = parse(input);
+    let obj = eval(&program).unwrap();
+
+    match vm::eval(&program) {
+        Ok(vm_obj) => assert_eq!(
+            vm_obj.as_ref().map(ToString::to_string),
+            obj.as_ref().map(ToString::to_string),
+            "the vm gave a different result for {}",
+            input
+        ),
+        Err(error) => panic!("the vm failed on {}: {}", input, error),
+    }
+
+    obj
+}
+
+/// Parses and evaluates the input source code, expecting it to fail at runtime. The program is also
+/// run on the `Vm`, which has to fail with the same error.
+fn run_error(input: &str) -> Error {
+    let program = parse(input);
+
+    let error = match eval(&program) {
+        Ok(obj) => panic!("Expected an error, found {:?}", obj),
+        Err(error) => error,
+    };
+
+    match vm::eval(&program) {
+        Ok(obj) => panic!("the vm didn't fail on {}, found {:?}", input, obj),
+        Err(vm_error) => assert_eq!(
+            (vm_error.kind, vm_error.to_string()),
+            (error.kind, error.to_string()),
+            "the vm gave a different error for {}",
+            input
+        ),
+    }
+
+    error
+}
+
 /// Tests an integer object
 fn test_integer_object(obj: Option<Object>, value: i64) {
     match obj {
@@ -19,6 +82,14 @@ fn test_integer_object(obj: Option<Object>, value: i64) {
     }
 }
 
+/// Tests a null object
+fn test_null_object(obj: Option<Object>) {
+    match obj {
+        Some(Object::Null(_)) => {}
+        _ => panic!("Object is not Null, found {:?}", obj),
+    }
+}
+
 /// Tests a boolean object
 fn test_boolean_object(obj: Option<Object>, value: bool) {
     match obj {
@@ -30,11 +101,26 @@ fn test_boolean_object(obj: Option<Object>, value: bool) {
 /// Tests the evaluation of an integer expression
 #[test]
 fn test_eval_integer_expression() {
-    let tests = vec![("5", 5), ("10", 10)];
+    let tests = vec![
+        ("5", 5),
+        ("10", 10),
+        ("-5", -5),
+        ("-10", -10),
+        ("5 + 5 + 5 + 5 - 10", 10),
+        ("2 * 2 * 2 * 2 * 2", 32),
+        ("-50 + 100 + -50", 0),
+        ("5 * 2 + 10", 20),
+        ("5 + 2 * 10", 25),
+        ("20 + 2 * -10", 0),
+        ("50 / 2 * 2 + 10", 60),
+        ("2 * (5 + 10)", 30),
+        ("3 * 3 * 3 + 10", 37),
+        ("3 * (3 * 3) + 10", 37),
+        ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+    ];
 
     for (input, expected) in tests {
-        let program = parse(input);
-        let obj = eval(program);
+        let obj = run(input);
 
         test_integer_object(obj, expected);
     }
@@ -43,11 +129,30 @@ fn test_eval_integer_expression() {
 /// Tests the evaluation of a boolean expression
 #[test]
 fn test_eval_boolean_expression() {
-    let tests = vec![("true", true), ("false", false)];
+    let tests = vec![
+        ("true", true),
+        ("false", false),
+        ("1 < 2", true),
+        ("1 > 2", false),
+        ("1 < 1", false),
+        ("1 > 1", false),
+        ("1 == 1", true),
+        ("1 != 1", false),
+        ("1 == 2", false),
+        ("1 != 2", true),
+        ("true == true", true),
+        ("false == false", true),
+        ("true == false", false),
+        ("true != false", true),
+        ("false != true", true),
+        ("(1 < 2) == true", true),
+        ("(1 < 2) == false", false),
+        ("(1 > 2) == true", false),
+        ("(1 > 2) == false", true),
+    ];
 
     for (input, expected) in tests {
-        let program = parse(input);
-        let obj = eval(program);
+        let obj = run(input);
 
         test_boolean_object(obj, expected);
     }
@@ -66,9 +171,2220 @@ fn test_eval_boolean_prefix() {
     ];
 
     for (input, expected) in tests {
-        let program = parse(input);
-        let obj = eval(program);
+        let obj = run(input);
 
         test_boolean_object(obj, expected);
     }
 }
+
+/// Tests the evaluation of if-else expressions
+#[test]
+fn test_eval_if_else_expressions() {
+    let tests = vec![
+        ("if (true) { 10 }", Some(10)),
+        ("if (false) { 10 }", None),
+        ("if (1) { 10 }", Some(10)),
+        ("if (1 < 2) { 10 }", Some(10)),
+        ("if (1 > 2) { 10 }", None),
+        ("if (1 > 2) { 10 } else { 20 }", Some(20)),
+        ("if (1 < 2) { 10 } else { 20 }", Some(10)),
+        (
+            "if (1 > 2) { 10 } else if (1 < 2) { 20 } else { 30 }",
+            Some(20),
+        ),
+        (
+            "if (1 > 2) { 10 } else if (1 > 2) { 20 } else { 30 }",
+            Some(30),
+        ),
+        ("if (1 > 2) { 10 } else if (1 > 2) { 20 }", None),
+        (
+            "if (false) { 1 } else if (false) { 2 } else if (true) { 3 } else { 4 }",
+            Some(3),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        match expected {
+            Some(expected) => test_integer_object(obj, expected),
+            None => test_null_object(obj),
+        }
+    }
+}
+
+/// Tests the evaluation of return statements
+#[test]
+fn test_eval_return_statements() {
+    let tests = vec![
+        ("return 10;", 10),
+        ("return 10; 9;", 10),
+        ("return 2 * 5; 9;", 10),
+        ("9; return 2 * 5; 9;", 10),
+        ("if (10 > 1) { if (10 > 1) { return 10; } return 1; }", 10),
+        ("if (10 > 1) { return 10 }", 10),
+        // A `return` anywhere in an expression ends the function that it is in, and is never a value.
+        (
+            "let f = fn() { let a = if (true) { return 1; }; 3 }; f()",
+            1,
+        ),
+        ("fn(){ 1 + if (true) { return 2; } }()", 2),
+        (
+            "let f = fn() { let [x] = try { return 7 } finally {}; x }; f()",
+            7,
+        ),
+        (
+            "let f = fn() { [if (true) { return 1; }, missing] }; f()",
+            1,
+        ),
+        ("let f = fn() { try { return 1; } catch (e) { 2 } }; f()", 1),
+        ("let a = try { return -3; } finally { 1 }; 5", -3),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        test_integer_object(obj, expected);
+    }
+}
+
+/// Tests a `return` from inside of each kind of expression, and each position that an expression can
+/// be in, which the `Vm` has to agree on
+#[test]
+fn test_eval_returns_in_expressions() {
+    let tests = vec![
+        // In an `if`, `try` and `match` that are values, not statements.
+        (
+            "let f = fn() { let a = if (true) { return 1; }; 3 }; f()",
+            "1",
+        ),
+        (
+            "let f = fn() { let a = try { return 2; } catch (e) { 0 }; 3 }; f()",
+            "2",
+        ),
+        (
+            "let f = fn() { let a = match (1) { 1 => if (true) { return 3; } }; 4 }; f()",
+            "3",
+        ),
+        (
+            "let f = fn() { let a = try { 1 } finally { return 4; }; 5 }; f()",
+            "4",
+        ),
+        (
+            "let f = fn() { let a = for (x in 0..3) { return x; }; 5 }; f()",
+            "0",
+        ),
+        // In the operands of operators.
+        ("fn(){ 1 + if (true) { return 2; } }()", "2"),
+        ("fn(){ if (true) { return 3; } + missing }()", "3"),
+        ("fn(){ -if (true) { return 4; } }()", "4"),
+        ("fn(){ [1, 2][if (true) { return 5; }] }()", "5"),
+        // In call arguments, and the elements of array and hash literals.
+        (
+            "let f = fn() { puts(if (true) { return 1; }); 2 }; f()",
+            "1",
+        ),
+        (
+            "let f = fn(...xs) { xs }; let g = fn() { f(...if (true) { return [2]; }) }; g()",
+            "[2]",
+        ),
+        (
+            "let f = fn() { [if (true) { return 3; }, missing] }; f()",
+            "3",
+        ),
+        (
+            r#"let f = fn() { {"a": if (true) { return 4; }} }; f()"#,
+            "4",
+        ),
+        // In destructuring, where the value is never bound.
+        (
+            "let f = fn() { let [x] = try { return 7 } finally {}; x }; f()",
+            "7",
+        ),
+        (
+            r#"let f = fn() { let {"a": a} = if (true) { return 8; }; a }; f()"#,
+            "8",
+        ),
+        // In a match guard.
+        (
+            "let f = fn() { match (1) { x if if (true) { return 9; } => 0 } }; f()",
+            "9",
+        ),
+        // Only the innermost function is returned from.
+        (
+            "let f = fn() { let g = fn() { 1 + if (true) { return 1; } }; g() + 1 }; f()",
+            "2",
+        ),
+        // At the top level, the program is returned from.
+        ("let a = try { return -3; } finally { 1 }; 5", "-3"),
+        ("let a = if (true) { return 1; }; 2", "1"),
+        ("puts(match (1) { _ => if (true) { return 2; } }); 3", "2"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the evaluation of let statements
+#[test]
+fn test_eval_let_statements() {
+    let tests = vec![
+        ("let a = 5; a;", 5),
+        ("let a = 5 * 5; a;", 25),
+        ("let a = 5; let b = a; b;", 5),
+        ("let a = 5; let b = a; let c = a + b + 5; c;", 15),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        test_integer_object(obj, expected);
+    }
+}
+
+/// Tests the runtime errors produced by the evaluator
+#[test]
+fn test_eval_errors() {
+    let tests = vec![
+        ("5 + true;", "type mismatch: INTEGER + BOOLEAN"),
+        ("5 + true; 5;", "type mismatch: INTEGER + BOOLEAN"),
+        ("-true", "unknown operator: -BOOLEAN"),
+        ("true + false;", "unknown operator: BOOLEAN + BOOLEAN"),
+        ("5; true + false; 5", "unknown operator: BOOLEAN + BOOLEAN"),
+        (
+            "if (10 > 1) { true + false; }",
+            "unknown operator: BOOLEAN + BOOLEAN",
+        ),
+        (
+            "if (10 > 1) { if (10 > 1) { return true + false; } return 1; }",
+            "unknown operator: BOOLEAN + BOOLEAN",
+        ),
+        ("foobar", "identifier not found: foobar"),
+        ("5(1)", "not a function: INTEGER"),
+        ("1 / 0", "division by zero"),
+        (
+            "9223372036854775807 + 1",
+            "integer overflow: 9223372036854775807 + 1",
+        ),
+        (
+            "-9223372036854775807 - 2",
+            "integer overflow: -9223372036854775807 - 2",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.message, expected, "input: {}", input);
+    }
+}
+
+/// Tests the evaluation of function literals
+#[test]
+fn test_eval_function_object() {
+    let obj = run("fn(x) { x + 2; };");
+
+    let Some(Object::Function(function)) = obj else {
+        panic!("Object is not a Function, found {:?}", obj);
+    };
+
+    assert_eq!(function.literal.parameters.len(), 1);
+    assert_eq!(function.literal.parameters[0].name().value, "x");
+    assert_eq!(function.literal.body.to_string(), "(x + 2)");
+}
+
+/// Tests the application of functions
+#[test]
+fn test_eval_function_application() {
+    let tests = vec![
+        ("let identity = fn(x) { x; }; identity(5);", 5),
+        ("let identity = fn(x) { return x; }; identity(5);", 5),
+        ("let double = fn(x) { x * 2; }; double(5);", 10),
+        ("let add = fn(x, y) { x + y; }; add(5, 5);", 10),
+        ("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));", 20),
+        ("fn(x) { x; }(5)", 5),
+        (
+            "let newAdder = fn(x) { fn(y) { x + y }; }; let addTwo = newAdder(2); addTwo(2);",
+            4,
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        test_integer_object(obj, expected);
+    }
+}
+
+/// Tests looking variables up in the slots that the resolver finds for them
+#[test]
+fn test_eval_resolved_variables() {
+    let tests = vec![
+        ("let x = 1; let f = fn(x) { x }; f(2) + x", "3"),
+        (
+            "let f = fn(a) { let g = fn(b) { let a = a + b; a }; [g(10), a] }; f(1)",
+            "[11, 1]",
+        ),
+        // Until a slot is bound, the name is looked up further out, like it would be by name.
+        (
+            "let x = 1; fn f(c) { if (c) { let x = 2; } x } [f(true), f(false)]",
+            "[2, 1]",
+        ),
+        ("fn f() { let g = fn() { y }; let y = 2; g() } f()", "2"),
+        (
+            "let xs = []; for (x in 0..3) { let xs = [...xs, x]; xs }",
+            "null",
+        ),
+        (
+            "fn f(n) { match (n) { [a, b] => a + b, a => a } } [f([1, 2]), f(3)]",
+            "[3, 3]",
+        ),
+        (
+            "let e = 1; let r = try { throw 2 } catch (e) { e.value }; [r, e]",
+            "[2, 1]",
+        ),
+        // Closures share the variables that they use with the scopes that they were created in.
+        (
+            "fn f() { let x = 1; let g = fn() { x }; let x = 2; g() } f()",
+            "2",
+        ),
+        (
+            "fn f() { let k = fn() { [a(), b()] }; fn a() { b() } fn b() { 2 } k() } f()",
+            "[2, 2]",
+        ),
+        (
+            "fn f(n) { fn g(m) { fn h() { n + m } h() } g(1) } f(2)",
+            "3",
+        ),
+        // Each loop iteration has variables of its own, which its closures keep.
+        (
+            "fn g() { for (i in 0..3) { let j = i * 10; yield fn() { [i, j] } } }
+            let fs = collect(g());
+            [map(fs, fn(f) { f() }), fs[0] == fs[0], fs[0] == fs[1]]",
+            "[[[0, 0], [1, 10], [2, 20]], true, false]",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(
+            run(input).unwrap().to_string(),
+            expected,
+            "input: {}",
+            input
+        );
+    }
+
+    let mut evaluator = Evaluator::new();
+    evaluator
+        .eval(&parse("let f = fn() { x }; let x = 1;"))
+        .unwrap();
+
+    assert_eq!(evaluator.warnings().len(), 0);
+
+    evaluator
+        .eval(&parse("fn g() { a; let a = 1; } z"))
+        .unwrap_err();
+
+    let warnings = evaluator
+        .warnings()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        warnings,
+        vec![
+            "1:10: a is used before it is defined",
+            "1:26: identifier not found: z"
+        ]
+    );
+}
+
+/// Tests named function declarations, including calling them before they are declared
+#[test]
+fn test_eval_function_declarations() {
+    let tests = vec![
+        ("fn add(x, y) { x + y } add(1, 2)", 3),
+        ("let x = add(1, 2); fn add(x, y) { x + y } x", 3),
+        (
+            "fn fib(n) { if (n < 2) { n } else { fib(n - 1) + fib(n - 2) } } fib(10)",
+            55,
+        ),
+        (
+            r"
+            fn is_even(n) { if (n == 0) { true } else { is_odd(n - 1) } }
+            fn is_odd(n) { if (n == 0) { false } else { is_even(n - 1) } }
+            if (is_even(10)) { 1 } else { 0 }
+            ",
+            1,
+        ),
+        (
+            r"
+            let check = fn(n) {
+                if (is_odd(n)) { return 1; }
+                return 0;
+
+                fn is_odd(n) { if (n == 0) { false } else { is_even(n - 1) } }
+                fn is_even(n) { if (n == 0) { true } else { is_odd(n - 1) } }
+            };
+            check(7)
+            ",
+            1,
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        test_integer_object(obj, expected);
+    }
+}
+
+/// Tests that errors raised inside of functions have a stack trace with the functions' names
+#[test]
+fn test_eval_error_trace() {
+    let tests = vec![
+        (
+            "fn fib(n) { if (n < 2) { n + true } else { 1 + fib(n - 1) } } fib(2)",
+            vec!["fib", "fib"],
+        ),
+        // A tail call replaces the function that made it, so it doesn't show up in the trace.
+        (
+            "fn count(n) { if (n < 2) { n + true } else { count(n - 1) } } count(5)",
+            vec!["count"],
+        ),
+        (
+            "let outer = fn() { let x = inner(); x }; fn inner() { missing } outer()",
+            vec!["inner", "outer"],
+        ),
+        ("fn(x) { x / 0 }(1)", vec!["<anonymous>"]),
+        ("fn add(x, y) { x + y } add(1)", vec!["add"]),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.trace, expected, "input: {}", input);
+    }
+
+    let error = run_error("fn fib(n) { fib + n } fib(1)");
+
+    assert_eq!(
+        error.to_string(),
+        "1:17: type mismatch: FUNCTION + INTEGER\n    in fib"
+    );
+}
+
+/// Tests that calls in tail position run in constant stack space
+#[test]
+fn test_eval_tail_calls() {
+    let tests = vec![
+        (
+            "let loop = fn(n) { if (n == 0) { 0 } else { loop(n - 1) } }; loop(100000)",
+            "0",
+        ),
+        (
+            "fn sum(n, acc) { if (n == 0) { return acc; } return sum(n - 1, acc + n); } sum(100000, 0)",
+            "5000050000",
+        ),
+        (
+            "fn sum(n, acc) { if (n > 0) { return sum(n - 1, acc + n); } acc } sum(100000, 0)",
+            "5000050000",
+        ),
+        (
+            "fn even(n) { if (n == 0) { true } else { odd(n - 1) } }
+            fn odd(n) { if (n == 0) { false } else { even(n - 1) } }
+            [even(100000), odd(100001)]",
+            "[true, true]",
+        ),
+        (
+            r#"fn down(n) { match (n) { 0 => "done", _ => down(n - 1) } } down(100000)"#,
+            "done",
+        ),
+        (
+            "fn down(n) { if (n == 0) { 0 } else if (n > 50000) { n - 1 |> down } else { down(n - 1) } } down(100000)",
+            "0",
+        ),
+        ("let down = |n| if (n == 0) { 0 } else { down(n - 1) }; down(100000)", "0"),
+        // Tail calls still get the right values for their parameters.
+        (
+            "fn f(a, b = a * 2, ...rest) { if (a == 0) { [b, rest] } else { f(a - 1, b, 9) } } f(3)",
+            "[6, [9]]",
+        ),
+        // A call inside of a `try` isn't in tail position, so its errors are still caught.
+        (
+            r#"fn boom() { throw "boom"; } fn f() { try { return boom(); } catch (e) { e.message } } f()"#,
+            "boom",
+        ),
+        (
+            r#"fn boom() { 1 } fn f() { try { return boom(); } finally { return 2; } } f()"#,
+            "2",
+        ),
+        // A `return` at the top level of a program isn't a tail call.
+        ("fn f() { 1 } return f(); 2", "1"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+
+    let tests = vec![
+        (
+            "fn f(n) { if (n == 0) { g() } else { f(n - 1) } } f(10)",
+            "1:25: identifier not found: g\n    in f",
+        ),
+        (
+            "fn f(n) { if (n == 0) { 1() } else { f(n - 1) } } f(10)",
+            "1:26: not a function: INTEGER\n    in f",
+        ),
+        (
+            "fn f(n) { f() } f(1)",
+            "1:12: wrong number of arguments: want=1, got=0\n    in f",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests that deep recursion and nesting are runtime errors instead of stack overflows
+#[test]
+fn test_eval_depth_limits() {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_max_call_depth(20);
+    evaluator.set_max_depth(100);
+
+    let error = evaluator
+        .eval(&parse("fn f(n) { 1 + f(n + 1) } f(1)"))
+        .unwrap_err();
+
+    assert_eq!(error.kind, ErrorKind::Limit);
+    assert_eq!(error.trace.len(), 20);
+    assert_eq!(
+        error.to_string(),
+        "1:16: maximum call depth of 20 exceeded\n    in f (and 19 more times)"
+    );
+
+    let error = evaluator
+        .eval(&parse(&format!("{}1", "-".repeat(120))))
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "1:101: maximum nesting depth of 100 exceeded"
+    );
+
+    // The depth of every call counts towards the nesting depth.
+    let error = evaluator
+        .eval(&parse(&format!(
+            "fn g(n) {{ {}g(n) }} g(1)",
+            "-".repeat(50)
+        )))
+        .unwrap_err();
+
+    assert_eq!(error.message, "maximum nesting depth of 100 exceeded");
+
+    let tests = vec![
+        // Tail calls don't count towards the call depth.
+        (
+            "let loop = fn(n) { if (n == 0) { 0 } else { loop(n - 1) } }; loop(1000)",
+            "0",
+        ),
+        (
+            "fn f(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } } f(19)",
+            "19",
+        ),
+        // Exceeding a limit unwinds the stack, so the error can be caught.
+        (
+            "fn f(n) { 1 + f(n) } try { f(1) } catch (e) { [e.kind, e.message] }",
+            "[limit, maximum call depth of 20 exceeded]",
+        ),
+        // The depths go back down after an error, so the evaluator can still be used afterwards.
+        (
+            "fn f(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } } f(19)",
+            "19",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = evaluator.eval(&parse(input)).unwrap();
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests that evaluation stops once it runs out of its budget
+#[test]
+fn test_eval_budgets() {
+    let mut evaluator = Evaluator::without_prelude();
+
+    evaluator.set_budget(Budget::unlimited().with_fuel(10).with_memory(100));
+    evaluator.eval(&parse(r#"1 + 2; "abc""#)).unwrap();
+
+    // Both `1 + 2` and its operands are a step each, and so is the string.
+    assert_eq!(
+        evaluator.remaining_budget(),
+        Budget::unlimited().with_fuel(6).with_memory(97)
+    );
+
+    let tests = vec![
+        (
+            Budget::unlimited().with_fuel(10_000),
+            "let f = fn() { f() }; f()",
+            "fuel budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_deadline(Instant::now()),
+            "let f = fn() { f() }; f()",
+            "time budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_timeout(Duration::from_millis(10)),
+            "let f = fn() { f() }; f()",
+            "time budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_memory(1_000_000),
+            r#"let f = fn(s) { f(s + s) }; f("a")"#,
+            "memory budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_memory(1_000),
+            "let f = fn(xs) { f([...xs, 1]) }; f([])",
+            "memory budget exceeded",
+        ),
+        // Running out of budget can't be caught, and `finally` blocks don't run afterwards either.
+        (
+            Budget::unlimited().with_fuel(10_000),
+            "let f = fn() { f() }; try { f() } catch (e) { 1 }",
+            "fuel budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_fuel(10_000),
+            "let f = fn() { f() }; let g = fn() { try { f() } finally { return 1 } }; g()",
+            "fuel budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_fuel(10_000),
+            "let f = fn() { 1 }; try { f() } catch (e) { 1 } finally { let g = fn() { g() }; g() }",
+            "fuel budget exceeded",
+        ),
+    ];
+
+    for (budget, input, expected) in tests {
+        evaluator.set_budget(budget);
+
+        let error = evaluator.eval(&parse(input)).unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::BudgetExceeded, "input: {}", input);
+        assert_eq!(error.message, expected, "input: {}", input);
+    }
+
+    // Once the budget has run out, nothing else can be evaluated until a new one is set.
+    assert_eq!(evaluator.remaining_budget().fuel, Some(0));
+    assert!(evaluator.eval(&parse("1")).is_err());
+
+    evaluator.set_budget(Budget::unlimited());
+
+    assert_eq!(
+        evaluator.eval(&parse("1")).unwrap().unwrap().to_string(),
+        "1"
+    );
+}
+
+/// Tests that evaluation can be interrupted from another thread
+#[test]
+fn test_eval_interrupts() {
+    let mut evaluator = Evaluator::without_prelude();
+    let interrupt = evaluator.interrupt_handle();
+
+    // An interrupt before evaluation starts stops it straight away, and is then cleared.
+    interrupt.interrupt();
+
+    let error = evaluator.eval(&parse("1")).unwrap_err();
+
+    assert_eq!(error.kind, ErrorKind::Interrupted);
+    assert_eq!(error.to_string(), "1:1: interrupted");
+    assert!(!interrupt.is_interrupted());
+
+    let tests = vec![
+        "let f = fn() { f() }; f()",
+        // Being interrupted can't be caught, and `finally` blocks don't run afterwards either.
+        "let f = fn() { f() }; try { f() } catch (e) { 1 }",
+        "let f = fn() { f() }; let g = fn() { try { f() } finally { return 1 } }; g()",
+    ];
+
+    for input in tests {
+        let interrupter = {
+            let interrupt = interrupt.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                interrupt.interrupt();
+            })
+        };
+
+        let error = evaluator.eval(&parse(input)).unwrap_err();
+        interrupter.join().unwrap();
+
+        assert_eq!(error.kind, ErrorKind::Interrupted, "input: {}", input);
+    }
+
+    // The evaluator, and its bindings, can still be used after being interrupted.
+    assert_eq!(
+        evaluator.eval(&parse("f")).unwrap().unwrap().to_string(),
+        "fn f() { f() }"
+    );
+}
+
+/// Tests that the default limits can't overflow the stack of a thread with the default stack size,
+/// which is what a host is likely to evaluate code on
+#[test]
+fn test_eval_default_stack() {
+    let tests = vec![
+        ("fn f(n) { 1 + f(n + 1) } f(1)", None),
+        ("fn f(n) { [f(n + 1)] } f(1)", None),
+        (
+            "let f = fn(n) { try { 1 + f(n + 1) } finally { 0 } }; f(1)",
+            None,
+        ),
+        ("fn f() { collect(g()) } fn g() { yield f(); } f()", None),
+        (
+            "fn f(n) { 1 + f(n + 1) } try { f(1) } catch (e) { e.kind }",
+            Some("limit"),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let result = thread::spawn(move || {
+            Evaluator::new()
+                .eval(&parse(input))
+                .map(|obj| obj.unwrap().to_string())
+                .map_err(|error| error.kind)
+        })
+        .join()
+        .unwrap();
+
+        match expected {
+            Some(expected) => assert_eq!(result, Ok(expected.to_string()), "input: {}", input),
+            None => assert_eq!(result, Err(ErrorKind::Limit), "input: {}", input),
+        }
+    }
+}
+
+/// Tests the evaluation of array literals
+#[test]
+fn test_eval_array_literals() {
+    let tests = vec![
+        ("[1, 2 * 2, 3 + 3]", "[1, 4, 6]"),
+        ("[]", "[]"),
+        ("let xs = [2, 3]; [1, ...xs, 4]", "[1, 2, 3, 4]"),
+        ("[...[], ...[1]]", "[1]"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        match obj {
+            Some(Object::Array(array)) => assert_eq!(array.to_string(), expected),
+            _ => panic!("Object is not an Array, found {:?}", obj),
+        }
+    }
+}
+
+/// Tests the evaluation of index expressions
+#[test]
+fn test_eval_index_expressions() {
+    let tests = vec![
+        ("[1, 2, 3][0]", Some(1)),
+        ("[1, 2, 3][1]", Some(2)),
+        ("[1, 2, 3][2]", Some(3)),
+        ("let i = 0; [1][i];", Some(1)),
+        ("[1, 2, 3][1 + 1];", Some(3)),
+        ("let myArray = [1, 2, 3]; myArray[2];", Some(3)),
+        (
+            "let myArray = [1, 2, 3]; myArray[0] + myArray[1] + myArray[2];",
+            Some(6),
+        ),
+        ("[1, 2, 3][3]", None),
+        ("[1, 2, 3][-1]", None),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        match expected {
+            Some(expected) => test_integer_object(obj, expected),
+            None => test_null_object(obj),
+        }
+    }
+}
+
+/// Tests default parameters, rest parameters and spread arguments
+#[test]
+fn test_eval_default_rest_and_spread() {
+    let tests = vec![
+        ("let f = fn(x, y = 10) { x + y }; f(1)", "11"),
+        ("let f = fn(x, y = 10) { x + y }; f(1, 2)", "3"),
+        ("let f = fn(x, y = x * 2) { x + y }; f(3)", "9"),
+        ("let f = fn(x = 1, y = 2) { [x, y] }; f()", "[1, 2]"),
+        ("let f = fn(first, ...rest) { rest }; f(1, 2, 3)", "[2, 3]"),
+        ("let f = fn(first, ...rest) { rest }; f(1)", "[]"),
+        (
+            "let f = fn(x, y = 2, ...rest) { [x, y, rest] }; f(1)",
+            "[1, 2, []]",
+        ),
+        (
+            "let f = fn(x, y = 2, ...rest) { [x, y, rest] }; f(1, 5, 6, 7)",
+            "[1, 5, [6, 7]]",
+        ),
+        (
+            "let add = fn(x, y) { x + y }; let xs = [1, 2]; add(...xs)",
+            "3",
+        ),
+        (
+            "let add = fn(x, y, z) { x + y + z }; add(...[1], 2, ...[3])",
+            "6",
+        ),
+        (
+            "let all = fn(...xs) { xs }; all(...[1, 2], ...[], 3)",
+            "[1, 2, 3]",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the runtime errors for calling functions with the wrong arguments
+#[test]
+fn test_eval_argument_errors() {
+    let tests = vec![
+        (
+            "let f = fn(x, y) { x }; f(1)",
+            "wrong number of arguments: want=2, got=1",
+        ),
+        (
+            "let f = fn(x, y = 1) { x }; f()",
+            "wrong number of arguments: want=1 to 2, got=0",
+        ),
+        (
+            "let f = fn(x, y = 1) { x }; f(1, 2, 3)",
+            "wrong number of arguments: want=1 to 2, got=3",
+        ),
+        (
+            "let f = fn(x, ...rest) { x }; f()",
+            "wrong number of arguments: want=at least 1, got=0",
+        ),
+        (
+            "let f = fn(x) { x }; f(...1)",
+            "cannot spread INTEGER, only arrays can be spread",
+        ),
+        (
+            "let f = fn(x, y = missing) { x }; f(1)",
+            "identifier not found: missing",
+        ),
+        ("1[0]", "index operator not supported: INTEGER"),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.message, expected, "input: {}", input);
+    }
+}
+
+/// Tests the evaluation of strings
+#[test]
+fn test_eval_strings() {
+    let tests = vec![
+        (r#""Hello World!""#, "Hello World!"),
+        (r#""Hello" + " " + "World!""#, "Hello World!"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        match obj {
+            Some(Object::String(string)) => assert_eq!(string.value, expected),
+            _ => panic!("Object is not a String, found {:?}", obj),
+        }
+    }
+
+    test_boolean_object(run(r#""a" == "a""#), true);
+    test_boolean_object(run(r#""a" != "a""#), false);
+
+    let error = run_error(r#""Hello" - "World""#);
+
+    assert_eq!(error.message, "unknown operator: STRING - STRING");
+}
+
+/// Tests the evaluation of hash literals and indexing into them
+#[test]
+fn test_eval_hashes() {
+    let obj = run(r#"
+        let two = "two";
+        {
+            "one": 10 - 9,
+            two: 1 + 1,
+            "thr" + "ee": 6 / 2,
+            4: 4,
+            true: 5,
+            false: 6
+        }
+    "#);
+
+    match obj {
+        Some(Object::Hash(hash)) => {
+            assert_eq!(hash.pairs.len(), 6);
+            assert_eq!(
+                hash.to_string(),
+                "{4: 4, false: 6, true: 5, one: 1, three: 3, two: 2}"
+            );
+        }
+        _ => panic!("Object is not a Hash, found {:?}", obj),
+    }
+
+    let tests = vec![
+        (r#"{"foo": 5}["foo"]"#, Some(5)),
+        (r#"{"foo": 5}["bar"]"#, None),
+        (r#"let key = "foo"; {"foo": 5}[key]"#, Some(5)),
+        (r#"{}["foo"]"#, None),
+        ("{5: 5}[5]", Some(5)),
+        ("{true: 5}[true]", Some(5)),
+        ("{false: 5}[false]", Some(5)),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        match expected {
+            Some(expected) => test_integer_object(obj, expected),
+            None => test_null_object(obj),
+        }
+    }
+
+    let tests = vec![
+        (
+            r#"{"name": "Monkey"}[fn(x) { x }];"#,
+            "unusable as hash key: FUNCTION",
+        ),
+        ("{[1]: 1}", "unusable as hash key: ARRAY"),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.message, expected, "input: {}", input);
+    }
+}
+
+/// Tests destructuring arrays and hashes in let statements
+#[test]
+fn test_eval_destructuring() {
+    let tests = vec![
+        ("let [a, b] = [1, 2]; [b, a]", "[2, 1]"),
+        (
+            "let [a, b, ...rest] = [1, 2, 3, 4]; [a, b, rest]",
+            "[1, 2, [3, 4]]",
+        ),
+        ("let [a, ...rest] = [1]; rest", "[]"),
+        ("let [] = []; 1", "1"),
+        (
+            r#"let {"name": n, "age": a} = {"name": "Monkey", "age": 3, "extra": 0}; [n, a]"#,
+            "[Monkey, 3]",
+        ),
+        (
+            r#"let [x, [y, z], {"w": [v, ...u]}] = [1, [2, 3], {"w": [4, 5, 6]}]; [x, y, z, v, u]"#,
+            "[1, 2, 3, 4, [5, 6]]",
+        ),
+        (
+            r#"let k = "key"; let {k: value} = {"key": 42}; value"#,
+            "42",
+        ),
+        ("let {1: one, true: yes} = {1: 1, true: 2}; one + yes", "3"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the errors from destructuring values that don't match the pattern
+#[test]
+fn test_eval_destructuring_errors() {
+    let tests = vec![
+        (
+            "let [a, b, c] = [1, 2];",
+            "1:5: not enough elements to destructure: want=3, got=2",
+        ),
+        (
+            "let [a, b, ...c] = [1];",
+            "1:5: not enough elements to destructure: want=at least 2, got=1",
+        ),
+        (
+            "let [a] = [1, 2];",
+            "1:5: too many elements to destructure: want=1, got=2",
+        ),
+        (
+            "let [a] = 1;",
+            "1:5: cannot destructure INTEGER as an array",
+        ),
+        (
+            "let x = 1;\nlet [a, [b, c]] = [1, [2]];",
+            "2:9: not enough elements to destructure: want=2, got=1",
+        ),
+        (
+            r#"let {"name": n, "age": a} = {"name": "Monkey"};"#,
+            r#"1:17: missing key "age" in hash"#,
+        ),
+        (
+            r#"let {"name": n} = [1];"#,
+            "1:5: cannot destructure ARRAY as a hash",
+        ),
+        (
+            r#"let [{"a": a}] = [{"a": [1]}]; let [{"a": [b, c]}] = [{"a": [1]}];"#,
+            "1:43: not enough elements to destructure: want=2, got=1",
+        ),
+        (
+            "let {fn() {}: a} = {};",
+            "1:6: unusable as hash key: FUNCTION",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the evaluation of match expressions
+#[test]
+fn test_eval_match() {
+    let tests = vec![
+        (r#"match (0) { 0 => "zero", _ => "other" }"#, "zero"),
+        (r#"match (5) { 0 => "zero", _ => "other" }"#, "other"),
+        (r#"match (-1) { -1 => "minus one", _ => "other" }"#, "minus one"),
+        (r#"match ("b") { "a" => 1, "b" => 2 }"#, "2"),
+        ("match (1 == 1) { false => 0, true => 1 }", "1"),
+        ("match (5) { n => n * 2 }", "10"),
+        (
+            r#"let classify = fn(n) {
+                match (n) {
+                    0 => "zero",
+                    n if n < 0 => "negative",
+                    n if n < 10 => "small",
+                    _ => "large",
+                }
+            };
+            [classify(0), classify(-3), classify(4), classify(40)]"#,
+            "[zero, negative, small, large]",
+        ),
+        (
+            "let sum = fn(xs) { match (xs) { [] => 0, [x, ...rest] => x + sum(rest) } }; sum([1, 2, 3, 4])",
+            "10",
+        ),
+        ("match ([1, 2]) { [a] => a, [a, b] => a + b, _ => 0 }", "3"),
+        ("match ([1, [2, 3]]) { [a, [b, c]] => a + b + c }", "6"),
+        ("match ([1, 2]) { [1, x] => x, _ => 0 }", "2"),
+        ("match ([3, 2]) { [1, x] => x, _ => 0 }", "0"),
+        (
+            r#"match ({"type": "circle", "r": 2}) {
+                {"type": "square", "side": s} => s * s,
+                {"type": "circle", "r": r} => 3 * r * r,
+            }"#,
+            "12",
+        ),
+        (r#"match ({"a": 1}) { {"b": b} => b, {"a": a} => a }"#, "1"),
+        ("match (1) { [a] => a, {1: a} => a, a => a }", "1"),
+        // A failed guard moves on to the next arm.
+        ("match ([1, 2]) { [a, b] if a > b => a, [a, b] => b }", "2"),
+        // Bindings from a failed arm don't leak into the next one.
+        ("let a = 10; match ([1, 2]) { [a, 3] => a, _ => a }", "10"),
+        // Bindings don't leak out of the match.
+        ("let x = 1; match (2) { x => x }; x", "1"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the errors from match expressions
+#[test]
+fn test_eval_match_errors() {
+    let tests = vec![
+        (
+            "match (3) { 1 => 1, 2 => 2 }",
+            "1:1: no match arm matches 3",
+        ),
+        (
+            "let x = [1];\nmatch (x) { [] => 0, [a, b] => 1 }",
+            "2:1: no match arm matches [1]",
+        ),
+        (
+            "match (1) { n if n > 1 => n }",
+            "1:1: no match arm matches 1",
+        ),
+        ("match (1) { n if m => n }", "1:18: identifier not found: m"),
+        (
+            "match ({}) { {fn() {}: a} => a }",
+            "1:15: unusable as hash key: FUNCTION",
+        ),
+        (
+            "let [1, a] = [2, 3];",
+            "1:6: 2 does not match the pattern 1",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+
+    let tests = vec![
+        ("let [_, a] = [2, 3]; a", "3"),
+        ("let [1, a] = [1, 3]; a", "3"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the evaluation of structs and records
+#[test]
+fn test_eval_records() {
+    let tests = vec![
+        (
+            "struct Point { x, y } Point { x: 1, y: 2 }",
+            "Point { x: 1, y: 2 }",
+        ),
+        // Fields are stored in the order they were declared in.
+        (
+            "struct Point { x, y } Point { y: 2, x: 1 }",
+            "Point { x: 1, y: 2 }",
+        ),
+        (
+            "struct Point { x, y } let p = Point { x: 1, y: 2 }; p.x + p.y",
+            "3",
+        ),
+        ("struct Point { x, y } Point { x: 1, y: 2 }.y", "2"),
+        ("struct Point { x, y } Point", "struct Point { x, y }"),
+        ("struct Unit {} Unit {}", "Unit {  }"),
+        (
+            "struct Line { from, to } struct Point { x, y }
+            let line = Line { from: Point { x: 0, y: 0 }, to: Point { x: 3, y: 4 } };
+            line.to.x - line.from.x",
+            "3",
+        ),
+        ("struct P { x } P { x: 1 } == P { x: 1 }", "true"),
+        ("struct P { x } P { x: 1 } == P { x: 2 }", "false"),
+        ("struct P { x } P { x: [1, 2] } != P { x: [1, 2] }", "false"),
+        (
+            "struct P { x } struct Q { x } P { x: 1 } == Q { x: 1 }",
+            "false",
+        ),
+        // Struct declarations are hoisted, like function declarations.
+        ("let p = P { x: 1 }; struct P { x } p.x", "1"),
+        (
+            "struct P { x } let xs = [P { x: 1 }, P { x: 2 }]; xs[1].x",
+            "2",
+        ),
+        (
+            "struct P { x } let f = fn(p) { p.x * 2 }; P { x: 4 } |> f",
+            "8",
+        ),
+        // A hash after a block is a new statement, not a record.
+        (
+            r#"fn config(debug) { if (debug) { puts("debug") } {"level": 1} } config(false)"#,
+            "{level: 1}",
+        ),
+        ("for (x in [1]) { x }\n{\"k\": 1}", "{k: 1}"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the errors from structs and records
+#[test]
+fn test_eval_record_errors() {
+    let tests = vec![
+        (
+            "struct Point { x, y }\nlet p = Point { x: 1, y: 2 };\np.z",
+            "3:3: Point has no field z",
+        ),
+        (
+            "struct Point { x, y } Point { x: 1, z: 2 }",
+            "1:37: Point has no field z",
+        ),
+        (
+            "struct Point { x, y } Point { x: 1 }",
+            "1:23: missing field y in Point literal",
+        ),
+        ("Point { x: 1 }", "1:1: identifier not found: Point"),
+        (
+            "let Point = 1; Point { x: 1 }",
+            "1:16: Point is not a struct, got INTEGER",
+        ),
+        (
+            "struct P { x } P { x: 1 } + P { x: 1 }",
+            "1:27: unknown operator: RECORD + RECORD",
+        ),
+        (
+            "struct P { x } P { x: 1 } == 1",
+            "1:27: type mismatch: RECORD == INTEGER",
+        ),
+        (
+            "struct P { x } P.x",
+            "1:17: cannot access member x of STRUCT",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests throwing and catching errors
+#[test]
+fn test_eval_exceptions() {
+    let tests = vec![
+        ("try { 1 } catch (e) { 2 }", "1"),
+        ("try { 1 / 0 } catch (e) { 2 }", "2"),
+        ("try { 1 / 0 } catch (e) { e.message }", "division by zero"),
+        ("try { 1 / 0 } catch (e) { e.kind }", "arithmetic"),
+        ("try { missing } catch (e) { e.kind }", "name"),
+        ("try { 1 + true } catch (e) { e.kind }", "type"),
+        ("let f = fn(x) { x }; try { f() } catch (e) { e.kind }", "argument"),
+        ("try {\n  1 + true\n} catch (e) { [e.line, e.column] }", "[2, 5]"),
+        ("try { throw \"oops\"; } catch (e) { [e.message, e.kind, e.value] }", "[oops, thrown, oops]"),
+        ("try { throw [1, 2]; } catch (e) { e.value[1] }", "2"),
+        ("try { throw 5; } catch (e) { e.message }", "5"),
+        ("try { throw 5; } catch (e) { e }", "<thrown error at 1:7: 5>"),
+        ("try { 1 / 0 } catch (e) { e }", "<arithmetic error at 1:9: division by zero>"),
+        // Rethrowing an error object keeps the original error.
+        (
+            "try { try { 1 / 0 } catch (e) { throw e; } } catch (e) { [e.kind, e.column] }",
+            "[arithmetic, 15]",
+        ),
+        // Errors unwind through function calls until they are caught.
+        (
+            "let check = fn(x) { if (x < 0) { throw \"negative\" } x }; let safe = fn(x) { try { check(x) } catch (e) { 0 } }; [safe(5), safe(-5)]",
+            "[5, 0]",
+        ),
+        ("let x = try { throw 1; 2 } catch (e) { 3 }; x", "3"),
+        ("try { 1 } finally { 2 }", "1"),
+        ("try { let a = 1; } catch (e) { 2 }", "null"),
+        // `finally` runs on every way out of the `try`.
+        ("try { 1 } catch (e) { 2 } finally { 3 }", "1"),
+        // A `return` or error from the `finally` block takes the place of the result of the `try`.
+        ("let f = fn() { try { return 1; } finally { return 2; } }; f()", "2"),
+        ("let f = fn() { try { return 1; } finally { 3 } }; f()", "1"),
+        ("let f = fn() { try { throw 1; } catch (e) { return 2; } finally { 3 } }; f()", "2"),
+        ("let f = fn() { try { throw 1; } finally { return 2; } }; f()", "2"),
+        ("let f = fn() { try { 1 } catch (e) { 2 } 3 }; f()", "3"),
+        // The caught error is only bound inside of the catch block.
+        ("let e = 1; try { throw 2; } catch (e) { e }; e", "1"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the errors from throw and try expressions. A `finally` block throwing shows that it ran,
+/// however the `try` was left.
+#[test]
+fn test_eval_exception_errors() {
+    let tests = vec![
+        ("try { 1 } finally { throw \"finally\"; }", "1:21: finally"),
+        ("try { 1 / 0 } finally { 2 }", "1:9: division by zero"),
+        (
+            "try { 1 / 0 } catch (e) { missing } finally { 2 }",
+            "1:27: identifier not found: missing",
+        ),
+        (
+            "try { 1 / 0 } finally { throw \"replaced\"; }",
+            "1:25: replaced",
+        ),
+        (
+            "let f = fn() { try { return 1; } finally { throw \"x\"; } }; f()",
+            "1:44: x\n    in f",
+        ),
+        ("throw \"uncaught\";", "1:1: uncaught"),
+        (
+            "try { 1 } catch (e) { 2 } finally { 3 }.x",
+            "1:40: cannot access member x of INTEGER",
+        ),
+        (
+            "try { 1 / 0 } catch (e) { e.nope }",
+            "1:29: error has no field nope",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the evaluation of ranges, for loops and the iterator builtins
+#[test]
+fn test_eval_iteration() {
+    let tests = vec![
+        ("0..3", "0..3"),
+        ("1..1 == 1..1", "true"),
+        ("collect(0..3)", "[0, 1, 2]"),
+        ("collect(3..0)", "[]"),
+        ("collect([1, 2])", "[1, 2]"),
+        ("collect(\"abc\")", "[a, b, c]"),
+        ("collect({\"b\": 2, \"a\": 1})", "[[a, 1], [b, 2]]"),
+        ("for (x in [1, 2]) { x }", "null"),
+        (
+            "let f = fn(xs) { let [total] = [0]; for (x in xs) { total + x } }; f(1..3)",
+            "null",
+        ),
+        // Values are bound with patterns, in their own environment for each value.
+        (
+            "let fs = collect(map(collect(0..3), |i| |x| x + i)); [fs[0](10), fs[2](10)]",
+            "[10, 12]",
+        ),
+        (
+            "let f = fn(xs) { for ([k, v] in xs) { if (v > 1) { return k; } } }; f({\"a\": 1, \"b\": 2})",
+            "b",
+        ),
+        ("let f = fn() { for (x in 0..10) { return x; } }; f()", "0"),
+        // Iterators are shared, so iterating over one again carries on where it was left.
+        ("let it = iter(0..3); next(it); collect(it)", "[1, 2]"),
+        (
+            "let it = iter([1, 2]); [next(it), next(it), next(it)]",
+            "[{done: false, value: 1}, {done: false, value: 2}, {done: true, value: null}]",
+        ),
+        ("let it = iter(0..2); it == it", "true"),
+        ("iter(0..2) == iter(0..2)", "false"),
+        ("iter([1])", "<iterator>"),
+        ("next", "<builtin next>"),
+        ("let next = 1; next", "1"),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the evaluation of generators
+#[test]
+fn test_eval_generators() {
+    let tests = vec![
+        ("fn g() { yield 1; yield 2; } collect(g())", "[1, 2]"),
+        ("fn g() { yield 1; } g()", "<generator g>"),
+        // The body isn't evaluated until the first value is asked for.
+        ("fn g() { throw \"never\"; yield 1; } g(); 1", "1"),
+        (
+            "fn evens(n) { for (i in 0..n) { if (i / 2 * 2 == i) { yield i; } } } collect(evens(7))",
+            "[0, 2, 4, 6]",
+        ),
+        (
+            "fn naturals() { fn count(n) { n } for (i in 0..1000000000) { yield count(i); } } let it = naturals(); [next(it)[\"value\"], next(it)[\"value\"]]",
+            "[0, 1]",
+        ),
+        (
+            "fn g(x) { if (x) { yield 1; } else if (!x) { yield 2; } yield 3; } [collect(g(true)), collect(g(false))]",
+            "[[1, 3], [2, 3]]",
+        ),
+        (
+            "fn pairs(xs) { for ([i, x] in xs) { for (y in 0..i) { yield [x, y]; } } } collect(pairs([[1, \"a\"], [2, \"b\"]]))",
+            "[[a, 0], [b, 0], [b, 1]]",
+        ),
+        // A `return` stops the generator, and it stays done. The first `next` that finds it done has the
+        // value that it returned.
+        (
+            "fn g() { yield 1; return 5; yield 2; } let it = g(); [next(it), next(it), next(it)]",
+            "[{done: false, value: 1}, {done: true, value: 5}, {done: true, value: null}]",
+        ),
+        (
+            "fn g() { yield 1; } let it = g(); [next(it), next(it)]",
+            "[{done: false, value: 1}, {done: true, value: null}]",
+        ),
+        (
+            "fn g() { for (x in 0..3) { if (x == 1) { return x * 10; } yield x; } } let it = g(); [next(it), next(it)]",
+            "[{done: false, value: 0}, {done: true, value: 10}]",
+        ),
+        // Generators can pause in the blocks of a `try`, and errors are caught, and `finally` blocks
+        // run, like anywhere else.
+        (
+            "fn g() { try { yield 1; yield 2; } finally { yield 3; } yield 4; } collect(g())",
+            "[1, 2, 3, 4]",
+        ),
+        (
+            "fn g() { try { yield 1; throw \"oops\"; yield 2; } catch (e) { yield e.message; } finally { yield 3; } } collect(g())",
+            "[1, oops, 3]",
+        ),
+        (
+            "fn g(xs) { for (x in xs) { try { yield 10 / x; } catch (e) { yield 0; } } } collect(g([1, 0, 2]))",
+            "[10, 0, 5]",
+        ),
+        (
+            "fn g() { try { try { yield 1; throw 2; } finally { yield 3; } } catch (e) { yield e.message; } } collect(g())",
+            "[1, 3, 2]",
+        ),
+        (
+            "fn g() { try { yield 1; return 2; } finally { yield 3; } yield 4; } let it = g(); [next(it)[\"value\"], next(it)[\"value\"], next(it)]",
+            "[1, 3, {done: true, value: 2}]",
+        ),
+        (
+            "fn g() { try { return 1; } finally { return 2; } yield 0; } next(g())",
+            "{done: true, value: 2}",
+        ),
+        (
+            "fn g() { try { throw 1; } finally { return 2; } yield 0; } next(g())",
+            "{done: true, value: 2}",
+        ),
+        // Generators can pause in the arms of a `match`, which keep their bindings.
+        (
+            "fn g(xs) { for (x in xs) { match (x) { [a, b] => for (y in [a, b]) { yield y; }, n if n > 1 => if (true) { yield n; }, _ => 0 } } } collect(g([[1, 2], 3, 1]))",
+            "[1, 2, 3]",
+        ),
+        (
+            "fn g(x) { match (x) { 1 => try { yield \"one\"; } finally {}, n => match (n) { 2 => if (true) { yield \"two\"; }, _ => 0 } } yield x; } [collect(g(1)), collect(g(2)), collect(g(3))]",
+            "[[one, 1], [two, 2], [3]]",
+        ),
+        (
+            "fn g() { for (x in 0..10) { if (x > 1) { return 0; } yield x; } } collect(g())",
+            "[0, 1]",
+        ),
+        // Generators keep their state between values.
+        (
+            "fn g(n) { let a = n; yield a; let b = a * 2; yield b; yield a + b; } collect(g(3))",
+            "[3, 6, 9]",
+        ),
+        // Generators can be iterated over by for loops, and nested.
+        (
+            "fn g() { yield 1; yield 2; } fn h() { for (x in g()) { yield x * 10; } } collect(h())",
+            "[10, 20]",
+        ),
+        (
+            "let g = fn(xs) { for (x in xs) { yield x; } }; let it = g(\"hi\"); for (c in it) { c }; next(it)",
+            "{done: true, value: null}",
+        ),
+        ("let g = |x| if (x) { yield x; }; collect(g(4))", "[4]"),
+        (
+            "fn g() { let f = fn() { yield 1; }; yield f; } next(next(g())[\"value\"]())[\"value\"]",
+            "1",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the errors from iteration and generators
+#[test]
+fn test_eval_iteration_errors() {
+    let tests = vec![
+        ("for (x in 5) { x }", "1:11: cannot iterate over INTEGER"),
+        ("0..true", "1:2: type mismatch: INTEGER .. BOOLEAN"),
+        ("\"a\"..\"b\"", "1:4: unknown operator: STRING .. STRING"),
+        (
+            "next([1])",
+            "1:5: argument to next must be ITERATOR, got ARRAY\n    in next",
+        ),
+        (
+            "collect()",
+            "1:8: wrong number of arguments: want=1, got=0\n    in collect",
+        ),
+        (
+            "for ([a, b] in [[1, 2], [3]]) { a }",
+            "1:6: not enough elements to destructure: want=2, got=1",
+        ),
+        (
+            "fn g() { yield 1; 1 / 0; } collect(g())",
+            "1:21: division by zero\n    in g\n    in collect",
+        ),
+        (
+            "fn g() { try { yield 1; 1 / 0; } finally { yield 2; } } collect(g())",
+            "1:27: division by zero\n    in g\n    in collect",
+        ),
+        (
+            "fn g() { match (1) { 2 => if (true) { yield 1; } } } collect(g())",
+            "1:10: no match arm matches 1\n    in g\n    in collect",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+
+    // A generator can't be resumed from inside of itself.
+    let error = run_error("let r = fn() { yield next(gen); }; let gen = r(); next(gen)");
+
+    assert_eq!(error.message, "generator r is already running");
+
+    // An empty loop still uses up fuel, so it can't run forever.
+    let mut evaluator = Evaluator::new();
+    evaluator.set_budget(Budget::unlimited().with_fuel(1_000));
+
+    let error = evaluator
+        .run(parse("for (x in 0..1000000000) {}"))
+        .unwrap_err();
+
+    assert_eq!(error.kind, ErrorKind::BudgetExceeded);
+}
+
+/// Tests the evaluation of pipes and short lambdas
+#[test]
+fn test_eval_pipes_and_lambdas() {
+    let tests = vec![
+        ("let double = |x| x * 2; 5 |> double", 10),
+        ("let add = fn(a, b) { a + b }; 1 |> add(2)", 3),
+        ("let sub = fn(a, b) { a - b }; 10 |> sub(3)", 7),
+        ("let sub = fn(a, b) { a - b }; 10 |> sub(3) |> sub(2)", 5),
+        ("3 |> (|x| x * x)", 9),
+        ("let inc = x => x + 1; 1 + 1 |> inc", 3),
+        ("let apply = fn(f, x) { f(x) }; apply(x => x * 10, 4)", 40),
+        ("let adder = |x| |y| x + y; adder(2)(3)", 5),
+        ("let f = |x, y = 10| x + y; f(1)", 11),
+        ("let f = || 42; f()", 42),
+    ];
+
+    for (input, expected) in tests {
+        test_integer_object(run(input), expected);
+    }
+
+    let error = run_error("let double = |x| x * 2; double(1, 2)");
+
+    assert_eq!(error.message, "wrong number of arguments: want=1, got=2");
+    assert_eq!(error.trace, vec!["double".to_string()]);
+}
+
+/// Tests quoting expressions
+#[test]
+fn test_eval_quote() {
+    let tests = vec![
+        ("quote(5)", "5"),
+        ("quote(5 + 8)", "(5 + 8)"),
+        ("quote(foobar)", "foobar"),
+        ("quote(foobar + barfoo)", "(foobar + barfoo)"),
+    ];
+
+    for (input, expected) in tests {
+        match run(input) {
+            Some(Object::Quote(quote)) => assert_eq!(quote.node.to_string(), expected),
+            obj => panic!("Object is not a Quote, found {:?}", obj),
+        }
+    }
+}
+
+/// Tests unquoting expressions inside of quotes
+#[test]
+fn test_eval_quote_unquote() {
+    let tests = vec![
+        ("quote(unquote(4))", "4"),
+        ("quote(unquote(4 + 4))", "8"),
+        ("quote(8 + unquote(4 + 4))", "(8 + 8)"),
+        ("quote(unquote(4 + 4) + 8)", "(8 + 8)"),
+        ("let foobar = 8; quote(foobar)", "foobar"),
+        ("let foobar = 8; quote(unquote(foobar))", "8"),
+        ("quote(unquote(true))", "true"),
+        ("quote(unquote(true == false))", "false"),
+        ("quote(unquote(\"a\" + \"b\"))", "\"ab\""),
+        ("quote(unquote(0 - 5))", "-5"),
+        ("quote(unquote(quote(4 + 4)))", "(4 + 4)"),
+        (
+            "let quotedInfixExpression = quote(4 + 4); quote(unquote(4 + 4) + unquote(quotedInfixExpression))",
+            "(8 + (4 + 4))",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        match run(input) {
+            Some(Object::Quote(quote)) => assert_eq!(quote.node.to_string(), expected),
+            obj => panic!("Object is not a Quote, found {:?}", obj),
+        }
+    }
+
+    let tests = vec![
+        ("quote(1, 2)", "wrong number of arguments: want=1, got=2"),
+        (
+            "quote(unquote())",
+            "wrong number of arguments: want=1, got=0",
+        ),
+        (
+            "quote(unquote([1]))",
+            "cannot unquote ARRAY, only integers, booleans, strings and quotes can be unquoted",
+        ),
+        ("quote(unquote(x))", "identifier not found: x"),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.message, expected, "input: {}", input);
+    }
+}
+
+/// Tests pulling macro definitions out of a program
+#[test]
+fn test_define_macros() {
+    let mut program = parse(
+        "let number = 1;
+        let function = fn(x, y) { x + y };
+        let mymacro = macro(x, y) { x + y; };",
+    );
+
+    let mut evaluator = Evaluator::new();
+    evaluator.define_macros(&mut program);
+
+    assert_eq!(program.statements.len(), 2);
+
+    assert!(evaluator.env.borrow().get(&"number".into()).is_none());
+    assert!(evaluator.env.borrow().get(&"function".into()).is_none());
+    assert!(evaluator.env.borrow().get(&"mymacro".into()).is_none());
+    assert!(evaluator.macro_env.borrow().get(&"number".into()).is_none());
+    assert!(evaluator
+        .macro_env
+        .borrow()
+        .get(&"function".into())
+        .is_none());
+
+    let mymacro = evaluator.macro_env.borrow().get(&"mymacro".into());
+
+    match mymacro {
+        Some(Object::Macro(macro_object)) => {
+            assert_eq!(macro_object.literal.parameters.len(), 2);
+            assert_eq!(macro_object.literal.body.to_string(), "(x + y)");
+        }
+        obj => panic!("Object is not a Macro, found {:?}", obj),
+    }
+}
+
+/// Tests expanding calls to macros
+#[test]
+fn test_expand_macros() {
+    let tests = vec![
+        (
+            "let infixExpression = macro() { quote(1 + 2); }; infixExpression();",
+            "(1 + 2)",
+        ),
+        (
+            "let reverse = macro(a, b) { quote(unquote(b) - unquote(a)); }; reverse(2 + 2, 10 - 5);",
+            "(10 - 5) - (2 + 2)",
+        ),
+        (
+            r#"let unless = macro(condition, consequence, alternative) {
+                quote(if (!(unquote(condition))) {
+                    unquote(consequence);
+                } else {
+                    unquote(alternative);
+                });
+            };
+
+            unless(10 > 5, puts("not greater"), puts("greater"));"#,
+            r#"if (!(10 > 5)) { puts("not greater") } else { puts("greater") }"#,
+        ),
+        (
+            "let twice = macro(x) { return quote(unquote(x) + unquote(x)); }; fn f() { twice(twice(1)) }",
+            "fn f() { (1 + 1) + (1 + 1) }",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut evaluator = Evaluator::new();
+        let mut program = parse(input);
+
+        evaluator.define_macros(&mut program);
+        let expanded = evaluator.expand_macros(program).unwrap();
+
+        assert_eq!(
+            expanded.to_string(),
+            parse(expected).to_string(),
+            "input: {}",
+            input
+        );
+    }
+}
+
+/// Tests expanding macros in a tree that is deeper than the stack could walk without growing it
+#[test]
+fn test_expand_macros_deep() {
+    let terms = 20_000;
+
+    // The tree is dropped on the thread's own stack, which has room for that but not for the walk.
+    let count = thread::Builder::new()
+        .stack_size(16 * 1024 * 1024)
+        .spawn(move || {
+            let source = format!(
+                "let one = macro() {{ quote(1) }}; one(){}",
+                " + one()".repeat(terms - 1)
+            );
+            let mut parser = Parser::new(Lexer::new(&source));
+            parser.set_max_depth(usize::MAX);
+            parser.set_max_chain(usize::MAX);
+
+            let mut evaluator = Evaluator::new();
+            let mut program = parser.parse_program().unwrap();
+            evaluator.define_macros(&mut program);
+            let program = evaluator.expand_macros(program).unwrap();
+
+            let Statement::Expression(statement) = &program.statements[0] else {
+                panic!("expected an expression statement, got {}", program);
+            };
+
+            // Walk down the left side of the chain, counting the expanded terms.
+            let mut count = 1;
+            let mut expression = &statement.expression;
+
+            while let Expression::Infix(infix) = expression {
+                assert!(matches!(*infix.right, Expression::Integer(_)));
+                expression = &infix.left;
+                count += 1;
+            }
+
+            assert!(matches!(expression, Expression::Integer(_)));
+            count
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(count, terms);
+}
+
+/// Tests running programs that define and use macros
+#[test]
+fn test_run_macros() {
+    let tests = vec![
+        (
+            r#"let unless = macro(condition, consequence, alternative) {
+                quote(if (!(unquote(condition))) {
+                    unquote(consequence);
+                } else {
+                    unquote(alternative);
+                });
+            };
+
+            unless(10 > 5, "not greater", "greater");"#,
+            "greater",
+        ),
+        (
+            "let twice = macro(x) { quote(unquote(x) + unquote(x)) }; twice(twice(1))",
+            "4",
+        ),
+        // Macro arguments aren't evaluated, so an undefined identifier is fine if it isn't used.
+        ("let first = macro(a, b) { a }; first(1, undefined)", "1"),
+        (
+            "let one = macro() { quote(1) }; let f = fn() { one() }; f()",
+            "1",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = Evaluator::new().run(parse(input)).unwrap();
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+
+    let tests = vec![
+        (
+            "let bad = macro() { 1 }; bad()",
+            "1:29: macro bad must return a quote, got INTEGER",
+        ),
+        (
+            "let bad = macro() { let x = 1; }; bad()",
+            "1:38: macro bad must return a quote, got nothing",
+        ),
+        (
+            "let m = macro(x) { x }; m(1, 2)",
+            "1:26: wrong number of arguments: want=1, got=2\n    in m",
+        ),
+        (
+            "let f = fn() { macro() { 1 } }; f()",
+            "1:16: unexpected macro literal: macro() 1, macros can only be defined by top-level let statements\n    in f",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = Evaluator::new().run(parse(input)).unwrap_err();
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Creates an evaluator that loads modules from memory, with the given modules, that is evaluating
+/// the file `main.monkey`
+fn module_evaluator(modules: &[(&str, &str)]) -> Evaluator {
+    let mut loader = MemoryLoader::new();
+
+    for (path, source) in modules {
+        loader.insert(path, source);
+    }
+
+    let mut evaluator = Evaluator::new();
+    evaluator.set_loader(loader);
+    evaluator.set_file("main.monkey");
+    evaluator
+}
+
+/// Tests importing modules and accessing their exports
+#[test]
+fn test_eval_modules() {
+    let modules = [
+        (
+            "lib/math.monkey",
+            r#"
+            import "./util.monkey" as util;
+            export let pi = 3;
+            export fn square(x) { util.times(x, x) }
+            export let [one, two] = [1, 2];
+            let secret = 42;
+            "#,
+        ),
+        ("lib/util.monkey", "export let times = fn(a, b) { a * b };"),
+        (
+            "other/uses_lib.monkey",
+            r#"import "../lib/math.monkey" as m; export let tau = m.pi * 2;"#,
+        ),
+    ];
+
+    let tests = vec![
+        (r#"import "lib/math.monkey" as math; math.pi"#, "3"),
+        (r#"import "lib/math.monkey" as math; math.square(4)"#, "16"),
+        (
+            r#"import "lib/math.monkey" as math; math.one + math.two"#,
+            "3",
+        ),
+        (r#"import "./lib/math.monkey" as math; math.pi"#, "3"),
+        (
+            r#"import "lib/math.monkey" as math; math"#,
+            "<module lib/math.monkey>",
+        ),
+        (r#"import "other/uses_lib.monkey" as o; o.tau"#, "6"),
+        (
+            r#"import "lib/../lib/util.monkey" as u; u.times(2, 3)"#,
+            "6",
+        ),
+        (
+            r#"import "lib/math.monkey" as a; import "lib/math.monkey" as b; a == b"#,
+            "true",
+        ),
+        (
+            r#"fn f() { import "lib/util.monkey" as u; u.times(3, 3) } f()"#,
+            "9",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = module_evaluator(&modules).run(parse(input)).unwrap();
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+
+    let tests = vec![
+        (
+            r#"import "lib/math.monkey" as math; math.secret"#,
+            "1:39: module lib/math.monkey has no export named secret",
+        ),
+        (
+            r#"import "lib/math.monkey" as math; util"#,
+            "1:35: identifier not found: util",
+        ),
+        (
+            r#"import "missing.monkey" as m;"#,
+            "1:1: cannot load module missing.monkey: no such module",
+        ),
+        ("let x = 1; x.y", "1:13: cannot access member y of INTEGER"),
+    ];
+
+    for (input, expected) in tests {
+        let error = module_evaluator(&modules).run(parse(input)).unwrap_err();
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the errors that can happen while loading modules
+#[test]
+fn test_eval_module_errors() {
+    let modules = [
+        ("a.monkey", r#"import "b.monkey" as b;"#),
+        ("b.monkey", r#"import "dir/c.monkey" as c;"#),
+        ("dir/c.monkey", r#"import "../a.monkey" as a;"#),
+        ("self.monkey", r#"import "self.monkey" as me;"#),
+        (
+            "back.monkey",
+            r#"puts("back"); import "./main.monkey" as main;"#,
+        ),
+        ("broken.monkey", "let = 1;"),
+        ("fails.monkey", "let x = 1;\nlet y = x + true;"),
+    ];
+
+    let tests = vec![
+        (
+            r#"import "a.monkey" as a;"#,
+            "1:1: import cycle: a.monkey -> b.monkey -> dir/c.monkey -> a.monkey\n    in module dir/c.monkey\n    in module b.monkey\n    in module a.monkey",
+        ),
+        (
+            r#"import "self.monkey" as me;"#,
+            "1:1: import cycle: self.monkey -> self.monkey\n    in module self.monkey",
+        ),
+        // The file being evaluated is part of the cycle when a module imports it back.
+        (
+            r#"import "back.monkey" as b;"#,
+            "1:15: import cycle: main.monkey -> back.monkey -> main.monkey\n    in module back.monkey",
+        ),
+        (
+            r#"import "broken.monkey" as b;"#,
+            "1:1: cannot parse module broken.monkey: 1:5: expected an identifier, literal, array pattern or hash pattern, got = instead",
+        ),
+        (
+            r#"import "fails.monkey" as f;"#,
+            "2:11: type mismatch: INTEGER + BOOLEAN\n    in module fails.monkey",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = module_evaluator(&modules).run(parse(input)).unwrap_err();
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests that errors know which file they happened in
+#[test]
+fn test_eval_error_files() {
+    let modules = [
+        ("fails.monkey", "let x = 1;\nlet y = x + true;"),
+        (
+            "lib.monkey",
+            "export fn apply(f) {\n  f() + 1\n}\nexport fn fail() { [].x }",
+        ),
+        ("broken.monkey", "let = 1;"),
+    ];
+
+    let tests = vec![
+        (r#"import "fails.monkey" as f;"#, "fails.monkey"),
+        // Functions are in the file that they were defined in, not the one that they were called from.
+        (r#"import "lib.monkey" as lib; lib.fail()"#, "lib.monkey"),
+        (
+            r#"import "lib.monkey" as lib; lib.apply(fn() { true })"#,
+            "lib.monkey",
+        ),
+        (
+            r#"import "lib.monkey" as lib; lib.apply(fn() { [].x })"#,
+            "main.monkey",
+        ),
+        // A module that can't be parsed fails at the import.
+        (r#"import "broken.monkey" as b;"#, "main.monkey"),
+    ];
+
+    for (input, expected) in tests {
+        let error = module_evaluator(&modules).run(parse(input)).unwrap_err();
+
+        assert_eq!(
+            error.file.as_deref(),
+            Some(Path::new(expected)),
+            "input: {}",
+            input
+        );
+    }
+}
+
+/// A loader that counts how many times each module is loaded
+struct CountingLoader {
+    loader: MemoryLoader,
+    loads: Rc<RefCell<Vec<PathBuf>>>,
+}
+
+impl Loader for CountingLoader {
+    fn load(&self, path: &Path) -> io::Result<String> {
+        self.loads.borrow_mut().push(path.to_path_buf());
+        self.loader.load(path)
+    }
+}
+
+/// Tests that each module is only loaded and evaluated once, no matter how often it is imported
+#[test]
+fn test_eval_module_cache() {
+    let mut loader = MemoryLoader::new();
+    loader.insert("lib/shared.monkey", "export let value = [1, 2, 3];");
+    loader.insert(
+        "lib/a.monkey",
+        r#"import "shared.monkey" as s; export let value = s.value;"#,
+    );
+    loader.insert(
+        "b.monkey",
+        r#"import "lib/shared.monkey" as s; export let value = s.value;"#,
+    );
+
+    let loads = Rc::new(RefCell::new(Vec::new()));
+
+    let mut evaluator = Evaluator::new();
+    evaluator.set_loader(CountingLoader {
+        loader,
+        loads: Rc::clone(&loads),
+    });
+
+    let obj = evaluator
+        .run(parse(
+            r#"
+            import "lib/a.monkey" as a;
+            import "b.monkey" as b;
+            import "./lib/shared.monkey" as s;
+            [a.value == b.value, b.value == s.value]
+            "#,
+        ))
+        .unwrap();
+
+    assert_eq!(obj.unwrap().to_string(), "[true, true]");
+
+    // Imports in later programs still use the cache.
+    evaluator
+        .run(parse(r#"import "lib/shared.monkey" as again;"#))
+        .unwrap();
+
+    assert_eq!(
+        *loads.borrow(),
+        vec![
+            PathBuf::from("lib/a.monkey"),
+            PathBuf::from("lib/shared.monkey"),
+            PathBuf::from("b.monkey"),
+        ]
+    );
+}
+
+/// Tests the functions in the prelude
+#[test]
+fn test_eval_prelude() {
+    let tests = vec![
+        ("map([1, 2, 3], |x| x * 2)", "[2, 4, 6]"),
+        ("map([], |x| x * 2)", "[]"),
+        ("filter([1, 2, 3, 4], |x| x > 2)", "[3, 4]"),
+        ("filter([1, 2, 3], |x| false)", "[]"),
+        ("reduce([1, 2, 3, 4], 0, |sum, x| sum + x)", "10"),
+        ("reduce([], 5, |sum, x| sum + x)", "5"),
+        ("reduce([1, 2, 3], [], |xs, x| [x, ...xs])", "[3, 2, 1]"),
+        ("each([1, 2], |x| x)", "[1, 2]"),
+        ("range(4)", "[0, 1, 2, 3]"),
+        ("range(2, 5)", "[2, 3, 4]"),
+        ("range(5, 2)", "[]"),
+        ("zip([1, 2, 3], [4, 5])", "[[1, 4], [2, 5]]"),
+        ("zip([], [1])", "[]"),
+        ("contains([1, 2, 3], 2)", "true"),
+        ("contains([1, 2, 3], 4)", "false"),
+        (r#"contains(["a", "b"], "b")"#, "true"),
+        (r#"join(["a", "b", "c"], ", ")"#, "a, b, c"),
+        (r#"join([], ", ")"#, ""),
+        (
+            "range(10) |> filter(|x| x > 5) |> map(|x| x * x) |> reduce(0, |a, b| a + b)",
+            "230",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+
+    let mut parser = Parser::new(Lexer::new(super::prelude::PRELUDE));
+    parser.parse_program().unwrap();
+    assert!(parser.warnings().is_empty());
+}
+
+/// Tests the `sort_by` builtin, and the errors that it raises
+#[test]
+fn test_eval_sort_by() {
+    let tests = vec![
+        ("sort_by([3, 1, 2], |x| x)", "[1, 2, 3]"),
+        ("sort_by([3, 1, 2], |x| -x)", "[3, 2, 1]"),
+        ("sort_by(0..3, |x| -x)", "[2, 1, 0]"),
+        ("sort_by([], |x| x)", "[]"),
+        (
+            r#"sort_by(["pear", "fig", "apple"], |s| s)"#,
+            "[apple, fig, pear]",
+        ),
+        // Values with the same key keep their order.
+        (
+            "sort_by([[2, 1], [1, 2], [2, 3], [1, 4]], |pair| pair[0])",
+            "[[1, 2], [1, 4], [2, 1], [2, 3]]",
+        ),
+        (
+            r#"sort_by([["b", 1], ["a", 2], ["b", 3], ["a", 4]], |pair| pair[0])"#,
+            "[[a, 2], [a, 4], [b, 1], [b, 3]]",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+
+    let tests = vec![
+        // Errors from the key function are raised where they happened, with the caller's frames.
+        (
+            "fn key(x) { x / 0 } fn sorted(xs) { sort_by(xs, key) } sorted([1])",
+            "1:15: division by zero\n    in key\n    in sort_by\n    in sorted",
+        ),
+        (
+            r#"sort_by([1, "a"], |x| x)"#,
+            "1:8: keys of sort_by must all have the same type, got INTEGER and STRING\n    in sort_by",
+        ),
+        (
+            "sort_by([true], |x| x)",
+            "1:8: keys of sort_by must be INTEGER or STRING, got BOOLEAN\n    in sort_by",
+        ),
+        (
+            "sort_by([1], 5)",
+            "1:8: not a function: INTEGER\n    in sort_by",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let error = run_error(input);
+
+        assert_eq!(error.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the functions in the prelude on arrays with more elements than the default call depth, which
+/// they must not recurse once per element on
+#[test]
+fn test_eval_prelude_large_arrays() {
+    let tests = vec![
+        ("map(range(3000), |x| x * 2)[2999]", "5998"),
+        ("filter(range(3000), |x| x > 2997)", "[2998, 2999]"),
+        ("reduce(range(3000), 0, |sum, x| sum + x)", "4498500"),
+        ("each(range(3000), |x| x)[2999]", "2999"),
+        ("range(1000, 4000)[2999]", "3999"),
+        ("zip(range(3000), range(2000, 5000))[2999]", "[2999, 4999]"),
+        ("sort_by(range(3000), |x| -x)[0]", "2999"),
+        ("sort_by(range(3000), |x| x)[2999]", "2999"),
+        // Whichever part of this array is left to sort, its middle element is the largest, which
+        // is the worst case for a quicksort that picks the middle element as its pivot.
+        (
+            "let s = sort_by([...map(range(600), |x| x * 2), ...map(range(600), |x| 1199 - x * 2)], |x| x); [s[0], s[599], s[1199]]",
+            "[0, 599, 1199]",
+        ),
+        (
+            "sort_by(map(range(3000), |x| [x / 1000, x]), |pair| -pair[0])[2999]",
+            "[0, 999]",
+        ),
+        ("contains(range(3000), 2999)", "true"),
+        (
+            r#"join(map(range(3000), |x| "a"), "") == join(map(range(1500), |x| "aa"), "")"#,
+            "true",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests that user-defined names shadow the prelude's names, without breaking the prelude
+#[test]
+fn test_eval_prelude_shadowing() {
+    let tests = vec![
+        ("let map = 5; map", "5"),
+        ("fn map(xs) { 0 } map([1])", "0"),
+        // `each` uses the prelude's `map`, even if the user's code has its own.
+        ("let map = fn(xs, f) { 0 }; each([1, 2], |x| x)", "[1, 2]"),
+        (
+            "fn range(n) { n } [range(3), zip([1], [2])]",
+            "[3, [[1, 2]]]",
+        ),
+        (
+            "let f = fn() { let filter = 1; filter }; [f(), filter([1], |x| true)]",
+            "[1, [1]]",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let obj = run(input);
+
+        assert_eq!(obj.unwrap().to_string(), expected, "input: {}", input);
+    }
+
+    // A second program in the same evaluator still sees the user's definition.
+    let mut evaluator = Evaluator::new();
+    evaluator.run(parse("let map = 5;")).unwrap();
+
+    let obj = evaluator
+        .run(parse("[map, filter([1, 2], |x| x > 1)]"))
+        .unwrap();
+
+    assert_eq!(obj.unwrap().to_string(), "[5, [2]]");
+}
+
+/// Tests that the prelude can be left out
+#[test]
+fn test_eval_without_prelude() {
+    let error = Evaluator::without_prelude()
+        .run(parse("map([1], |x| x)"))
+        .unwrap_err();
+
+    assert_eq!(error.to_string(), "1:1: identifier not found: map");
+
+    // Modules see the prelude only if the importer does.
+    let mut loader = MemoryLoader::new();
+    loader.insert("m.monkey", "export let xs = range(3);");
+
+    let mut evaluator = Evaluator::new();
+    evaluator.set_loader(loader.clone());
+
+    let obj = evaluator
+        .run(parse(r#"import "m.monkey" as m; m.xs"#))
+        .unwrap();
+
+    assert_eq!(obj.unwrap().to_string(), "[0, 1, 2]");
+
+    let mut evaluator = Evaluator::without_prelude();
+    evaluator.set_loader(loader);
+
+    let error = evaluator
+        .run(parse(r#"import "m.monkey" as m;"#))
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "1:17: identifier not found: range\n    in module m.monkey"
+    );
+}
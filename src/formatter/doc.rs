@@ -0,0 +1,176 @@
+/// A document that describes how code can be laid out, which is rendered to fit into a given width. The
+/// formatter builds a document for a program, and `render` picks where its lines are broken. This is
+/// the algorithm from Philip Wadler's "A prettier printer".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Doc {
+    /// Text, which only has a newline in it if it is a string literal that does.
+    Text(String),
+
+    /// A space, or a newline if the group that it is in is broken.
+    Line,
+
+    /// Nothing, or a newline if the group that it is in is broken.
+    SoftLine,
+
+    /// A newline, which breaks every group that it is in.
+    HardLine,
+
+    /// Nothing, but breaks every group that it is in. This is used after a comment, which runs to the
+    /// end of its line, so nothing else can be put on the line after it.
+    BreakParent,
+
+    /// Documents, one after the other.
+    Concat(Vec<Doc>),
+
+    /// A document whose lines are indented one level deeper.
+    Nest(Box<Doc>),
+
+    /// A document that is laid out on one line if it fits, and otherwise has all of its lines broken.
+    Group(Box<Doc>),
+}
+
+/// Returns a text document.
+pub fn text<S: Into<String>>(text: S) -> Doc {
+    Doc::Text(text.into())
+}
+
+/// Returns the documents, one after the other.
+pub fn concat<I: IntoIterator<Item = Doc>>(docs: I) -> Doc {
+    Doc::Concat(docs.into_iter().collect())
+}
+
+/// Returns `doc`, indented one level deeper.
+pub fn nest(doc: Doc) -> Doc {
+    Doc::Nest(Box::new(doc))
+}
+
+/// Returns `doc` as a group.
+pub fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+/// Returns the documents with `separator` between each of them.
+pub fn join<I: IntoIterator<Item = Doc>>(docs: I, separator: Doc) -> Doc {
+    let mut joined = Vec::new();
+
+    for (i, doc) in docs.into_iter().enumerate() {
+        if i > 0 {
+            joined.push(separator.clone());
+        }
+
+        joined.push(doc);
+    }
+
+    Doc::Concat(joined)
+}
+
+/// Whether the lines of a group are broken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Renders `doc` into lines of at most `width` characters where it can, indenting each level by
+/// `indent` spaces. Lines never end with whitespace.
+pub fn render(doc: &Doc, width: usize, indent: usize) -> String {
+    let mut renderer = Renderer {
+        out: String::new(),
+        column: 0,
+        pending_indent: None,
+    };
+
+    let mut stack = vec![(0, Mode::Break, doc)];
+
+    while let Some((level, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(text) => renderer.write(text),
+
+            Doc::Line if mode == Mode::Flat => renderer.write(" "),
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine | Doc::HardLine => renderer.newline(level),
+            Doc::BreakParent => {}
+
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (level, mode, doc))),
+            Doc::Nest(doc) => stack.push((level + indent, mode, doc)),
+
+            Doc::Group(doc) if mode == Mode::Flat => stack.push((level, mode, doc)),
+            Doc::Group(doc) => {
+                let remaining = width as isize - renderer.column as isize;
+
+                let mode = if fits(remaining, (level, Mode::Flat, doc), &stack) {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+
+                stack.push((level, mode, doc));
+            }
+        }
+    }
+
+    renderer.out
+}
+
+/// The state of rendering a document.
+struct Renderer {
+    /// What has been rendered so far.
+    out: String,
+
+    /// The column that the next character is written at, counting from 0.
+    column: usize,
+
+    /// The indentation of the current line, which is only written once something is written on the
+    /// line, so that blank lines stay empty.
+    pending_indent: Option<usize>,
+}
+
+impl Renderer {
+    /// Writes `text` on the current line.
+    fn write(&mut self, text: &str) {
+        if let Some(indent) = self.pending_indent.take() {
+            self.out.extend(std::iter::repeat_n(' ', indent));
+        }
+
+        self.out.push_str(text);
+
+        // Only string literals can have newlines in them, which are written as they are.
+        self.column = match text.rfind('\n') {
+            Some(newline) => text[newline + 1..].chars().count(),
+            None => self.column + text.chars().count(),
+        };
+    }
+
+    /// Starts a new line, indented by `indent` spaces.
+    fn newline(&mut self, indent: usize) {
+        self.out.truncate(self.out.trim_end_matches(' ').len());
+        self.out.push('\n');
+        self.pending_indent = Some(indent);
+        self.column = indent;
+    }
+}
+
+/// Returns whether `next` fits into the `remaining` width, along with whatever comes after it on the
+/// same line (from the `rest` of the stack).
+fn fits(mut remaining: isize, next: (usize, Mode, &Doc), rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut stack = vec![next];
+    let mut rest = rest.iter().rev();
+
+    while remaining >= 0 {
+        let Some((level, mode, doc)) = stack.pop().or_else(|| rest.next().copied()) else {
+            return true;
+        };
+
+        match doc {
+            Doc::Text(text) => remaining -= text.chars().count() as isize,
+            Doc::Line if mode == Mode::Flat => remaining -= 1,
+            Doc::SoftLine if mode == Mode::Flat => {}
+            Doc::Line | Doc::SoftLine => return true,
+            Doc::HardLine | Doc::BreakParent => return mode == Mode::Break,
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (level, mode, doc))),
+            Doc::Nest(doc) | Doc::Group(doc) => stack.push((level, mode, doc)),
+        }
+    }
+
+    false
+}
@@ -0,0 +1,660 @@
+use super::{
+    doc::{concat, group, join, nest, text, Doc},
+    Formatter,
+};
+use crate::{
+    ast::{
+        expressions::{
+            CallExpression, Expression, FunctionForm, FunctionLiteral, MatchExpression, Parameter,
+        },
+        operators::{InfixOperator, InfixOperatorType, PrefixOperatorType},
+        patterns::Pattern,
+        statements::Statement,
+    },
+    parser::operators::Precedence,
+    token::{Position, TokenType},
+};
+
+/// Returns the body of `function` if it is a short lambda, e.g. `|x| x + 1` or `x => x + 1`. The parser
+/// makes a block for the body of a lambda, with the body as its only statement.
+fn lambda_body(function: &FunctionLiteral) -> Option<&Expression> {
+    match function.body.statements.as_slice() {
+        [Statement::Expression(statement)] if function.form != FunctionForm::Fn => {
+            Some(&statement.expression)
+        }
+        _ => None,
+    }
+}
+
+/// Returns whether `call` is written as a pipe, e.g. `x |> f` or `x |> f(y)`.
+fn is_pipe(call: &CallExpression) -> bool {
+    call.piped && !call.arguments.is_empty()
+}
+
+/// Returns the calls of a chain of pipes, e.g. `xs |> map(f) |> filter(g)`, along with the value that
+/// is piped into the first of them. Like a chain of operators, a chain of pipes is laid out as one
+/// group, so that it is broken before every `|>` if it is broken at all.
+fn pipes(call: &CallExpression) -> (&Expression, Vec<&CallExpression>) {
+    let mut calls = vec![call];
+    let mut first = &call.arguments[0];
+
+    while let Expression::Call(call) = first {
+        if !is_pipe(call) {
+            break;
+        }
+
+        calls.push(call);
+        first = &call.arguments[0];
+    }
+
+    calls.reverse();
+    (first, calls)
+}
+
+/// Returns the precedence of `expression`, i.e. how tightly it holds together. A short lambda has the
+/// lowest precedence, since its body goes on for as long as it can.
+fn precedence(expression: &Expression) -> Precedence {
+    match expression {
+        Expression::Infix(infix) => match infix.operator.r#type {
+            InfixOperatorType::Add | InfixOperatorType::Sub => Precedence::Sum,
+            InfixOperatorType::Mul | InfixOperatorType::Div => Precedence::Product,
+            InfixOperatorType::Eq | InfixOperatorType::NotEq => Precedence::Equals,
+            InfixOperatorType::Lt | InfixOperatorType::Gt => Precedence::Ordering,
+            InfixOperatorType::Range => Precedence::Range,
+        },
+        Expression::Prefix(_) => Precedence::Prefix,
+        Expression::Call(call) if is_pipe(call) => Precedence::Pipe,
+        Expression::Call(_) | Expression::Record(_) => Precedence::Call,
+        Expression::Function(function) if lambda_body(function).is_some() => Precedence::Lowest,
+        Expression::Spread(_) => Precedence::Lowest,
+        _ => Precedence::Index,
+    }
+}
+
+/// Returns whether `expression` has to be parenthesized to be an operand with the given precedence.
+/// Operators are left associative, so the `right` operand of one has to be parenthesized if it has the
+/// same precedence as the operator.
+fn needs_parentheses(expression: &Expression, context: Precedence, right: bool) -> bool {
+    let own = precedence(expression);
+
+    if right {
+        own <= context
+    } else {
+        own < context
+    }
+}
+
+/// Returns the operands of a chain of infix operators with the same precedence, e.g. `a + b - c`, as the
+/// first operand and each operator with the operand after it. The operators are left associative, so the
+/// chain goes down the left operands. A chain is laid out as one group, so that if it is broken, it is
+/// broken after every operator rather than just the outermost ones.
+fn chain(expression: &Expression) -> (&Expression, Vec<(&InfixOperator, &Expression)>) {
+    let mut rest = Vec::new();
+    let mut first = expression;
+
+    while let Expression::Infix(infix) = first {
+        if !rest.is_empty() && precedence(first) != precedence(expression) {
+            break;
+        }
+
+        rest.push((&infix.operator, infix.right.as_ref()));
+        first = &infix.left;
+    }
+
+    rest.reverse();
+    (first, rest)
+}
+
+/// Returns whether `expression` is short and simple enough to be written before a function literal
+/// that a call hugs, e.g. the `xs` in `each(xs, fn(x) { ... })`.
+fn is_simple(expression: &Expression) -> bool {
+    match expression {
+        Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::Boolean(_)
+        | Expression::String(_) => true,
+        Expression::Member(member) => is_simple(&member.object),
+        _ => false,
+    }
+}
+
+/// Returns where `expression` starts in the source code. Infix, call, index and member expressions only
+/// know where their operator is, so they start where their leftmost operand does.
+fn start(expression: &Expression) -> Position {
+    match expression {
+        Expression::Infix(infix) => start(&infix.left),
+        Expression::Call(call) => {
+            let function = start(&call.function);
+
+            // The value of a pipe comes before the function that it is passed to.
+            call.arguments
+                .first()
+                .map_or(function, |first| function.min(start(first)))
+        }
+        Expression::Index(index) => start(&index.left),
+        Expression::Member(member) => start(&member.object),
+        Expression::Record(record) => record.name.token.position,
+        _ => expression.position(),
+    }
+}
+
+/// Returns where `parameter` starts in the source code.
+fn parameter_start(parameter: &Parameter) -> Position {
+    match parameter {
+        Parameter::Required(name) | Parameter::Default { name, .. } => name.token.position,
+        Parameter::Rest { token, .. } => token.position,
+    }
+}
+
+/// Returns where `pattern` starts in the source code.
+fn pattern_start(pattern: &Pattern) -> Position {
+    match pattern {
+        Pattern::Literal(literal) => start(literal),
+        pattern => pattern.position(),
+    }
+}
+
+/// Returns the document for a list of things, which are written between `open` and `close` separated
+/// by commas, or on lines of their own if they don't fit, e.g. the arguments of a call.
+fn list(open: &str, docs: Vec<Doc>, close: &str) -> Doc {
+    let items = docs.into_iter().map(|doc| (doc, concat([]))).collect();
+    commented_list(open, Doc::SoftLine, concat([]), items, close)
+}
+
+/// Returns the document for a list like `list` does, with the `leading` comments before its first
+/// thing, and the comments of each thing after it (and the comma after it). The `padding` goes inside
+/// the brackets, which is `Doc::Line` for the fields in braces, e.g. `{ x, y }`.
+fn commented_list(
+    open: &str,
+    padding: Doc,
+    leading: Doc,
+    items: Vec<(Doc, Doc)>,
+    close: &str,
+) -> Doc {
+    if items.is_empty() {
+        return text(format!("{}{}", open, close));
+    }
+
+    let last = items.len() - 1;
+    let mut docs = vec![padding.clone(), leading];
+
+    for (i, (doc, comments)) in items.into_iter().enumerate() {
+        docs.push(doc);
+
+        if i < last {
+            docs.push(text(","));
+        }
+
+        docs.push(comments);
+
+        if i < last {
+            docs.push(Doc::Line);
+        }
+    }
+
+    group(concat([
+        text(open),
+        nest(concat(docs)),
+        padding,
+        text(close),
+    ]))
+}
+
+impl Formatter<'_> {
+    /// Returns the document for `expression`, where it doesn't need to be parenthesized.
+    pub(super) fn expression(&mut self, expression: &Expression) -> Doc {
+        self.operand(expression, Precedence::Lowest, false)
+    }
+
+    /// Returns the document for `expression` as an operand with the given precedence, which is
+    /// parenthesized if it needs to be, and only then.
+    fn operand(&mut self, expression: &Expression, precedence: Precedence, right: bool) -> Doc {
+        let doc = self.bare_expression(expression);
+
+        if needs_parentheses(expression, precedence, right) {
+            concat([text("("), doc, text(")")])
+        } else {
+            doc
+        }
+    }
+
+    /// Returns whether a statement of `expression` would start with something that could also continue
+    /// the statement before it, if it ends with a block. These are `(` and `[`, which would call or index
+    /// it, `-`, which would subtract from it, and `{`, which would make a record of it.
+    pub(super) fn starts_ambiguously(&self, expression: &Expression) -> bool {
+        fn starts(expression: &Expression, precedence: Precedence, right: bool) -> bool {
+            if needs_parentheses(expression, precedence, right) {
+                return true;
+            }
+
+            match expression {
+                Expression::Infix(infix) => {
+                    starts(&infix.left, self::precedence(expression), false)
+                }
+                Expression::Call(call) if is_pipe(call) => {
+                    starts(&call.arguments[0], Precedence::Pipe, false)
+                }
+                Expression::Call(call) => starts(&call.function, Precedence::Call, false),
+                Expression::Index(index) => starts(&index.left, Precedence::Call, false),
+                Expression::Member(member) => starts(&member.object, Precedence::Call, false),
+                Expression::Prefix(prefix) => prefix.operator.r#type == PrefixOperatorType::Neg,
+                Expression::Array(_) => true,
+                _ => false,
+            }
+        }
+
+        starts(expression, Precedence::Lowest, false)
+    }
+
+    /// Returns the document for `expression`, without any parentheses around it.
+    fn bare_expression(&mut self, expression: &Expression) -> Doc {
+        match expression {
+            Expression::Identifier(identifier) => text(identifier.value.as_str()),
+            Expression::Integer(integer) => text(integer.value.to_string()),
+            Expression::Boolean(boolean) => text(boolean.value.to_string()),
+            Expression::String(string) => text(format!("\"{}\"", string.value)),
+            Expression::Prefix(prefix) => concat([
+                text(prefix.operator.to_string()),
+                self.operand(&prefix.right, Precedence::Prefix, false),
+            ]),
+            Expression::Infix(infix) if infix.operator.r#type == InfixOperatorType::Range => {
+                concat([
+                    self.operand(&infix.left, Precedence::Range, false),
+                    text(".."),
+                    self.operand(&infix.right, Precedence::Range, true),
+                ])
+            }
+            Expression::Infix(_) => {
+                let precedence = precedence(expression);
+                let (first, rest) = chain(expression);
+
+                let first_doc = self.operand(first, precedence, false);
+                let mut previous = first;
+                let mut docs = Vec::new();
+
+                // The comments between two operands go after the operator between them.
+                for (operator, operand) in rest {
+                    let comments = self.take_comments(start(previous), Some(start(operand)));
+
+                    docs.push(text(format!(" {}", operator)));
+                    docs.push(self.trailing_comments(comments));
+                    docs.push(Doc::Line);
+                    docs.push(self.operand(operand, precedence, true));
+
+                    previous = operand;
+                }
+
+                group(concat([first_doc, nest(concat(docs))]))
+            }
+            Expression::Call(call) if is_pipe(call) => {
+                let (first, calls) = pipes(call);
+
+                let first_doc = self.operand(first, Precedence::Pipe, false);
+                let mut previous = start(first);
+                let mut docs = Vec::new();
+
+                // The comments between two parts of the chain go after the `|>` between them.
+                for call in calls {
+                    let function = start(&call.function);
+                    let comments = self.take_comments(previous, Some(function));
+
+                    docs.push(text(" |>"));
+                    docs.push(self.trailing_comments(comments));
+                    docs.push(Doc::Line);
+                    docs.push(self.piped_call(call));
+
+                    previous = function;
+                }
+
+                group(concat([first_doc, nest(concat(docs))]))
+            }
+            Expression::Call(call) => {
+                let function = self.operand(&call.function, Precedence::Call, false);
+                let open = (call.token.r#type == TokenType::LParen).then_some(call.token.position);
+
+                concat([function, self.arguments(&call.arguments, open)])
+            }
+            Expression::Array(array) => {
+                let elements = array
+                    .elements
+                    .iter()
+                    .map(|element| (self.expression(element), start(element)))
+                    .collect();
+
+                self.list_in_source(("[", Doc::SoftLine), array.token.position, elements, "]")
+            }
+            Expression::Index(index) => concat([
+                self.operand(&index.left, Precedence::Call, false),
+                text("["),
+                self.expression(&index.index),
+                text("]"),
+            ]),
+            Expression::Member(member) => concat([
+                self.operand(&member.object, Precedence::Call, false),
+                text(format!(".{}", member.member.value)),
+            ]),
+            Expression::Spread(spread) => concat([text("..."), self.expression(&spread.value)]),
+            Expression::Hash(hash) => {
+                let pairs = hash
+                    .pairs
+                    .iter()
+                    .map(|(key, value)| {
+                        let doc =
+                            concat([self.expression(key), text(": "), self.expression(value)]);
+                        (doc, start(key))
+                    })
+                    .collect();
+
+                self.list_in_source(("{", Doc::SoftLine), hash.token.position, pairs, "}")
+            }
+            Expression::Record(record) => {
+                let fields = record
+                    .fields
+                    .iter()
+                    .map(|(name, value)| {
+                        let doc =
+                            concat([text(format!("{}: ", name.value)), self.expression(value)]);
+                        (doc, name.token.position)
+                    })
+                    .collect();
+
+                concat([
+                    text(format!("{} ", record.name.value)),
+                    self.list_in_source(("{", Doc::Line), record.token.position, fields, "}"),
+                ])
+            }
+            Expression::Function(function) => match lambda_body(function) {
+                // Only a lambda with a single required parameter can be written with an arrow.
+                Some(body)
+                    if function.form == FunctionForm::Arrow
+                        && matches!(function.parameters.as_slice(), [Parameter::Required(_)]) =>
+                {
+                    concat([
+                        text(format!("{} => ", function.parameters[0])),
+                        self.expression(body),
+                    ])
+                }
+                Some(body) => {
+                    let parameters = function
+                        .parameters
+                        .iter()
+                        .map(|parameter| self.parameter(parameter))
+                        .collect::<Vec<_>>();
+
+                    concat([
+                        text("|"),
+                        join(parameters, text(", ")),
+                        text("| "),
+                        self.expression(body),
+                    ])
+                }
+                None => concat([
+                    text("fn"),
+                    self.parameters(&function.parameters, function.token.position),
+                    text(" "),
+                    self.block(&function.body),
+                ]),
+            },
+            Expression::Macro(macro_literal) => concat([
+                text("macro"),
+                self.parameters(&macro_literal.parameters, macro_literal.token.position),
+                text(" "),
+                self.block(&macro_literal.body),
+            ]),
+            Expression::If(if_expression) => {
+                let mut docs = vec![
+                    text("if ("),
+                    self.expression(&if_expression.condition),
+                    text(") "),
+                    self.block(&if_expression.consequence),
+                ];
+
+                for branch in &if_expression.else_ifs {
+                    docs.push(text(" else if ("));
+                    docs.push(self.expression(&branch.condition));
+                    docs.push(text(") "));
+                    docs.push(self.block(&branch.consequence));
+                }
+
+                if let Some(alternative) = &if_expression.alternative {
+                    docs.push(text(" else "));
+                    docs.push(self.block(alternative));
+                }
+
+                concat(docs)
+            }
+            Expression::Try(try_expression) => {
+                let mut docs = vec![text("try "), self.block(&try_expression.body)];
+
+                if let Some(catch) = &try_expression.catch {
+                    docs.push(text(format!(" catch ({}) ", catch.parameter.value)));
+                    docs.push(self.block(&catch.body));
+                }
+
+                if let Some(finally) = &try_expression.finally {
+                    docs.push(text(" finally "));
+                    docs.push(self.block(finally));
+                }
+
+                concat(docs)
+            }
+            Expression::For(for_expression) => concat([
+                text("for ("),
+                self.pattern(&for_expression.pattern),
+                text(" in "),
+                self.expression(&for_expression.iterable),
+                text(") "),
+                self.block(&for_expression.body),
+            ]),
+            Expression::Match(match_expression) => self.match_expression(match_expression),
+        }
+    }
+
+    /// Returns the document for a match expression, with each arm on a line of its own. The comments
+    /// in its body are kept with the arms: a comment on the same line as an arm (or as the `{`) stays at
+    /// the end of it, and the others stay on lines of their own, before the arm that they are before.
+    fn match_expression(&mut self, match_expression: &MatchExpression) -> Doc {
+        let subject = self.expression(&match_expression.subject);
+
+        // The body's `{` is the first bracket opened after the parentheses around the subject.
+        let parentheses = self.closing.range(match_expression.token.position..).next();
+        let body = parentheses.and_then(|(_, &close)| self.closing.range(close..).next());
+        let (mut previous, end) = match body {
+            Some((&open, &close)) => (open, Some(close)),
+            None => (match_expression.token.position, None),
+        };
+
+        let mut arms = Vec::new();
+
+        for arm in &match_expression.arms {
+            let position = pattern_start(&arm.pattern);
+
+            let comments = self.take_comments(previous, Some(position));
+            arms.push(self.trailing_comments(comments));
+
+            arms.push(Doc::HardLine);
+            arms.push(self.pattern(&arm.pattern));
+
+            if let Some(guard) = &arm.guard {
+                arms.push(text(" if "));
+                arms.push(self.operand(guard, Precedence::Lambda, false));
+            }
+
+            arms.push(text(" => "));
+            arms.push(self.expression(&arm.body));
+            arms.push(text(","));
+
+            previous = position;
+        }
+
+        let comments = self.take_comments(previous, end);
+
+        if match_expression.arms.is_empty() && comments.is_empty() {
+            return concat([text("match ("), subject, text(") {}")]);
+        }
+
+        arms.push(self.trailing_comments(comments));
+
+        concat([
+            text("match ("),
+            subject,
+            text(") {"),
+            nest(concat(arms)),
+            Doc::HardLine,
+            text("}"),
+        ])
+    }
+
+    /// Returns the document for what a value is piped into, i.e. a call without its first argument, e.g.
+    /// the `f(y)` of `x |> f(y)`. A call that was written without parentheses, e.g. the `f` of `x |> f`,
+    /// is written without them again.
+    fn piped_call(&mut self, call: &CallExpression) -> Doc {
+        if call.token.r#type == TokenType::Pipe && call.arguments.len() == 1 {
+            return self.operand(&call.function, Precedence::Pipe, true);
+        }
+
+        let function = self.operand(&call.function, Precedence::Call, false);
+        let open = (call.token.r#type == TokenType::LParen).then_some(call.token.position);
+
+        concat([function, self.arguments(&call.arguments[1..], open)])
+    }
+
+    /// Returns the document for the arguments of a call. If the last argument is a function literal and
+    /// the others are simple, the call hugs the function, so that only the function's body is broken
+    /// onto lines of its own, e.g. `each(xs, fn(x) {` rather than having each argument on a line. The
+    /// arguments are in parentheses that `open` at the given position, if they are in any.
+    fn arguments(&mut self, arguments: &[Expression], open: Option<Position>) -> Doc {
+        if let Some((Expression::Function(function), rest)) = arguments.split_last() {
+            if lambda_body(function).is_none() && rest.iter().all(is_simple) {
+                let mut docs = vec![text("(")];
+
+                for argument in rest {
+                    docs.push(self.expression(argument));
+                    docs.push(text(", "));
+                }
+
+                docs.push(self.bare_expression(&arguments[arguments.len() - 1]));
+                docs.push(text(")"));
+
+                return concat(docs);
+            }
+        }
+
+        let arguments = arguments
+            .iter()
+            .map(|argument| (self.expression(argument), start(argument)))
+            .collect::<Vec<_>>();
+
+        match open {
+            Some(open) => self.list_in_source(("(", Doc::SoftLine), open, arguments, ")"),
+            None => list(
+                "(",
+                arguments.into_iter().map(|(doc, _)| doc).collect(),
+                ")",
+            ),
+        }
+    }
+
+    /// Returns the document for the parameters of a function or macro literal, in parentheses. They are
+    /// in the first parentheses after `after` in the source code, e.g. after the `fn`.
+    pub(super) fn parameters(&mut self, parameters: &[Parameter], after: Position) -> Doc {
+        let parameters = parameters
+            .iter()
+            .map(|parameter| (self.parameter(parameter), parameter_start(parameter)))
+            .collect::<Vec<_>>();
+
+        match self.closing.range(after..).next() {
+            Some((&open, _)) => self.list_in_source(("(", Doc::SoftLine), open, parameters, ")"),
+            None => list(
+                "(",
+                parameters.into_iter().map(|(doc, _)| doc).collect(),
+                ")",
+            ),
+        }
+    }
+
+    /// Returns the document for a list of things like `list` does, along with the comments in it. The
+    /// list is between the bracket at `open` in the source code and the bracket that closes it, and
+    /// each of its things is given with where it starts. The comments before the first thing go on lines
+    /// of their own before it, and the others go after the thing that they follow. The things have to be
+    /// turned into documents first, so that the comments inside of them have already been placed.
+    pub(super) fn list_in_source(
+        &mut self,
+        (open, padding): (&str, Doc),
+        position: Position,
+        items: Vec<(Doc, Position)>,
+        close: &str,
+    ) -> Doc {
+        let end = self.closing.get(&position).copied();
+
+        let leading = match items.first() {
+            Some(&(_, first)) => self.take_comments(position, Some(first)),
+            None => Vec::new(),
+        };
+
+        let leading = leading
+            .into_iter()
+            .flat_map(|comment| [text(comment.text), Doc::HardLine]);
+
+        let starts = items.iter().map(|&(_, start)| start).collect::<Vec<_>>();
+        let mut commented = Vec::new();
+
+        for (i, (doc, start)) in items.into_iter().enumerate() {
+            let comments = match starts.get(i + 1).copied().or(end) {
+                Some(next) => self.take_comments(start, Some(next)),
+                None => Vec::new(),
+            };
+
+            commented.push((doc, self.trailing_comments(comments)));
+        }
+
+        commented_list(open, padding, concat(leading), commented, close)
+    }
+
+    /// Returns the document for `parameter`. A default value that is a short lambda is parenthesized,
+    /// so that the body of the lambda can't run on into the rest of a lambda's parameters.
+    fn parameter(&mut self, parameter: &Parameter) -> Doc {
+        match parameter {
+            Parameter::Required(name) => text(name.value.as_str()),
+            Parameter::Default { name, value } => concat([
+                text(format!("{} = ", name.value)),
+                self.operand(value, Precedence::Lambda, false),
+            ]),
+            Parameter::Rest { name, .. } => text(format!("...{}", name.value)),
+        }
+    }
+
+    /// Returns the document for `pattern`.
+    pub(super) fn pattern(&mut self, pattern: &Pattern) -> Doc {
+        match pattern {
+            Pattern::Identifier(identifier) => text(identifier.value.as_str()),
+            Pattern::Wildcard(_) => text("_"),
+            Pattern::Literal(literal) => self.expression(literal),
+            Pattern::Array(array) => {
+                let mut elements = array
+                    .elements
+                    .iter()
+                    .map(|element| (self.pattern(element), element.position()))
+                    .collect::<Vec<_>>();
+
+                if let Some(rest) = &array.rest {
+                    elements.push((text(format!("...{}", rest.value)), rest.token.position));
+                }
+
+                self.list_in_source(("[", Doc::SoftLine), array.token.position, elements, "]")
+            }
+            Pattern::Hash(hash) => {
+                let pairs = hash
+                    .pairs
+                    .iter()
+                    .map(|(key, value)| {
+                        let doc = concat([self.expression(key), text(": "), self.pattern(value)]);
+                        (doc, start(key))
+                    })
+                    .collect();
+
+                self.list_in_source(("{", Doc::SoftLine), hash.token.position, pairs, "}")
+            }
+        }
+    }
+}
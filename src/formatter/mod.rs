@@ -0,0 +1,313 @@
+pub mod doc;
+mod expressions;
+mod statements;
+pub mod tests;
+
+use self::doc::{concat, group, nest, text, Doc};
+use crate::{
+    ast::statements::{BlockStatement, Statement},
+    lexer::Lexer,
+    parser::{errors::Error, Parser},
+    token::{Comment, Position, TokenType},
+};
+use std::collections::BTreeMap;
+
+/// How code is formatted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// The number of spaces that each level is indented by.
+    pub indent: usize,
+
+    /// The width that lines are kept within where they can be. Lines can still be wider than this if
+    /// there is nowhere to break them, e.g. if they have a long string in them.
+    pub width: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            width: 80,
+        }
+    }
+}
+
+/// Formats `source` in the one way that the formatter lays code out, keeping its comments and the
+/// blank lines between its statements (though never more than one in a row). The source has to
+/// parse, so the errors are returned if it doesn't. The formatted code parses into the same program,
+/// and formatting it again doesn't change it.
+pub fn format(source: &str, config: &Config) -> Result<String, Vec<Error>> {
+    let program = Parser::new(Lexer::new(source)).parse_program()?;
+
+    let mut formatter = Formatter::new(source);
+    let body = formatter
+        .statements(&program.statements, Position::new(0, 0), None)
+        .unwrap_or_else(|| concat([]));
+
+    let mut out = String::new();
+
+    // The shebang line isn't part of the program, so it is kept as it is.
+    if source.starts_with("#!") {
+        out.push_str(formatter.lines[0].trim_end());
+        out.push('\n');
+    }
+
+    let body = doc::render(&body, config.width, config.indent);
+
+    if !body.is_empty() {
+        out.push_str(&body);
+        out.push('\n');
+    }
+
+    Ok(out)
+}
+
+/// Builds the document for a program, keeping track of which of its comments have been placed.
+struct Formatter<'a> {
+    /// The lines of the source code, which are used to find blank lines and comments that are on a
+    /// line of their own.
+    lines: Vec<&'a str>,
+
+    /// Where every token of the source code is, in order.
+    tokens: Vec<Position>,
+
+    /// Where the bracket that closes each `(`, `[` and `{` is.
+    closing: BTreeMap<Position, Position>,
+
+    /// Every comment in the source code, in order.
+    comments: Vec<Comment>,
+
+    /// Whether each comment has been placed in the document yet.
+    placed: Vec<bool>,
+}
+
+/// A statement or a comment, which is laid out on lines of its own in a list of statements.
+struct Item {
+    /// The document for the item.
+    doc: Doc,
+
+    /// Where the item starts in the source code.
+    start: Position,
+
+    /// The line that the item ends on in the source code.
+    end_line: usize,
+}
+
+impl<'a> Formatter<'a> {
+    /// Creates a `Formatter` for `source`, which has already been parsed.
+    fn new(source: &'a str) -> Self {
+        let mut lexer = Lexer::new(source);
+        let mut tokens = Vec::new();
+        let mut closing = BTreeMap::new();
+        let mut open = Vec::new();
+
+        loop {
+            let token = lexer.next_token();
+
+            match token.r#type {
+                TokenType::EOF => break,
+                TokenType::LParen | TokenType::LBracket | TokenType::LBrace => {
+                    open.push(token.position)
+                }
+                TokenType::RParen | TokenType::RBracket | TokenType::RBrace => {
+                    if let Some(open) = open.pop() {
+                        closing.insert(open, token.position);
+                    }
+                }
+                _ => {}
+            }
+
+            tokens.push(token.position);
+        }
+
+        let comments = lexer.comments().to_vec();
+
+        Self {
+            lines: source.lines().collect(),
+            tokens,
+            closing,
+            placed: vec![false; comments.len()],
+            comments,
+        }
+    }
+
+    /// Returns the document for a block, with its statements on lines of their own. A block with a
+    /// single statement is kept on one line if it fits.
+    fn block(&mut self, block: &BlockStatement) -> Doc {
+        let start = block.token.position;
+        let end = self.closing.get(&start).copied();
+
+        match self.statements(&block.statements, start, end) {
+            Some(body) => group(concat([
+                text("{"),
+                nest(concat([Doc::Line, body])),
+                Doc::Line,
+                text("}"),
+            ])),
+            None => text("{}"),
+        }
+    }
+
+    /// Returns the document for a list of statements, which are between `start` and `end` (or the end of
+    /// the source code if there is no `end`), along with the comments between them. Returns `None` if
+    /// there are neither statements nor comments.
+    fn statements(
+        &mut self,
+        statements: &[Statement],
+        start: Position,
+        end: Option<Position>,
+    ) -> Option<Doc> {
+        let mut items = Vec::new();
+        let mut previous = start;
+
+        for (i, statement) in statements.iter().enumerate() {
+            let position = statements::position(statement);
+
+            for comment in self.take_comments(previous, Some(position)) {
+                items.push(self.comment(comment));
+            }
+
+            let next = statements.get(i + 1);
+            let mut doc = self.statement(statement, next, end.is_some());
+
+            // The comments after the statement, up to the next one, are either on the same line as
+            // some of its code, or on lines of their own. The first of those that aren't on lines of
+            // their own stays at the end of the statement, and the others go after it.
+            let boundary = next.map(statements::position).or(end);
+            let mut after = Vec::new();
+            let mut trailing = false;
+
+            for comment in self.take_comments(position, boundary) {
+                if trailing || self.is_on_own_line(&comment) {
+                    after.push(self.comment(comment));
+                } else {
+                    doc = concat([doc, text(" "), text(comment.text), Doc::BreakParent]);
+                    trailing = true;
+                }
+            }
+
+            items.push(Item {
+                doc,
+                start: position,
+                end_line: self.last_line_before(boundary),
+            });
+
+            items.extend(after);
+            previous = position;
+        }
+
+        if statements.is_empty() {
+            for comment in self.take_comments(start, end) {
+                items.push(self.comment(comment));
+            }
+        }
+
+        if items.is_empty() {
+            return None;
+        }
+
+        let mut docs = Vec::new();
+
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                docs.push(Doc::HardLine);
+
+                if self.has_blank_line(items[i - 1].end_line, item.start.line) {
+                    docs.push(Doc::HardLine);
+                }
+            }
+
+            docs.push(item.doc.clone());
+        }
+
+        Some(concat(docs))
+    }
+
+    /// Returns a comment as an item of a list of statements.
+    fn comment(&self, comment: Comment) -> Item {
+        Item {
+            start: comment.position,
+            end_line: comment.position.line,
+            doc: concat([text(comment.text), Doc::BreakParent]),
+        }
+    }
+
+    /// Takes the comments that haven't been placed yet from between `from` and `to` (or the end of the
+    /// source code if there is no `to`), and marks them as placed.
+    fn take_comments(&mut self, from: Position, to: Option<Position>) -> Vec<Comment> {
+        let first = self
+            .comments
+            .partition_point(|comment| comment.position < from);
+
+        let last = match to {
+            Some(to) => self
+                .comments
+                .partition_point(|comment| comment.position < to),
+            None => self.comments.len(),
+        };
+
+        let mut taken = Vec::new();
+
+        for i in first..last.max(first) {
+            if !self.placed[i] {
+                self.placed[i] = true;
+                taken.push(self.comments[i].clone());
+            }
+        }
+
+        taken
+    }
+
+    /// Returns the documents for comments that go after a piece of code that a line break follows, e.g.
+    /// an argument of a call and the comma after it. A comment that is on a line of its own stays on a
+    /// line of its own, and the others stay at the end of the line, so the line break is always taken.
+    fn trailing_comments(&self, comments: Vec<Comment>) -> Doc {
+        if comments.is_empty() {
+            return concat([]);
+        }
+
+        let mut docs = Vec::new();
+
+        for comment in comments {
+            docs.push(match self.is_on_own_line(&comment) {
+                true => Doc::HardLine,
+                false => text(" "),
+            });
+
+            docs.push(text(comment.text));
+        }
+
+        docs.push(Doc::BreakParent);
+        concat(docs)
+    }
+
+    /// Returns whether there is nothing but whitespace before `comment` on its line.
+    fn is_on_own_line(&self, comment: &Comment) -> bool {
+        self.lines[comment.position.line - 1]
+            .chars()
+            .take(comment.position.column - 1)
+            .all(char::is_whitespace)
+    }
+
+    /// Returns the line of the last token before `position` (or the end of the source code if there is
+    /// no `position`).
+    fn last_line_before(&self, position: Option<Position>) -> usize {
+        let count = match position {
+            Some(position) => self.tokens.partition_point(|token| *token < position),
+            None => self.tokens.len(),
+        };
+
+        count
+            .checked_sub(1)
+            .map_or(0, |last| self.tokens[last].line)
+    }
+
+    /// Returns whether any of the lines after `after` and before `before` are blank.
+    fn has_blank_line(&self, after: usize, before: usize) -> bool {
+        (after + 1..before).any(|line| {
+            self.lines
+                .get(line - 1)
+                .is_some_and(|line| line.trim().is_empty())
+        })
+    }
+}
@@ -0,0 +1,127 @@
+use super::{
+    doc::{concat, text, Doc},
+    Formatter,
+};
+use crate::{
+    ast::{expressions::Expression, statements::Statement},
+    token::Position,
+};
+
+/// Returns where `statement` starts in the source code.
+pub(super) fn position(statement: &Statement) -> Position {
+    match statement {
+        Statement::Let(let_statement) => let_statement.token.position,
+        Statement::Return(return_statement) => return_statement.token.position,
+        Statement::Expression(expression_statement) => expression_statement.token.position,
+        Statement::Function(function_statement) => function_statement.token.position,
+        Statement::Import(import_statement) => import_statement.token.position,
+        Statement::Export(export_statement) => export_statement.token.position,
+        Statement::Struct(struct_statement) => struct_statement.token.position,
+        Statement::Throw(throw_statement) => throw_statement.token.position,
+        Statement::Yield(yield_statement) => yield_statement.token.position,
+    }
+}
+
+/// Returns whether `expression` ends with a block, so that a statement of it doesn't need a semicolon
+/// to be told apart from the statement after it.
+fn ends_with_block(expression: &Expression) -> bool {
+    matches!(
+        expression,
+        Expression::If(_) | Expression::For(_) | Expression::Try(_) | Expression::Match(_)
+    )
+}
+
+impl Formatter<'_> {
+    /// Returns the document for `statement`, which is followed by `next` in a block (if `in_block`) or
+    /// at the top level of the program.
+    ///
+    /// Semicolons are written after every statement, except for function and struct declarations,
+    /// and expression statements that end with a block, or that are the last statement of a block.
+    /// An expression statement that ends with a block still gets a semicolon if the statement after
+    /// it starts with something that would otherwise continue it, e.g. `(` which would call it.
+    pub(super) fn statement(
+        &mut self,
+        statement: &Statement,
+        next: Option<&Statement>,
+        in_block: bool,
+    ) -> Doc {
+        match statement {
+            Statement::Let(let_statement) => concat([
+                text("let "),
+                self.pattern(&let_statement.pattern),
+                text(" = "),
+                self.expression(&let_statement.value),
+                text(";"),
+            ]),
+            Statement::Return(return_statement) => concat([
+                text("return "),
+                self.expression(&return_statement.return_value),
+                text(";"),
+            ]),
+            Statement::Throw(throw_statement) => concat([
+                text("throw "),
+                self.expression(&throw_statement.value),
+                text(";"),
+            ]),
+            Statement::Yield(yield_statement) => concat([
+                text("yield "),
+                self.expression(&yield_statement.value),
+                text(";"),
+            ]),
+            Statement::Expression(expression_statement) => {
+                let expression = &expression_statement.expression;
+
+                let semicolon = if ends_with_block(expression) {
+                    matches!(next, Some(Statement::Expression(next)) if self.starts_ambiguously(&next.expression))
+                } else {
+                    next.is_some() || !in_block
+                };
+
+                let doc = self.expression(expression);
+
+                if semicolon {
+                    concat([doc, text(";")])
+                } else {
+                    doc
+                }
+            }
+            Statement::Function(function_statement) => concat([
+                text("fn "),
+                text(function_statement.name.value.as_str()),
+                self.parameters(
+                    &function_statement.function.parameters,
+                    function_statement.token.position,
+                ),
+                text(" "),
+                self.block(&function_statement.function.body),
+            ]),
+            Statement::Import(import_statement) => text(format!(
+                "import \"{}\" as {};",
+                import_statement.path.value, import_statement.alias.value
+            )),
+            Statement::Export(export_statement) => concat([
+                text("export "),
+                self.statement(&export_statement.declaration, None, false),
+            ]),
+            Statement::Struct(struct_statement) => {
+                let fields = struct_statement
+                    .fields
+                    .iter()
+                    .map(|field| (text(field.value.as_str()), field.token.position))
+                    .collect();
+
+                // The fields are in the braces after the struct's name.
+                let open = self
+                    .closing
+                    .range(struct_statement.name.token.position..)
+                    .next()
+                    .map_or(struct_statement.token.position, |(&open, _)| open);
+
+                concat([
+                    text(format!("struct {} ", struct_statement.name.value)),
+                    self.list_in_source(("{", Doc::Line), open, fields, "}"),
+                ])
+            }
+        }
+    }
+}
@@ -0,0 +1,407 @@
+#![cfg(test)]
+
+use super::{format, Config};
+use crate::{
+    dump::{dump_program, Format},
+    lexer::Lexer,
+    parser::Parser,
+};
+
+/// Formats `input` with the default config
+fn fmt(input: &str) -> String {
+    format(input, &Config::default())
+        .unwrap_or_else(|errors| panic!("couldn't format {:?}: {:?}", input, errors))
+}
+
+/// Parses `input`, and dumps it as S-expressions, which leave out where each node is
+fn sexpr(input: &str) -> String {
+    let program = Parser::new(Lexer::new(input))
+        .parse_program()
+        .unwrap_or_else(|errors| panic!("couldn't parse {:?}: {:?}", input, errors));
+
+    dump_program(&program, Format::Sexpr)
+}
+
+/// Checks that formatting `input` gives the same program, and that formatting it again doesn't change
+/// it, and returns it formatted
+fn check(input: &str) -> String {
+    let formatted = fmt(input);
+
+    assert_eq!(sexpr(&formatted), sexpr(input), "formatted: {}", formatted);
+    assert_eq!(fmt(&formatted), formatted, "input: {}", input);
+
+    formatted
+}
+
+/// Tests formatting statements and expressions of every kind
+#[test]
+fn test_format() {
+    let tests = vec![
+        ("let x=1;", "let x = 1;\n"),
+        ("let   [a,b,...c]  =  xs;", "let [a, b, ...c] = xs;\n"),
+        ("let {\"a\":a} = h;", "let {\"a\": a} = h;\n"),
+        ("return x;", "return x;\n"),
+        ("fn f() { yield 1 }", "fn f() { yield 1; }\n"),
+        ("throw \"oops\"", "throw \"oops\";\n"),
+        ("x", "x;\n"),
+        ("-a + !b", "-a + !b;\n"),
+        ("f(1,2)(3)", "f(1, 2)(3);\n"),
+        ("xs[0].y", "xs[0].y;\n"),
+        ("[1,2,...xs]", "[1, 2, ...xs];\n"),
+        ("{\"a\":1,\"b\":2,}", "{\"a\": 1, \"b\": 2};\n"),
+        ("{}", "{};\n"),
+        ("0 .. 10", "0..10;\n"),
+        ("x |> f", "x |> f;\n"),
+        ("x |> f(y)", "x |> f(y);\n"),
+        ("x|>f()", "x |> f();\n"),
+        ("f(x, y)", "f(x, y);\n"),
+        ("xs |> map(f) |> filter(g)", "xs |> map(f) |> filter(g);\n"),
+        ("x => x * 2", "x => x * 2;\n"),
+        ("map(xs, x=>x)", "map(xs, x => x);\n"),
+        ("|| 1", "|| 1;\n"),
+        ("|a, b = 1, ...c| a", "|a, b = 1, ...c| a;\n"),
+        ("fn(x){x}", "fn(x) { x };\n"),
+        ("fn(){}", "fn() {};\n"),
+        (
+            "macro(x) { quote(unquote(x)) }",
+            "macro(x) { quote(unquote(x)) };\n",
+        ),
+        (
+            "fn add(a,b){return a+b;}",
+            "fn add(a, b) { return a + b; }\n",
+        ),
+        (
+            "fn f(x) { let y = x; y }",
+            "fn f(x) {\n    let y = x;\n    y\n}\n",
+        ),
+        (
+            "if(a){1}else if(b){2}else{3}",
+            "if (a) { 1 } else if (b) { 2 } else { 3 }\n",
+        ),
+        (
+            "try { f() } catch(e) { g(e) } finally { h() }",
+            "try { f() } catch (e) { g(e) } finally { h() }\n",
+        ),
+        ("for (x in xs) { puts(x) }", "for (x in xs) { puts(x) }\n"),
+        (
+            "match (x) { 1 => \"one\", [a, ..._] if a > 1 => a, _ => 0 }",
+            "match (x) {\n    1 => \"one\",\n    [a, ..._] if a > 1 => a,\n    _ => 0,\n}\n",
+        ),
+        ("match (x) {}", "match (x) {}\n"),
+        ("struct Point{x,y,}", "struct Point { x, y }\n"),
+        ("struct Unit {}", "struct Unit {}\n"),
+        ("Point{x:1,y:2}", "Point { x: 1, y: 2 };\n"),
+        (
+            "import \"lib.monkey\" as lib;",
+            "import \"lib.monkey\" as lib;\n",
+        ),
+        ("export let x = 1;", "export let x = 1;\n"),
+        ("export fn f() { 1 }", "export fn f() { 1 }\n"),
+        ("let s = \"a\nb\";", "let s = \"a\nb\";\n"),
+        ("1;2;3", "1;\n2;\n3;\n"),
+        ("", ""),
+        ("  \n\n", ""),
+        (
+            "#!/usr/bin/env monkey\nputs(1)",
+            "#!/usr/bin/env monkey\nputs(1);\n",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(check(input), expected, "input: {:?}", input);
+    }
+}
+
+/// Tests that parentheses are only kept where they are needed
+#[test]
+fn test_format_parentheses() {
+    let tests = vec![
+        ("(1 + 2) * 3", "(1 + 2) * 3;\n"),
+        ("1 + (2 * 3)", "1 + 2 * 3;\n"),
+        ("(1 + 2) + 3", "1 + 2 + 3;\n"),
+        ("1 + (2 + 3)", "1 + (2 + 3);\n"),
+        ("a - (b - c)", "a - (b - c);\n"),
+        ("((a))", "a;\n"),
+        ("-(a + b)", "-(a + b);\n"),
+        ("(-a)[0]", "(-a)[0];\n"),
+        ("-(a[0])", "-a[0];\n"),
+        ("(a < b) == (c > d)", "a < b == c > d;\n"),
+        ("(0..10) == r", "0..10 == r;\n"),
+        ("(x |> f) |> g", "x |> f |> g;\n"),
+        ("x |> (f |> g)", "x |> g(f);\n"),
+        ("(x => x)(1)", "(x => x)(1);\n"),
+        ("1 + (x => x)", "1 + (x => x);\n"),
+        ("x |> (y => y)", "x |> (y => y);\n"),
+        ("fn(f = (x => x)) { f }", "fn(f = (x => x)) { f };\n"),
+        ("(x |> f)(1)", "(x |> f)(1);\n"),
+        ("(|x| x)(1)", "(|x| x)(1);\n"),
+        ("(|x| x) + 1", "(|x| x) + 1;\n"),
+        ("1 + (|x| x)", "1 + (|x| x);\n"),
+        ("x |> (|y| y)", "x |> (|y| y);\n"),
+        ("|x| (|y| x + y)", "|x| |y| x + y;\n"),
+        ("f(|x| x, 1)", "f(|x| x, 1);\n"),
+        (
+            "match (x) { _ if (|y| y)(x) => 1 }",
+            "match (x) {\n    _ if (|y| y)(x) => 1,\n}\n",
+        ),
+        ("(fn(x) { x })(1)", "fn(x) { x }(1);\n"),
+        ("(P { x: 1 }).x", "P { x: 1 }.x;\n"),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(check(input), expected, "input: {:?}", input);
+    }
+}
+
+/// Tests where semicolons are needed after statements that end with a block, so that the statement
+/// after them isn't read as a continuation of them
+#[test]
+fn test_format_semicolons() {
+    let tests = vec![
+        ("if (a) { b } c", "if (a) { b }\nc;\n"),
+        ("if (a) { b }; (c)", "if (a) { b }\nc;\n"),
+        ("if (a) { b }; (c + d) * e", "if (a) { b };\n(c + d) * e;\n"),
+        ("if (a) { b }; [1]", "if (a) { b };\n[1];\n"),
+        ("if (a) { b }; -1", "if (a) { b };\n-1;\n"),
+        ("if (a) { b }; {}", "if (a) { b }\n{};\n"),
+        ("if (a) { b }; let x = 1;", "if (a) { b }\nlet x = 1;\n"),
+        (
+            "fn f() { if (a) { b }; [1] }",
+            "fn f() {\n    if (a) { b };\n    [1]\n}\n",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(check(input), expected, "input: {:?}", input);
+    }
+}
+
+/// Tests that comments are kept, along with single blank lines between statements
+#[test]
+fn test_format_comments() {
+    let input = "// A program.
+let x = 1; // one
+
+
+let y = 2;
+// Before f.
+fn f(a) {
+    // Inside f.
+    a // the result
+    // The end of f.
+}
+fn g() {
+    // Nothing here.
+}
+let z = [1, // in a list
+  2];
+// The end.
+";
+
+    let expected = "// A program.
+let x = 1; // one
+
+let y = 2;
+// Before f.
+fn f(a) {
+    // Inside f.
+    a // the result
+    // The end of f.
+}
+fn g() {
+    // Nothing here.
+}
+let z = [
+    1, // in a list
+    2
+];
+// The end.
+";
+
+    assert_eq!(check(input), expected);
+}
+
+/// Tests that comments inside of expressions stay next to the code that they are about
+#[test]
+fn test_format_nested_comments() {
+    let tests = vec![
+        (
+            "fn f(a, // first\n b) { a + b }",
+            "fn f(\n    a, // first\n    b\n) { a + b }\n",
+        ),
+        (
+            "let y = 1 +\n // mid\n 2;",
+            "let y = 1 +\n    // mid\n    2;\n",
+        ),
+        (
+            "let y = 1 + // one\n 2 * 3 + 4;",
+            "let y = 1 + // one\n    2 * 3 +\n    4;\n",
+        ),
+        (
+            "f(\n // the first\n a,\n b // the last\n);",
+            "f(\n    // the first\n    a,\n    b // the last\n);\n",
+        ),
+        (
+            "f(a,\n  // about b\n  b, g(c, // c\n d));",
+            "f(\n    a,\n    // about b\n    b,\n    g(\n        c, // c\n        d\n    )\n);\n",
+        ),
+        (
+            "let h = {\"a\": 1, // one\n \"b\": 2};",
+            "let h = {\n    \"a\": 1, // one\n    \"b\": 2\n};\n",
+        ),
+        (
+            "let g = fn(x, // x\n y) { x };",
+            "let g = fn(\n    x, // x\n    y\n) { x };\n",
+        ),
+        (
+            "xs |> // c\n map(f) |> g;",
+            "xs |> // c\n    map(f) |>\n    g;\n",
+        ),
+        (
+            "P { x: 1, // c\n y: 2 };",
+            "P {\n    x: 1, // c\n    y: 2\n};\n",
+        ),
+        (
+            "struct P { x, // c\n y }",
+            "struct P {\n    x, // c\n    y\n}\n",
+        ),
+        (
+            "let [a, // c\n b, ...r] = xs;",
+            "let [\n    a, // c\n    b,\n    ...r\n] = xs;\n",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(check(input), expected, "input: {:?}", input);
+    }
+}
+
+/// Tests that the comments in the body of a match expression stay with the arms that they are about,
+/// instead of being moved out of the match
+#[test]
+fn test_format_match_comments() {
+    let tests = vec![
+        (
+            "match (x) {\n // zero\n 0 => a, // after zero\n // rest\n _ => b // last\n}",
+            "match (x) {\n    // zero\n    0 => a, // after zero\n    // rest\n    _ => b, // last\n}\n",
+        ),
+        (
+            "match (x) { // about x\n 0 => a,\n _ => b\n // end\n}",
+            "match (x) { // about x\n    0 => a,\n    _ => b,\n    // end\n}\n",
+        ),
+        (
+            "let y = match (x) {\n 1 if ok => f(1, // one\n 2),\n [a, // first\n b] => a\n};",
+            "let y = match (x) {\n    1 if ok => f(\n        1, // one\n        2\n    ),\n    [\n        a, // first\n        b\n    ] => a,\n};\n",
+        ),
+        (
+            "match (x) {\n -1 => a, // negative\n \"s\" => b\n}",
+            "match (x) {\n    -1 => a, // negative\n    \"s\" => b,\n}\n",
+        ),
+        (
+            "fn f(x) {\n match (x) {\n // only\n _ => 1\n }\n}",
+            "fn f(x) {\n    match (x) {\n        // only\n        _ => 1,\n    }\n}\n",
+        ),
+        ("match (x) {\n // nothing\n}", "match (x) {\n    // nothing\n}\n"),
+        ("match (x) { // nothing\n}", "match (x) { // nothing\n}\n"),
+        ("match (x) {} // after", "match (x) {} // after\n"),
+        (
+            "match (f(x)[0]) { // c\n _ => {\"a\": 1}\n}",
+            "match (f(x)[0]) { // c\n    _ => {\"a\": 1},\n}\n",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(check(input), expected, "input: {:?}", input);
+    }
+}
+
+/// Tests that code that doesn't fit into the width is broken onto more lines
+#[test]
+fn test_format_line_breaking() {
+    let config = Config {
+        indent: 2,
+        width: 30,
+    };
+
+    let tests = vec![
+        (
+            "let result = compute(alpha, beta, gamma, delta);",
+            "let result = compute(\n  alpha,\n  beta,\n  gamma,\n  delta\n);\n",
+        ),
+        (
+            "let total = first + second + third + fourth;",
+            "let total = first +\n  second +\n  third +\n  fourth;\n",
+        ),
+        (
+            "let total = first * second + third - fourth;",
+            "let total = first * second +\n  third -\n  fourth;\n",
+        ),
+        (
+            "let r = items |> map(f) |> filter(g) |> sort();",
+            "let r = items |>\n  map(f) |>\n  filter(g) |>\n  sort();\n",
+        ),
+        (
+            "each(items, fn(item) { puts(item); puts(item) })",
+            "each(items, fn(item) {\n  puts(item);\n  puts(item)\n});\n",
+        ),
+        (
+            "let p = Point { x: 100000, y: 200000 };",
+            "let p = Point {\n  x: 100000,\n  y: 200000\n};\n",
+        ),
+        (
+            "if (ok) { let message = \"fine\"; message }",
+            "if (ok) {\n  let message = \"fine\";\n  message\n}\n",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let formatted = format(input, &config).unwrap();
+
+        assert_eq!(formatted, expected, "input: {:?}", input);
+        assert_eq!(sexpr(&formatted), sexpr(input), "input: {:?}", input);
+        assert_eq!(format(&formatted, &config).unwrap(), formatted);
+    }
+}
+
+/// Tests that a chain of operators is broken after every operator, however wide the lines can be
+#[test]
+fn test_format_chains() {
+    let input = "let total = alpha_value + bravo_value + charlie_value + delta_value + echo_value;";
+
+    for width in [30, 60, 80] {
+        let config = Config { indent: 4, width };
+
+        assert_eq!(
+            format(input, &config).unwrap(),
+            "let total = alpha_value +\n    bravo_value +\n    charlie_value +\n    delta_value +\n    echo_value;\n",
+            "width: {}",
+            width
+        );
+    }
+
+    assert_eq!(
+        format(
+            input,
+            &Config {
+                indent: 4,
+                width: 100
+            }
+        )
+        .unwrap(),
+        format!("{}\n", input)
+    );
+}
+
+/// Tests that a program that doesn't parse isn't formatted
+#[test]
+fn test_format_errors() {
+    let errors = format("let x = ;", &Config::default()).unwrap_err();
+
+    assert!(!errors.is_empty());
+}
+
+/// Tests that the prelude keeps its meaning when it's formatted, and is formatted the same way again
+#[test]
+fn test_format_prelude() {
+    check(include_str!("../evaluator/prelude.monkey"));
+}
@@ -0,0 +1,283 @@
+pub mod tests;
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+// Objects are reference counted, which frees most of them as soon as they are no longer used. What it
+// can't free are cycles: a closure holds on to the environment that it was created in, and when that
+// environment binds the closure (or anything that holds on to it), each keeps the other alive forever.
+//
+// So the shared, mutable parts of the object graph that cycles go through (the evaluator's
+// environments, the variables that the VM's closures capture and the states of iterators) are
+// allocated on a heap that keeps track of them (see `alloc`), and a mark-and-sweep collector frees the
+// cycles among them (see `collect`).
+//
+// The roots that the collector marks from are the nodes that something outside of the heap refers to:
+// the environments that the engines are running code in, the values on their stacks, and the handles
+// that the host holds. These are found by counting the references that the nodes on the heap hold to
+// each other, as a node with more references than that is referred to from outside of it. Everything
+// that the roots don't lead to is garbage, even though its reference counts aren't zero, and is
+// cleared out to break its cycles, after which it is freed as usual.
+
+/// The least number of nodes that are allocated on the heap before the first collection.
+const MIN_THRESHOLD: usize = 10_000;
+
+thread_local! {
+    /// The heap of the current thread. Objects are reference counted with `Rc`, so they never leave the
+    /// thread that they were created on, and neither does anything that could refer to them.
+    static HEAP: RefCell<Heap> = RefCell::new(Heap::new());
+}
+
+/// A value that can hold references to the nodes on the heap.
+pub trait Trace {
+    /// Calls `tracer` with every node on the heap that this value refers to, other than through another
+    /// node.
+    fn trace(&self, tracer: &mut Tracer);
+}
+
+/// A value that can be allocated on the heap as a node.
+pub trait Node: Trace + 'static {
+    /// Takes everything that could refer to other nodes out of this node, to break the cycles that it
+    /// is in, and returns it to be dropped once the node isn't borrowed anymore.
+    fn clear(&mut self) -> Box<dyn Any>;
+}
+
+/// Collects the references to the nodes on the heap that a node holds.
+#[derive(Default)]
+pub struct Tracer {
+    /// The addresses of the nodes that have been referred to, once for each reference.
+    edges: Vec<usize>,
+}
+
+impl Tracer {
+    /// Records a reference to `node`.
+    pub fn node<T>(&mut self, node: &Rc<RefCell<T>>) {
+        self.edges.push(address(node));
+    }
+
+    /// Traces the value behind a reference that isn't to a node with `trace`, if the reference is the
+    /// only one to it. The references in a value that is shared could be held by something outside of
+    /// the heap, so they count as such.
+    pub fn shared<T: ?Sized>(&mut self, value: &Rc<T>, trace: impl FnOnce(&T, &mut Self)) {
+        if Rc::strong_count(value) == 1 {
+            trace(value, self);
+        }
+    }
+}
+
+/// Statistics about the heap of the current thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    /// How many nodes are on the heap, as of the last collection or allocation.
+    pub live: usize,
+
+    /// How many collections have been run.
+    pub collections: u64,
+
+    /// How many nodes collections have freed in total.
+    pub freed: u64,
+}
+
+/// Allocates `value` as a node on the heap of the current thread. If enough nodes have been allocated
+/// since the last collection, garbage is collected first.
+pub fn alloc<T: Node>(value: T) -> Rc<RefCell<T>> {
+    let due = HEAP.with(|heap| {
+        let heap = heap.borrow();
+        heap.nodes.len() >= heap.threshold
+    });
+
+    if due {
+        collect();
+    }
+
+    let node = Rc::new(RefCell::new(value));
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.nodes.push(Box::new(Rc::downgrade(&node)));
+        heap.stats.live = heap.nodes.len();
+    });
+
+    node
+}
+
+/// Collects the garbage on the heap of the current thread, returning how many nodes were freed. This
+/// is safe to call at any time, even while code is running, as whatever the code is using is
+/// referred to from outside of the heap.
+pub fn collect() -> usize {
+    let garbage = HEAP.with(|heap| heap.borrow_mut().sweep());
+    let freed = garbage.len();
+
+    // The garbage is dropped once the heap isn't borrowed anymore, and only then are the nodes freed.
+    drop(garbage);
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.prune();
+        heap.threshold = MIN_THRESHOLD.max(heap.nodes.len() * 2);
+    });
+
+    freed
+}
+
+/// Returns the statistics of the heap of the current thread.
+pub fn stats() -> Stats {
+    HEAP.with(|heap| heap.borrow().stats)
+}
+
+/// The nodes that have been allocated on a thread, and when to collect them.
+struct Heap {
+    /// Weak references to the nodes, some of which may have been freed since they were last pruned.
+    nodes: Vec<Box<dyn Entry>>,
+
+    /// How many nodes there can be before the next collection.
+    threshold: usize,
+
+    /// The statistics of the heap.
+    stats: Stats,
+}
+
+impl Heap {
+    /// Creates an empty heap.
+    fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            threshold: MIN_THRESHOLD,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Forgets the nodes that have been freed.
+    fn prune(&mut self) {
+        self.nodes.retain(|node| node.strong_count() > 0);
+        self.stats.live = self.nodes.len();
+    }
+
+    /// Marks the nodes that the roots lead to, and clears out the rest, returning what was taken out of
+    /// them.
+    fn sweep(&mut self) -> Vec<Box<dyn Any>> {
+        self.prune();
+
+        let indices = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.address(), index))
+            .collect::<HashMap<_, _>>();
+
+        let counts = self
+            .nodes
+            .iter()
+            .map(|node| node.strong_count())
+            .collect::<Vec<_>>();
+
+        // The references that the nodes hold to each other. A node that is borrowed can't be traced,
+        // so it is a root, and the references that it holds count as coming from outside of the heap.
+        let mut internal = vec![0; self.nodes.len()];
+        let mut roots = Vec::new();
+
+        let edges = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| {
+                let mut tracer = Tracer::default();
+
+                if !node.trace(&mut tracer) {
+                    roots.push(index);
+                }
+
+                let edges = tracer
+                    .edges
+                    .into_iter()
+                    .filter_map(|address| indices.get(&address).copied())
+                    .collect::<Vec<_>>();
+
+                for &edge in &edges {
+                    internal[edge] += 1;
+                }
+
+                edges
+            })
+            .collect::<Vec<_>>();
+
+        roots.extend((0..self.nodes.len()).filter(|&index| counts[index] > internal[index]));
+
+        let mut marked = vec![false; self.nodes.len()];
+
+        while let Some(index) = roots.pop() {
+            if !marked[index] {
+                marked[index] = true;
+                roots.extend(&edges[index]);
+            }
+        }
+
+        let garbage = self
+            .nodes
+            .iter()
+            .zip(marked)
+            .filter(|(_, marked)| !marked)
+            .filter_map(|(node, _)| node.clear())
+            .collect::<Vec<_>>();
+
+        self.stats.collections += 1;
+        self.stats.freed += garbage.len() as u64;
+
+        garbage
+    }
+}
+
+/// A node on the heap, as far as the collector is concerned.
+trait Entry {
+    /// Returns the address of the node, which is what references to it are recorded as.
+    fn address(&self) -> usize;
+
+    /// Returns how many references there are to the node, or 0 if it has been freed.
+    fn strong_count(&self) -> usize;
+
+    /// Traces the references that the node holds, returning whether it could be, i.e. whether it has
+    /// been freed or isn't borrowed.
+    fn trace(&self, tracer: &mut Tracer) -> bool;
+
+    /// Clears out the node (see `Node::clear`), unless it is borrowed.
+    fn clear(&self) -> Option<Box<dyn Any>>;
+}
+
+impl<T: Node> Entry for Weak<RefCell<T>> {
+    fn address(&self) -> usize {
+        self.as_ptr() as *const () as usize
+    }
+
+    fn strong_count(&self) -> usize {
+        Weak::strong_count(self)
+    }
+
+    fn trace(&self, tracer: &mut Tracer) -> bool {
+        match self.upgrade() {
+            Some(node) => match node.try_borrow() {
+                Ok(node) => {
+                    node.trace(tracer);
+                    true
+                }
+                Err(_) => false,
+            },
+            None => true,
+        }
+    }
+
+    fn clear(&self) -> Option<Box<dyn Any>> {
+        let node = self.upgrade()?;
+        let garbage = node.try_borrow_mut().ok()?.clear();
+
+        Some(garbage)
+    }
+}
+
+/// Returns the address of `node`, which is what references to it are recorded as.
+fn address<T>(node: &Rc<RefCell<T>>) -> usize {
+    Rc::as_ptr(node) as *const () as usize
+}
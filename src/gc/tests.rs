@@ -0,0 +1,115 @@
+#![cfg(test)]
+
+use super::{collect, stats};
+use crate::{
+    ast::Program, evaluator::Evaluator, lexer::Lexer, object::Object, parser::Parser, vm::Vm,
+};
+
+/// Parses the input source code
+fn parse(input: &str) -> Program {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    parser.parse_program().unwrap()
+}
+
+/// Runs the input source code on both engines, expecting it to succeed, and returns its values as
+/// strings
+fn run(input: &str) -> [String; 2] {
+    let show = |result: Result<Option<Object>, _>| match result {
+        Ok(Some(object)) => object.to_string(),
+        Ok(None) => panic!("no value for {}", input),
+        Err(error) => panic!("running {} failed: {}", input, error),
+    };
+
+    [
+        show(Evaluator::new().run(parse(input))),
+        show(Vm::new().run(parse(input))),
+    ]
+}
+
+/// Tests that the cycles between closures and the environments that they capture are freed
+#[test]
+fn test_gc_cycles() {
+    let input = "
+        let make = fn() { let f = fn() { f }; f };
+        for (i in 0..1000) { make(); }
+        fn count() { for (i in 0..10) { yield i; } }
+        let counter = fn() { let it = count(); next(it); it };
+        for (i in 0..1000) { counter(); }
+        1
+    ";
+
+    collect();
+    let before = stats();
+
+    let mut evaluator = Evaluator::new();
+    evaluator.run(parse(input)).unwrap();
+
+    let mut vm = Vm::new();
+    vm.run(parse(input)).unwrap();
+
+    // Each engine leaves a thousand environments (or, on the VM, captured variables) in cycles with
+    // the closures that they bind, which collecting frees, whether that happened while the code was
+    // running or happens now.
+    collect();
+    let after = stats();
+
+    assert!(after.freed - before.freed >= 2_000);
+    assert!(after.live < before.live + 100);
+
+    // Once the engines are gone, nothing is left of them, even though their global environments are
+    // in cycles with the functions that they bind.
+    drop((evaluator, vm));
+    collect();
+
+    assert_eq!(stats().live, before.live);
+}
+
+/// Tests that memory usage holds steady across many runs on many engines
+#[test]
+fn test_gc_steady() {
+    let input = "let make = fn(n) { let f = fn() { [f, n] }; f }; make(1)()[1]";
+
+    collect();
+    let before = stats().live;
+
+    for _ in 0..20 {
+        assert_eq!(run(input), ["1", "1"]);
+    }
+
+    collect();
+
+    assert_eq!(stats().live, before);
+}
+
+/// Tests that collecting while code is running keeps everything that the code is still using, even
+/// what only the engine's stack refers to
+#[test]
+fn test_gc_running() {
+    let tests = vec![
+        (
+            "let make = fn() { let y = 1; fn() { y } }; [make(), gc()][0]()",
+            "1",
+        ),
+        (
+            "let make = fn(x) { fn() { x } }; let f = make(make(2)); gc(); f()()",
+            "2",
+        ),
+        (
+            "fn xs() { let a = [1, 2, 3]; for (x in a) { yield x; } } let it = xs(); next(it); gc(); next(it)[\"value\"]",
+            "2",
+        ),
+        (
+            "fn f(n) { if (n == 0) { gc(); 0 } else { let g = fn() { n }; g() + f(n - 1) } } f(10)",
+            "55",
+        ),
+        (
+            "try { throw fn() { gc()[\"collections\"] > 0 }; } catch (e) { e.value() }",
+            "true",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(run(input), [expected, expected], "input: {}", input);
+    }
+}
@@ -1,6 +1,6 @@
 mod tests;
 
-use crate::token::{Token, TokenType};
+use crate::token::{Comment, Position, Token, TokenType};
 
 // The `Lexer` struct preforms lexical analysis on the input string, and returns
 // tokens that can be used by the parser.
@@ -20,18 +20,28 @@ pub struct Lexer {
     /// remove this field and just use `current_position + 1`, I decided to keep it because it
     /// 1) allows for us to "prime" the lexer without having complicated code, and 2) makes
     /// it easier to understand what is going on.
+    #[allow(clippy::doc_lazy_continuation)]
     next_position: usize,
 
     /// The current character we are lexing in the input string. This is used as a "storage
     /// space" to keep the current character in, so that we don't have to do annoying things to
     /// get the current character from the input string.
     ch: char,
+
+    /// The line and column of the current character, so that tokens can remember where they are
+    /// in the source code.
+    position: Position,
+
+    /// The comments that have been skipped over so far, in the order that they are in.
+    comments: Vec<Comment>,
 }
 
 /// Public API for the `Lexer` struct.
 impl Lexer {
     /// Creates a new `Lexer` from the given input string. This also "primes" the lexer by calling
     /// `read_char` once, so that the `ch` field is set to the first character in the input string.
+    /// If the input starts with a `#!` line, so that a script can be run directly, that line is
+    /// skipped.
     pub fn new(input: &str) -> Self {
         let mut lexer = Self {
             // Convert the input string to a `Vec<char>` so that we can easily get characters.
@@ -47,11 +57,24 @@ impl Lexer {
             // `ch` is set to `\0` because we don't know what the next character is. This will be
             // set to said character when `read_char` is called.
             ch: '\0',
+
+            // The first character in the input string is on the first line, in the first column.
+            position: Position::default(),
+
+            comments: Vec::new(),
         };
 
         // "prime" the lexer by calling `read_char` once
         lexer.read_char();
 
+        // Skip the shebang line, up to (but not including) the newline, so that the positions of the
+        // tokens after it are still counted from the start of the file.
+        if lexer.ch == '#' && lexer.peek_char() == '!' {
+            while lexer.ch != '\n' && lexer.ch != '\0' {
+                lexer.read_char();
+            }
+        }
+
         lexer
     }
 
@@ -60,7 +83,16 @@ impl Lexer {
         // Skip whitespace characters.
         self.skip_whitespace();
 
+        // Remember where the token starts, as lexing the token moves the position along.
+        let position = self.position;
+
         let token = match self.ch {
+            '=' if self.peek_char() == '>' => {
+                // Skip over the `>`, the `=` is skipped at the end of the function.
+                self.read_char();
+
+                Token::new(TokenType::Arrow, "=>")
+            }
             '=' => self.make_two_char_token('=', TokenType::Assign, TokenType::Eq),
             '+' => Token::new(TokenType::Plus, self.ch),
             '-' => Token::new(TokenType::Minus, self.ch),
@@ -69,12 +101,31 @@ impl Lexer {
             '/' => Token::new(TokenType::Slash, self.ch),
             '<' => Token::new(TokenType::Lt, self.ch),
             '>' => Token::new(TokenType::Gt, self.ch),
+            '|' => self.make_two_char_token('>', TokenType::Bar, TokenType::Pipe),
             ',' => Token::new(TokenType::Comma, self.ch),
             ';' => Token::new(TokenType::Semicolon, self.ch),
+            ':' => Token::new(TokenType::Colon, self.ch),
+            '"' => self.read_string(),
             '(' => Token::new(TokenType::LParen, self.ch),
             ')' => Token::new(TokenType::RParen, self.ch),
             '{' => Token::new(TokenType::LBrace, self.ch),
             '}' => Token::new(TokenType::RBrace, self.ch),
+            '[' => Token::new(TokenType::LBracket, self.ch),
+            ']' => Token::new(TokenType::RBracket, self.ch),
+            '.' if self.peek_char() == '.' && self.peek_nth_char(2) == '.' => {
+                // Skip over the second and third dots, the first is skipped at the end of the function.
+                self.read_char();
+                self.read_char();
+
+                Token::new(TokenType::Ellipsis, "...")
+            }
+            '.' if self.peek_char() == '.' => {
+                // Skip over the second dot, the first is skipped at the end of the function.
+                self.read_char();
+
+                Token::new(TokenType::DotDot, "..")
+            }
+            '.' => Token::new(TokenType::Dot, self.ch),
             '\0' => Token::new(TokenType::EOF, "".to_string()),
 
             // The nice thing about rust is that we can match only if the character satisfies
@@ -82,11 +133,15 @@ impl Lexer {
             // a letter or an underscore. Returning here because we don't need to call `read_char`
             // again, as we already did that in the `read_identifier` function, at the end of the
             // loop.
-            s if s.is_alphabetic() || s == '_' => return Token::from_ident(self.read_identifier()),
+            s if s.is_alphabetic() || s == '_' => {
+                return Token::from_ident(self.read_identifier()).at(position)
+            }
 
             // Parse integers. Returning here because we don't need to call `read_char` again, as we
             // already did that in the `read_number` function, at the end of the loop.
-            s if s.is_ascii_digit() => return Token::new(TokenType::Int, self.read_number()),
+            s if s.is_ascii_digit() => {
+                return Token::new(TokenType::Int, self.read_number()).at(position)
+            }
 
             _ => Token::new(TokenType::Illegal, self.ch),
         };
@@ -94,7 +149,13 @@ impl Lexer {
         // Update the lexer's state to the next character in the input string.
         self.read_char();
 
-        token
+        token.at(position)
+    }
+
+    /// Returns the comments that have been skipped over so far. Once the end of the input has been
+    /// reached, these are all of the comments in it.
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
     }
 }
 
@@ -109,6 +170,17 @@ impl Lexer {
         // Bounds checking.
         // We don't need to update`next_position` here, because
         // we are at the end of the input string.
+        // Move the position along to the next character, unless we are "priming" the lexer or are
+        // already at the end of the input string (in which case we aren't moving anywhere).
+        if self.next_position != self.current_position {
+            if self.ch == '\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else {
+                self.position.column += 1;
+            }
+        }
+
         if self.next_position >= self.input.len() {
             self.ch = '\0';
 
@@ -139,20 +211,56 @@ impl Lexer {
     /// will return the same token. Note that if we are at the end of the input string, this
     /// function will return `\0`.
     fn peek_char(&self) -> char {
+        self.peek_nth_char(1)
+    }
+
+    /// Peeks at the character `n` characters after the current character, and returns it. This is
+    /// like `peek_char` (which is the same as `peek_nth_char(1)`), but for tokens that are longer
+    /// than two characters, such as `...`. Returns `\0` if that is past the end of the input string.
+    fn peek_nth_char(&self, n: usize) -> char {
         // Bounds checking.
-        if self.next_position >= self.input.len() {
+        if self.current_position + n >= self.input.len() {
             '\0'
         } else {
-            self.input[self.next_position]
+            self.input[self.current_position + n]
         }
     }
 
-    /// Skips whitespace characters from the input string. This is used when we encounter a
-    /// whitespace character, because that means we are lexing whitespace.
+    /// Skips whitespace characters and comments from the input string. This is used when we encounter a
+    /// whitespace character, because that means we are lexing whitespace. Comments are remembered, so
+    /// that they can be returned by `comments`.
     fn skip_whitespace(&mut self) {
-        while self.ch.is_whitespace() {
+        loop {
+            while self.ch.is_whitespace() {
+                self.read_char();
+            }
+
+            if self.ch != '/' || self.peek_char() != '/' {
+                return;
+            }
+
+            self.read_comment();
+        }
+    }
+
+    /// Reads a comment from the input string, up to (but not including) the newline that ends it. It
+    /// expects that `ch` is the first `/` of the comment.
+    fn read_comment(&mut self) {
+        let position = self.position;
+        let start = self.current_position;
+
+        while self.ch != '\n' && self.ch != '\0' {
             self.read_char();
         }
+
+        let text = self.input[start..self.current_position]
+            .iter()
+            .collect::<String>();
+
+        self.comments.push(Comment {
+            text: text.trim_end().to_string(),
+            position,
+        });
     }
 
     /// Reads an identifier from the input string, and returns it as a `String`. This is used
@@ -189,6 +297,34 @@ impl Lexer {
         self.input[position..self.current_position].iter().collect()
     }
 
+    /// Reads a string literal from the input string, and returns it as a `Token`. The literal value
+    /// of the token is the contents of the string, without the quotes. It expects that `ch` is the
+    /// opening `"`, and leaves `ch` at the closing `"`. If the string is never closed, this returns
+    /// an illegal token with the rest of the input as its literal value.
+    fn read_string(&mut self) -> Token {
+        // Get the position of the first character in the string, after the opening quote.
+        let position = self.current_position + 1;
+
+        // Keep reading characters until we encounter the closing quote, or the end of the input.
+        loop {
+            self.read_char();
+
+            if self.ch == '"' || self.ch == '\0' {
+                break;
+            }
+        }
+
+        let literal = self.input[position..self.current_position]
+            .iter()
+            .collect::<String>();
+
+        if self.ch == '"' {
+            Token::new(TokenType::String, literal)
+        } else {
+            Token::new(TokenType::Illegal, format!("\"{}", literal))
+        }
+    }
+
     /// Abstraction for creating a new `Token` based on a two-character token. This is used
     /// when we encounter a character that could be the start of a two-character token, such
     /// as `==`. It expects that `ch` is the first character in the two-character token.
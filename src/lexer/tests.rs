@@ -1,11 +1,11 @@
 #![cfg(test)]
 
 use super::Lexer;
-use crate::token::{Token, TokenType};
+use crate::token::{Comment, Position, Token, TokenType};
 
 #[test]
 fn next_token() {
-    let input = "let five = 5;
+    let input = r#"let five = 5;
     let ten = 10;
 
     let add = fn(x, y) {
@@ -24,7 +24,18 @@ fn next_token() {
     
     10 == 10;
     10 != 9;
-    ";
+    [1, ...xs];
+    "foobar";
+    "foo bar";
+    {"foo": "bar"};
+    match (x) { _ => 1 };
+    x |> f(|y| y);
+    macro(x) { x };
+    import "m" as m; export m.x;
+    struct P { x }
+    try { throw e; } catch (e) {} finally {}
+    for (x in 0..n) { yield x; }
+    "#;
 
     let tests = vec![
         Token::new(TokenType::Let, "let"),
@@ -100,6 +111,94 @@ fn next_token() {
         Token::new(TokenType::NotEq, "!="),
         Token::new(TokenType::Int, "9"),
         Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::LBracket, "["),
+        Token::new(TokenType::Int, "1"),
+        Token::new(TokenType::Comma, ","),
+        Token::new(TokenType::Ellipsis, "..."),
+        Token::new(TokenType::Ident, "xs"),
+        Token::new(TokenType::RBracket, "]"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::String, "foobar"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::String, "foo bar"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::LBrace, "{"),
+        Token::new(TokenType::String, "foo"),
+        Token::new(TokenType::Colon, ":"),
+        Token::new(TokenType::String, "bar"),
+        Token::new(TokenType::RBrace, "}"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::Match, "match"),
+        Token::new(TokenType::LParen, "("),
+        Token::new(TokenType::Ident, "x"),
+        Token::new(TokenType::RParen, ")"),
+        Token::new(TokenType::LBrace, "{"),
+        Token::new(TokenType::Ident, "_"),
+        Token::new(TokenType::Arrow, "=>"),
+        Token::new(TokenType::Int, "1"),
+        Token::new(TokenType::RBrace, "}"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::Ident, "x"),
+        Token::new(TokenType::Pipe, "|>"),
+        Token::new(TokenType::Ident, "f"),
+        Token::new(TokenType::LParen, "("),
+        Token::new(TokenType::Bar, "|"),
+        Token::new(TokenType::Ident, "y"),
+        Token::new(TokenType::Bar, "|"),
+        Token::new(TokenType::Ident, "y"),
+        Token::new(TokenType::RParen, ")"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::Macro, "macro"),
+        Token::new(TokenType::LParen, "("),
+        Token::new(TokenType::Ident, "x"),
+        Token::new(TokenType::RParen, ")"),
+        Token::new(TokenType::LBrace, "{"),
+        Token::new(TokenType::Ident, "x"),
+        Token::new(TokenType::RBrace, "}"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::Import, "import"),
+        Token::new(TokenType::String, "m"),
+        Token::new(TokenType::As, "as"),
+        Token::new(TokenType::Ident, "m"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::Export, "export"),
+        Token::new(TokenType::Ident, "m"),
+        Token::new(TokenType::Dot, "."),
+        Token::new(TokenType::Ident, "x"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::Struct, "struct"),
+        Token::new(TokenType::Ident, "P"),
+        Token::new(TokenType::LBrace, "{"),
+        Token::new(TokenType::Ident, "x"),
+        Token::new(TokenType::RBrace, "}"),
+        Token::new(TokenType::Try, "try"),
+        Token::new(TokenType::LBrace, "{"),
+        Token::new(TokenType::Throw, "throw"),
+        Token::new(TokenType::Ident, "e"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::RBrace, "}"),
+        Token::new(TokenType::Catch, "catch"),
+        Token::new(TokenType::LParen, "("),
+        Token::new(TokenType::Ident, "e"),
+        Token::new(TokenType::RParen, ")"),
+        Token::new(TokenType::LBrace, "{"),
+        Token::new(TokenType::RBrace, "}"),
+        Token::new(TokenType::Finally, "finally"),
+        Token::new(TokenType::LBrace, "{"),
+        Token::new(TokenType::RBrace, "}"),
+        Token::new(TokenType::For, "for"),
+        Token::new(TokenType::LParen, "("),
+        Token::new(TokenType::Ident, "x"),
+        Token::new(TokenType::In, "in"),
+        Token::new(TokenType::Int, "0"),
+        Token::new(TokenType::DotDot, ".."),
+        Token::new(TokenType::Ident, "n"),
+        Token::new(TokenType::RParen, ")"),
+        Token::new(TokenType::LBrace, "{"),
+        Token::new(TokenType::Yield, "yield"),
+        Token::new(TokenType::Ident, "x"),
+        Token::new(TokenType::Semicolon, ";"),
+        Token::new(TokenType::RBrace, "}"),
         Token::new(TokenType::EOF, ""),
     ];
 
@@ -113,3 +212,129 @@ fn next_token() {
         assert_eq!(token, *tt, "tests[{}] failed - literal wrong.", i);
     }
 }
+
+#[test]
+fn token_positions() {
+    let input = "let x = 5;\n  x + \"a b\"\n";
+
+    let tests = vec![
+        (TokenType::Let, 1, 1),
+        (TokenType::Ident, 1, 5),
+        (TokenType::Assign, 1, 7),
+        (TokenType::Int, 1, 9),
+        (TokenType::Semicolon, 1, 10),
+        (TokenType::Ident, 2, 3),
+        (TokenType::Plus, 2, 5),
+        (TokenType::String, 2, 7),
+        (TokenType::EOF, 3, 1),
+    ];
+
+    let mut lexer = Lexer::new(input);
+
+    for (i, (r#type, line, column)) in tests.into_iter().enumerate() {
+        let token = lexer.next_token();
+
+        assert_eq!(token.r#type, r#type, "tests[{}] failed - type wrong.", i);
+
+        assert_eq!(
+            token.position,
+            Position::new(line, column),
+            "tests[{}] failed - position wrong.",
+            i
+        );
+    }
+}
+
+#[test]
+fn unterminated_string() {
+    let mut lexer = Lexer::new("\"foo");
+
+    assert_eq!(lexer.next_token(), Token::new(TokenType::Illegal, "\"foo"));
+    assert_eq!(lexer.next_token(), Token::new(TokenType::EOF, ""));
+}
+
+#[test]
+fn shebang() {
+    let tests = vec![
+        (
+            "#!/usr/bin/env monkey\nlet x",
+            vec![(TokenType::Let, 2, 1), (TokenType::Ident, 2, 5)],
+        ),
+        ("#!monkey", vec![]),
+        // Only the first line can be a shebang.
+        (
+            "x\n#!y",
+            vec![
+                (TokenType::Ident, 1, 1),
+                (TokenType::Illegal, 2, 1),
+                (TokenType::Bang, 2, 2),
+                (TokenType::Ident, 2, 3),
+            ],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut lexer = Lexer::new(input);
+
+        for (r#type, line, column) in expected {
+            let token = lexer.next_token();
+
+            assert_eq!(token.r#type, r#type, "input: {:?}", input);
+            assert_eq!(
+                token.position,
+                Position::new(line, column),
+                "input: {:?}",
+                input
+            );
+        }
+
+        assert_eq!(
+            lexer.next_token().r#type,
+            TokenType::EOF,
+            "input: {:?}",
+            input
+        );
+    }
+}
+
+#[test]
+fn comments() {
+    let input = "// leading\nlet x = 1; // trailing  \n  x / 2 //y\n//\n// last";
+
+    let mut lexer = Lexer::new(input);
+    let tokens = std::iter::from_fn(|| Some(lexer.next_token()))
+        .take_while(|token| token.r#type != TokenType::EOF)
+        .map(|token| (token.r#type, token.position))
+        .collect::<Vec<_>>();
+
+    // Comments are skipped like whitespace, and `x / 2` is still a division.
+    assert_eq!(
+        tokens,
+        vec![
+            (TokenType::Let, Position::new(2, 1)),
+            (TokenType::Ident, Position::new(2, 5)),
+            (TokenType::Assign, Position::new(2, 7)),
+            (TokenType::Int, Position::new(2, 9)),
+            (TokenType::Semicolon, Position::new(2, 10)),
+            (TokenType::Ident, Position::new(3, 3)),
+            (TokenType::Slash, Position::new(3, 5)),
+            (TokenType::Int, Position::new(3, 7)),
+        ]
+    );
+
+    let comment = |text: &str, line, column| Comment {
+        text: text.to_string(),
+        position: Position::new(line, column),
+    };
+
+    assert_eq!(
+        lexer.comments(),
+        [
+            comment("// leading", 1, 1),
+            comment("// trailing", 2, 12),
+            comment("//y", 3, 9),
+            comment("//", 4, 1),
+            comment("// last", 5, 1),
+        ]
+    );
+}
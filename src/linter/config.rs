@@ -0,0 +1,101 @@
+use super::Rule;
+use std::collections::HashSet;
+
+/// The name of the file that the linter is configured with, which is looked for in the directory that
+/// `monkey lint` is run in.
+pub const CONFIG_FILE: &str = "monkey.toml";
+
+/// Which rules the linter uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// The rules that are on.
+    enabled: HashSet<Rule>,
+}
+
+impl Default for Config {
+    /// Returns a config with the rules that are on by default.
+    fn default() -> Self {
+        Self {
+            enabled: Rule::ALL
+                .into_iter()
+                .filter(|rule| rule.is_enabled_by_default())
+                .collect(),
+        }
+    }
+}
+
+impl Config {
+    /// Returns whether `rule` is on.
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        self.enabled.contains(&rule)
+    }
+
+    /// Turns `rule` on or off.
+    pub fn set(&mut self, rule: Rule, enabled: bool) {
+        match enabled {
+            true => self.enabled.insert(rule),
+            false => self.enabled.remove(&rule),
+        };
+    }
+
+    /// Parses a config from the source of a `monkey.toml`. This only understands the little bit of
+    /// TOML that the config needs: a `[lint]` table with a boolean for each rule that is turned on or
+    /// off, along with comments and blank lines. Any other tables are skipped, so that the file can
+    /// configure other things too.
+    ///
+    /// ```toml
+    /// [lint]
+    /// shadowed-name = true
+    /// unused-parameter = false
+    /// ```
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut in_lint = false;
+
+        for (i, line) in source.lines().enumerate() {
+            let error = |message: String| format!("line {}: {}", i + 1, message);
+
+            // None of the lines that are read have a `#` in a string, so a `#` always starts a comment.
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(table) = line.strip_prefix('[') {
+                let table = table
+                    .strip_suffix(']')
+                    .ok_or_else(|| error(format!("expected ] after [{}", table)))?;
+
+                in_lint = table.trim() == "lint";
+                continue;
+            }
+
+            if !in_lint {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error(format!("expected <rule> = <true|false>, got {}", line)))?;
+
+            let key = key.trim();
+            let key = key
+                .strip_prefix('"')
+                .and_then(|key| key.strip_suffix('"'))
+                .unwrap_or(key);
+
+            let rule = key.parse().map_err(error)?;
+
+            let enabled = match value.trim() {
+                "true" => true,
+                "false" => false,
+                value => return Err(error(format!("expected true or false, got {}", value))),
+            };
+
+            config.set(rule, enabled);
+        }
+
+        Ok(config)
+    }
+}
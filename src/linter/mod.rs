@@ -0,0 +1,184 @@
+pub mod config;
+mod rules;
+pub mod span;
+pub mod tests;
+
+pub use self::{config::Config, span::Span};
+use self::{rules::Linter, span::Spans};
+use crate::{
+    ast::Program,
+    lexer::Lexer,
+    parser::{errors::Error, Parser},
+    token::Comment,
+};
+use std::{fmt::Display, str::FromStr};
+
+/// The rules that code is linted with. Each of them can be turned on or off on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rule {
+    /// A `let` binding that is never used, e.g. the `x` in `let x = 1;` if nothing uses `x`.
+    UnusedVariable,
+
+    /// A function parameter that is never used.
+    UnusedParameter,
+
+    /// A binding that shadows one with the same name in a scope around it, e.g. a parameter with the
+    /// same name as a global. Binding a name again in the same scope is how values are updated in
+    /// Monkey, so that doesn't count.
+    ShadowedName,
+
+    /// Statements after a `return` or `throw`, which never run.
+    UnreachableCode,
+
+    /// An `if` condition that is always true or always false, e.g. `if (true) { ... }`.
+    ConstantCondition,
+
+    /// A comparison of something with itself, e.g. `x == x`, which always has the same result.
+    SelfComparison,
+
+    /// A double negation, e.g. `!!x`.
+    DoubleNegation,
+}
+
+impl Rule {
+    /// Every rule, in the order that they are documented in.
+    pub const ALL: [Rule; 7] = [
+        Rule::UnusedVariable,
+        Rule::UnusedParameter,
+        Rule::ShadowedName,
+        Rule::UnreachableCode,
+        Rule::ConstantCondition,
+        Rule::SelfComparison,
+        Rule::DoubleNegation,
+    ];
+
+    /// Returns the ID of the rule, which is how it is named in configs, suppression comments and
+    /// findings.
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::UnusedVariable => "unused-variable",
+            Rule::UnusedParameter => "unused-parameter",
+            Rule::ShadowedName => "shadowed-name",
+            Rule::UnreachableCode => "unreachable-code",
+            Rule::ConstantCondition => "constant-condition",
+            Rule::SelfComparison => "self-comparison",
+            Rule::DoubleNegation => "double-negation",
+        }
+    }
+
+    /// Returns whether the rule is on when a config doesn't say. Shadowing is common in code that
+    /// destructures its arguments, e.g. `[x, ...xs]` in a function that takes `xs`, so it is off.
+    pub fn is_enabled_by_default(self) -> bool {
+        self != Rule::ShadowedName
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Rule::ALL
+            .into_iter()
+            .find(|rule| rule.id() == id)
+            .ok_or_else(|| format!("unknown rule: {:?}", id))
+    }
+}
+
+/// Something that a rule found in the code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The rule that found it.
+    pub rule: Rule,
+
+    /// Where it is in the source code.
+    pub span: Span,
+
+    /// What is wrong.
+    pub message: String,
+
+    /// How it could be fixed, if there is an obvious way to.
+    pub fix: Option<Fix>,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} [{}]", self.span.start, self.message, self.rule)
+    }
+}
+
+/// A change to the source code that fixes a finding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// What the change does, e.g. "remove it".
+    pub message: String,
+
+    /// The source code that is replaced.
+    pub span: Span,
+
+    /// What it is replaced with.
+    pub replacement: String,
+}
+
+/// Parses `source`, and lints it with the rules that `config` turns on. Findings that are suppressed by
+/// comments in the source code are left out, and the rest are sorted by where they are.
+///
+/// A comment like `// lint-allow: unused-variable, self-comparison` suppresses the findings of those
+/// rules on its line, or on the line after it if it is on a line of its own.
+pub fn lint(source: &str, config: &Config) -> Result<Vec<Finding>, Vec<Error>> {
+    let program = Parser::new(Lexer::new(source)).parse_program()?;
+
+    Ok(lint_program(&program, source, config))
+}
+
+/// Lints `program`, which was parsed from `source`, like `lint`.
+pub fn lint_program(program: &Program, source: &str, config: &Config) -> Vec<Finding> {
+    let spans = Spans::new(source);
+    let suppressions = suppressions(&spans.comments, source);
+
+    let mut findings = Linter::new(&spans).lint(program);
+
+    findings.retain(|finding| {
+        config.is_enabled(finding.rule)
+            && !suppressions
+                .iter()
+                .any(|(line, rule)| *line == finding.span.start.line && *rule == finding.rule)
+    });
+
+    findings.sort_by_key(|finding| (finding.span, finding.rule));
+    findings
+}
+
+/// Returns the lines and rules that the suppression comments in `comments` suppress findings of.
+/// Unknown rules in them are ignored.
+fn suppressions(comments: &[Comment], source: &str) -> Vec<(usize, Rule)> {
+    let lines = source.lines().collect::<Vec<_>>();
+    let mut suppressions = Vec::new();
+
+    for comment in comments {
+        let Some(rules) = comment.text[2..].trim().strip_prefix("lint-allow:") else {
+            continue;
+        };
+
+        let on_own_line = lines[comment.position.line - 1]
+            .chars()
+            .take(comment.position.column - 1)
+            .all(char::is_whitespace);
+
+        let line = match on_own_line {
+            true => comment.position.line + 1,
+            false => comment.position.line,
+        };
+
+        for rule in rules.split(',').filter_map(|id| id.trim().parse().ok()) {
+            suppressions.push((line, rule));
+        }
+    }
+
+    suppressions
+}
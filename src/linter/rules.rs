@@ -0,0 +1,607 @@
+use super::{span::Spans, Finding, Fix, Rule, Span};
+use crate::{
+    ast::{
+        expressions::{Expression, FunctionLiteral, Identifier, Parameter},
+        operators::{InfixOperatorType, PrefixOperatorType},
+        patterns::Pattern,
+        statements::Statement,
+        Program,
+    },
+    dump::{ast::ToTree, Format},
+    evaluator::{expressions::is_quote, macros::is_unquote},
+    resolver::{declare, declare_expression, declare_statements},
+    symbol::Symbol,
+    token::Position,
+};
+use std::{collections::HashSet, mem};
+
+/// Goes through a program, keeping track of the scopes that it is in like the resolver does, and finds
+/// what each rule is about. Whether the rules are on is left to the caller.
+pub(super) struct Linter<'a> {
+    /// The spans of the nodes of the program.
+    spans: &'a Spans<'a>,
+
+    /// The scopes that the code being linted is in, from the top level of the program to the innermost
+    /// one.
+    scopes: Vec<Scope>,
+
+    /// What has been found so far.
+    findings: Vec<Finding>,
+
+    /// Whether the code being linted is quoted, i.e. in a call to `quote` but not in a call to
+    /// `unquote` inside of it. Quoted code isn't run where it is, so nothing is found in it.
+    quoted: bool,
+}
+
+/// A scope that is being linted.
+struct Scope {
+    /// The names that are bound anywhere in the scope, like the slots of the resolver's scopes.
+    names: Vec<Symbol>,
+
+    /// The bindings that have been made so far, going through the scope's code in order.
+    bindings: Vec<Binding>,
+
+    /// The names that have been used before they were bound in the scope, from a function that is
+    /// called later on.
+    used_early: HashSet<Symbol>,
+}
+
+/// A name being bound.
+struct Binding {
+    /// The identifier that binds the name.
+    identifier: Identifier,
+
+    /// What kind of binding it is.
+    kind: BindingKind,
+
+    /// Whether the name has been used.
+    used: bool,
+}
+
+/// The kinds of bindings, which decide what is found if a binding is never used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BindingKind {
+    /// A name bound by `let`.
+    Variable,
+
+    /// A parameter of a function.
+    Parameter,
+
+    /// Any other binding, e.g. a function declaration or an export, which doesn't have to be used.
+    Other,
+}
+
+impl<'a> Linter<'a> {
+    /// Creates a new `Linter` for a program with the given spans.
+    pub fn new(spans: &'a Spans<'a>) -> Self {
+        Self {
+            spans,
+            scopes: Vec::new(),
+            findings: Vec::new(),
+            quoted: false,
+        }
+    }
+
+    /// Lints `program`, and returns what was found.
+    pub fn lint(mut self, program: &Program) -> Vec<Finding> {
+        let mut names = Vec::new();
+        declare_statements(&mut names, &program.statements);
+
+        self.scoped(names, |linter| linter.lint_block(&program.statements));
+        self.findings
+    }
+
+    /// Adds a finding, unless the code is quoted.
+    fn report(&mut self, rule: Rule, span: Span, message: String, fix: Option<Fix>) {
+        if !self.quoted {
+            self.findings.push(Finding {
+                rule,
+                span,
+                message,
+                fix,
+            });
+        }
+    }
+
+    /// Runs `lint` in a new scope in which `names` are bound, and then reports the bindings in it that
+    /// were never used.
+    fn scoped(&mut self, names: Vec<Symbol>, lint: impl FnOnce(&mut Self)) {
+        self.scopes.push(Scope {
+            names,
+            bindings: Vec::new(),
+            used_early: HashSet::new(),
+        });
+
+        lint(self);
+
+        let scope = self.scopes.pop().expect("the scope has just been pushed");
+
+        for binding in scope.bindings {
+            let name = &binding.identifier.value;
+
+            if binding.used || name.starts_with('_') {
+                continue;
+            }
+
+            let (rule, message) = match binding.kind {
+                BindingKind::Variable => (Rule::UnusedVariable, format!("{} is never used", name)),
+                BindingKind::Parameter => (
+                    Rule::UnusedParameter,
+                    format!("parameter {} is never used", name),
+                ),
+                BindingKind::Other => continue,
+            };
+
+            let span = self.spans.of(&binding.identifier);
+
+            let fix = Fix {
+                message: format!("rename it to _{} if it is meant to be unused", name),
+                span,
+                replacement: format!("_{}", name),
+            };
+
+            self.report(rule, span, message, Some(fix));
+        }
+    }
+
+    /// Records a use of `identifier`, as the latest binding of its name in the scopes around it.
+    fn use_name(&mut self, identifier: &Identifier) {
+        if self.quoted {
+            return;
+        }
+
+        let name = &identifier.value;
+
+        for scope in self.scopes.iter_mut().rev() {
+            let binding = scope
+                .bindings
+                .iter_mut()
+                .rev()
+                .find(|binding| binding.identifier.value == *name);
+
+            if let Some(binding) = binding {
+                binding.used = true;
+                return;
+            }
+
+            if scope.names.contains(name) {
+                scope.used_early.insert(name.clone());
+                return;
+            }
+        }
+    }
+
+    /// Records `identifier` being bound in the innermost scope, reporting it if it shadows a binding in
+    /// a scope around that.
+    fn bind(&mut self, identifier: &Identifier, kind: BindingKind) {
+        let name = &identifier.value;
+        let (scope, outer) = self
+            .scopes
+            .split_last_mut()
+            .expect("there is always a scope");
+
+        let shadows = !name.starts_with('_')
+            && outer.iter().any(|scope| {
+                scope.names.contains(name)
+                    || scope
+                        .bindings
+                        .iter()
+                        .any(|binding| binding.identifier.value == *name)
+            });
+
+        declare(&mut scope.names, name.clone());
+
+        scope.bindings.push(Binding {
+            identifier: identifier.clone(),
+            kind: match self.quoted {
+                true => BindingKind::Other,
+                false => kind,
+            },
+            used: scope.used_early.remove(name),
+        });
+
+        if shadows {
+            let message = format!("{} shadows a binding in an outer scope", name);
+            self.report(Rule::ShadowedName, self.spans.of(identifier), message, None);
+        }
+    }
+
+    /// Lints the statements of a block, in the scope that it is in. The functions and structs that the
+    /// block declares are bound as soon as it is entered.
+    fn lint_block(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            let statement = match statement {
+                Statement::Export(export) => export.declaration.as_ref(),
+                statement => statement,
+            };
+
+            match statement {
+                Statement::Function(declaration) => {
+                    self.bind(&declaration.name, BindingKind::Other)
+                }
+                Statement::Struct(declaration) => self.bind(&declaration.name, BindingKind::Other),
+                _ => {}
+            }
+        }
+
+        let exit = statements
+            .iter()
+            .position(|statement| matches!(statement, Statement::Return(_) | Statement::Throw(_)));
+
+        if let Some(exit) = exit.filter(|exit| exit + 1 < statements.len()) {
+            let keyword = match statements[exit] {
+                Statement::Return(_) => "return",
+                _ => "throw",
+            };
+
+            let span = self
+                .spans
+                .between(&statements[exit + 1], &statements[statements.len() - 1]);
+
+            let fix = Fix {
+                message: "remove it".to_string(),
+                span,
+                replacement: String::new(),
+            };
+
+            let message = format!("unreachable code after {}", keyword);
+            self.report(Rule::UnreachableCode, span, message, Some(fix));
+        }
+
+        for statement in statements {
+            self.lint_statement(statement, false);
+        }
+    }
+
+    /// Lints a single statement. The names that an `exported` statement binds don't have to be used.
+    fn lint_statement(&mut self, statement: &Statement, exported: bool) {
+        match statement {
+            Statement::Let(let_statement) => {
+                self.lint_expression(&let_statement.value);
+                self.lint_pattern_values(&let_statement.pattern);
+
+                let kind = match exported {
+                    true => BindingKind::Other,
+                    false => BindingKind::Variable,
+                };
+
+                for identifier in let_statement.pattern.identifiers() {
+                    self.bind(identifier, kind);
+                }
+            }
+
+            Statement::Return(return_statement) => {
+                self.lint_expression(&return_statement.return_value)
+            }
+            Statement::Throw(throw) => self.lint_expression(&throw.value),
+            Statement::Yield(yield_statement) => self.lint_expression(&yield_statement.value),
+            Statement::Expression(expression) => self.lint_expression(&expression.expression),
+
+            // Declarations have already been bound when their block was entered.
+            Statement::Function(declaration) => self.lint_function(&declaration.function),
+            Statement::Struct(_) => {}
+
+            Statement::Import(import) => self.bind(&import.alias, BindingKind::Other),
+            Statement::Export(export) => self.lint_statement(&export.declaration, true),
+        }
+    }
+
+    /// Lints an `Expression`.
+    fn lint_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Identifier(identifier) => self.use_name(identifier),
+
+            Expression::Integer(_) | Expression::Boolean(_) | Expression::String(_) => {}
+
+            Expression::Function(function) => self.lint_function(function),
+
+            // Macros are only evaluated while expanding them, so they are left alone, like the
+            // resolver does.
+            Expression::Macro(_) => {}
+
+            Expression::Prefix(prefix) => match prefix.right.as_ref() {
+                Expression::Prefix(inner)
+                    if prefix.operator.r#type == PrefixOperatorType::Bang
+                        && inner.operator.r#type == PrefixOperatorType::Bang =>
+                {
+                    self.report_double_negation(expression, inner.token.position);
+                    self.lint_expression(&inner.right);
+                }
+                right => self.lint_expression(right),
+            },
+
+            Expression::Infix(infix) => {
+                let always = match infix.operator.r#type {
+                    InfixOperatorType::Eq => Some(true),
+                    InfixOperatorType::NotEq | InfixOperatorType::Lt | InfixOperatorType::Gt => {
+                        Some(false)
+                    }
+                    _ => None,
+                };
+
+                if let Some(always) = always {
+                    if is_pure(&infix.left) && is_same(&infix.left, &infix.right) {
+                        let span = self.spans.of(expression);
+
+                        let fix = Fix {
+                            message: format!("replace it with {}", always),
+                            span,
+                            replacement: always.to_string(),
+                        };
+
+                        let message = format!(
+                            "comparing {} with itself is always {}",
+                            self.spans.text(self.spans.of(infix.left.as_ref())),
+                            always
+                        );
+
+                        self.report(Rule::SelfComparison, span, message, Some(fix));
+                    }
+                }
+
+                self.lint_expression(&infix.left);
+                self.lint_expression(&infix.right);
+            }
+
+            Expression::If(if_expression) => {
+                self.lint_condition(&if_expression.condition);
+                self.lint_block(&if_expression.consequence.statements);
+
+                for else_if in &if_expression.else_ifs {
+                    self.lint_condition(&else_if.condition);
+                    self.lint_block(&else_if.consequence.statements);
+                }
+
+                if let Some(alternative) = &if_expression.alternative {
+                    self.lint_block(&alternative.statements);
+                }
+            }
+
+            Expression::Try(try_expression) => {
+                self.lint_block(&try_expression.body.statements);
+
+                if let Some(catch) = &try_expression.catch {
+                    let mut names = vec![catch.parameter.value.clone()];
+                    declare_statements(&mut names, &catch.body.statements);
+
+                    self.scoped(names, |linter| {
+                        linter.bind(&catch.parameter, BindingKind::Other);
+                        linter.lint_block(&catch.body.statements);
+                    });
+                }
+
+                if let Some(finally) = &try_expression.finally {
+                    self.lint_block(&finally.statements);
+                }
+            }
+
+            Expression::For(for_expression) => {
+                self.lint_expression(&for_expression.iterable);
+
+                let mut names = Vec::new();
+
+                for identifier in for_expression.pattern.identifiers() {
+                    declare(&mut names, identifier.value.clone());
+                }
+
+                declare_statements(&mut names, &for_expression.body.statements);
+
+                self.scoped(names, |linter| {
+                    linter.lint_pattern_values(&for_expression.pattern);
+
+                    for identifier in for_expression.pattern.identifiers() {
+                        linter.bind(identifier, BindingKind::Other);
+                    }
+
+                    linter.lint_block(&for_expression.body.statements);
+                });
+            }
+
+            Expression::Match(match_expression) => {
+                self.lint_expression(&match_expression.subject);
+
+                for arm in &match_expression.arms {
+                    self.lint_pattern_values(&arm.pattern);
+
+                    let mut names = Vec::new();
+
+                    for identifier in arm.pattern.identifiers() {
+                        declare(&mut names, identifier.value.clone());
+                    }
+
+                    if let Some(guard) = &arm.guard {
+                        declare_expression(&mut names, guard);
+                    }
+
+                    declare_expression(&mut names, &arm.body);
+
+                    self.scoped(names, |linter| {
+                        for identifier in arm.pattern.identifiers() {
+                            linter.bind(identifier, BindingKind::Other);
+                        }
+
+                        if let Some(guard) = &arm.guard {
+                            linter.lint_expression(guard);
+                        }
+
+                        linter.lint_expression(&arm.body);
+                    });
+                }
+            }
+
+            Expression::Call(call) => {
+                let quoted = match self.quoted {
+                    true => !is_unquote(call),
+                    false => is_quote(call),
+                };
+
+                let outer = mem::replace(&mut self.quoted, quoted);
+
+                if !is_quote(call) && !is_unquote(call) {
+                    self.lint_expression(&call.function);
+                }
+
+                for argument in &call.arguments {
+                    self.lint_expression(argument);
+                }
+
+                self.quoted = outer;
+            }
+
+            Expression::Array(array) => {
+                for element in &array.elements {
+                    self.lint_expression(element);
+                }
+            }
+
+            Expression::Index(index) => {
+                self.lint_expression(&index.left);
+                self.lint_expression(&index.index);
+            }
+
+            // The member is the name of a field or export, not a variable.
+            Expression::Member(member) => self.lint_expression(&member.object),
+
+            Expression::Spread(spread) => self.lint_expression(&spread.value),
+
+            Expression::Hash(hash) => {
+                for (key, value) in &hash.pairs {
+                    self.lint_expression(key);
+                    self.lint_expression(value);
+                }
+            }
+
+            Expression::Record(record) => {
+                self.use_name(&record.name);
+
+                for (_, value) in &record.fields {
+                    self.lint_expression(value);
+                }
+            }
+        }
+    }
+
+    /// Lints a `FunctionLiteral`, whose body is in a scope of its own with its parameters.
+    fn lint_function(&mut self, function: &FunctionLiteral) {
+        let mut names = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name().value.clone())
+            .collect::<Vec<_>>();
+
+        for parameter in &function.parameters {
+            if let Parameter::Default { value, .. } = parameter {
+                declare_expression(&mut names, value);
+            }
+        }
+
+        declare_statements(&mut names, &function.body.statements);
+
+        self.scoped(names, |linter| {
+            for parameter in &function.parameters {
+                linter.bind(parameter.name(), BindingKind::Parameter);
+            }
+
+            for parameter in &function.parameters {
+                if let Parameter::Default { value, .. } = parameter {
+                    linter.lint_expression(value);
+                }
+            }
+
+            linter.lint_block(&function.body.statements);
+        });
+    }
+
+    /// Lints the condition of an `if` or `else if`, reporting it if it is constant.
+    fn lint_condition(&mut self, condition: &Expression) {
+        if let Some(truthy) = constant_truthiness(condition) {
+            let message = format!("this condition is always {}", truthy);
+            self.report(
+                Rule::ConstantCondition,
+                self.spans.of(condition),
+                message,
+                None,
+            );
+        }
+
+        self.lint_expression(condition);
+    }
+
+    /// Lints the values in `pattern` that are compared to (or looked up in) the value being matched.
+    fn lint_pattern_values(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(_) | Pattern::Wildcard(_) => {}
+
+            Pattern::Literal(literal) => self.lint_expression(literal),
+
+            Pattern::Array(array) => {
+                for element in &array.elements {
+                    self.lint_pattern_values(element);
+                }
+            }
+
+            Pattern::Hash(hash) => {
+                for (key, value) in &hash.pairs {
+                    self.lint_expression(key);
+                    self.lint_pattern_values(value);
+                }
+            }
+        }
+    }
+
+    /// Reports `expression`, which is `!!x`, where the second `!` is at `inner`. The fix is to replace it
+    /// with `x`, which is only the same if `x` is already a boolean.
+    fn report_double_negation(&mut self, expression: &Expression, inner: Position) {
+        let span = self.spans.of(expression);
+        let after = Position::new(inner.line, inner.column + 1);
+        let operand = self.spans.text(Span::new(after, span.end));
+        let operand = operand.trim_start();
+
+        let fix = Fix {
+            message: format!("replace it with {} if that is already a boolean", operand),
+            span,
+            replacement: operand.to_string(),
+        };
+
+        let message = "double negation".to_string();
+        self.report(Rule::DoubleNegation, span, message, Some(fix));
+    }
+}
+
+/// Returns whether evaluating `expression` can't have any effects, and always gives the same value, so
+/// that comparing it with itself always has the same result.
+fn is_pure(expression: &Expression) -> bool {
+    match expression {
+        Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::Boolean(_)
+        | Expression::String(_) => true,
+        Expression::Member(member) => is_pure(&member.object),
+        Expression::Index(index) => is_pure(&index.left) && is_pure(&index.index),
+        Expression::Prefix(prefix) => is_pure(&prefix.right),
+        Expression::Infix(infix) => is_pure(&infix.left) && is_pure(&infix.right),
+        _ => false,
+    }
+}
+
+/// Returns whether `left` and `right` are the same code, wherever they are.
+fn is_same(left: &Expression, right: &Expression) -> bool {
+    left.to_tree().render(Format::Sexpr) == right.to_tree().render(Format::Sexpr)
+}
+
+/// Returns whether `condition` is always truthy or always falsy, if it is either. Only `false` and
+/// `null` are falsy, so every literal but `false` is truthy.
+fn constant_truthiness(condition: &Expression) -> Option<bool> {
+    match condition {
+        Expression::Boolean(boolean) => Some(boolean.value),
+        Expression::Integer(_)
+        | Expression::String(_)
+        | Expression::Array(_)
+        | Expression::Hash(_)
+        | Expression::Function(_) => Some(true),
+        Expression::Prefix(prefix) if prefix.operator.r#type == PrefixOperatorType::Bang => {
+            constant_truthiness(&prefix.right).map(|truthy| !truthy)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{
+    dump::{ast::ToTree, Tree, Value},
+    lexer::Lexer,
+    token::{Comment, Position, Token, TokenType},
+};
+use std::fmt::Display;
+
+/// A stretch of the source code, from `start` up to (but not including) `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Span {
+    /// The position of the first character of the span.
+    pub start: Position,
+
+    /// The position just after the last character of the span.
+    pub end: Position,
+}
+
+impl Span {
+    /// Creates a new `Span` from `start` to `end`.
+    pub fn new(start: Position, end: Position) -> Self {
+        Self { start, end }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Finds the spans of the nodes of a program in its source code. The nodes of the AST only know where
+/// they start (or where their operator is), so a node's span is found from the tokens of the source
+/// code: it runs from its first token to its last, along with any brackets around or after those that
+/// are needed to balance them, e.g. the `)` at the end of a call.
+pub(super) struct Spans<'a> {
+    /// The lines of the source code.
+    lines: Vec<&'a str>,
+
+    /// Every token of the source code, in order, without the EOF.
+    tokens: Vec<Token>,
+
+    /// Every comment in the source code, in order.
+    pub comments: Vec<Comment>,
+}
+
+impl<'a> Spans<'a> {
+    /// Lexes `source`, to find the spans of the nodes of the program that it is parsed into.
+    pub fn new(source: &'a str) -> Self {
+        let mut lexer = Lexer::new(source);
+        let mut tokens = Vec::new();
+
+        loop {
+            let token = lexer.next_token();
+
+            if token.r#type == TokenType::EOF {
+                break;
+            }
+
+            tokens.push(token);
+        }
+
+        Self {
+            lines: source.lines().collect(),
+            tokens,
+            comments: lexer.comments().to_vec(),
+        }
+    }
+
+    /// Returns the span of `node`. A statement's span takes in the `;` after it, if it has one.
+    pub fn of<T: ToTree + ?Sized>(&self, node: &T) -> Span {
+        let tree = node.to_tree();
+        let mut positions = Vec::new();
+        collect_positions(&tree, &mut positions);
+
+        let (Some(&first), Some(&last)) = (positions.iter().min(), positions.iter().max()) else {
+            return Span::new(Position::new(0, 0), Position::new(0, 0));
+        };
+
+        let mut start = self.index(first);
+        let mut end = self.index(last);
+
+        // Each bracket in the tokens that isn't closed (or opened) inside of them is closed after
+        // them (or opened before them).
+        let mut depth = 0;
+        let mut lowest = 0;
+
+        for token in &self.tokens[start..=end] {
+            depth += bracket(token);
+            lowest = lowest.min(depth);
+        }
+
+        let mut unopened = -lowest;
+
+        while unopened > 0 && start > 0 {
+            start -= 1;
+            unopened -= bracket(&self.tokens[start]);
+        }
+
+        let mut unclosed = depth - lowest;
+
+        while unclosed > 0 && end + 1 < self.tokens.len() {
+            end += 1;
+            unclosed += bracket(&self.tokens[end]);
+        }
+
+        if tree.kind.ends_with("Statement")
+            && self
+                .tokens
+                .get(end + 1)
+                .is_some_and(|token| token.r#type == TokenType::Semicolon)
+        {
+            end += 1;
+        }
+
+        Span::new(self.tokens[start].position, end_of(&self.tokens[end]))
+    }
+
+    /// Returns the span from the start of `first` to the end of `last`.
+    pub fn between<T: ToTree + ?Sized>(&self, first: &T, last: &T) -> Span {
+        Span::new(self.of(first).start, self.of(last).end)
+    }
+
+    /// Returns the source code in `span`.
+    pub fn text(&self, span: Span) -> String {
+        let mut text = String::new();
+
+        for line in span.start.line..=span.end.line {
+            let Some(source) = self.lines.get(line - 1) else {
+                break;
+            };
+
+            let from = if line == span.start.line {
+                span.start.column - 1
+            } else {
+                text.push('\n');
+                0
+            };
+
+            let to = if line == span.end.line {
+                span.end.column - 1
+            } else {
+                usize::MAX
+            };
+
+            text.extend(source.chars().take(to).skip(from));
+        }
+
+        text
+    }
+
+    /// Returns the index of the token at `position`, or of the first token after it if there isn't one.
+    fn index(&self, position: Position) -> usize {
+        self.tokens
+            .partition_point(|token| token.position < position)
+            .min(self.tokens.len().saturating_sub(1))
+    }
+}
+
+/// Returns the position just after the end of `token`.
+fn end_of(token: &Token) -> Position {
+    let text = match token.r#type {
+        TokenType::String => format!("\"{}\"", token.literal),
+        _ => token.literal.to_string(),
+    };
+
+    let mut end = token.position;
+
+    for ch in text.chars() {
+        if ch == '\n' {
+            end = Position::new(end.line + 1, 1);
+        } else {
+            end.column += 1;
+        }
+    }
+
+    end
+}
+
+/// Returns 1 for a token that opens a bracket, -1 for one that closes one, and 0 for any other token.
+fn bracket(token: &Token) -> isize {
+    match token.r#type {
+        TokenType::LParen | TokenType::LBracket | TokenType::LBrace => 1,
+        TokenType::RParen | TokenType::RBracket | TokenType::RBrace => -1,
+        _ => 0,
+    }
+}
+
+/// Adds the positions of every node in `tree` to `positions`.
+fn collect_positions(tree: &Tree, positions: &mut Vec<Position>) {
+    positions.extend(tree.position);
+
+    for (_, value) in &tree.fields {
+        collect_value_positions(value, positions);
+    }
+}
+
+/// Adds the positions of every node in `value` to `positions`.
+fn collect_value_positions(value: &Value, positions: &mut Vec<Position>) {
+    match value {
+        Value::Tree(tree) => collect_positions(tree, positions),
+        Value::List(values) => {
+            for value in values {
+                collect_value_positions(value, positions);
+            }
+        }
+        _ => {}
+    }
+}
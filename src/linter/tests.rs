@@ -0,0 +1,321 @@
+#![cfg(test)]
+
+use super::{lint, Config, Rule};
+
+/// Lints `input` with every rule turned on, and returns the rule, start, end and message of each
+/// finding, with the start and end as `line:column`
+fn findings(input: &str) -> Vec<(Rule, String, String, String)> {
+    let mut config = Config::default();
+
+    for rule in Rule::ALL {
+        config.set(rule, true);
+    }
+
+    lint_with(input, &config)
+}
+
+/// Lints `input` with `config`, like `findings`
+fn lint_with(input: &str, config: &Config) -> Vec<(Rule, String, String, String)> {
+    lint(input, config)
+        .unwrap_or_else(|errors| panic!("couldn't parse {:?}: {:?}", input, errors))
+        .into_iter()
+        .map(|finding| {
+            (
+                finding.rule,
+                finding.span.start.to_string(),
+                finding.span.end.to_string(),
+                finding.message,
+            )
+        })
+        .collect()
+}
+
+/// Returns a finding as `findings` does
+fn finding(rule: Rule, start: &str, end: &str, message: &str) -> (Rule, String, String, String) {
+    (
+        rule,
+        start.to_string(),
+        end.to_string(),
+        message.to_string(),
+    )
+}
+
+/// Tests what each rule finds, and what it doesn't
+#[test]
+fn test_rules() {
+    use Rule::*;
+
+    let tests = vec![
+        ("let x = 1; puts(x);", vec![]),
+        (
+            "let x = 1;",
+            vec![finding(UnusedVariable, "1:5", "1:6", "x is never used")],
+        ),
+        ("let _x = 1;", vec![]),
+        (
+            "let [a, ...b] = [1, 2]; puts(b);",
+            vec![finding(UnusedVariable, "1:6", "1:7", "a is never used")],
+        ),
+        ("let x = 1; let x = x + 1; puts(x);", vec![]),
+        (
+            "let x = 1; let x = 2; puts(x);",
+            vec![finding(UnusedVariable, "1:5", "1:6", "x is never used")],
+        ),
+        ("fn f() { g() } fn g() { 1 } f();", vec![]),
+        ("let f = fn() { g() }; let g = fn() { 1 }; f();", vec![]),
+        ("export let x = 1; export fn f(a) { a }", vec![]),
+        ("struct P { x } P { x: 1 };", vec![]),
+        ("for (x in xs) { 1 }", vec![]),
+        ("match (1) { x => 1 }", vec![]),
+        (
+            "fn f(a, b) { a } f(1, 2);",
+            vec![finding(
+                UnusedParameter,
+                "1:9",
+                "1:10",
+                "parameter b is never used",
+            )],
+        ),
+        ("let f = |_a, b = 1| b; f(1);", vec![]),
+        (
+            "let f = |a, b = a| 1; f(1);",
+            vec![finding(
+                UnusedParameter,
+                "1:13",
+                "1:14",
+                "parameter b is never used",
+            )],
+        ),
+        (
+            "let x = 1; let f = fn(x) { x }; f(x);",
+            vec![finding(
+                ShadowedName,
+                "1:23",
+                "1:24",
+                "x shadows a binding in an outer scope",
+            )],
+        ),
+        (
+            "let f = fn() { let y = 1; y }; let y = 2; f(y);",
+            vec![finding(
+                ShadowedName,
+                "1:20",
+                "1:21",
+                "y shadows a binding in an outer scope",
+            )],
+        ),
+        ("if (x) { let x = 1; x }", vec![]),
+        (
+            "fn f() { return 1; puts(2); 3 } f();",
+            vec![finding(
+                UnreachableCode,
+                "1:20",
+                "1:30",
+                "unreachable code after return",
+            )],
+        ),
+        (
+            "fn f() { if (x) { throw 1; 2 } } f();",
+            vec![finding(
+                UnreachableCode,
+                "1:28",
+                "1:29",
+                "unreachable code after throw",
+            )],
+        ),
+        ("fn f() { if (x) { return 1; } 2 } f();", vec![]),
+        (
+            "if (true) { 1 } else if (!0) { 2 }",
+            vec![
+                finding(
+                    ConstantCondition,
+                    "1:5",
+                    "1:9",
+                    "this condition is always true",
+                ),
+                finding(
+                    ConstantCondition,
+                    "1:26",
+                    "1:28",
+                    "this condition is always false",
+                ),
+            ],
+        ),
+        (
+            "if ([]) { 1 }",
+            vec![finding(
+                ConstantCondition,
+                "1:5",
+                "1:7",
+                "this condition is always true",
+            )],
+        ),
+        ("if (x) { 1 }", vec![]),
+        (
+            "x == x;",
+            vec![finding(
+                SelfComparison,
+                "1:1",
+                "1:7",
+                "comparing x with itself is always true",
+            )],
+        ),
+        (
+            "a.b[0] > a.b[0];",
+            vec![finding(
+                SelfComparison,
+                "1:1",
+                "1:16",
+                "comparing a.b[0] with itself is always false",
+            )],
+        ),
+        ("f() == f();", vec![]),
+        ("x == y; x + x;", vec![]),
+        (
+            "!!x;",
+            vec![finding(DoubleNegation, "1:1", "1:4", "double negation")],
+        ),
+        (
+            "!!!x;",
+            vec![finding(DoubleNegation, "1:1", "1:5", "double negation")],
+        ),
+        ("!x;", vec![]),
+        ("let x = 1; quote(x == x); quote(unquote(x));", vec![]),
+        (
+            "quote(unquote(!!x));",
+            vec![finding(DoubleNegation, "1:15", "1:18", "double negation")],
+        ),
+        ("let m = macro(a) { let b = 1; quote(1) }; m(1);", vec![]),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(findings(input), expected, "input: {}", input);
+    }
+}
+
+/// Tests the fixes that findings suggest
+#[test]
+fn test_fixes() {
+    let tests = vec![
+        ("let x = 1;", "let _x = 1;"),
+        ("fn f(a) { 1 } f(2);", "fn f(_a) { 1 } f(2);"),
+        (
+            "fn f() { return 1; 2; 3; }\nf();",
+            "fn f() { return 1;  }\nf();",
+        ),
+        ("puts(a.b == a.b);", "puts(true);"),
+        ("puts(x != x);", "puts(false);"),
+        ("puts(!!x);", "puts(x);"),
+        ("puts(!! (x + 1) * 2);", "puts((x + 1) * 2);"),
+        ("puts(!!\"a\");", "puts(\"a\");"),
+        ("puts(!!!x);", "puts(!x);"),
+    ];
+
+    for (input, expected) in tests {
+        let findings = lint(input, &Config::default()).unwrap();
+        assert_eq!(findings.len(), 1, "input: {}", input);
+
+        let fix = findings[0].fix.as_ref().expect("a fix");
+        let lines = input.lines().collect::<Vec<_>>();
+
+        // Replaces the span in the input, which is only on one line in these tests.
+        let line = lines[fix.span.start.line - 1].chars().collect::<Vec<_>>();
+        let before = line[..fix.span.start.column - 1].iter().collect::<String>();
+        let after = line[fix.span.end.column - 1..].iter().collect::<String>();
+
+        let mut fixed = lines.clone();
+        let replaced = format!("{}{}{}", before, fix.replacement, after);
+        fixed[fix.span.start.line - 1] = &replaced;
+
+        assert_eq!(fixed.join("\n"), expected, "input: {}", input);
+    }
+
+    let findings = lint("if (true) { 1 }", &Config::default()).unwrap();
+    assert_eq!(findings[0].fix, None);
+}
+
+/// Tests that comments suppress the findings of the rules that they name, on their line or the next
+#[test]
+fn test_suppressions() {
+    let tests = vec![
+        ("let x = 1; // lint-allow: unused-variable", 0),
+        ("// lint-allow: unused-variable\nlet x = 1;", 0),
+        ("// lint-allow: unused-variable\n\nlet x = 1;", 1),
+        ("let x = 1; // lint-allow: self-comparison", 1),
+        (
+            "let y = x == x; // lint-allow: self-comparison, unused-variable",
+            0,
+        ),
+        ("let y = x == x; // lint-allow: self-comparison", 1),
+        ("let x = 1; // lint-allow: frob", 1),
+        ("let x = 1; // lint-allow:unused-variable", 0),
+        ("let x = 1; // not lint-allow: unused-variable", 1),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(findings(input).len(), expected, "input: {}", input);
+    }
+}
+
+/// Tests parsing configs, and which rules are on by default
+#[test]
+fn test_config() {
+    let default = Config::default();
+
+    for rule in Rule::ALL {
+        assert_eq!(
+            default.is_enabled(rule),
+            rule != Rule::ShadowedName,
+            "rule: {}",
+            rule
+        );
+        assert_eq!(rule.id().parse(), Ok(rule));
+    }
+
+    let config = Config::parse(
+        "# the linter\n[package]\nname = \"x\"\n\n[lint]\nshadowed-name = true # on\n\"unused-parameter\" = false\n\n[other]\nself-comparison = false\n",
+    )
+    .unwrap();
+
+    assert!(config.is_enabled(Rule::ShadowedName));
+    assert!(!config.is_enabled(Rule::UnusedParameter));
+    assert!(config.is_enabled(Rule::SelfComparison));
+    assert!(config.is_enabled(Rule::UnusedVariable));
+
+    assert_eq!(Config::parse(""), Ok(Config::default()));
+
+    let errors = vec![
+        ("[lint]\nfrob = true", "line 2: unknown rule: \"frob\""),
+        (
+            "[lint]\nshadowed-name = yes",
+            "line 2: expected true or false, got yes",
+        ),
+        (
+            "[lint]\nshadowed-name",
+            "line 2: expected <rule> = <true|false>, got shadowed-name",
+        ),
+        ("[lint", "line 1: expected ] after [lint"),
+    ];
+
+    for (input, expected) in errors {
+        assert_eq!(
+            Config::parse(input),
+            Err(expected.to_string()),
+            "input: {}",
+            input
+        );
+    }
+
+    let mut config = Config::default();
+    config.set(Rule::UnusedVariable, false);
+
+    assert_eq!(lint_with("let x = 1; x == x;", &config).len(), 1);
+}
+
+/// Tests that the prelude has nothing for the default rules to find
+#[test]
+fn test_prelude() {
+    let prelude = include_str!("../evaluator/prelude.monkey");
+
+    assert_eq!(lint(prelude, &Config::default()), Ok(Vec::new()));
+}
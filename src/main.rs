@@ -1,22 +1,54 @@
 pub mod ast;
+pub mod cli;
+pub mod compiler;
+pub mod dump;
 pub mod evaluator;
+pub mod formatter;
+pub mod gc;
 pub mod lexer;
+pub mod linter;
 pub mod object;
+pub mod optimizer;
 pub mod parser;
 pub mod repl;
+pub mod resolver;
+pub mod symbol;
 pub mod token;
+pub mod vm;
 
-use std::io::Result;
-use whoami::username;
+use cli::{Options, Status, USAGE};
+use std::{env, io, process::ExitCode, thread};
 
-fn main() -> Result<()> {
-    println!(
-        "Hello, {}! This is the Monkey programming language!",
-        username()
-    );
-    println!("Feel free to type in commands");
+/// The size of the stack that code is evaluated on. The parser and evaluator recurse on the stack, and
+/// their default limits (see `Parser::set_max_depth`, `Parser::set_max_chain`,
+/// `Evaluator::set_max_call_depth` and `Evaluator::set_max_depth`) need more of it than the main
+/// thread has. The `Vm` doesn't recurse
+/// on it to make calls, but still compiles programs and expands macros on it.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
-    repl::start(std::io::stdin(), std::io::stdout())?;
+fn main() -> ExitCode {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            return Status::Usage.into();
+        }
+    };
 
-    Ok(())
+    let status = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || cli::execute(options, io::stdin(), io::stdout(), io::stderr()))
+        .map(|thread| {
+            thread
+                .join()
+                .expect("the main thread of the interpreter panicked")
+        });
+
+    match status {
+        Ok(status) => status.into(),
+        Err(error) => {
+            eprintln!("error: couldn't start the interpreter: {}", error);
+            Status::IoError.into()
+        }
+    }
 }
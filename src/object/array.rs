@@ -0,0 +1,35 @@
+use super::{Object, ObjectType, Objective};
+use std::fmt::Display;
+
+/// The Array object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Array {
+    pub elements: Vec<Object>,
+}
+
+impl Array {
+    /// Creates a new Array object.
+    pub fn new(elements: Vec<Object>) -> Self {
+        Self { elements }
+    }
+}
+
+impl Objective for Array {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Array
+    }
+}
+
+impl Display for Array {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}]",
+            self.elements
+                .iter()
+                .map(Object::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
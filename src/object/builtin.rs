@@ -0,0 +1,62 @@
+use super::{iterator::Iter, Object, ObjectType, Objective};
+use crate::evaluator::EvalResult;
+use std::fmt::Display;
+
+/// The signature of the Rust function behind a builtin. It is given the engine that is running it, so
+/// that it can run Monkey code, e.g. to resume a generator.
+pub type BuiltinFunction = fn(&mut dyn Runtime, Vec<Object>) -> EvalResult<Object>;
+
+/// What a builtin needs from the engine that is running it, which is either the tree-walking
+/// `Evaluator` or the bytecode `Vm`.
+pub trait Runtime {
+    /// Takes the next value from `iterator`, or returns `None` if it is done.
+    fn next_value(&mut self, iterator: &Iter) -> EvalResult<Option<Object>>;
+
+    /// Uses up the memory budget for an array of `length` elements.
+    fn allocate_array(&mut self, length: usize) -> EvalResult<()>;
+
+    /// Uses up the memory budget for a hash of `length` pairs.
+    fn allocate_hash(&mut self, length: usize) -> EvalResult<()>;
+
+    /// Calls `function`, which is a function or builtin, with `arguments`, and returns its value.
+    fn call(&mut self, function: Object, arguments: Vec<Object>) -> EvalResult<Object>;
+}
+
+/// The Builtin object. This is a function that is written in Rust instead of Monkey, for things that
+/// can't be written in Monkey itself, like stepping through an iterator.
+#[derive(Debug, Copy, Clone)]
+pub struct Builtin {
+    /// The name that the builtin is looked up by.
+    pub name: &'static str,
+
+    /// The Rust function that is run when the builtin is called.
+    pub function: BuiltinFunction,
+}
+
+impl Builtin {
+    /// Creates a new Builtin object.
+    pub fn new(name: &'static str, function: BuiltinFunction) -> Self {
+        Self { name, function }
+    }
+}
+
+impl Objective for Builtin {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Builtin
+    }
+}
+
+// Function pointers can't be reliably compared, but every builtin has its own name.
+impl PartialEq for Builtin {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Builtin {}
+
+impl Display for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<builtin {}>", self.name)
+    }
+}
@@ -0,0 +1,78 @@
+use super::{environment::Environment, function::Function, ObjectType, Objective};
+use crate::{compiler::code::CompiledFunction, vm::locals::Cell};
+use std::{cell::RefCell, fmt::Display, path::Path, rc::Rc};
+
+/// The Closure object. This is the `Vm`'s version of a `Function`: a compiled function, together with
+/// the variables that it uses from the functions that it was created in, and the global environment
+/// that it looks its globals up in. It behaves just like a function, so it has the same type.
+#[derive(Clone)]
+pub struct Closure {
+    /// The function that this is a closure of.
+    pub function: Rc<CompiledFunction>,
+
+    /// The variables that the closure captured when it was created (see
+    /// `CompiledFunction::captures`).
+    pub captures: Rc<[Cell]>,
+
+    /// The global environment that the closure was created in.
+    pub globals: Rc<RefCell<Environment>>,
+
+    /// The path of the file that the closure was created in, if it was created in one.
+    pub file: Option<Rc<Path>>,
+}
+
+impl Closure {
+    /// Creates a new Closure object.
+    pub fn new(
+        function: Rc<CompiledFunction>,
+        captures: Rc<[Cell]>,
+        globals: Rc<RefCell<Environment>>,
+        file: Option<Rc<Path>>,
+    ) -> Self {
+        Self {
+            function,
+            captures,
+            globals,
+            file,
+        }
+    }
+
+    /// Returns the name of the closure's function, or `<anonymous>` if it does not have one.
+    pub fn name(&self) -> &str {
+        self.function.name()
+    }
+}
+
+impl Objective for Closure {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Function
+    }
+}
+
+// Like functions, two closures are only the same if they come from the same literal and were created
+// in the same scope. Every closure that is created gets captures of its own, so that is only the case
+// for the copies of a closure.
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.captures, &other.captures)
+    }
+}
+
+impl Eq for Closure {}
+
+impl std::fmt::Debug for Closure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Closure")
+            .field("literal", &self.function.literal)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for Closure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.function.literal {
+            Some(literal) => Function::fmt_literal(literal, f),
+            None => write!(f, "<program>"),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use super::Object;
+use crate::{
+    ast::expressions::Identifier,
+    gc::{Node, Trace, Tracer},
+    symbol::Symbol,
+};
+use std::{any::Any, cell::RefCell, collections::HashMap, mem, rc::Rc};
+
+/// The `Environment` struct keeps track of the values that are bound to identifiers. Each function
+/// call gets its own environment, which is "enclosed" by the environment that the function was
+/// defined in. This is what allows closures to work, as a function can look up identifiers in the
+/// environment that it was created in, even after that environment has been left.
+///
+/// Variables whose identifiers have been resolved (see `resolver::resolve`) are kept in slots, by
+/// index, apart from globals. Everything else is kept by name, as the `Symbol` that the name is interned
+/// as.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Environment {
+    /// The values that are bound in this environment.
+    store: HashMap<Symbol, Object>,
+
+    /// The values that are bound to the slots of this environment, by index, or `None` for the slots
+    /// that haven't been bound yet.
+    slots: Vec<Option<Object>>,
+
+    /// The environment that encloses this environment, if there is one. If an identifier cannot be
+    /// found in `store`, it is looked up here.
+    outer: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    /// Creates a new, empty `Environment` with no enclosing environment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, empty `Environment` that is enclosed by `outer`.
+    pub fn new_enclosed(outer: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            store: HashMap::new(),
+            slots: Vec::new(),
+            outer: Some(outer),
+        }
+    }
+
+    /// Returns whether nothing is bound in this environment, not counting the enclosing environments.
+    pub fn is_empty(&self) -> bool {
+        self.store.is_empty() && self.slots.iter().all(Option::is_none)
+    }
+
+    /// Looks up the value bound to `name`, first in this environment, and then in the enclosing
+    /// environments. Returns `None` if `name` is not bound anywhere.
+    pub fn get(&self, name: &Symbol) -> Option<Object> {
+        match self.store.get(name) {
+            Some(object) => Some(object.clone()),
+            None => self.outer.as_ref()?.borrow().get(name),
+        }
+    }
+
+    /// Binds `value` to `name` in this environment, shadowing any binding of `name` in the
+    /// enclosing environments.
+    pub fn set(&mut self, name: Symbol, value: Object) {
+        self.store.insert(name, value);
+    }
+
+    /// Looks up the value that `identifier` refers to. A resolved identifier is looked up in each of
+    /// its slots in turn, as a slot is only bound once the code that binds it has run, and then by name,
+    /// as a global. An identifier that hasn't been resolved is looked up by name.
+    pub fn lookup(&self, identifier: &Identifier) -> Option<Object> {
+        identifier
+            .slots
+            .iter()
+            .flatten()
+            .find_map(|slot| self.get_slot(slot.depth, slot.index))
+            .or_else(|| self.get(&identifier.value))
+    }
+
+    /// Binds `value` to `identifier` in this environment, in its slot if it has been resolved to one,
+    /// and by name otherwise.
+    pub fn bind(&mut self, identifier: &Identifier, value: Object) {
+        match identifier.slots.as_deref() {
+            Some([slot, ..]) => self.set_slot(slot.index, value),
+            _ => self.set(identifier.value.clone(), value),
+        }
+    }
+
+    /// Returns the value bound to slot `index` of the environment `depth` environments out from this
+    /// one, or `None` if it hasn't been bound.
+    fn get_slot(&self, depth: usize, index: usize) -> Option<Object> {
+        match depth {
+            0 => self.slots.get(index)?.clone(),
+            depth => self.outer.as_ref()?.borrow().get_slot(depth - 1, index),
+        }
+    }
+
+    /// Binds `value` to slot `index` of this environment.
+    fn set_slot(&mut self, index: usize, value: Object) {
+        if index >= self.slots.len() {
+            self.slots.resize(index + 1, None);
+        }
+
+        self.slots[index] = Some(value);
+    }
+}
+
+impl Trace for Environment {
+    fn trace(&self, tracer: &mut Tracer) {
+        for value in self.store.values().chain(self.slots.iter().flatten()) {
+            value.trace(tracer);
+        }
+
+        if let Some(outer) = &self.outer {
+            tracer.node(outer);
+        }
+    }
+}
+
+impl Node for Environment {
+    fn clear(&mut self) -> Box<dyn Any> {
+        Box::new(mem::take(self))
+    }
+}
@@ -0,0 +1,63 @@
+use super::{integer::Integer, null::Null, string::Str, Object, ObjectType, Objective};
+use crate::evaluator::errors::Error;
+use std::{fmt::Display, rc::Rc};
+
+/// The Error object. This is what a `catch` binds a caught error to, so that code can look at what
+/// went wrong through the `message`, `kind`, `line`, `column` and `value` fields. Throwing an error
+/// object again re-raises the original error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorValue {
+    /// The error that was caught.
+    pub error: Rc<Error>,
+}
+
+impl ErrorValue {
+    /// Creates a new Error object from a caught error.
+    pub fn new(error: Error) -> Self {
+        Self {
+            error: Rc::new(error),
+        }
+    }
+
+    /// Returns the value of the field with the given name, if the error object has one. The location
+    /// fields are null if it isn't known where the error happened.
+    pub fn get(&self, field: &str) -> Option<Object> {
+        let position = self.error.position;
+
+        Some(match field {
+            "message" => Object::String(Str::new(&self.error.message)),
+            "kind" => Object::String(Str::new(self.error.kind)),
+            "line" => position.map_or(Object::Null(Null), |position| {
+                Object::Integer(Integer::new(position.line as i64))
+            }),
+            "column" => position.map_or(Object::Null(Null), |position| {
+                Object::Integer(Integer::new(position.column as i64))
+            }),
+            "value" => self
+                .error
+                .value
+                .as_deref()
+                .cloned()
+                .unwrap_or(Object::Null(Null)),
+            _ => return None,
+        })
+    }
+}
+
+impl Objective for ErrorValue {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Error
+    }
+}
+
+impl Display for ErrorValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<{} error", self.error.kind)?;
+
+        if let Some(position) = self.error.position {
+            write!(f, " at {}", position)?;
+        }
+
+        write!(f, ": {}>", self.error.message)
+    }
+}
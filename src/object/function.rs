@@ -0,0 +1,90 @@
+use super::{environment::Environment, ObjectType, Objective};
+use crate::ast::expressions::{FunctionLiteral, Parameter};
+use std::{cell::RefCell, fmt::Display, path::Path, rc::Rc};
+
+/// The Function object. This is a function literal that has been evaluated, which means that it
+/// has captured the environment that it was defined in (making it a closure).
+#[derive(Clone)]
+pub struct Function {
+    /// The function literal that this function was created from. This is wrapped in an `Rc` so
+    /// that functions can be cheaply cloned when they are looked up from an environment.
+    pub literal: Rc<FunctionLiteral>,
+
+    /// The environment that the function was defined in.
+    pub env: Rc<RefCell<Environment>>,
+
+    /// The path of the file that the function was defined in, if it was defined in one.
+    pub file: Option<Rc<Path>>,
+}
+
+impl Function {
+    /// Creates a new Function object.
+    pub fn new(
+        literal: Rc<FunctionLiteral>,
+        env: Rc<RefCell<Environment>>,
+        file: Option<Rc<Path>>,
+    ) -> Self {
+        Self { literal, env, file }
+    }
+
+    /// Returns the name of the function, or `<anonymous>` if it does not have one.
+    pub fn name(&self) -> &str {
+        self.literal.name.as_deref().unwrap_or("<anonymous>")
+    }
+}
+
+impl Objective for Function {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Function
+    }
+}
+
+// We can't derive these, as a function's environment usually contains the function itself (so
+// that it can recurse), which would make comparing or printing the environment loop forever.
+// Instead, two functions are the same only if they come from the same literal and were created
+// in the same environment.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.env, &other.env) && self.literal == other.literal
+    }
+}
+
+impl Eq for Function {}
+
+impl std::fmt::Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Function")
+            .field("literal", &self.literal)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Function {
+    /// Writes a function created from `literal` the way that it is displayed.
+    pub(crate) fn fmt_literal(
+        literal: &FunctionLiteral,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        write!(f, "fn")?;
+        if let Some(name) = &literal.name {
+            write!(f, " {}", name)?;
+        }
+        write!(
+            f,
+            "({}) {{ {} }}",
+            literal
+                .parameters
+                .iter()
+                .map(Parameter::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            literal.body
+        )
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        Self::fmt_literal(&self.literal, f)
+    }
+}
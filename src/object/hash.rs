@@ -0,0 +1,74 @@
+use super::{Object, ObjectType, Objective};
+use std::{collections::BTreeMap, fmt::Display};
+
+/// The Hash object. The pairs are kept in a `BTreeMap` instead of a `HashMap`, so that hashes are
+/// always printed (and iterated over) in the same order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Hash {
+    pub pairs: BTreeMap<HashKey, HashPair>,
+}
+
+impl Hash {
+    /// Creates a new Hash object.
+    pub fn new(pairs: BTreeMap<HashKey, HashPair>) -> Self {
+        Self { pairs }
+    }
+
+    /// Looks up the value for the given key.
+    pub fn get(&self, key: &HashKey) -> Option<&Object> {
+        self.pairs.get(key).map(|pair| &pair.value)
+    }
+}
+
+impl Objective for Hash {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Hash
+    }
+}
+
+impl Display for Hash {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{{{}}}",
+            self.pairs
+                .values()
+                .map(|pair| format!("{}: {}", pair.key, pair.value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// A key-value pair in a Hash object. The original key object is kept around (and not just its
+/// `HashKey`), so that the hash can be printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashPair {
+    pub key: Object,
+    pub value: Object,
+}
+
+/// The key that a value is stored under in a Hash object. In the original implementation, this is a
+/// struct with the object's type and a hash of its value, but as we can derive `Ord` (and `Hash`), we
+/// can use the values directly. Only integers, booleans and strings can be used as hash keys.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, std::hash::Hash)]
+pub enum HashKey {
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+}
+
+impl TryFrom<&Object> for HashKey {
+    type Error = ObjectType;
+
+    /// Converts an object into a `HashKey`. If the object can't be used as a hash key, its type is
+    /// returned as the error.
+    fn try_from(object: &Object) -> Result<Self, Self::Error> {
+        match object {
+            Object::Integer(integer) => Ok(HashKey::Integer(integer.value)),
+            Object::Boolean(boolean) => Ok(HashKey::Boolean(boolean.value)),
+            Object::String(string) => Ok(HashKey::String(string.value.clone())),
+            _ => Err(object.object_type()),
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use super::{
+    environment::Environment, function::Function, integer::Integer, Object, ObjectType, Objective,
+};
+use crate::{
+    ast::{expressions::TryExpression, patterns::Pattern, statements::BlockStatement},
+    compiler::code::CompiledFunction,
+    evaluator::errors::Error,
+    gc::{self, Node, Trace, Tracer},
+    vm::frame::Frame as VmFrame,
+};
+use std::{any::Any, cell::RefCell, fmt::Display, mem, rc::Rc, vec};
+
+/// The Iterator object. This is called `Iter` instead of `Iterator` so that it doesn't clash with Rust's
+/// own `Iterator` trait. An iterator hands out the values of something that can be iterated over one at
+/// a time, e.g. to a `for` loop or the `next` builtin, and is done once it runs out of them.
+///
+/// Iterators are shared rather than copied, so taking a value from an iterator takes it from every
+/// binding of that iterator.
+#[derive(Clone)]
+pub struct Iter {
+    /// Where the iterator is up to.
+    pub state: Rc<RefCell<IterState>>,
+}
+
+impl Iter {
+    /// Creates a new Iterator object.
+    pub fn new(state: IterState) -> Self {
+        Self {
+            state: gc::alloc(state),
+        }
+    }
+}
+
+/// Where an `Iter` is up to, which depends on what it is iterating over.
+#[derive(Debug)]
+pub enum IterState {
+    /// The values that are left, for iterating over arrays, strings and hashes, whose values are all
+    /// known up front.
+    Values(vec::IntoIter<Object>),
+
+    /// The integers that are left of a range, from `next` up to, but not including, `end`.
+    Range { next: i64, end: i64 },
+
+    /// A call to a generator function, whose values are only known once it is resumed.
+    Generator(Generator),
+
+    /// A call to a compiled generator function, which is the `Vm`'s version of a `Generator`.
+    Coroutine(Coroutine),
+}
+
+impl IterState {
+    /// Takes the next value of an iterator whose values don't need any code to be run to get them, or
+    /// returns `None` if it is a generator, which has to be resumed instead. The inner `Option` is
+    /// `None` once the iterator is done.
+    pub fn next_known(&mut self) -> Option<Option<Object>> {
+        match self {
+            IterState::Values(values) => Some(values.next()),
+
+            IterState::Range { next, end } => {
+                if next >= end {
+                    return Some(None);
+                }
+
+                let value = *next;
+                *next += 1;
+
+                Some(Some(Object::Integer(Integer::new(value))))
+            }
+
+            IterState::Generator(_) | IterState::Coroutine(_) => None,
+        }
+    }
+
+    /// Takes the value that a generator returned when it was done, so that only the first time that the
+    /// generator is found to be done has it. Other iterators don't return anything.
+    pub fn take_returned(&mut self) -> Option<Object> {
+        match self {
+            IterState::Generator(generator) => generator.returned.take(),
+            IterState::Coroutine(coroutine) => coroutine.returned.take(),
+            IterState::Values(_) | IterState::Range { .. } => None,
+        }
+    }
+}
+
+/// A call to a generator function, which is paused at the `yield` that it last reached. Instead of
+/// evaluating the body recursively, which would keep where it is up to on the Rust stack, the
+/// evaluator keeps it in `frames`, so that it can leave the body at a `yield`, and come back later.
+pub struct Generator {
+    /// The generator function that was called.
+    pub function: Function,
+
+    /// The blocks and `for` loops that the generator is inside of, from the outermost to the innermost.
+    /// The generator is done once this is empty.
+    pub frames: Vec<Frame>,
+
+    /// Whether the generator is currently being resumed, so that it can't be resumed from inside of
+    /// itself.
+    pub running: bool,
+
+    /// The value of the `return` that the generator was left by, until it is taken.
+    pub returned: Option<Object>,
+}
+
+impl Generator {
+    /// Creates a new Generator, that hasn't started evaluating the body of `function` yet. The body is
+    /// evaluated in `env`, which has the arguments bound in it.
+    pub fn new(function: Function, env: Rc<RefCell<Environment>>) -> Self {
+        let body = Rc::new(function.literal.body.clone());
+
+        Self {
+            function,
+            frames: vec![Frame::Block {
+                block: body,
+                index: 0,
+                env,
+            }],
+            running: false,
+            returned: None,
+        }
+    }
+}
+
+// We can't derive this, as the environments usually contain the generator itself.
+impl std::fmt::Debug for Generator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Generator")
+            .field("function", &self.function)
+            .field("running", &self.running)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A call to a compiled generator function. Unlike a `Generator`, the `Vm` doesn't evaluate the body
+/// recursively anyway, so where it is up to is just its call frame, which is put aside at a `yield`.
+#[derive(Debug)]
+pub struct Coroutine {
+    /// The generator function that was called.
+    pub function: Rc<CompiledFunction>,
+
+    /// Whether the call is paused, running or done.
+    pub state: CoroutineState,
+
+    /// The value that the call returned, until it is taken.
+    pub returned: Option<Object>,
+}
+
+/// Whether a `Coroutine` is paused, running or done.
+#[derive(Debug)]
+pub enum CoroutineState {
+    /// The call is paused, at the start of its body or at the `yield` that it last reached.
+    Suspended(Box<VmFrame>),
+
+    /// The call is currently being resumed, so that it can't be resumed from inside of itself.
+    Running,
+
+    /// The call has left its body, and has no more values.
+    Done,
+}
+
+/// A block, `for` loop or `try` that a `Generator` is inside of.
+pub enum Frame {
+    /// A block, with the index of the next statement in it to evaluate.
+    Block {
+        block: Rc<BlockStatement>,
+        index: usize,
+        env: Rc<RefCell<Environment>>,
+    },
+
+    /// A `for` loop, with the iterator that it is taking values from. Each value is bound to `pattern` in
+    /// a new environment enclosed by `env`, and then `body` is entered as a new block.
+    For {
+        iterator: Iter,
+        pattern: Rc<Pattern>,
+        body: Rc<BlockStatement>,
+        env: Rc<RefCell<Environment>>,
+    },
+
+    /// A `try`, with which of its blocks is being evaluated. The errors raised, and the `return`s made,
+    /// in the frames inside of it are handled by it, like they are by `Evaluator::eval_try`. Its blocks
+    /// are entered in `env`.
+    Try {
+        try_expression: Rc<TryExpression>,
+        stage: TryStage,
+        env: Rc<RefCell<Environment>>,
+    },
+}
+
+/// Which block of a `try` a `Generator` is in.
+pub enum TryStage {
+    /// The `try` block itself.
+    Body,
+
+    /// The `catch` block, after the `try` block raised an error.
+    Catch,
+
+    /// The `finally` block, with how the `try` is left once the block is done.
+    Finally(Completion),
+}
+
+/// How a `Generator` leaves a `try` once its `finally` block is done.
+pub enum Completion {
+    /// By carrying on after the `try`.
+    Normal,
+
+    /// By raising the error that the `try` or `catch` block raised again.
+    Raise(Error),
+
+    /// By returning the value that the `try` or `catch` block returned.
+    Return(Object),
+}
+
+impl Trace for IterState {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            IterState::Values(values) => {
+                for value in values.as_slice() {
+                    value.trace(tracer);
+                }
+            }
+
+            IterState::Range { .. } => {}
+
+            IterState::Generator(generator) => {
+                tracer.node(&generator.function.env);
+
+                for frame in &generator.frames {
+                    match frame {
+                        Frame::Block { env, .. } => tracer.node(env),
+                        Frame::For { iterator, env, .. } => {
+                            tracer.node(&iterator.state);
+                            tracer.node(env);
+                        }
+                        Frame::Try { stage, env, .. } => {
+                            match stage {
+                                TryStage::Finally(Completion::Raise(error)) => error.trace(tracer),
+                                TryStage::Finally(Completion::Return(value)) => value.trace(tracer),
+                                _ => {}
+                            }
+
+                            tracer.node(env);
+                        }
+                    }
+                }
+
+                if let Some(returned) = &generator.returned {
+                    returned.trace(tracer);
+                }
+            }
+
+            IterState::Coroutine(coroutine) => {
+                if let CoroutineState::Suspended(frame) = &coroutine.state {
+                    frame.trace(tracer);
+                }
+
+                if let Some(returned) = &coroutine.returned {
+                    returned.trace(tracer);
+                }
+            }
+        }
+    }
+}
+
+impl Node for IterState {
+    fn clear(&mut self) -> Box<dyn Any> {
+        Box::new(mem::replace(
+            self,
+            IterState::Values(Vec::new().into_iter()),
+        ))
+    }
+}
+
+impl Objective for Iter {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Iterator
+    }
+}
+
+// Like functions, two iterators are only the same if they are the same iterator.
+impl PartialEq for Iter {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl Eq for Iter {}
+
+impl std::fmt::Debug for Iter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Iter").finish_non_exhaustive()
+    }
+}
+
+impl Display for Iter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.state.try_borrow().as_deref() {
+            Ok(IterState::Generator(generator)) => {
+                write!(f, "<generator {}>", generator.function.name())
+            }
+            Ok(IterState::Coroutine(coroutine)) => {
+                write!(f, "<generator {}>", coroutine.function.name())
+            }
+            _ => write!(f, "<iterator>"),
+        }
+    }
+}
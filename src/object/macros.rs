@@ -0,0 +1,61 @@
+use super::{environment::Environment, ObjectType, Objective};
+use crate::ast::expressions::{MacroLiteral, Parameter};
+use std::{cell::RefCell, fmt::Display, rc::Rc};
+
+/// The Macro object. This is a macro literal that has been defined, along with the environment
+/// that it was defined in. Macros only exist while macros are being expanded, before the program
+/// is evaluated.
+#[derive(Clone)]
+pub struct Macro {
+    /// The macro literal that this macro was created from.
+    pub literal: Rc<MacroLiteral>,
+
+    /// The environment that the macro was defined in.
+    pub env: Rc<RefCell<Environment>>,
+}
+
+impl Macro {
+    /// Creates a new Macro object.
+    pub fn new(literal: Rc<MacroLiteral>, env: Rc<RefCell<Environment>>) -> Self {
+        Self { literal, env }
+    }
+}
+
+impl Objective for Macro {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Macro
+    }
+}
+
+// Like functions, macros can't derive these, as their environment can contain the macro itself.
+impl PartialEq for Macro {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.env, &other.env) && self.literal == other.literal
+    }
+}
+
+impl Eq for Macro {}
+
+impl std::fmt::Debug for Macro {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Macro")
+            .field("literal", &self.literal)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for Macro {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "macro({}) {{ {} }}",
+            self.literal
+                .parameters
+                .iter()
+                .map(Parameter::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.literal.body
+        )
+    }
+}
@@ -1,8 +1,33 @@
+pub mod array;
 pub mod boolean;
+pub mod builtin;
+pub mod closure;
+pub mod environment;
+pub mod error;
+pub mod function;
+pub mod hash;
 pub mod integer;
+pub mod iterator;
+pub mod macros;
+pub mod module;
 pub mod null;
+pub mod quote;
+pub mod range;
+pub mod record;
+pub mod string;
+pub mod structs;
+pub mod tail_call;
 
-use self::{boolean::Boolean, integer::Integer, null::Null};
+use self::{
+    array::Array, boolean::Boolean, builtin::Builtin, closure::Closure, error::ErrorValue,
+    function::Function, hash::Hash, integer::Integer, iterator::Iter, macros::Macro,
+    module::Module, null::Null, quote::Quote, range::Range, record::Record, string::Str,
+    structs::Struct, tail_call::TailCall,
+};
+use crate::{
+    evaluator::errors::Error,
+    gc::{Trace, Tracer},
+};
 use std::fmt::{Debug, Display};
 
 /// An object in the Monkey programming language. This is the base trait for all
@@ -19,6 +44,51 @@ pub enum Object {
 
     /// The Null object.
     Null(Null),
+
+    /// The Function object.
+    Function(Function),
+
+    /// The Array object.
+    Array(Array),
+
+    /// The String object.
+    String(Str),
+
+    /// The Hash object.
+    Hash(Hash),
+
+    /// The Quote object.
+    Quote(Quote),
+
+    /// The Macro object.
+    Macro(Macro),
+
+    /// The Module object.
+    Module(Module),
+
+    /// The Struct object.
+    Struct(Struct),
+
+    /// The Record object.
+    Record(Record),
+
+    /// The Error object.
+    Error(ErrorValue),
+
+    /// The TailCall object.
+    TailCall(TailCall),
+
+    /// The Range object.
+    Range(Range),
+
+    /// The Iterator object.
+    Iterator(Iter),
+
+    /// The Builtin object.
+    Builtin(Builtin),
+
+    /// The Closure object.
+    Closure(Closure),
 }
 
 impl Objective for Object {
@@ -27,6 +97,21 @@ impl Objective for Object {
             Object::Integer(_) => ObjectType::Integer,
             Object::Boolean(_) => ObjectType::Boolean,
             Object::Null(_) => ObjectType::Null,
+            Object::Function(_) => ObjectType::Function,
+            Object::Array(_) => ObjectType::Array,
+            Object::String(_) => ObjectType::String,
+            Object::Hash(_) => ObjectType::Hash,
+            Object::Quote(_) => ObjectType::Quote,
+            Object::Macro(_) => ObjectType::Macro,
+            Object::Module(_) => ObjectType::Module,
+            Object::Struct(_) => ObjectType::Struct,
+            Object::Record(_) => ObjectType::Record,
+            Object::Error(_) => ObjectType::Error,
+            Object::TailCall(_) => ObjectType::TailCall,
+            Object::Range(_) => ObjectType::Range,
+            Object::Iterator(_) => ObjectType::Iterator,
+            Object::Builtin(_) => ObjectType::Builtin,
+            Object::Closure(closure) => closure.object_type(),
         }
     }
 }
@@ -37,6 +122,83 @@ impl Display for Object {
             Object::Integer(integer) => write!(f, "{}", integer),
             Object::Boolean(boolean) => write!(f, "{}", boolean),
             Object::Null(null) => write!(f, "{}", null),
+            Object::Function(function) => write!(f, "{}", function),
+            Object::Array(array) => write!(f, "{}", array),
+            Object::String(string) => write!(f, "{}", string),
+            Object::Hash(hash) => write!(f, "{}", hash),
+            Object::Quote(quote) => write!(f, "{}", quote),
+            Object::Macro(macro_object) => write!(f, "{}", macro_object),
+            Object::Module(module) => write!(f, "{}", module),
+            Object::Struct(struct_object) => write!(f, "{}", struct_object),
+            Object::Record(record) => write!(f, "{}", record),
+            Object::Error(error) => write!(f, "{}", error),
+            Object::TailCall(tail_call) => write!(f, "{}", tail_call),
+            Object::Range(range) => write!(f, "{}", range),
+            Object::Iterator(iterator) => write!(f, "{}", iterator),
+            Object::Builtin(builtin) => write!(f, "{}", builtin),
+            Object::Closure(closure) => write!(f, "{}", closure),
+        }
+    }
+}
+
+impl Trace for Object {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            Object::Function(function) => tracer.node(&function.env),
+            Object::Macro(macro_object) => tracer.node(&macro_object.env),
+            Object::Iterator(iterator) => tracer.node(&iterator.state),
+            Object::Closure(closure) => {
+                tracer.node(&closure.globals);
+                tracer.shared(&closure.captures, |captures, tracer| {
+                    for cell in captures {
+                        tracer.node(cell);
+                    }
+                });
+            }
+
+            Object::TailCall(tail_call) => {
+                tracer.node(&tail_call.function.env);
+
+                for argument in &tail_call.arguments {
+                    argument.trace(tracer);
+                }
+            }
+
+            Object::Array(array) => {
+                for element in &array.elements {
+                    element.trace(tracer);
+                }
+            }
+
+            Object::Hash(hash) => {
+                for pair in hash.pairs.values() {
+                    pair.key.trace(tracer);
+                    pair.value.trace(tracer);
+                }
+            }
+
+            Object::Record(record) => {
+                for (_, value) in &record.fields {
+                    value.trace(tracer);
+                }
+            }
+
+            Object::Module(module) => tracer.shared(&module.exports, |exports, tracer| {
+                for value in exports.values() {
+                    value.trace(tracer);
+                }
+            }),
+
+            Object::Error(error) => tracer.shared(&error.error, Error::trace),
+
+            Object::Integer(_)
+            | Object::Boolean(_)
+            | Object::Null(_)
+            | Object::String(_)
+            | Object::Quote(_)
+            | Object::Struct(_)
+            | Object::Range(_)
+            | Object::Builtin(_) => {}
         }
     }
 }
@@ -65,4 +227,74 @@ pub enum ObjectType {
 
     /// The Null object.
     Null,
+
+    /// The Function object.
+    Function,
+
+    /// The Array object.
+    Array,
+
+    /// The String object.
+    String,
+
+    /// The Hash object.
+    Hash,
+
+    /// The Quote object.
+    Quote,
+
+    /// The Macro object.
+    Macro,
+
+    /// The Module object.
+    Module,
+
+    /// The Struct object.
+    Struct,
+
+    /// The Record object.
+    Record,
+
+    /// The Error object.
+    Error,
+
+    /// The TailCall object.
+    TailCall,
+
+    /// The Range object.
+    Range,
+
+    /// The Iterator object.
+    Iterator,
+
+    /// The Builtin object.
+    Builtin,
+}
+
+impl Display for ObjectType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ObjectType::Integer => "INTEGER",
+                ObjectType::Boolean => "BOOLEAN",
+                ObjectType::Null => "NULL",
+                ObjectType::Function => "FUNCTION",
+                ObjectType::Array => "ARRAY",
+                ObjectType::String => "STRING",
+                ObjectType::Hash => "HASH",
+                ObjectType::Quote => "QUOTE",
+                ObjectType::Macro => "MACRO",
+                ObjectType::Module => "MODULE",
+                ObjectType::Struct => "STRUCT",
+                ObjectType::Record => "RECORD",
+                ObjectType::Error => "ERROR",
+                ObjectType::TailCall => "TAIL_CALL",
+                ObjectType::Range => "RANGE",
+                ObjectType::Iterator => "ITERATOR",
+                ObjectType::Builtin => "BUILTIN",
+            }
+        )
+    }
 }
@@ -0,0 +1,42 @@
+use super::{Object, ObjectType, Objective};
+use crate::symbol::Symbol;
+use std::{collections::BTreeMap, fmt::Display, rc::Rc};
+
+/// The Module object. This is what an `import` binds its alias to, and holds the bindings that the
+/// imported module exported. Modules are only evaluated once, so every import of the same module
+/// shares the same exports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    /// The (resolved) path of the module.
+    pub path: String,
+
+    /// The exported bindings of the module, by name.
+    pub exports: Rc<BTreeMap<Symbol, Object>>,
+}
+
+impl Module {
+    /// Creates a new Module object.
+    pub fn new<S: ToString>(path: S, exports: BTreeMap<Symbol, Object>) -> Self {
+        Self {
+            path: path.to_string(),
+            exports: Rc::new(exports),
+        }
+    }
+
+    /// Returns the exported binding with the given name, if there is one.
+    pub fn get(&self, name: &Symbol) -> Option<&Object> {
+        self.exports.get(name)
+    }
+}
+
+impl Objective for Module {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Module
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<module {}>", self.path)
+    }
+}
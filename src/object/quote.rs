@@ -0,0 +1,32 @@
+use super::{ObjectType, Objective};
+use crate::ast::expressions::Expression;
+use std::fmt::Display;
+
+/// The Quote object. This is the unevaluated AST of an expression, which is what `quote` returns,
+/// what macros are called with and what they return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quote {
+    /// The quoted expression. It is boxed, as expressions are much bigger than any other object.
+    pub node: Box<Expression>,
+}
+
+impl Quote {
+    /// Creates a new Quote object.
+    pub fn new(node: Expression) -> Self {
+        Self {
+            node: Box::new(node),
+        }
+    }
+}
+
+impl Objective for Quote {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Quote
+    }
+}
+
+impl Display for Quote {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "QUOTE({})", self.node)
+    }
+}
@@ -0,0 +1,33 @@
+use super::{ObjectType, Objective};
+use std::fmt::Display;
+
+/// The Range object. This is the integers from `start` up to, but not including, `end`, which is what
+/// `start..end` evaluates to. A range doesn't hold its integers, they are only counted out as it is
+/// iterated over.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Range {
+    /// The first integer in the range.
+    pub start: i64,
+
+    /// The integer that the range stops before.
+    pub end: i64,
+}
+
+impl Range {
+    /// Creates a new Range object.
+    pub fn new(start: i64, end: i64) -> Self {
+        Self { start, end }
+    }
+}
+
+impl Objective for Range {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Range
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
@@ -0,0 +1,51 @@
+use super::{Object, ObjectType, Objective};
+use crate::symbol::Symbol;
+use std::fmt::Display;
+
+/// The Record object. This is a value of a struct, with a value for each of the struct's fields. Records
+/// are compared structurally, so two records are equal if they are of the same struct and all of their
+/// fields are equal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The name of the struct that this is a record of.
+    pub name: Symbol,
+
+    /// The fields of the record, in the order they were declared in the struct.
+    pub fields: Vec<(Symbol, Object)>,
+}
+
+impl Record {
+    /// Creates a new Record object.
+    pub fn new(name: Symbol, fields: Vec<(Symbol, Object)>) -> Self {
+        Self { name, fields }
+    }
+
+    /// Returns the value of the field with the given name, if the record has one.
+    pub fn get(&self, field: &Symbol) -> Option<&Object> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value)
+    }
+}
+
+impl Objective for Record {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Record
+    }
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {{ {} }}",
+            self.name,
+            self.fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
@@ -0,0 +1,30 @@
+use super::{ObjectType, Objective};
+use std::fmt::Display;
+
+/// The String object. This is called `Str` instead of `String` so that it doesn't clash with Rust's
+/// own `String` type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Str {
+    pub value: String,
+}
+
+impl Str {
+    /// Creates a new String object.
+    pub fn new<S: ToString>(value: S) -> Self {
+        Self {
+            value: value.to_string(),
+        }
+    }
+}
+
+impl Objective for Str {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::String
+    }
+}
+
+impl Display for Str {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
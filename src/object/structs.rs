@@ -0,0 +1,45 @@
+use super::{ObjectType, Objective};
+use crate::symbol::Symbol;
+use std::{fmt::Display, rc::Rc};
+
+/// The Struct object. This is what a `struct` declaration binds its name to, and describes the fields
+/// that records of the struct have. Records are constructed from it with `Name { field: value, ... }`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Struct {
+    /// The name of the struct.
+    pub name: Symbol,
+
+    /// The names of the fields of the struct, in the order they were declared.
+    pub fields: Rc<Vec<Symbol>>,
+}
+
+impl Struct {
+    /// Creates a new Struct object.
+    pub fn new(name: Symbol, fields: Vec<Symbol>) -> Self {
+        Self {
+            name,
+            fields: Rc::new(fields),
+        }
+    }
+}
+
+impl Objective for Struct {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::Struct
+    }
+}
+
+impl Display for Struct {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "struct {} {{ {} }}",
+            self.name,
+            self.fields
+                .iter()
+                .map(|field| field.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
@@ -0,0 +1,42 @@
+use super::{function::Function, Object, ObjectType, Objective};
+use crate::token::Position;
+use std::fmt::Display;
+
+/// The TailCall object. A call in tail position evaluates to this instead of calling the function,
+/// and the function call that it is returned from then makes the call in its place. This means that
+/// a chain of tail calls runs in a loop, instead of using up more of the Rust stack with every call.
+/// It never escapes a function call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TailCall {
+    /// The function that is being called.
+    pub function: Function,
+
+    /// The arguments that the function is being called with.
+    pub arguments: Vec<Object>,
+
+    /// The position of the call in the source code.
+    pub position: Position,
+}
+
+impl TailCall {
+    /// Creates a new TailCall object.
+    pub fn new(function: Function, arguments: Vec<Object>, position: Position) -> Self {
+        Self {
+            function,
+            arguments,
+            position,
+        }
+    }
+}
+
+impl Objective for TailCall {
+    fn object_type(&self) -> ObjectType {
+        ObjectType::TailCall
+    }
+}
+
+impl Display for TailCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "<tail call of {}>", self.function.name())
+    }
+}
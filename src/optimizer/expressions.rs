@@ -0,0 +1,302 @@
+use super::Optimizer;
+use crate::{
+    ast::{
+        expressions::{
+            ElseIfBranch, Expression, FunctionLiteral, IfExpression, InfixExpression, Parameter,
+            PrefixExpression,
+        },
+        statements::BlockStatement,
+    },
+    evaluator::{
+        expressions::{eval_infix, eval_prefix, is_quote, is_truthy},
+        macros::object_to_expression,
+    },
+    object::{boolean::Boolean, integer::Integer, string::Str, Object},
+    token::Position,
+};
+use std::mem;
+
+impl Optimizer {
+    /// Optimizes an `Expression`. The children of an expression are optimized before the expression
+    /// itself, so that e.g. an operator whose operands fold to constants folds too.
+    pub(super) fn optimize_expression(&mut self, expression: &mut Expression) {
+        let replacement = match expression {
+            Expression::Identifier(_)
+            | Expression::Integer(_)
+            | Expression::Boolean(_)
+            | Expression::String(_) => None,
+
+            // `quote` needs the AST of its argument as it was written, and macros only exist before
+            // the program is run.
+            Expression::Call(call) if is_quote(call) => None,
+            Expression::Macro(_) => None,
+
+            Expression::Function(function) => {
+                self.optimize_function(function);
+                None
+            }
+
+            Expression::Prefix(prefix) => {
+                self.optimize_expression(&mut prefix.right);
+                fold_prefix(prefix)
+            }
+
+            Expression::Infix(infix) => {
+                self.optimize_expression(&mut infix.left);
+                self.optimize_expression(&mut infix.right);
+                fold_infix(infix)
+            }
+
+            Expression::If(if_expression) => {
+                self.optimize_expression(&mut if_expression.condition);
+                self.optimize_block(&mut if_expression.consequence.statements);
+
+                for else_if in &mut if_expression.else_ifs {
+                    self.optimize_expression(&mut else_if.condition);
+                    self.optimize_block(&mut else_if.consequence.statements);
+                }
+
+                if let Some(alternative) = &mut if_expression.alternative {
+                    self.optimize_block(&mut alternative.statements);
+                }
+
+                prune(if_expression);
+                None
+            }
+
+            Expression::Call(call) => {
+                self.optimize_expression(&mut call.function);
+
+                for argument in &mut call.arguments {
+                    self.optimize_expression(argument);
+                }
+
+                self.inline_call(call)
+            }
+
+            Expression::Array(array) => {
+                for element in &mut array.elements {
+                    self.optimize_expression(element);
+                }
+
+                None
+            }
+
+            Expression::Index(index) => {
+                self.optimize_expression(&mut index.left);
+                self.optimize_expression(&mut index.index);
+                None
+            }
+
+            Expression::Member(member) => {
+                self.optimize_expression(&mut member.object);
+                None
+            }
+
+            Expression::Spread(spread) => {
+                self.optimize_expression(&mut spread.value);
+                None
+            }
+
+            Expression::Hash(hash) => {
+                for (key, value) in &mut hash.pairs {
+                    self.optimize_expression(key);
+                    self.optimize_expression(value);
+                }
+
+                None
+            }
+
+            Expression::Record(record) => {
+                for (_, value) in &mut record.fields {
+                    self.optimize_expression(value);
+                }
+
+                None
+            }
+
+            Expression::Match(match_expression) => {
+                self.optimize_expression(&mut match_expression.subject);
+
+                for arm in &mut match_expression.arms {
+                    if let Some(guard) = &mut arm.guard {
+                        self.optimize_expression(guard);
+                    }
+
+                    self.optimize_expression(&mut arm.body);
+                }
+
+                None
+            }
+
+            Expression::Try(try_expression) => {
+                self.optimize_block(&mut try_expression.body.statements);
+
+                if let Some(catch) = &mut try_expression.catch {
+                    self.optimize_block(&mut catch.body.statements);
+                }
+
+                if let Some(finally) = &mut try_expression.finally {
+                    self.optimize_block(&mut finally.statements);
+                }
+
+                None
+            }
+
+            Expression::For(for_expression) => {
+                self.optimize_expression(&mut for_expression.iterable);
+                self.optimize_block(&mut for_expression.body.statements);
+                None
+            }
+        };
+
+        if let Some(replacement) = replacement {
+            *expression = replacement;
+        }
+    }
+
+    /// Optimizes a `FunctionLiteral`'s default parameter values and body.
+    pub(super) fn optimize_function(&mut self, function: &mut FunctionLiteral) {
+        for parameter in &mut function.parameters {
+            if let Parameter::Default { value, .. } = parameter {
+                self.optimize_expression(value);
+            }
+        }
+
+        self.optimize_block(&mut function.body.statements);
+    }
+}
+
+/// Returns the value of `expression` if it is a constant, i.e. an integer, boolean or string literal.
+pub(super) fn constant(expression: &Expression) -> Option<Object> {
+    match expression {
+        Expression::Integer(integer) => Some(Object::Integer(Integer::new(integer.value))),
+        Expression::Boolean(boolean) => Some(Object::Boolean(Boolean::new(boolean.value))),
+        Expression::String(string) => Some(Object::String(Str::new(string.value.clone()))),
+        _ => None,
+    }
+}
+
+/// Returns the literal for `value` at `position`, if there is a literal for it.
+fn literal(value: Object, position: Position) -> Option<Expression> {
+    match value {
+        Object::Integer(_) | Object::Boolean(_) | Object::String(_) => {
+            object_to_expression(value, position).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Folds a `PrefixExpression` whose operand is a constant into the constant that it evaluates to. If
+/// evaluating it would fail, it isn't folded, so that it fails at runtime.
+fn fold_prefix(prefix: &PrefixExpression) -> Option<Expression> {
+    let right = constant(&prefix.right)?;
+    let value = eval_prefix(prefix.operator.r#type, right).ok()?;
+
+    literal(value, prefix.token.position)
+}
+
+/// Folds an `InfixExpression` whose operands are constants into the constant that it evaluates to, like
+/// `fold_prefix`.
+fn fold_infix(infix: &InfixExpression) -> Option<Expression> {
+    let left = constant(&infix.left)?;
+    let right = constant(&infix.right)?;
+    let value = eval_infix(infix.operator.r#type, left, right).ok()?;
+
+    literal(value, infix.token.position)
+}
+
+/// Removes the branches of an `IfExpression` that can never be chosen. A branch whose condition is a
+/// falsy constant is never chosen, and neither is any branch after one whose condition is a truthy
+/// constant, which becomes the `else` block instead. If only the `else` block is left, it is chosen
+/// with a `true` condition.
+fn prune(if_expression: &mut IfExpression) {
+    let position = if_expression.token.position;
+    let empty = BlockStatement {
+        token: if_expression.consequence.token.clone(),
+        statements: Vec::new(),
+    };
+
+    let first = ElseIfBranch {
+        token: if_expression.token.clone(),
+        condition: mem::replace(&mut if_expression.condition, boolean(false, position)),
+        consequence: mem::replace(&mut if_expression.consequence, empty),
+    };
+
+    let mut branches = Vec::new();
+
+    for branch in [first]
+        .into_iter()
+        .chain(mem::take(&mut if_expression.else_ifs))
+    {
+        match constant(&branch.condition) {
+            Some(condition) if is_truthy(&condition) => {
+                if_expression.alternative = Some(branch.consequence);
+                break;
+            }
+            Some(_) => {}
+            None => branches.push(branch),
+        }
+    }
+
+    let mut branches = branches.into_iter();
+
+    match branches.next() {
+        Some(first) => {
+            if_expression.condition = first.condition;
+            if_expression.consequence = first.consequence;
+            if_expression.else_ifs = branches.collect();
+        }
+
+        // Without any branches or `else` block, this is left as `if (false) {}`.
+        None => {
+            if let Some(alternative) = if_expression.alternative.take() {
+                if_expression.condition = boolean(true, position);
+                if_expression.consequence = alternative;
+            }
+        }
+    }
+}
+
+/// Returns a boolean literal at `position`.
+fn boolean(value: bool, position: Position) -> Box<Expression> {
+    Box::new(
+        literal(Object::Boolean(Boolean::new(value)), position).expect("booleans have literals"),
+    )
+}
+
+/// Returns the block that an `IfExpression` always chooses, if it has been pruned down to one that
+/// always chooses its only block.
+pub(super) fn chosen(if_expression: &IfExpression) -> Option<&BlockStatement> {
+    let always = constant(&if_expression.condition).is_some_and(|value| is_truthy(&value));
+
+    (always && if_expression.else_ifs.is_empty() && if_expression.alternative.is_none())
+        .then_some(&if_expression.consequence)
+}
+
+/// Returns whether evaluating `expression` can't fail or have any effects, so that it doesn't need to
+/// be evaluated if its value is unused.
+pub(super) fn is_pure(expression: &Expression) -> bool {
+    match expression {
+        Expression::Integer(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Function(_) => true,
+
+        Expression::Array(array) => array.elements.iter().all(is_pure),
+
+        Expression::Hash(hash) => hash
+            .pairs
+            .iter()
+            .all(|(key, value)| constant(key).is_some() && is_pure(value)),
+
+        // What is left of an `if` that never chooses any block.
+        Expression::If(if_expression) => {
+            constant(&if_expression.condition).is_some_and(|value| !is_truthy(&value))
+                && if_expression.else_ifs.is_empty()
+                && if_expression.alternative.is_none()
+        }
+
+        _ => false,
+    }
+}
@@ -0,0 +1,197 @@
+use super::{expressions::constant, Optimizer};
+use crate::ast::{
+    expressions::{CallExpression, Expression, FunctionLiteral, Parameter},
+    operators::PrefixOperatorType,
+    statements::Statement,
+};
+use crate::symbol::Symbol;
+
+/// The most nodes that the body of a function that is inlined can have.
+const INLINE_LIMIT: usize = 10;
+
+/// A function that calls can be inlined from. Its body is a single expression of just its parameters,
+/// constants and operators, so it doesn't refer to anything that could mean something else where it is
+/// inlined, and it is small enough that inlining it doesn't make the code much bigger.
+pub(super) struct Inlinable {
+    /// The names of the function's parameters.
+    parameters: Vec<Symbol>,
+
+    /// The expression that the function's body consists of.
+    body: Expression,
+
+    /// Whether the body uses every parameter, for the first time in the order that they come in, before
+    /// it applies any operators. The arguments of a call are evaluated in order before the body, so a
+    /// call whose arguments could fail can only be inlined if this is the case.
+    in_order: bool,
+}
+
+impl Inlinable {
+    /// Returns the `function` as an `Inlinable`, if it can be inlined.
+    pub(super) fn new(function: &FunctionLiteral) -> Option<Self> {
+        if function.generator {
+            return None;
+        }
+
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| match parameter {
+                Parameter::Required(name) => Some(name.value.clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let [Statement::Expression(statement)] = function.body.statements.as_slice() else {
+            return None;
+        };
+
+        let mut uses = Uses::default();
+
+        if uses.size(&statement.expression, &parameters)? > INLINE_LIMIT {
+            return None;
+        }
+
+        Some(Self {
+            in_order: uses.in_order && uses.names == parameters,
+            parameters,
+            body: statement.expression.clone(),
+        })
+    }
+}
+
+/// The uses of a function's parameters in its body, in the order that they are evaluated in.
+struct Uses {
+    /// The parameters that have been used, in the order that they were first used in.
+    names: Vec<Symbol>,
+
+    /// Whether no operators have been applied yet, or they were only applied once every parameter was
+    /// used.
+    in_order: bool,
+}
+
+impl Default for Uses {
+    fn default() -> Self {
+        Self {
+            names: Vec::new(),
+            in_order: true,
+        }
+    }
+}
+
+impl Uses {
+    /// Returns the number of nodes in `expression`, if it only consists of `parameters`, constants and
+    /// operators, recording the uses of the parameters as it goes.
+    fn size(&mut self, expression: &Expression, parameters: &[Symbol]) -> Option<usize> {
+        let size = match expression {
+            Expression::Identifier(identifier) => {
+                if !parameters.contains(&identifier.value) {
+                    return None;
+                }
+
+                if !self.names.contains(&identifier.value) {
+                    self.names.push(identifier.value.clone());
+                }
+
+                return Some(1);
+            }
+
+            Expression::Integer(_) | Expression::Boolean(_) | Expression::String(_) => {
+                return Some(1)
+            }
+
+            Expression::Prefix(prefix) => 1 + self.size(&prefix.right, parameters)?,
+
+            Expression::Infix(infix) => {
+                1 + self.size(&infix.left, parameters)? + self.size(&infix.right, parameters)?
+            }
+
+            _ => return None,
+        };
+
+        // The operator is applied once its operands have been evaluated.
+        self.in_order &= self.names.len() == parameters.len();
+
+        Some(size)
+    }
+}
+
+impl Optimizer {
+    /// Inlines a call to a global that an `Inlinable` function is bound to, replacing it with the
+    /// function's body with the arguments in place of the parameters. Only arguments that are constants
+    /// or identifiers are inlined, as the others could have effects, and the parameter that they are
+    /// for could be used more than once, or not at all.
+    ///
+    /// A call is only inlined if the body that replaces it can't fail once it has been folded, as an
+    /// error in the body would otherwise be missing the function from its stack trace. That is on
+    /// purpose a narrow gate: it lets through calls whose constant arguments fold the body into a
+    /// constant, e.g. `double(21)`, and bodies of nothing but `!`s, and keeps every other call.
+    pub(super) fn inline_call(&mut self, call: &CallExpression) -> Option<Expression> {
+        let Expression::Identifier(function) = call.function.as_ref() else {
+            return None;
+        };
+
+        // A global's identifier resolves to no slots.
+        if !function.slots.as_ref().is_some_and(Vec::is_empty) {
+            return None;
+        }
+
+        let inlinable = self.inlinable.get(&function.value)?;
+
+        if call.arguments.len() != inlinable.parameters.len() {
+            return None;
+        }
+
+        for argument in &call.arguments {
+            match argument {
+                argument if constant(argument).is_some() => {}
+                Expression::Identifier(_) if inlinable.in_order => {}
+                _ => return None,
+            }
+        }
+
+        let mut body = inlinable.body.clone();
+        substitute(&mut body, &inlinable.parameters, &call.arguments);
+        self.optimize_expression(&mut body);
+
+        (!can_fail(&body)).then_some(body)
+    }
+}
+
+/// Replaces the `parameters` in `expression` with the `arguments` for them.
+fn substitute(expression: &mut Expression, parameters: &[Symbol], arguments: &[Expression]) {
+    match expression {
+        Expression::Identifier(identifier) => {
+            if let Some(index) = parameters.iter().position(|name| *name == identifier.value) {
+                *expression = arguments[index].clone();
+            }
+        }
+
+        Expression::Prefix(prefix) => substitute(&mut prefix.right, parameters, arguments),
+
+        Expression::Infix(infix) => {
+            substitute(&mut infix.left, parameters, arguments);
+            substitute(&mut infix.right, parameters, arguments);
+        }
+
+        _ => {}
+    }
+}
+
+/// Returns whether evaluating `expression` could fail. The optimizer doesn't know what identifiers are
+/// bound to, and every operator but `!` fails for some operands, so an expression is only known not to
+/// fail if it has nothing but constants and identifiers under `!`s. An identifier fails if it isn't
+/// bound, but as an argument, it would have failed in the caller anyway.
+fn can_fail(expression: &Expression) -> bool {
+    match expression {
+        Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::Boolean(_)
+        | Expression::String(_) => false,
+
+        Expression::Prefix(prefix) => {
+            prefix.operator.r#type != PrefixOperatorType::Bang || can_fail(&prefix.right)
+        }
+
+        _ => true,
+    }
+}
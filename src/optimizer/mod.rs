@@ -0,0 +1,229 @@
+mod expressions;
+mod inline;
+pub mod tests;
+
+use self::inline::Inlinable;
+use crate::{
+    ast::{
+        expressions::Expression,
+        patterns::Pattern,
+        statements::{ExpressionStatement, Statement},
+        Program,
+    },
+    resolver::{declare_statements, resolve},
+    symbol::Symbol,
+};
+use std::{collections::HashMap, mem, str::FromStr};
+
+/// How much a program is optimized before it is run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum OptLevel {
+    /// The program is run as it is written.
+    #[default]
+    None,
+
+    /// Constant expressions are folded, dead branches are removed, and pure expression statements
+    /// whose values are unused are dropped.
+    Basic,
+
+    /// Everything that `Basic` does, and calls to small functions are inlined too, where that can't
+    /// change the stack trace of an error. That is mostly calls with constant arguments, which fold
+    /// into a constant.
+    Full,
+}
+
+impl FromStr for OptLevel {
+    type Err = String;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level {
+            "0" => Ok(OptLevel::None),
+            "1" => Ok(OptLevel::Basic),
+            "2" => Ok(OptLevel::Full),
+            _ => Err(format!(
+                "unknown optimization level: {:?}, expected 0, 1 or 2",
+                level
+            )),
+        }
+    }
+}
+
+/// Optimizes `program` as much as possible, i.e. at `OptLevel::Full`.
+pub fn optimize(program: Program) -> Program {
+    optimize_at(program, OptLevel::Full)
+}
+
+/// Optimizes `program` at the given level. This is meant to be run after macros have been expanded, as
+/// it would otherwise change the code that macros are given.
+///
+/// The optimized program evaluates to the same values and fails with the same errors as the original,
+/// at the same positions. Expressions that would fail, e.g. because they overflow or divide by zero,
+/// are left to fail at runtime, and so are calls to functions that would fail, so that the functions
+/// are in the errors' stack traces. The exceptions are the limits that a program runs under: the
+/// optimized program takes fewer steps and makes fewer calls.
+pub fn optimize_at(mut program: Program, level: OptLevel) -> Program {
+    if level == OptLevel::None {
+        return program;
+    }
+
+    // Only calls to globals are inlined, so the identifiers are resolved to find out which those are.
+    if level == OptLevel::Full {
+        resolve(&mut program, |_| true);
+    }
+
+    let mut optimizer = Optimizer {
+        inline: level == OptLevel::Full,
+        inlinable: HashMap::new(),
+    };
+
+    optimizer.optimize_program(&mut program.statements);
+    program
+}
+
+/// The optimizer. It keeps track of the functions that calls can be inlined from.
+struct Optimizer {
+    /// Whether calls to small functions are inlined.
+    inline: bool,
+
+    /// The functions that the code being optimized can inline calls to, by the name of the global that
+    /// they are bound to.
+    inlinable: HashMap<Symbol, Inlinable>,
+}
+
+impl Optimizer {
+    /// Optimizes the top-level statements of a program. A function that is bound to a global by a `let`
+    /// can be inlined into the statements after it, which only run once the `let` has.
+    fn optimize_program(&mut self, statements: &mut Vec<Statement>) {
+        for index in 0..statements.len() {
+            match &mut statements[index] {
+                // Function declarations are hoisted, so they might be called before any `let`.
+                statement @ Statement::Function(_) => {
+                    let inlinable = mem::take(&mut self.inlinable);
+                    self.optimize_statement(statement);
+                    self.inlinable = inlinable;
+                }
+
+                statement => self.optimize_statement(statement),
+            }
+
+            if self.inline {
+                if let Some((name, inlinable)) = inlinable_binding(statements, index) {
+                    self.inlinable.insert(name, inlinable);
+                }
+            }
+        }
+
+        flatten(statements);
+    }
+
+    /// Optimizes the statements of a block.
+    fn optimize_block(&mut self, statements: &mut Vec<Statement>) {
+        for statement in statements.iter_mut() {
+            self.optimize_statement(statement);
+        }
+
+        flatten(statements);
+    }
+
+    /// Optimizes a single statement.
+    fn optimize_statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::Let(let_statement) => self.optimize_expression(&mut let_statement.value),
+            Statement::Return(return_statement) => {
+                self.optimize_expression(&mut return_statement.return_value)
+            }
+            Statement::Throw(throw) => self.optimize_expression(&mut throw.value),
+            Statement::Yield(yield_statement) => {
+                self.optimize_expression(&mut yield_statement.value)
+            }
+            Statement::Expression(expression) => {
+                self.optimize_expression(&mut expression.expression)
+            }
+            Statement::Function(declaration) => self.optimize_function(&mut declaration.function),
+            Statement::Export(export) => self.optimize_statement(&mut export.declaration),
+            Statement::Struct(_) | Statement::Import(_) => {}
+        }
+    }
+}
+
+/// Returns the name and function that the statement at `index` binds, if it is a `let` that binds a
+/// small function (see `Inlinable`) to a name that nothing else in the top-level scope binds.
+fn inlinable_binding(statements: &[Statement], index: usize) -> Option<(Symbol, Inlinable)> {
+    let statement = match &statements[index] {
+        Statement::Export(export) => export.declaration.as_ref(),
+        statement => statement,
+    };
+
+    let Statement::Let(let_statement) = statement else {
+        return None;
+    };
+
+    let (Pattern::Identifier(name), Expression::Function(function)) =
+        (&let_statement.pattern, let_statement.value.as_ref())
+    else {
+        return None;
+    };
+
+    let mut names = Vec::new();
+    declare_statements(&mut names, &statements[..index]);
+    declare_statements(&mut names, &statements[index + 1..]);
+
+    if names.contains(&name.value) {
+        return None;
+    }
+
+    Some((name.value.clone(), Inlinable::new(function)?))
+}
+
+/// Flattens optimized statements: the blocks of `if`s that always choose them are spliced into the
+/// statements around them, and pure expression statements whose values are unused are dropped.
+///
+/// The value of the last statement is the value of the statements, so it is only spliced if the block
+/// ends with an expression too, and is never dropped. A block that declares functions or structs isn't
+/// spliced either, as they would be hoisted out of it.
+fn flatten(statements: &mut Vec<Statement>) {
+    let count = statements.len();
+    let mut flattened = Vec::with_capacity(count);
+
+    for (index, statement) in mem::take(statements).into_iter().enumerate() {
+        let last = index + 1 == count;
+
+        match statement {
+            Statement::Expression(ExpressionStatement {
+                expression: Expression::If(if_expression),
+                ..
+            }) if expressions::chosen(&if_expression)
+                .is_some_and(|block| can_splice(&block.statements, last)) =>
+            {
+                flattened.extend(if_expression.consequence.statements)
+            }
+
+            statement => flattened.push(statement),
+        }
+    }
+
+    let count = flattened.len();
+
+    *statements = flattened
+        .into_iter()
+        .enumerate()
+        .filter(|(index, statement)| {
+            index + 1 == count
+                || !matches!(statement, Statement::Expression(statement) if expressions::is_pure(&statement.expression))
+        })
+        .map(|(_, statement)| statement)
+        .collect();
+}
+
+/// Returns whether a block's statements can be spliced into the statements around it. `last` is
+/// whether the block's `if` is the last of those statements.
+fn can_splice(statements: &[Statement], last: bool) -> bool {
+    let declares = statements.iter().any(|statement| {
+        matches!(
+            statement,
+            Statement::Function(_) | Statement::Struct(_) | Statement::Export(_)
+        )
+    });
+
+    !declares && (!last || matches!(statements.last(), Some(Statement::Expression(_))))
+}
@@ -0,0 +1,281 @@
+#![cfg(test)]
+
+use super::{optimize, optimize_at, OptLevel};
+use crate::{
+    ast::Program, evaluator::Evaluator, lexer::Lexer, object::Object, parser::Parser, vm::Vm,
+};
+
+/// Parses the input source code
+fn parse(input: &str) -> Program {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    parser.parse_program().unwrap()
+}
+
+/// Returns the value that running a program produced, or the error it failed with, as a string
+fn show(result: Result<Option<Object>, impl ToString>) -> String {
+    match result {
+        Ok(Some(object)) => object.to_string(),
+        Ok(None) => "".to_string(),
+        Err(error) => format!("ERROR: {}", error.to_string()),
+    }
+}
+
+/// Tests the code that optimizing programs produces
+#[test]
+fn test_optimize_programs() {
+    let tests = vec![
+        ("2 * 3 + 1", "7"),
+        ("-(2 - 5)", "3"),
+        ("!true == false", "true"),
+        (r#""mon" + "key""#, r#""monkey""#),
+        ("1 < 2 == true", "true"),
+        // Expressions that fail at runtime are left to fail at runtime.
+        ("9223372036854775807 + 1", "(9223372036854775807 + 1)"),
+        ("1 / (2 - 2)", "(1 / 0)"),
+        ("-true", "(-true)"),
+        ("1 + true", "(1 + true)"),
+        ("1..3", "(1 .. 3)"),
+        ("x * (2 + 3)", "(x * 5)"),
+        // Dead branches are removed, and blocks that are always chosen are spliced in.
+        ("if (true) { a } else { b }", "a"),
+        ("if (1 > 2) { a } else { b }", "b"),
+        ("if (false) { a }", "if false "),
+        (
+            "if (false) { a } else if (c) { b } else { d }",
+            "if c b else d",
+        ),
+        (
+            "if (c) { a } else if (true) { b } else { d }",
+            "if c a else b",
+        ),
+        ("if (c) { a } else if (false) { b }", "if c a"),
+        ("if (true) { let x = 1; }", "if true let x = 1;"),
+        ("if (true) { let x = 1; } x", "let x = 1;x"),
+        ("if (true) { fn f() { 1 } } f()", "if true fn f() 1f()"),
+        // Pure expression statements are dropped, unless they are the value of their block.
+        ("1; [2, fn() { 3 }]; {4: 5}; 6", "6"),
+        ("fn() { 1; x; 2 }", "fn() x2"),
+        ("if (false) { a } 1", "1"),
+        ("x; y", "xy"),
+        // Small functions that are bound once are inlined.
+        (
+            "let double = fn(x) { x * 2 }; double(21)",
+            "let double = fn(x) (x * 2);42",
+        ),
+        (
+            "let not = fn(x) { !x }; fn(n) { not(n) }",
+            "let not = fn(x) (!x);fn(n) (!n)",
+        ),
+        (
+            "let add = fn(a, b) { a + b }; add(\"a\", \"b\")",
+            "let add = fn(a, b) (a + b);\"ab\"",
+        ),
+        // Calls that could fail aren't inlined, so that the function is in the error's stack trace.
+        // Every operator but `!` could fail on an identifier.
+        ("let f = fn() { 1 / 0 }; f()", "let f = fn() (1 / 0);f()"),
+        (
+            "let sq = fn(x) { x * x }; fn(n) { sq(n) }",
+            "let sq = fn(x) (x * x);fn(n) sq(n)",
+        ),
+        (
+            "let add = fn(a, b) { a + b }; add(y, x); add(1, \"a\")",
+            "let add = fn(a, b) (a + b);add(y, x)add(1, \"a\")",
+        ),
+        // Arguments that could have effects aren't inlined, and neither are ones that could fail
+        // before the arguments after them are evaluated.
+        (
+            "let not = fn(x) { !x }; not(f())",
+            "let not = fn(x) (!x);not(f())",
+        ),
+        (
+            "let f = fn(a, b) { !b }; f(x, y); f(1, y); f(1, 2)",
+            "let f = fn(a, b) (!b);f(x, y)f(1, y)false",
+        ),
+        // Functions that are bound more than once, recursive, called where they might not be bound yet,
+        // or too big aren't inlined.
+        (
+            "let f = fn(x) { x }; let f = fn(x) { 2 }; f(1)",
+            "let f = fn(x) x;let f = fn(x) 2;f(1)",
+        ),
+        (
+            "let f = fn(x) { x }; if (c) { let f = 2; } f(1)",
+            "let f = fn(x) x;if c let f = 2;f(1)",
+        ),
+        (
+            "let f = fn(x) { x }; fn(f) { f(1) }",
+            "let f = fn(x) x;fn(f) f(1)",
+        ),
+        ("let f = fn(n) { f(n) }; f(1)", "let f = fn(n) f(n);f(1)"),
+        (
+            "fn g() { f(1) } let f = fn(x) { x }; g()",
+            "fn g() f(1)let f = fn(x) x;g()",
+        ),
+        (
+            "let f = fn(x) { x + x + x + x + x + x }; f(1)",
+            "let f = fn(x) (((((x + x) + x) + x) + x) + x);f(1)",
+        ),
+        // `quote` gets its argument as it was written.
+        ("quote(1 + 2)", "quote((1 + 2))"),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(
+            optimize(parse(input)).to_string(),
+            expected,
+            "input: {}",
+            input
+        );
+    }
+
+    // Only folding, dead branches and pure statements are optimized at the basic level.
+    assert_eq!(
+        optimize_at(
+            parse("let f = fn(x) { x + 1 }; if (true) { 1; f(2 * 3) }"),
+            OptLevel::Basic
+        )
+        .to_string(),
+        "let f = fn(x) (x + 1);f(6)"
+    );
+}
+
+/// Tests that optimized programs evaluate to the same values, and fail with the same errors, as the
+/// programs do when they aren't optimized, on both engines
+#[test]
+fn test_optimize_semantics() {
+    let tests = vec![
+        "2 * 3 + 1",
+        "9223372036854775807 + 1",
+        "-(-9223372036854775807 - 1)",
+        "10 / (5 - 5)",
+        r#""a" + "b" == "ab""#,
+        "if (1 > 2) { 1 }",
+        "if (true) { let x = 1; }",
+        "if (true) { let x = 2; } x * 3",
+        "let f = fn(x) { if (false) { x } else { x * 2 } }; f(4)",
+        "1; 2; 3",
+        "let double = fn(x) { x * 2 }; double(21)",
+        "let add = fn(a, b) { a + b }; let x = 1; add(x, 2)",
+        "let add = fn(a, b) { a + b }; add(missing, 2)",
+        "let f = fn(a, b) { -a + b }; f(true, missing)",
+        "let f = fn(x) { x * 2 }; let g = fn(f) { f(3) }; g(fn(x) { x })",
+        "let f = fn(x) { x + 1 }; map([1, 2, 3], f)",
+        "fn xs() { if (true) { yield 1; } yield 2 + 3; } collect(xs())",
+        "let r = quote(1 + 2); r",
+    ];
+
+    for input in tests {
+        let expected = show(Evaluator::new().run(parse(input)));
+
+        for opt_level in [OptLevel::Basic, OptLevel::Full] {
+            let mut evaluator = Evaluator::new();
+            evaluator.set_opt_level(opt_level);
+
+            let mut vm = Vm::new();
+            vm.set_opt_level(opt_level);
+
+            assert_eq!(
+                show(evaluator.run(parse(input))),
+                expected,
+                "input: {}, evaluator at {:?}",
+                input,
+                opt_level
+            );
+
+            assert_eq!(
+                show(vm.run(parse(input))),
+                expected,
+                "input: {}, vm at {:?}",
+                input,
+                opt_level
+            );
+        }
+    }
+}
+
+/// Tests that calls which can't fail are inlined when programs run, so that they don't call the
+/// functions at all, on both engines
+#[test]
+fn test_optimize_inlined_calls() {
+    let input = "let double = fn(x) { x * 2 }; let not = fn(x) { !x }; [double(21), not(double)]";
+
+    for (opt_level, expected) in [
+        (
+            OptLevel::None,
+            "ERROR: 1:62: maximum call depth of 0 exceeded",
+        ),
+        (OptLevel::Full, "[42, false]"),
+    ] {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_opt_level(opt_level);
+        evaluator.set_max_call_depth(0);
+
+        let mut vm = Vm::new();
+        vm.set_opt_level(opt_level);
+        vm.set_max_call_depth(0);
+
+        assert_eq!(
+            show(evaluator.run(parse(input))),
+            expected,
+            "evaluator at {:?}",
+            opt_level
+        );
+
+        assert_eq!(
+            show(vm.run(parse(input))),
+            expected,
+            "vm at {:?}",
+            opt_level
+        );
+    }
+}
+
+/// Tests that errors in functions that could have been inlined have the same stack traces at every
+/// optimization level, on both engines
+#[test]
+fn test_optimize_traces() {
+    let tests = vec![
+        (
+            "let f = fn() { 1 / 0 }; f()",
+            "ERROR: 1:18: division by zero\n    in f",
+        ),
+        (
+            "let f = fn(x) { x * 2 }; f(\"a\") + 1",
+            "ERROR: 1:19: type mismatch: STRING * INTEGER\n    in f",
+        ),
+        (
+            "let f = fn(x) { -x }; let g = fn(y) { 1 + f(y) }; g(true)",
+            "ERROR: 1:17: unknown operator: -BOOLEAN\n    in f\n    in g",
+        ),
+        (
+            "let f = fn(a, b) { a + b }; let x = 9223372036854775807; [f(x, 1)]",
+            "ERROR: 1:22: integer overflow: 9223372036854775807 + 1\n    in f",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        for opt_level in [OptLevel::None, OptLevel::Full] {
+            let mut evaluator = Evaluator::new();
+            evaluator.set_opt_level(opt_level);
+
+            let mut vm = Vm::new();
+            vm.set_opt_level(opt_level);
+
+            assert_eq!(
+                show(evaluator.run(parse(input))),
+                expected,
+                "input: {}, evaluator at {:?}",
+                input,
+                opt_level
+            );
+
+            assert_eq!(
+                show(vm.run(parse(input))),
+                expected,
+                "input: {}, vm at {:?}",
+                input,
+                opt_level
+            );
+        }
+    }
+}
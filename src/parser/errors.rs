@@ -1,3 +1,4 @@
+use crate::token::Position;
 use std::fmt::Display;
 
 /// Custom error type for the parser. Note that the original implementation of the parser in the book
@@ -6,19 +7,50 @@ use std::fmt::Display;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Error {
     pub message: String,
+
+    /// The position in the source code that the parser got stuck at.
+    pub position: Position,
 }
 
 impl Error {
-    /// Creates a new `Error` with the given `message`.
-    pub fn new<S: ToString>(message: S) -> Self {
+    /// Creates a new `Error` with the given `message`, about the code at `position`.
+    pub fn new<S: ToString>(message: S, position: Position) -> Self {
         Self {
             message: message.to_string(),
+            position,
         }
     }
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.message)
+        write!(f, "{}: {}", self.position, self.message)
+    }
+}
+
+/// A warning from the parser. Unlike an `Error`, a warning doesn't stop the program from being parsed,
+/// it only points out code that is valid but is probably a mistake, like a `match` arm that can never
+/// match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    pub message: String,
+
+    /// The position in the source code that the warning is about.
+    pub position: Position,
+}
+
+impl Warning {
+    /// Creates a new `Warning` with the given `message`, about the code at `position`.
+    pub fn new<S: ToString>(message: S, position: Position) -> Self {
+        Self {
+            message: message.to_string(),
+            position,
+        }
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.position, self.message)
     }
 }
@@ -6,13 +6,19 @@ use super::{
 use crate::{
     ast::{
         expressions::{
-            BooleanLiteral, CallExpression, Expression, FunctionLiteral, Identifier, IfExpression,
-            InfixExpression, IntegerLiteral, PrefixExpression,
+            ArrayLiteral, BooleanLiteral, CallExpression, CatchClause, ElseIfBranch, Expression,
+            ForExpression, FunctionForm, FunctionLiteral, HashLiteral, Identifier, IfExpression,
+            IndexExpression, InfixExpression, IntegerLiteral, MacroLiteral, MemberExpression,
+            Parameter, PrefixExpression, RecordLiteral, SpreadExpression, StringLiteral,
+            TryExpression,
         },
         operators::{InfixOperator, InfixOperatorType, PrefixOperator, PrefixOperatorType},
+        statements::{BlockStatement, ExpressionStatement, Statement},
     },
-    token::TokenType,
+    symbol::Symbol,
+    token::{Position, Token, TokenType},
 };
+use std::mem;
 
 /// Describes a prefix parse function, which parses an operator that comes before a literal value/grouped expression.
 pub(crate) type PrefixParseFn = fn(&mut Parser) -> ParseResult<Expression>;
@@ -24,16 +30,50 @@ impl Parser {
     /// Parses an expression from the input, using the Pratt Parsing technique.
     /// See: https://en.wikipedia.org/wiki/Pratt_parser
     /// Expects the current token to be the first token of the expression, i.e. a literal value/grouped expression/identifier.
+    /// Every expression counts as a level of nesting, so that deeply nested input errors instead of overflowing the stack.
+    ///
+    /// A generator can't suspend in the middle of an expression, so `yield` isn't allowed in one, unless
+    /// it is in the body of a function in it.
     pub(crate) fn parse_expression(&mut self, precedence: Precedence) -> ParseResult<Expression> {
+        let suspends = mem::replace(&mut self.suspends, false);
+        let expression = self.nested(|parser| parser.parse_expression_inner(precedence));
+        self.suspends = suspends;
+
+        expression
+    }
+
+    /// Parses an expression like `parse_expression`, without counting it as a level of nesting.
+    pub(super) fn parse_expression_inner(
+        &mut self,
+        precedence: Precedence,
+    ) -> ParseResult<Expression> {
         let Some(prefix) = self.prefix_parse_fns.get(&self.current_token.r#type) else {
-            return Err(Error::new(
-                format!("no prefix parse function for {} found", self.current_token.r#type),
-            ));
+            return Err(self.error(format!(
+                "no prefix parse function for {} found",
+                self.current_token.r#type
+            )));
         };
 
         // Mutable because we may need to modify the left-hand side of the expression later on in the loop.
-        let mut left = prefix(self)?;
+        let left = prefix(self)?;
 
+        // Each operator, call or index in the loop wraps `left` in another node without recursing, so a
+        // long chain of them builds a tree that is as deep as the chain is long. That isn't nesting, but
+        // every later pass recurses over the tree, so each link is counted until the whole chain is parsed.
+        let chain = self.chain;
+        let result = self.parse_infixes(precedence, left);
+        self.chain = chain;
+
+        result
+    }
+
+    /// Parses the operators, calls and indexes that follow `left`, for as long as they bind tighter than
+    /// `precedence`, counting each one as a link of the chain.
+    fn parse_infixes(
+        &mut self,
+        precedence: Precedence,
+        mut left: Expression,
+    ) -> ParseResult<Expression> {
         // If the next token is a semicolon, we've reached the end of the expression. Therefore, we can
         // return the left-hand side of the expression, and stop parsing.
         //
@@ -52,13 +92,15 @@ impl Parser {
         while !self.peek_token_is(TokenType::Semicolon) && precedence < self.peek_precedence() {
             let Some(infix) = self.infix_parse_fns.get(&self.peek_token.r#type).copied() else {
                 // If we don't have an infix parse function for the next token, we can return the left-hand side of
-                // the expression, because the statement has ended, and we need to parse the next statement. The 
+                // the expression, because the statement has ended, and we need to parse the next statement. The
                 // `parse_expression_statement` function will handle the remaining tokens.
                 return Ok(left);
             };
 
-            // Advance the token pointer.
+            // Advance the token pointer, and count the link before parsing its right-hand side, so that
+            // any chain in it (e.g. in call arguments) adds up with this one.
             self.next_token();
+            self.extend_chain()?;
 
             // Append the next expression to the current tree (`left`).
             left = infix(self, left)?;
@@ -68,12 +110,24 @@ impl Parser {
         Ok(left)
     }
 
-    /// Parses an identifier from the input. Expects the current token to be an identifier.
+    /// Parses an identifier from the input. Expects the current token to be an identifier. An identifier
+    /// right before a `{` is the name of the struct of a record literal, e.g. `Point { x: 1 }`.
+    ///
+    /// A `{` after anything else isn't a record, so that a hash literal statement can follow a statement
+    /// that ends with a block, e.g. `if (a) { b } {"k": 1}`.
     fn parse_identifier(&mut self) -> ParseResult<Expression> {
-        Ok(Expression::Identifier(Identifier {
+        let name = Identifier {
             token: self.current_token.clone(),
             value: self.current_token.literal.clone(),
-        }))
+            slots: None,
+        };
+
+        if !self.peek_token_is(TokenType::LBrace) {
+            return Ok(Expression::Identifier(name));
+        }
+
+        self.next_token();
+        self.parse_record(name)
     }
 
     /// Parses an integer from the input. Expects the current token to be an integer.
@@ -83,16 +137,21 @@ impl Parser {
         let value = match token.literal.parse::<i64>() {
             Ok(value) => value,
             Err(_) => {
-                return Err(Error::new(format!(
-                    "could not parse {} as integer",
-                    token.literal
-                )));
+                return Err(self.error(format!("could not parse {} as integer", token.literal)));
             }
         };
 
         Ok(Expression::Integer(IntegerLiteral { token, value }))
     }
 
+    /// Parses a string from the input. Expects the current token to be a string.
+    fn parse_string(&mut self) -> ParseResult<Expression> {
+        Ok(Expression::String(StringLiteral {
+            token: self.current_token.clone(),
+            value: self.current_token.literal.clone(),
+        }))
+    }
+
     /// Parses a boolean from the input. Expects the current token to be a boolean.
     fn parse_boolean(&mut self) -> ParseResult<Expression> {
         Ok(Expression::Boolean(BooleanLiteral {
@@ -130,7 +189,7 @@ impl Parser {
             r#type: match operator.as_str() {
                 "!" => PrefixOperatorType::Bang,
                 "-" => PrefixOperatorType::Neg,
-                _ => return Err(Error::new(format!("unknown prefix operator {}", operator))),
+                _ => return Err(self.error(format!("unknown prefix operator {}", operator))),
             },
         })
     }
@@ -175,11 +234,108 @@ impl Parser {
                 ">" => InfixOperatorType::Gt,
                 "==" => InfixOperatorType::Eq,
                 "!=" => InfixOperatorType::NotEq,
-                _ => return Err(Error::new(format!("unknown infix operator {}", operator))),
+                ".." => InfixOperatorType::Range,
+                _ => return Err(self.error(format!("unknown infix operator {}", operator))),
             },
         })
     }
 
+    /// Parses a member access expression from the input, e.g. `math.add`. Expects the current token to be a `.`
+    /// (TokenKind::Dot), and `left` to be the expression whose member is being accessed.
+    fn parse_member(&mut self, left: Expression) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
+
+        self.expect_peek(TokenType::Ident)?;
+
+        Ok(Expression::Member(MemberExpression {
+            token,
+            object: Box::new(left),
+            member: Identifier {
+                token: self.current_token.clone(),
+                value: self.current_token.literal.clone(),
+                slots: None,
+            },
+        }))
+    }
+
+    /// Parses a record literal from the input, e.g. `Point { x: 1, y: 2 }`. Expects the current token to be a `{`
+    /// (TokenKind::LBrace), and `name` to be the name of the struct that is being constructed.
+    fn parse_record(&mut self, name: Identifier) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
+        let mut fields: Vec<(Identifier, Expression)> = Vec::new();
+
+        // While the next token isn't a right brace, we have more fields that we need to parse.
+        while !self.peek_token_is(TokenType::RBrace) {
+            self.expect_peek(TokenType::Ident)?;
+
+            let field = Identifier {
+                token: self.current_token.clone(),
+                value: self.current_token.literal.clone(),
+                slots: None,
+            };
+
+            if fields.iter().any(|(other, _)| other.value == field.value) {
+                return Err(self.error(format!(
+                    "field {} is given more than once in {} literal",
+                    field, name
+                )));
+            }
+
+            // If the next token isn't a colon, we have an error.
+            self.expect_peek(TokenType::Colon)?;
+
+            // Advance to the next token so we can parse the value.
+            self.next_token();
+
+            let value = self.parse_expression(Precedence::Lowest)?;
+
+            fields.push((field, value));
+
+            // If the next token isn't a right brace, it has to be a comma separating the fields.
+            if !self.peek_token_is(TokenType::RBrace) {
+                self.expect_peek(TokenType::Comma)?;
+            }
+        }
+
+        // Advance to the next token, which is the right brace.
+        self.next_token();
+
+        Ok(Expression::Record(RecordLiteral {
+            token,
+            name,
+            fields,
+        }))
+    }
+
+    /// Parses a pipe expression from the input, e.g. `x |> f(y)`. Expects the current token to be a `|>` (TokenKind::Pipe).
+    /// There's no separate AST node for pipes: the value on the left is passed as the first argument to the call on the
+    /// right, so `x |> f(y)` is parsed as `f(x, y)`. If the right-hand side isn't a call, it is called with just the
+    /// value on the left, so `x |> f` is parsed as `f(x)`. Either way, the call is marked as piped, so that it can be written
+    /// back out as a pipe.
+    fn parse_pipe(&mut self, left: Expression) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
+
+        // Advance to the next token so we can parse the right-hand side. Parsing it with the pipe's own precedence
+        // makes pipes left-associative, so `x |> f |> g` is `g(f(x))`.
+        self.next_token();
+
+        let right = self.parse_expression(Precedence::Pipe)?;
+
+        Ok(Expression::Call(match right {
+            Expression::Call(mut call) => {
+                call.arguments.insert(0, left);
+                call.piped = true;
+                call
+            }
+            function => CallExpression {
+                token,
+                function: Box::new(function),
+                arguments: vec![left],
+                piped: true,
+            },
+        }))
+    }
+
     /// Parses a grouped expression from the input. e.g. `(5 + 5)`. Expects the current token to be a left parenthesis.
     fn parse_grouped(&mut self) -> ParseResult<Expression> {
         // Advance to the next token so we can parse the expression inside the parentheses.
@@ -194,19 +350,92 @@ impl Parser {
         Ok(expr)
     }
 
-    /// Parses an if expression from the input. e.g. `if (x < y) { x }`. Expects the current token to be an `if` keyword
-    /// (TokenKind::If).
-    fn parse_if(&mut self) -> ParseResult<Expression> {
+    /// Parses a try expression from the input. e.g. `try { f() } catch (e) { 0 } finally { g() }`. Expects the current
+    /// token to be a `try` keyword (TokenKind::Try). Either the `catch` clause or the `finally` block may be left out, but
+    /// not both.
+    fn parse_try(&mut self) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
+
+        // If the next token isn't a left brace, we have an error.
+        self.expect_peek(TokenType::LBrace)?;
+
+        let body = self.parse_block_statement()?;
+
+        let mut catch = None;
+        let mut finally = None;
+
+        if self.peek_token_is(TokenType::Catch) {
+            // Advance to the next token so that the current token is the `catch` keyword.
+            self.next_token();
+
+            let token = self.current_token.clone();
+
+            self.expect_peek(TokenType::LParen)?;
+            self.expect_peek(TokenType::Ident)?;
+
+            let parameter = Identifier {
+                token: self.current_token.clone(),
+                value: self.current_token.literal.clone(),
+                slots: None,
+            };
+
+            self.expect_peek(TokenType::RParen)?;
+            self.expect_peek(TokenType::LBrace)?;
+
+            catch = Some(Box::new(CatchClause {
+                token,
+                parameter,
+                body: self.parse_block_statement()?,
+            }));
+        }
+
+        if self.peek_token_is(TokenType::Finally) {
+            // Advance to the next token so that the current token is the `finally` keyword.
+            self.next_token();
+
+            self.expect_peek(TokenType::LBrace)?;
+
+            finally = Some(self.parse_block_statement()?);
+        }
+
+        if catch.is_none() && finally.is_none() {
+            return Err(Error::new(
+                format!(
+                    "expected catch or finally after try block, got {} instead",
+                    self.peek_token.r#type
+                ),
+                self.peek_token.position,
+            ));
+        }
+
+        Ok(Expression::Try(TryExpression {
+            token,
+            body,
+            catch,
+            finally,
+        }))
+    }
+
+    /// Parses a for loop from the input. e.g. `for ([key, value] in pairs) { f(key, value) }`. Expects the current token
+    /// to be a `for` keyword (TokenKind::For). The pattern can be any pattern that a `let` statement can bind.
+    fn parse_for(&mut self) -> ParseResult<Expression> {
         let token = self.current_token.clone();
 
         // If the next token isn't a left parenthesis, we have an error.
         self.expect_peek(TokenType::LParen)?;
 
-        // Advance to the next token so we can parse the condition expression.
+        // Advance to the next token so we can parse the pattern.
         self.next_token();
 
-        // Parse the condition expression.
-        let condition = self.parse_expression(Precedence::Lowest)?;
+        let pattern = self.parse_pattern()?;
+
+        // If the next token isn't an `in` keyword, we have an error.
+        self.expect_peek(TokenType::In)?;
+
+        // Advance to the next token so we can parse the iterable expression.
+        self.next_token();
+
+        let iterable = self.parse_expression(Precedence::Lowest)?;
 
         // If the next token isn't a right parenthesis, we have an error.
         self.expect_peek(TokenType::RParen)?;
@@ -214,77 +443,259 @@ impl Parser {
         // If the next token isn't a left brace, we have an error.
         self.expect_peek(TokenType::LBrace)?;
 
-        // Parse the consequence block.
-        let consequence = self.parse_block_statement()?;
+        let body = self.parse_block_statement()?;
 
-        // Parse the alternative block, if it exists.
-        let alternative = if self.peek_token_is(TokenType::Else) {
-            // Advance to the next token so we can parse the alternative block.
+        Ok(Expression::For(ForExpression {
+            token,
+            pattern: Box::new(pattern),
+            iterable: Box::new(iterable),
+            body,
+        }))
+    }
+
+    /// Parses an if expression from the input. e.g. `if (x < y) { x }`. Expects the current token to be an `if` keyword
+    /// (TokenKind::If). Any number of `else if (...) { ... }` branches may follow the consequence, optionally followed by
+    /// a final `else { ... }` block.
+    fn parse_if(&mut self) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
+
+        // Parse the condition and consequence block.
+        let (condition, consequence) = self.parse_if_branch()?;
+
+        let mut else_ifs = Vec::new();
+        let mut alternative = None;
+
+        // Parse the `else if` branches and the alternative block, if they exist. We keep looping for as long as
+        // there is an `else` token, and stop once we parse a plain `else` block, as that must be the last branch.
+        while self.peek_token_is(TokenType::Else) {
+            // Advance to the next token so that the current token is the `else` keyword.
             self.next_token();
 
-            // If the next token isn't a left brace, we have an error.
-            self.expect_peek(TokenType::LBrace)?;
+            if self.peek_token_is(TokenType::If) {
+                // Advance to the next token so that the current token is the `if` keyword.
+                self.next_token();
 
-            // Parse the alternative block.
-            Some(self.parse_block_statement()?)
-        } else {
-            None
-        };
+                let token = self.current_token.clone();
+
+                // Parse the branch's condition and consequence block.
+                let (condition, consequence) = self.parse_if_branch()?;
+
+                else_ifs.push(ElseIfBranch {
+                    token,
+                    condition: Box::new(condition),
+                    consequence,
+                });
+            } else {
+                // If the next token isn't a left brace, we have an error.
+                self.expect_peek(TokenType::LBrace)?;
+
+                // Parse the alternative block.
+                alternative = Some(self.parse_block_statement()?);
+
+                break;
+            }
+        }
 
         Ok(Expression::If(IfExpression {
             token,
             condition: Box::new(condition),
             consequence,
+            else_ifs,
             alternative,
         }))
     }
 
+    /// Parses the condition and consequence block of an `if` or `else if` branch. e.g. `(x < y) { x }`. Expects the
+    /// current token to be an `if` keyword (TokenKind::If).
+    fn parse_if_branch(&mut self) -> ParseResult<(Expression, BlockStatement)> {
+        // If the next token isn't a left parenthesis, we have an error.
+        self.expect_peek(TokenType::LParen)?;
+
+        // Advance to the next token so we can parse the condition expression.
+        self.next_token();
+
+        // Parse the condition expression.
+        let condition = self.parse_expression(Precedence::Lowest)?;
+
+        // If the next token isn't a right parenthesis, we have an error.
+        self.expect_peek(TokenType::RParen)?;
+
+        // If the next token isn't a left brace, we have an error.
+        self.expect_peek(TokenType::LBrace)?;
+
+        // Parse the consequence block.
+        let consequence = self.parse_block_statement()?;
+
+        Ok((condition, consequence))
+    }
+
     /// Parses a function literal from the input. e.g. `fn(x, y) { x + y; }`. Expects the current token to be a `fn` keyword
     /// (TokenKind::Fn).
     fn parse_function(&mut self) -> ParseResult<Expression> {
         let token = self.current_token.clone();
 
+        Ok(Expression::Function(
+            self.parse_function_parameters_and_body(token, None)?,
+        ))
+    }
+
+    /// Parses the parameters and body of a function, and builds a `FunctionLiteral` out of them with the given `fn`
+    /// token and name. This is shared between function literals and named function declarations. Expects the next
+    /// token to be a left parenthesis (TokenKind::LParen).
+    pub(crate) fn parse_function_parameters_and_body(
+        &mut self,
+        token: Token,
+        name: Option<Symbol>,
+    ) -> ParseResult<FunctionLiteral> {
         // If the next token isn't a left parenthesis, we have an error.
         self.expect_peek(TokenType::LParen)?;
 
         // Parse the function's parameters.
-        let parameters = self.parse_function_parameters()?;
+        let parameters = self.parse_function_parameters(TokenType::RParen)?;
 
         // If the next token isn't a left brace, we have an error.
         self.expect_peek(TokenType::LBrace)?;
 
-        // Parse the function's body.
-        let body = self.parse_block_statement()?;
+        // Parse the function's body, keeping track of whether it has a `yield` in it.
+        let (body, generator) = self.parse_function_body(Self::parse_block_statement)?;
 
-        Ok(Expression::Function(FunctionLiteral {
+        Ok(FunctionLiteral {
+            token,
+            name,
+            parameters,
+            body,
+            generator,
+            form: FunctionForm::Fn,
+        })
+    }
+
+    /// Parses the body of a function with `parse_body`, returning the body and whether it has a `yield` in it, which
+    /// makes the function a generator. A `yield` inside of a function that is nested in the body belongs to that
+    /// function instead.
+    fn parse_function_body<T>(
+        &mut self,
+        parse_body: impl FnOnce(&mut Self) -> ParseResult<T>,
+    ) -> ParseResult<(T, bool)> {
+        let outer = self.yields.replace(false);
+        let suspends = mem::replace(&mut self.suspends, true);
+        let body = parse_body(self);
+        self.suspends = suspends;
+        let generator = mem::replace(&mut self.yields, outer) == Some(true);
+
+        Ok((body?, generator))
+    }
+
+    /// Parses a macro literal from the input. e.g. `macro(a, b) { quote(unquote(a) + unquote(b)); }`. Expects the
+    /// current token to be a `macro` keyword (TokenKind::Macro). Macro literals take the same parameters as function
+    /// literals.
+    fn parse_macro(&mut self) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
+
+        // If the next token isn't a left parenthesis, we have an error.
+        self.expect_peek(TokenType::LParen)?;
+
+        // Parse the macro's parameters.
+        let parameters = self.parse_function_parameters(TokenType::RParen)?;
+
+        // If the next token isn't a left brace, we have an error.
+        self.expect_peek(TokenType::LBrace)?;
+
+        // Parse the macro's body. Macros aren't functions, so there can't be a `yield` in it.
+        let outer = self.yields.take();
+        let body = self.parse_block_statement();
+        self.yields = outer;
+        let body = body?;
+
+        Ok(Expression::Macro(MacroLiteral {
             token,
             parameters,
             body,
         }))
     }
 
-    /// Parses a function literal's parameters from the input. e.g. `fn(x, y) { x + y; }`. Expects the current token to be a
-    /// left parenthesis (TokenKind::LParen).
-    fn parse_function_parameters(&mut self) -> ParseResult<Vec<Identifier>> {
-        let mut identifiers: Vec<Identifier> = Vec::new();
+    /// Parses a short lambda from the input, e.g. `|x, y| x + y`. Expects the current token to be a bar (TokenKind::Bar).
+    /// The parameters can be anything that a function literal's parameters can be, and the body is a single expression.
+    fn parse_bar_lambda(&mut self) -> ParseResult<Expression> {
+        let position = self.current_token.position;
+
+        let parameters = self.parse_function_parameters(TokenType::Bar)?;
+
+        // Advance to the next token so we can parse the body.
+        self.next_token();
 
-        // If the next token is a right parenthesis, we have no parameters.
-        if self.peek_token_is(TokenType::RParen) {
+        self.make_lambda(position, parameters, FunctionForm::Bar)
+    }
+
+    /// Parses a short lambda with a single parameter from the input, e.g. `x => x * 2`. Expects the current token to be
+    /// a `=>` (TokenKind::Arrow), and `left` to be the parameter.
+    fn parse_arrow_lambda(&mut self, left: Expression) -> ParseResult<Expression> {
+        let Expression::Identifier(parameter) = left else {
+            return Err(self.error(format!(
+                "expected an identifier before =>, got {} instead",
+                left
+            )));
+        };
+
+        // Advance to the next token so we can parse the body.
+        self.next_token();
+
+        self.make_lambda(
+            parameter.token.position,
+            vec![Parameter::Required(parameter)],
+            FunctionForm::Arrow,
+        )
+    }
+
+    /// Parses the body of a short lambda, and builds a `FunctionLiteral` out of it and the `parameters`. The body is a
+    /// single expression, which becomes the only statement of the function's body. Short lambdas don't have a `fn`
+    /// token, so one is made up at `position`, the start of the lambda. Expects the current token to be the first token
+    /// of the body.
+    fn make_lambda(
+        &mut self,
+        position: Position,
+        parameters: Vec<Parameter>,
+        form: FunctionForm,
+    ) -> ParseResult<Expression> {
+        let token = Token::new(TokenType::Function, "fn").at(position);
+
+        let (body, generator) = self.parse_function_body(Self::parse_statement_expression)?;
+
+        Ok(Expression::Function(FunctionLiteral {
+            token: token.clone(),
+            name: None,
+            parameters,
+            generator,
+            form,
+            body: BlockStatement {
+                token,
+                statements: vec![Statement::Expression(ExpressionStatement {
+                    token: body.token().clone(),
+                    expression: body,
+                })],
+            },
+        }))
+    }
+
+    /// Parses a function literal's parameters from the input. e.g. `fn(x, y = 10, ...rest) { x + y; }`. Expects the
+    /// current token to be the token that opens the parameter list, and stops after the `end` token that closes it.
+    /// This is a right parenthesis (TokenKind::RParen) for function literals, and a bar (TokenKind::Bar) for short
+    /// lambdas.
+    fn parse_function_parameters(&mut self, end: TokenType) -> ParseResult<Vec<Parameter>> {
+        let mut parameters: Vec<Parameter> = Vec::new();
+
+        // If the next token is the end of the parameter list, we have no parameters.
+        if self.peek_token_is(end) {
             self.next_token();
-            return Ok(identifiers);
+            return Ok(parameters);
         }
 
         // Advance to the next token so we can parse the first parameter.
         self.next_token();
 
         // Parse the first parameter.
-        let Expression::Identifier(identifier) = self.parse_identifier()? else {
-            return Err(Error::new(
-                "Expected an identifier in function literal parameters",
-            ));
-        };
+        let parameter = self.parse_function_parameter(&parameters)?;
 
-        identifiers.push(identifier);
+        parameters.push(parameter);
 
         // Parse the rest of the parameters.
         // While the next token is a comma, we have more parameters that we need
@@ -299,19 +710,97 @@ impl Parser {
             self.next_token();
 
             // Parse the next parameter.
-            let Expression::Identifier(identifier) = self.parse_identifier()? else {
+            let parameter = self.parse_function_parameter(&parameters)?;
+
+            parameters.push(parameter);
+        }
+
+        // If the next token isn't the end of the parameter list, we have an error.
+        self.expect_peek(end)?;
+
+        Ok(parameters)
+    }
+
+    /// Parses a single function literal parameter from the input. e.g. `x`, `y = 10` or `...rest`. Expects the current
+    /// token to be an identifier or `...` (TokenKind::Ellipsis). This also makes sure that the parameter can come after
+    /// the `previous` parameters: its name can't be one of theirs, required parameters can't come after parameters with
+    /// default values, and nothing can come after a rest parameter.
+    fn parse_function_parameter(&mut self, previous: &[Parameter]) -> ParseResult<Parameter> {
+        if let Some(Parameter::Rest { name, .. }) = previous.last() {
+            return Err(self.error(format!(
+                "rest parameter ...{} must be the last parameter",
+                name
+            )));
+        }
+
+        // Rest parameters start with a `...`, and collect all of the remaining arguments.
+        if self.cur_token_is(TokenType::Ellipsis) {
+            let token = self.current_token.clone();
+
+            self.expect_peek(TokenType::Ident)?;
+
+            let name = self.parse_parameter_name(previous)?;
+
+            if self.peek_token_is(TokenType::Assign) {
                 return Err(Error::new(
-                    "Expected an identifier in function literal parameters",
+                    format!("rest parameter ...{} cannot have a default value", name),
+                    self.peek_token.position,
                 ));
-            };
+            }
 
-            identifiers.push(identifier);
+            return Ok(Parameter::Rest { token, name });
         }
 
-        // If the next token isn't a right parenthesis, we have an error.
-        self.expect_peek(TokenType::RParen)?;
+        let name = self.parse_parameter_name(previous)?;
+
+        // If the name is followed by a `=`, the parameter has a default value.
+        if self.peek_token_is(TokenType::Assign) {
+            // Advance twice, skipping over the `=`, so that the current token is the start of the default value.
+            self.next_token();
+            self.next_token();
+
+            let value = self.parse_expression(Precedence::Lowest)?;
+
+            return Ok(Parameter::Default {
+                name,
+                value: Box::new(value),
+            });
+        }
+
+        if previous
+            .iter()
+            .any(|parameter| matches!(parameter, Parameter::Default { .. }))
+        {
+            return Err(self.error(format!(
+                "required parameter {} cannot come after a parameter with a default value",
+                name
+            )));
+        }
 
-        Ok(identifiers)
+        Ok(Parameter::Required(name))
+    }
+
+    /// Parses the name of a function literal parameter from the input. Expects the current token to be an identifier
+    /// that isn't the name of any of the `previous` parameters, as a call couldn't bind both of them.
+    fn parse_parameter_name(&mut self, previous: &[Parameter]) -> ParseResult<Identifier> {
+        if !self.cur_token_is(TokenType::Ident) {
+            return Err(self.error("Expected an identifier in function literal parameters"));
+        }
+
+        let name = self.current_token.literal.clone();
+
+        if previous
+            .iter()
+            .any(|parameter| parameter.name().value == *name)
+        {
+            return Err(self.error(format!("duplicate parameter {}", name)));
+        }
+
+        Ok(Identifier {
+            token: self.current_token.clone(),
+            value: name.clone(),
+            slots: None,
+        })
     }
 
     /// Parses a call expression from the input. e.g. `add(1, 2)`. Expects the current token to be a left-parenthesis.
@@ -325,46 +814,139 @@ impl Parser {
             token,
             function: Box::new(function),
             arguments,
+            piped: false,
         }))
     }
 
-    /// Parses a call expression's arguments from the input. e.g. `add(1, 2)`. Expects the current token to be a left
-    /// parenthesis (TokenKind::LParen).
+    /// Parses a call expression's arguments from the input. e.g. `add(1, 2, ...xs)`. Expects the current token to be a
+    /// left parenthesis (TokenKind::LParen).
     fn parse_call_arguments(&mut self) -> ParseResult<Vec<Expression>> {
-        let mut arguments: Vec<Expression> = Vec::new();
+        self.parse_expression_list(TokenType::RParen)
+    }
+
+    /// Parses an array literal from the input. e.g. `[1, 2 * 3, ...xs]`. Expects the current token to be a left bracket
+    /// (TokenKind::LBracket).
+    fn parse_array(&mut self) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
 
-        // If the next token is a right parenthesis, we have no arguments.
-        if self.peek_token_is(TokenType::RParen) {
+        let elements = self.parse_expression_list(TokenType::RBracket)?;
+
+        Ok(Expression::Array(ArrayLiteral { token, elements }))
+    }
+
+    /// Parses an index expression from the input. e.g. `xs[1]`. Expects the current token to be a left bracket
+    /// (TokenKind::LBracket).
+    fn parse_index(&mut self, left: Expression) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
+
+        // Advance to the next token so we can parse the index.
+        self.next_token();
+
+        let index = self.parse_expression(Precedence::Lowest)?;
+
+        // If the next token isn't a right bracket, we have an error.
+        self.expect_peek(TokenType::RBracket)?;
+
+        Ok(Expression::Index(IndexExpression {
+            token,
+            left: Box::new(left),
+            index: Box::new(index),
+        }))
+    }
+
+    /// Parses a hash literal from the input. e.g. `{"one": 1, "two": 2}`. Expects the current token to be a left brace
+    /// (TokenKind::LBrace).
+    fn parse_hash(&mut self) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
+        let mut pairs = Vec::new();
+
+        // While the next token isn't a right brace, we have more pairs that we need to parse.
+        while !self.peek_token_is(TokenType::RBrace) {
+            // Advance to the next token so we can parse the key.
             self.next_token();
-            return Ok(arguments);
+
+            let key = self.parse_expression(Precedence::Lowest)?;
+
+            // If the next token isn't a colon, we have an error.
+            self.expect_peek(TokenType::Colon)?;
+
+            // Advance to the next token so we can parse the value.
+            self.next_token();
+
+            let value = self.parse_expression(Precedence::Lowest)?;
+
+            pairs.push((key, value));
+
+            // If the next token isn't a right brace, it has to be a comma separating the pairs.
+            if !self.peek_token_is(TokenType::RBrace) {
+                self.expect_peek(TokenType::Comma)?;
+            }
         }
 
-        // Advance to the next token so we can parse the first argument.
+        // Advance to the next token, which is the right brace.
         self.next_token();
 
-        // Parse the first argument.
-        arguments.push(self.parse_expression(Precedence::Lowest)?);
+        Ok(Expression::Hash(HashLiteral { token, pairs }))
+    }
+
+    /// Parses a comma-separated list of expressions from the input, up to and including the `end` token. This is used
+    /// for call arguments and array literal elements, both of which may also contain spreads (e.g. `...xs`). Expects the
+    /// current token to be the token that opens the list.
+    fn parse_expression_list(&mut self, end: TokenType) -> ParseResult<Vec<Expression>> {
+        let mut list: Vec<Expression> = Vec::new();
+
+        // If the next token is the end token, the list is empty.
+        if self.peek_token_is(end) {
+            self.next_token();
+            return Ok(list);
+        }
 
-        // Parse the rest of the arguments.
-        // While the next token is a comma, we have more arguments that we need
+        // Advance to the next token so we can parse the first element.
+        self.next_token();
+
+        // Parse the first element.
+        list.push(self.parse_list_element()?);
+
+        // Parse the rest of the elements.
+        // While the next token is a comma, we have more elements that we need
         // to parse.
         while self.peek_token_is(TokenType::Comma) {
             // Advance to the next token, which is a comma. The next line skips the comma
-            // so we can parse the next argument.
+            // so we can parse the next element.
             self.next_token();
 
             // Advance to the next token so we skip the comma, making the current
-            // token the argument.
+            // token the element.
             self.next_token();
 
-            // Parse the next argument.
-            arguments.push(self.parse_expression(Precedence::Lowest)?);
+            // Parse the next element.
+            list.push(self.parse_list_element()?);
         }
 
-        // If the next token isn't a right parenthesis, we have an error.
-        self.expect_peek(TokenType::RParen)?;
+        // If the next token isn't the end token, we have an error.
+        self.expect_peek(end)?;
+
+        Ok(list)
+    }
+
+    /// Parses a single element of an expression list, which is either an expression or a spread of an expression.
+    /// Expects the current token to be the first token of the element.
+    fn parse_list_element(&mut self) -> ParseResult<Expression> {
+        if !self.cur_token_is(TokenType::Ellipsis) {
+            return self.parse_expression(Precedence::Lowest);
+        }
+
+        let token = self.current_token.clone();
+
+        // Advance to the next token so we can parse the expression that is being spread.
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::Lowest)?;
 
-        Ok(arguments)
+        Ok(Expression::Spread(SpreadExpression {
+            token,
+            value: Box::new(value),
+        }))
     }
 }
 
@@ -395,13 +977,21 @@ impl Parser {
         // Registering prefix tokens.
         self.register_prefix(TokenType::Ident, Parser::parse_identifier);
         self.register_prefix(TokenType::Int, Parser::parse_integer);
+        self.register_prefix(TokenType::String, Parser::parse_string);
         self.register_prefix(TokenType::True, Parser::parse_boolean);
         self.register_prefix(TokenType::False, Parser::parse_boolean);
         self.register_prefix(TokenType::Bang, Parser::parse_prefix);
         self.register_prefix(TokenType::Minus, Parser::parse_prefix);
         self.register_prefix(TokenType::LParen, Parser::parse_grouped);
         self.register_prefix(TokenType::If, Parser::parse_if);
+        self.register_prefix(TokenType::Try, Parser::parse_try);
+        self.register_prefix(TokenType::For, Parser::parse_for);
         self.register_prefix(TokenType::Function, Parser::parse_function);
+        self.register_prefix(TokenType::LBracket, Parser::parse_array);
+        self.register_prefix(TokenType::LBrace, Parser::parse_hash);
+        self.register_prefix(TokenType::Match, Parser::parse_match);
+        self.register_prefix(TokenType::Bar, Parser::parse_bar_lambda);
+        self.register_prefix(TokenType::Macro, Parser::parse_macro);
 
         // Registering infix tokens.
         self.register_infix(TokenType::Plus, Self::parse_infix);
@@ -412,6 +1002,11 @@ impl Parser {
         self.register_infix(TokenType::NotEq, Self::parse_infix);
         self.register_infix(TokenType::Lt, Self::parse_infix);
         self.register_infix(TokenType::Gt, Self::parse_infix);
+        self.register_infix(TokenType::DotDot, Self::parse_infix);
         self.register_infix(TokenType::LParen, Self::parse_call);
+        self.register_infix(TokenType::LBracket, Self::parse_index);
+        self.register_infix(TokenType::Dot, Self::parse_member);
+        self.register_infix(TokenType::Pipe, Self::parse_pipe);
+        self.register_infix(TokenType::Arrow, Self::parse_arrow_lambda);
     }
 }
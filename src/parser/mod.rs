@@ -1,11 +1,12 @@
 pub mod errors;
 mod expressions;
-mod operators;
+pub(crate) mod operators;
+mod patterns;
 mod statements;
 mod tests;
 
 use self::{
-    errors::Error,
+    errors::{Error, Warning},
     expressions::{InfixParseFn, PrefixParseFn},
 };
 use crate::{
@@ -15,6 +16,40 @@ use crate::{
 };
 use std::collections::HashMap;
 
+/// The maximum nesting depth that a `Parser` allows by default. See `Parser::set_max_depth`.
+pub const DEFAULT_MAX_DEPTH: usize = 1_000;
+
+/// The maximum chain length that a `Parser` allows by default. See `Parser::set_max_chain`.
+pub const DEFAULT_MAX_CHAIN: usize = 5_000;
+
+/// How little stack is left when the parser grows the stack, before going a level of nesting deeper.
+const STACK_RED_ZONE: usize = 64 * 1024;
+
+/// How much the parser grows the stack by at a time.
+const STACK_GROWTH: usize = 1024 * 1024;
+
+/// The limits on how deeply a `Parser` lets code nest, so that they can be passed around together,
+/// e.g. to parse imported modules with the same limits as the file that imports them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The deepest that expressions, patterns and blocks are allowed to be nested. See
+    /// `Parser::set_max_depth`.
+    pub max_depth: usize,
+
+    /// The most operators, calls and indexes that are allowed to wrap an expression. See
+    /// `Parser::set_max_chain`.
+    pub max_chain: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_chain: DEFAULT_MAX_CHAIN,
+        }
+    }
+}
+
 /// The `ParseResult` type is a shorthand for a `Result` type that returns a `()` error type. This is
 /// used to simplify the return type of the `parse_*` functions.
 type ParseResult<T> = Result<T, Error>;
@@ -37,6 +72,46 @@ pub struct Parser {
     /// The `infix_parse_fns` field is a map of token types to infix parse functions. This is used to
     /// determine how to parse a given token for an infix expression.
     infix_parse_fns: HashMap<TokenType, InfixParseFn>,
+
+    /// The `warnings` field is the warnings that the parser has found so far.
+    warnings: Vec<Warning>,
+
+    /// The `depth` field is how deeply nested the expression or pattern currently being parsed is.
+    depth: usize,
+
+    /// The `max_depth` field is the deepest that expressions and patterns are allowed to be nested.
+    max_depth: usize,
+
+    /// The `chain` field is how many operators, calls and indexes wrap the expression currently being
+    /// parsed, counting those of every chain that it is a part of.
+    chain: usize,
+
+    /// The `max_chain` field is the most operators, calls and indexes that are allowed to wrap an
+    /// expression.
+    max_chain: usize,
+
+    /// The `exceeded` field is whether the nesting depth or chain length has gone over its limit. That
+    /// stops parsing at the error, as going on would only run into the limit again and again, e.g. for
+    /// each of thousands of nested `if`s.
+    exceeded: bool,
+
+    /// The `yields` field is whether the body of the function currently being parsed has a `yield` in
+    /// it so far, or `None` if no function body is being parsed, as `yield` isn't allowed outside of one.
+    yields: Option<bool>,
+
+    /// The `suspends` field is whether a `yield` parsed now could suspend the generator that it is in,
+    /// which it can only do as a statement of the function's body, or of the blocks and arms of the
+    /// `if`, `for`, `try` and `match` statements in it. Anywhere else, e.g. in a `let` statement's value
+    /// or a call's arguments, it is an error.
+    suspends: bool,
+
+    /// The `yield_count` field is how many `yield`s have been parsed so far, to tell whether an
+    /// expression has any in it.
+    yield_count: usize,
+
+    /// The `blocks` field is how many blocks the statement currently being parsed is inside of, as
+    /// `export` is only allowed at the top level of a module, outside of any block.
+    blocks: usize,
 }
 
 /// Public API for the `Parser` struct.
@@ -52,6 +127,20 @@ impl Parser {
 
             prefix_parse_fns: HashMap::new(),
             infix_parse_fns: HashMap::new(),
+
+            warnings: vec![],
+
+            depth: 0,
+            max_depth: DEFAULT_MAX_DEPTH,
+            chain: 0,
+            max_chain: DEFAULT_MAX_CHAIN,
+            exceeded: false,
+
+            yields: None,
+            suspends: false,
+            yield_count: 0,
+
+            blocks: 0,
         };
 
         // Register tokens with their respective parse functions.
@@ -64,7 +153,9 @@ impl Parser {
         parser
     }
 
-    /// Parses the input from the `Lexer` into an AST.
+    /// Parses the input from the `Lexer` into an AST. After most errors, parsing picks up again at the
+    /// next statement, so that all of them are reported, but it stops at an error for going over the
+    /// nesting or chain limit.
     pub fn parse_program(&mut self) -> Result<Program, Vec<Error>> {
         let mut program = Program { statements: vec![] };
         let mut errors = vec![];
@@ -76,6 +167,11 @@ impl Parser {
                 program.statements.push(stmt);
             } else if let Err(err) = stmt {
                 errors.push(err.clone());
+
+                if self.exceeded {
+                    break;
+                }
+
                 self.synchronize();
             }
 
@@ -88,10 +184,86 @@ impl Parser {
             Err(errors)
         }
     }
+
+    /// Sets the deepest that expressions, patterns and blocks are allowed to be nested, which defaults
+    /// to `DEFAULT_MAX_DEPTH`. As the parser recurses for each level of nesting, this stops hostile
+    /// input like thousands of `(`s from overflowing the stack, by making it a parse error instead.
+    ///
+    /// The parser grows the stack when it runs low, but the passes after parsing recurse over the tree
+    /// too, so the defaults of this and `set_max_chain` need more stack than the 2 MiB that a thread
+    /// gets by default. `main` runs code on a 256 MiB stack, which they fit in even in a debug build.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Sets the most operators, calls and indexes that are allowed to wrap an expression, which
+    /// defaults to `DEFAULT_MAX_CHAIN`. A chain like `1 + 2 + 3` is parsed in a loop rather than by
+    /// nesting, so it isn't limited by `set_max_depth`, but each link wraps the tree in another node.
+    /// This stops input like a million `+ 1`s from building a tree too deep for the passes after
+    /// parsing to recurse over. The links of chains nested in each other (e.g. in call arguments) add
+    /// up, as they wrap each other too.
+    pub fn set_max_chain(&mut self, max_chain: usize) {
+        self.max_chain = max_chain;
+    }
+
+    /// Sets both of the `limits` on how deeply code can nest at once.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.set_max_depth(limits.max_depth);
+        self.set_max_chain(limits.max_chain);
+    }
+
+    /// Returns the warnings found while parsing. These don't stop the program from being parsed, so
+    /// they are reported separately from errors.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
 }
 
 /// Private API for the `Parser` struct.
 impl Parser {
+    /// Runs `parse` one level of nesting deeper than the current one, erroring instead if that is deeper
+    /// than the maximum nesting depth. The stack is grown on the heap when it runs low, so that the
+    /// limit can be set higher than the thread's stack has room for.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        self.descend()?;
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || parse(self));
+        self.depth -= 1;
+
+        result
+    }
+
+    /// Goes one level of nesting deeper, erroring instead if that is deeper than the maximum nesting
+    /// depth. The caller is responsible for going back up.
+    fn descend(&mut self) -> ParseResult<()> {
+        if self.depth >= self.max_depth {
+            self.exceeded = true;
+
+            return Err(self.error(format!(
+                "maximum nesting depth of {} exceeded",
+                self.max_depth
+            )));
+        }
+
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Adds a link to the chain that is being parsed, erroring instead if that makes it longer than
+    /// the maximum chain length. The caller is responsible for shortening it again.
+    fn extend_chain(&mut self) -> ParseResult<()> {
+        if self.chain >= self.max_chain {
+            self.exceeded = true;
+
+            return Err(self.error(format!(
+                "maximum chain length of {} exceeded",
+                self.max_chain
+            )));
+        }
+
+        self.chain += 1;
+        Ok(())
+    }
+
     /// When parsing code, sometimes we run into parsing errors. This function, `synchronize`, helps to recover
     /// from parsing errors by consuming code input until we reach a token that we can continue parsing from (i.e.
     /// a semicolon). This does not guarantee that the new position creates valid code, but does help in preventing
@@ -159,12 +331,20 @@ impl Parser {
         }
     }
 
-    /// The `peek_error` method creates a new `Error` with a given message, and adds it to the `errors`
-    /// field.
+    /// Creates a new `Error` with the given `message`, about the current token.
+    fn error<S: ToString>(&self, message: S) -> Error {
+        Error::new(message, self.current_token.position)
+    }
+
+    /// The `peek_error` method creates the `Error` for a peek token that isn't of the expected type,
+    /// about the peek token.
     fn peek_error(&mut self, tt: TokenType) -> Error {
-        Error::new(format!(
-            "expected next token to be {}, got {} instead",
-            tt, self.peek_token.r#type
-        ))
+        Error::new(
+            format!(
+                "expected next token to be {}, got {} instead",
+                tt, self.peek_token.r#type
+            ),
+            self.peek_token.position,
+        )
     }
 }
@@ -4,25 +4,34 @@ use crate::token::TokenType;
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Precedence {
     Lowest,
+    Lambda,   // x => x
+    Pipe,     // x |> f(y)
     Equals,   // ==
     Ordering, // > or <. Note that in the original implementation, this is called `LessGreater`.
+    Range,    // 0..10
     Sum,      // +
     Product,  // *
     Prefix,   // -X or !X
-    Call,     // myFunction(X)
+    Call,     // myFunction(X) or Point { x: X }
+    Index,    // array[index] or module.member
 }
 
 pub fn token_precedence(token_type: &TokenType) -> Precedence {
     match token_type {
+        TokenType::Arrow => Precedence::Lambda,
+        TokenType::Pipe => Precedence::Pipe,
         TokenType::Eq => Precedence::Equals,
         TokenType::NotEq => Precedence::Equals,
         TokenType::Lt => Precedence::Ordering,
         TokenType::Gt => Precedence::Ordering,
+        TokenType::DotDot => Precedence::Range,
         TokenType::Plus => Precedence::Sum,
         TokenType::Minus => Precedence::Sum,
         TokenType::Slash => Precedence::Product,
         TokenType::Star => Precedence::Product,
         TokenType::LParen => Precedence::Call,
+        TokenType::LBracket => Precedence::Index,
+        TokenType::Dot => Precedence::Index,
         _ => Precedence::Lowest,
     }
 }
@@ -0,0 +1,272 @@
+use super::{
+    errors::{Error, Warning},
+    operators::Precedence,
+    ParseResult, Parser,
+};
+use crate::{
+    ast::{
+        expressions::{Expression, Identifier, MatchArm, MatchExpression},
+        patterns::{ArrayPattern, HashPattern, Pattern},
+    },
+    token::TokenType,
+};
+use std::collections::HashSet;
+
+impl Parser {
+    /// Parses a pattern from the input, e.g. `x`, `_`, `1`, `[a, b, ...rest]` or `{"name": n}`. Expects the
+    /// current token to be the first token of the pattern. Like the parameters of a function, a pattern
+    /// can't bind the same name more than once, as only one of the values could end up bound to it.
+    pub(crate) fn parse_pattern(&mut self) -> ParseResult<Pattern> {
+        let pattern = self.parse_subpattern()?;
+        let mut names = HashSet::new();
+
+        for identifier in pattern.identifiers() {
+            if !names.insert(&identifier.value) {
+                return Err(Error::new(
+                    format!("duplicate name {} in pattern", identifier),
+                    identifier.token.position,
+                ));
+            }
+        }
+
+        Ok(pattern)
+    }
+
+    /// Parses a pattern like `parse_pattern`, as a part of a bigger pattern, whose names are checked
+    /// all together.
+    fn parse_subpattern(&mut self) -> ParseResult<Pattern> {
+        self.nested(Self::parse_pattern_inner)
+    }
+
+    /// Parses a pattern like `parse_pattern`, without counting it as a level of nesting.
+    fn parse_pattern_inner(&mut self) -> ParseResult<Pattern> {
+        match self.current_token.r#type {
+            TokenType::Ident if self.current_token.literal == "_" => {
+                Ok(Pattern::Wildcard(self.current_token.clone()))
+            }
+            TokenType::Ident => Ok(Pattern::Identifier(self.parse_pattern_identifier()?)),
+            TokenType::Int | TokenType::String | TokenType::True | TokenType::False => {
+                Ok(Pattern::Literal(self.parse_expression(Precedence::Prefix)?))
+            }
+            // Negative integer literals, e.g. `-1`.
+            TokenType::Minus if self.peek_token_is(TokenType::Int) => {
+                Ok(Pattern::Literal(self.parse_expression(Precedence::Prefix)?))
+            }
+            TokenType::LBracket => Ok(Pattern::Array(self.parse_array_pattern()?)),
+            TokenType::LBrace => Ok(Pattern::Hash(self.parse_hash_pattern()?)),
+            _ => Err(self.error(format!(
+                "expected an identifier, literal, array pattern or hash pattern, got {} instead",
+                self.current_token.r#type
+            ))),
+        }
+    }
+
+    /// Parses an identifier in a pattern. Expects the current token to be an identifier.
+    fn parse_pattern_identifier(&mut self) -> ParseResult<Identifier> {
+        if !self.cur_token_is(TokenType::Ident) {
+            return Err(self.error(format!(
+                "expected an identifier in pattern, got {} instead",
+                self.current_token.r#type
+            )));
+        }
+
+        Ok(Identifier {
+            token: self.current_token.clone(),
+            value: self.current_token.literal.clone(),
+            slots: None,
+        })
+    }
+
+    /// Parses an array pattern from the input, e.g. `[a, [b, c], ...rest]`. Expects the current token to be a left
+    /// bracket (TokenKind::LBracket).
+    fn parse_array_pattern(&mut self) -> ParseResult<ArrayPattern> {
+        let token = self.current_token.clone();
+        let mut elements = Vec::new();
+        let mut rest = None;
+
+        // If the next token is a right bracket, the pattern is empty.
+        if self.peek_token_is(TokenType::RBracket) {
+            self.next_token();
+            return Ok(ArrayPattern {
+                token,
+                elements,
+                rest,
+            });
+        }
+
+        loop {
+            // Advance to the next token so we can parse the next element (skipping over the `[` or `,`).
+            self.next_token();
+
+            if self.cur_token_is(TokenType::Ellipsis) {
+                // Advance to the next token so that the current token is the rest element's name.
+                self.next_token();
+
+                let name = self.parse_pattern_identifier()?;
+
+                // The rest element collects everything that is left, so it has to be last.
+                if self.peek_token_is(TokenType::Comma) {
+                    return Err(self.error(format!(
+                        "rest element ...{} must be the last element of an array pattern",
+                        name
+                    )));
+                }
+
+                rest = Some(name);
+
+                break;
+            }
+
+            elements.push(self.parse_subpattern()?);
+
+            if !self.peek_token_is(TokenType::Comma) {
+                break;
+            }
+
+            // Advance to the next token, which is a comma.
+            self.next_token();
+        }
+
+        // If the next token isn't a right bracket, we have an error.
+        self.expect_peek(TokenType::RBracket)?;
+
+        Ok(ArrayPattern {
+            token,
+            elements,
+            rest,
+        })
+    }
+
+    /// Parses a hash pattern from the input, e.g. `{"name": n, "point": [x, y]}`. Expects the current token to be a
+    /// left brace (TokenKind::LBrace).
+    fn parse_hash_pattern(&mut self) -> ParseResult<HashPattern> {
+        let token = self.current_token.clone();
+        let mut pairs = Vec::new();
+
+        // While the next token isn't a right brace, we have more pairs that we need to parse.
+        while !self.peek_token_is(TokenType::RBrace) {
+            // Advance to the next token so we can parse the key.
+            self.next_token();
+
+            let key = self.parse_expression(Precedence::Lowest)?;
+
+            // If the next token isn't a colon, we have an error.
+            self.expect_peek(TokenType::Colon)?;
+
+            // Advance to the next token so we can parse the value's pattern.
+            self.next_token();
+
+            let pattern = self.parse_subpattern()?;
+
+            pairs.push((key, pattern));
+
+            // If the next token isn't a right brace, it has to be a comma separating the pairs.
+            if !self.peek_token_is(TokenType::RBrace) {
+                self.expect_peek(TokenType::Comma)?;
+            }
+        }
+
+        // Advance to the next token, which is the right brace.
+        self.next_token();
+
+        Ok(HashPattern { token, pairs })
+    }
+
+    /// Parses a match expression from the input, e.g. `match (x) { 0 => "zero", n if n < 0 => "negative", _ => x }`.
+    /// Expects the current token to be a `match` keyword (TokenKind::Match). Arms are separated by commas, and a
+    /// trailing comma is allowed. Any arm that can never match because of an earlier arm is reported as a warning.
+    pub(crate) fn parse_match(&mut self) -> ParseResult<Expression> {
+        let token = self.current_token.clone();
+
+        // If the next token isn't a left parenthesis, we have an error.
+        self.expect_peek(TokenType::LParen)?;
+
+        // Advance to the next token so we can parse the subject expression.
+        self.next_token();
+
+        let subject = self.parse_expression(Precedence::Lowest)?;
+
+        // If the next token isn't a right parenthesis, we have an error.
+        self.expect_peek(TokenType::RParen)?;
+
+        // If the next token isn't a left brace, we have an error.
+        self.expect_peek(TokenType::LBrace)?;
+
+        let mut arms = Vec::new();
+
+        // While the next token isn't a right brace, we have more arms that we need to parse.
+        while !self.peek_token_is(TokenType::RBrace) {
+            // Advance to the next token so we can parse the arm's pattern.
+            self.next_token();
+
+            let arm = self.parse_match_arm()?;
+
+            self.check_reachable(&arms, &arm);
+
+            arms.push(arm);
+
+            // If the next token isn't a right brace, it has to be a comma separating the arms.
+            if !self.peek_token_is(TokenType::RBrace) {
+                self.expect_peek(TokenType::Comma)?;
+            }
+        }
+
+        // Advance to the next token, which is the right brace.
+        self.next_token();
+
+        Ok(Expression::Match(MatchExpression {
+            token,
+            subject: Box::new(subject),
+            arms,
+        }))
+    }
+
+    /// Parses a single arm of a match expression, e.g. `[x, ...rest] if x > 0 => x`. Expects the current token to
+    /// be the first token of the arm's pattern.
+    fn parse_match_arm(&mut self) -> ParseResult<MatchArm> {
+        let pattern = self.parse_pattern()?;
+
+        let mut guard = None;
+
+        if self.peek_token_is(TokenType::If) {
+            // Advance twice, so that the current token is the first token of the guard (skipping over the `if`).
+            self.next_token();
+            self.next_token();
+
+            // The guard is parsed with `Lambda` precedence, so that the arm's `=>` isn't mistaken for a short lambda.
+            guard = Some(Box::new(self.parse_expression(Precedence::Lambda)?));
+        }
+
+        // If the next token isn't a `=>`, we have an error.
+        self.expect_peek(TokenType::Arrow)?;
+
+        // Advance to the next token so we can parse the arm's body.
+        self.next_token();
+
+        let body = self.parse_statement_expression()?;
+
+        Ok(MatchArm {
+            pattern,
+            guard,
+            body: Box::new(body),
+        })
+    }
+
+    /// Adds a warning if `arm` can never match, because one of the `previous` arms (without a guard) already
+    /// matches every value that it would.
+    fn check_reachable(&mut self, previous: &[MatchArm], arm: &MatchArm) {
+        let covering = previous
+            .iter()
+            .find(|previous| previous.guard.is_none() && previous.pattern.covers(&arm.pattern));
+
+        if let Some(covering) = covering {
+            self.warnings.push(Warning::new(
+                format!(
+                    "unreachable match arm: {} is already matched by the earlier arm {}",
+                    arm.pattern, covering.pattern
+                ),
+                arm.pattern.position(),
+            ));
+        }
+    }
+}
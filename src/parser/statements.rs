@@ -1,20 +1,37 @@
-use super::{operators::Precedence, ParseResult, Parser};
+use super::{errors::Error, operators::Precedence, ParseResult, Parser};
 use crate::{
     ast::{
-        expressions::Identifier,
+        expressions::{Expression, Identifier, StringLiteral},
+        patterns::Pattern,
         statements::{
-            BlockStatement, ExpressionStatement, LetStatement, ReturnStatement, Statement,
+            BlockStatement, ExportStatement, ExpressionStatement, FunctionStatement,
+            ImportStatement, LetStatement, ReturnStatement, Statement, StructStatement,
+            ThrowStatement, YieldStatement,
         },
     },
     token::TokenType,
 };
 
+/// The error for a `yield` that is somewhere a generator can't suspend at.
+const YIELD_POSITION_ERROR: &str =
+    "yield is only allowed in a generator's body, and the if, for, try and match statements in it";
+
 impl Parser {
     /// The `parse_statement` method parses a single statement from the input.
     pub(crate) fn parse_statement(&mut self) -> ParseResult<Statement> {
         match self.current_token.r#type {
             TokenType::Let => Ok(Statement::Let(self.parse_let_statement()?)),
             TokenType::Return => Ok(Statement::Return(self.parse_return_statement()?)),
+            TokenType::Throw => Ok(Statement::Throw(self.parse_throw_statement()?)),
+            TokenType::Yield => Ok(Statement::Yield(self.parse_yield_statement()?)),
+            // A `fn` followed by an identifier is a named function declaration. Otherwise, it is a
+            // function literal in an expression statement (e.g. an immediately-called `fn(x) { x }(5)`).
+            TokenType::Function if self.peek_token_is(TokenType::Ident) => {
+                Ok(Statement::Function(self.parse_function_statement()?))
+            }
+            TokenType::Import => Ok(Statement::Import(self.parse_import_statement()?)),
+            TokenType::Export => Ok(Statement::Export(self.parse_export_statement()?)),
+            TokenType::Struct => Ok(Statement::Struct(self.parse_struct_statement()?)),
             _ => Ok(Statement::Expression(self.parse_expression_statement()?)),
         }
     }
@@ -24,12 +41,10 @@ impl Parser {
     fn parse_let_statement(&mut self) -> ParseResult<LetStatement> {
         let token = self.current_token.clone();
 
-        self.expect_peek(TokenType::Ident)?;
+        // Advance to the next token so we can parse the pattern that is being assigned to.
+        self.next_token();
 
-        let name = Identifier {
-            token: self.current_token.clone(),
-            value: self.current_token.literal.clone(),
-        };
+        let pattern = self.parse_pattern()?;
 
         // Need to check for `TokenType::Assign` here.
         self.expect_peek(TokenType::Assign)?;
@@ -37,7 +52,14 @@ impl Parser {
         // Advance, as the current token is an `Assign`.
         self.next_token();
 
-        let value = self.parse_expression(Precedence::Lowest)?;
+        let mut value = self.parse_expression(Precedence::Lowest)?;
+
+        // If we are directly binding a function literal, name the function after the binding, so that
+        // the function knows its own name, in the same way that `fn name(...) { ... }` would.
+        if let (Pattern::Identifier(name), Expression::Function(function)) = (&pattern, &mut value)
+        {
+            function.name.get_or_insert(name.value.clone());
+        }
 
         // Expect a semicolon at the end of the statement.
         self.expect_peek(TokenType::Semicolon)?;
@@ -47,7 +69,7 @@ impl Parser {
 
         Ok(LetStatement {
             token,
-            name,
+            pattern,
             value: Box::new(value),
         })
     }
@@ -61,8 +83,10 @@ impl Parser {
 
         let return_value = self.parse_expression(Precedence::Lowest)?;
 
-        while !self.cur_token_is(TokenType::Semicolon) {
-            self.next_token()
+        // Parse the ending semicolon (if it exists). Like expression statements, we don't error here,
+        // so that `return` can be the last statement in a block without a semicolon.
+        if self.peek_token_is(TokenType::Semicolon) {
+            self.next_token();
         }
 
         Ok(ReturnStatement {
@@ -71,11 +95,210 @@ impl Parser {
         })
     }
 
+    /// The `parse_throw_statement` method parses a `throw` statement from the input, e.g. `throw "oops";`.
+    /// Expects the current token to be a `TokenType::Throw`.
+    fn parse_throw_statement(&mut self) -> ParseResult<ThrowStatement> {
+        let token = self.current_token.clone();
+
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::Lowest)?;
+
+        // Like `return`, a `throw` can be the last statement in a block without a semicolon.
+        if self.peek_token_is(TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(ThrowStatement {
+            token,
+            value: Box::new(value),
+        })
+    }
+
+    /// The `parse_yield_statement` method parses a `yield` statement from the input, e.g. `yield x;`.
+    /// Expects the current token to be a `TokenType::Yield`. This makes the function that it is in a
+    /// generator, so it is an error outside of a function.
+    fn parse_yield_statement(&mut self) -> ParseResult<YieldStatement> {
+        let token = self.current_token.clone();
+
+        let Some(yields) = &mut self.yields else {
+            return Err(self.error("yield is only allowed inside of a function"));
+        };
+
+        if !self.suspends {
+            return Err(self.error(YIELD_POSITION_ERROR));
+        }
+
+        *yields = true;
+        self.yield_count += 1;
+
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::Lowest)?;
+
+        // Like `return`, a `yield` can be the last statement in a block without a semicolon.
+        if self.peek_token_is(TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(YieldStatement {
+            token,
+            value: Box::new(value),
+        })
+    }
+
+    /// The `parse_function_statement` method parses a named function declaration from the input, e.g.
+    /// `fn add(x, y) { x + y }`. Expects the current token to be a `TokenType::Function`, and the peek
+    /// token to be a `TokenType::Ident`.
+    fn parse_function_statement(&mut self) -> ParseResult<FunctionStatement> {
+        let token = self.current_token.clone();
+
+        self.expect_peek(TokenType::Ident)?;
+
+        let name = Identifier {
+            token: self.current_token.clone(),
+            value: self.current_token.literal.clone(),
+            slots: None,
+        };
+
+        let function =
+            self.parse_function_parameters_and_body(token.clone(), Some(name.value.clone()))?;
+
+        // Declarations don't need a semicolon, but we skip over one if it is there.
+        if self.peek_token_is(TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(FunctionStatement {
+            token,
+            name,
+            function,
+        })
+    }
+
+    /// The `parse_import_statement` method parses an `import` statement from the input, e.g.
+    /// `import "lib/math.monkey" as math;`. Expects the current token to be a `TokenType::Import`.
+    fn parse_import_statement(&mut self) -> ParseResult<ImportStatement> {
+        let token = self.current_token.clone();
+
+        self.expect_peek(TokenType::String)?;
+
+        let path = StringLiteral {
+            token: self.current_token.clone(),
+            value: self.current_token.literal.clone(),
+        };
+
+        self.expect_peek(TokenType::As)?;
+        self.expect_peek(TokenType::Ident)?;
+
+        let alias = Identifier {
+            token: self.current_token.clone(),
+            value: self.current_token.literal.clone(),
+            slots: None,
+        };
+
+        // Expect a semicolon at the end of the statement.
+        self.expect_peek(TokenType::Semicolon)?;
+
+        Ok(ImportStatement { token, path, alias })
+    }
+
+    /// The `parse_export_statement` method parses an `export` declaration from the input, e.g.
+    /// `export let pi = 3;`, `export fn add(x, y) { x + y }` or `export struct Point { x, y }`. Expects
+    /// the current token to be a `TokenType::Export`. Exports are only allowed at the top level of a
+    /// module, so this is an error inside of a block.
+    fn parse_export_statement(&mut self) -> ParseResult<ExportStatement> {
+        let token = self.current_token.clone();
+
+        if self.blocks > 0 {
+            return Err(self.error("export is only allowed at the top level of a module"));
+        }
+
+        // Advance to the next token so we can parse the declaration that is being exported.
+        self.next_token();
+
+        let declaration = match self.current_token.r#type {
+            TokenType::Let => Statement::Let(self.parse_let_statement()?),
+            TokenType::Function if self.peek_token_is(TokenType::Ident) => {
+                Statement::Function(self.parse_function_statement()?)
+            }
+            TokenType::Struct => Statement::Struct(self.parse_struct_statement()?),
+            _ => {
+                return Err(self.error(format!(
+                    "expected a let statement, function or struct declaration after export, got {} instead",
+                    self.current_token.r#type
+                )))
+            }
+        };
+
+        Ok(ExportStatement {
+            token,
+            declaration: Box::new(declaration),
+        })
+    }
+
+    /// The `parse_struct_statement` method parses a `struct` declaration from the input, e.g.
+    /// `struct Point { x, y }`. Expects the current token to be a `TokenType::Struct`.
+    fn parse_struct_statement(&mut self) -> ParseResult<StructStatement> {
+        let token = self.current_token.clone();
+
+        self.expect_peek(TokenType::Ident)?;
+
+        let name = Identifier {
+            token: self.current_token.clone(),
+            value: self.current_token.literal.clone(),
+            slots: None,
+        };
+
+        self.expect_peek(TokenType::LBrace)?;
+
+        let mut fields: Vec<Identifier> = Vec::new();
+
+        // While the next token isn't a right brace, we have more fields that we need to parse.
+        while !self.peek_token_is(TokenType::RBrace) {
+            self.expect_peek(TokenType::Ident)?;
+
+            let field = Identifier {
+                token: self.current_token.clone(),
+                value: self.current_token.literal.clone(),
+                slots: None,
+            };
+
+            if fields.iter().any(|other| other.value == field.value) {
+                return Err(self.error(format!(
+                    "field {} is declared more than once in struct {}",
+                    field, name
+                )));
+            }
+
+            fields.push(field);
+
+            // If the next token isn't a right brace, it has to be a comma separating the fields.
+            if !self.peek_token_is(TokenType::RBrace) {
+                self.expect_peek(TokenType::Comma)?;
+            }
+        }
+
+        // Advance to the next token, which is the right brace.
+        self.next_token();
+
+        // Declarations don't need a semicolon, but we skip over one if it is there.
+        if self.peek_token_is(TokenType::Semicolon) {
+            self.next_token();
+        }
+
+        Ok(StructStatement {
+            token,
+            name,
+            fields,
+        })
+    }
+
     /// The `parse_expression_statement` method parses an expression statement from the input. Expects
     /// the current token to be an expression, starting with a literal value or identifier.
     fn parse_expression_statement(&mut self) -> ParseResult<ExpressionStatement> {
         let token = self.current_token.clone();
-        let expression = self.parse_expression(Precedence::Lowest)?;
+        let expression = self.parse_statement_expression()?;
 
         // Parse the ending semicolon (if it exists).
         // It is necessary that we do not error here, as this allows for expressions that are not
@@ -88,9 +311,49 @@ impl Parser {
         Ok(ExpressionStatement { token, expression })
     }
 
+    /// Parses the expression of an expression statement, a short lambda's body or a match arm's body
+    /// (which are all evaluated like one). If it is only an `if`, `for`, `try` or `match`, that is a
+    /// statement that a generator can suspend in the blocks (or arms) of, if it can suspend where the
+    /// statement is. Anything else starts a new expression, which a generator can't suspend in the
+    /// middle of.
+    pub(crate) fn parse_statement_expression(&mut self) -> ParseResult<Expression> {
+        if !self.suspends
+            || !matches!(
+                self.current_token.r#type,
+                TokenType::If | TokenType::For | TokenType::Try | TokenType::Match
+            )
+        {
+            return self.parse_expression(Precedence::Lowest);
+        }
+
+        let yield_count = self.yield_count;
+        let expression = self.nested(|parser| parser.parse_expression_inner(Precedence::Lowest))?;
+
+        // The statement might turn out to be the start of a longer expression, e.g. a call.
+        if self.yield_count != yield_count
+            && !matches!(
+                expression,
+                Expression::If(_) | Expression::For(_) | Expression::Try(_) | Expression::Match(_)
+            )
+        {
+            return Err(Error::new(YIELD_POSITION_ERROR, expression.position()));
+        }
+
+        Ok(expression)
+    }
+
     /// The `parse_block_statement` method parses a block statement from the input. Expects the
     /// current token to be a `TokenType::LBrace`.
     pub(crate) fn parse_block_statement(&mut self) -> ParseResult<BlockStatement> {
+        self.blocks += 1;
+        let block = self.nested(Self::parse_block_statement_inner);
+        self.blocks -= 1;
+
+        block
+    }
+
+    /// Parses a block statement without checking the nesting depth.
+    fn parse_block_statement_inner(&mut self) -> ParseResult<BlockStatement> {
         let token = self.current_token.clone();
         let mut statements = Vec::new();
 
@@ -1,7 +1,8 @@
 #![cfg(test)]
 
-use crate::ast::expressions::Expression;
-use crate::ast::statements::Statement;
+use crate::ast::expressions::{Expression, FunctionForm, Parameter};
+use crate::ast::patterns::Pattern;
+use crate::ast::statements::{ExpressionStatement, Statement};
 use crate::ast::Node;
 use crate::lexer::Lexer;
 use crate::parser::Parser;
@@ -108,8 +109,13 @@ fn test_let_statements() {
         };
 
         assert_eq!(stmt.token_literal(), "let");
-        assert_eq!(stmt.name.value, expected_identifier);
-        assert_eq!(stmt.name.token_literal(), expected_identifier);
+
+        let Pattern::Identifier(name) = &stmt.pattern else {
+            panic!("Pattern is not an Identifier pattern, got {}", stmt.pattern);
+        };
+
+        assert_eq!(name.value, expected_identifier);
+        assert_eq!(name.token_literal(), expected_identifier);
 
         test_literal(&stmt.value, expected_value);
     }
@@ -207,6 +213,7 @@ fn test_integer_expression() {
 
 /// Tests the parsing of boolean literals.
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_boolean_expression() {
     let input = r"
 true;
@@ -340,6 +347,8 @@ fn test_operator_precedence_parsing() {
         ("3 + 4; -5 * 5", "(3 + 4)((-5) * 5)"),
         ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4))"),
         ("5 < 4 != 3 > 4", "((5 < 4) != (3 > 4))"),
+        ("a..b + 1", "(a .. (b + 1))"),
+        ("0..n == r", "((0 .. n) == r)"),
         (
             "3 + 4 * 5 == 3 * 1 + 4 * 5",
             "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
@@ -362,14 +371,15 @@ fn test_operator_precedence_parsing() {
             "add(a + b + c * d / f + g)",
             "add((((a + b) + ((c * d) / f)) + g))",
         ),
-        // (
-        //     "a * [1, 2, 3, 4][b * c] * d",
-        //     "((a * ([1, 2, 3, 4][(b * c)])) * d)",
-        // ),
-        // (
-        //     "add(a * b[2], b[1], 2 * [1, 2][1])",
-        //     "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
-        // ),
+        (
+            "a * [1, 2, 3, 4][b * c] * d",
+            "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+        ),
+        (
+            "add(a * b[2], b[1], 2 * [1, 2][1])",
+            "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+        ),
+        ("add(...xs, 1 + 2)", "add(...xs, (1 + 2))"),
     ];
 
     for (i, (input, expected)) in tests.into_iter().enumerate() {
@@ -430,6 +440,8 @@ fn test_if_expressions() {
 
     test_identifier(&consequence.expression, "x");
 
+    assert!(if_expr.else_ifs.is_empty());
+
     assert!(if_expr.alternative.is_none());
 }
 
@@ -483,7 +495,9 @@ fn test_if_else_expressions() {
 
     assert!(if_expr.alternative.is_some());
 
-    let Statement::Expression(alternative) = if_expr.alternative.as_ref().unwrap().statements[0].clone() else {
+    let Statement::Expression(alternative) =
+        if_expr.alternative.as_ref().unwrap().statements[0].clone()
+    else {
         panic!(
             "Statement is not an ExpressionStatement statement, got {}",
             if_expr.alternative.as_ref().unwrap().statements[0].token_literal()
@@ -493,6 +507,101 @@ fn test_if_else_expressions() {
     test_identifier(&alternative.expression, "y");
 }
 
+/// Tests the parsing of `else if` chains.
+#[test]
+fn test_if_else_if_expressions() {
+    let input = "if (x < y) { x } else if (x > y) { y } else if (x == y) { z } else { w }";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    assert_eq!(
+        program.statements.len(),
+        1,
+        "Expected 1 statement, but got {} statements",
+        program.statements.len()
+    );
+
+    let Statement::Expression(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::If(if_expr) = &stmt.expression else {
+        panic!(
+            "Expression is not an IfExpression expression, got {}",
+            stmt.expression.token_literal()
+        );
+    };
+
+    test_infix(&if_expr.condition, &"x", "<", &"y");
+
+    assert_eq!(
+        if_expr.else_ifs.len(),
+        2,
+        "Expected 2 else-if branches, but got {} branches",
+        if_expr.else_ifs.len()
+    );
+
+    let expected = [("x", ">", "y", "y"), ("x", "==", "y", "z")];
+
+    for (else_if, (left, operator, right, consequence)) in if_expr.else_ifs.iter().zip(expected) {
+        assert_eq!(else_if.token_literal(), "if");
+
+        test_infix(&else_if.condition, &left, operator, &right);
+
+        let Statement::Expression(stmt) = &else_if.consequence.statements[0] else {
+            panic!(
+                "Statement is not an ExpressionStatement statement, got {}",
+                else_if.consequence.statements[0].token_literal()
+            );
+        };
+
+        test_identifier(&stmt.expression, consequence);
+    }
+
+    let Some(alternative) = &if_expr.alternative else {
+        panic!("Expected an alternative block, but got none");
+    };
+
+    let Statement::Expression(alternative) = &alternative.statements[0] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            alternative.statements[0].token_literal()
+        );
+    };
+
+    test_identifier(&alternative.expression, "w");
+}
+
+/// Tests that `else if` chains are printed back as a flat chain.
+#[test]
+fn test_if_else_if_formatting() {
+    let tests = vec![
+        ("if (a) { x } else if (b) { y }", "if a x else if b y"),
+        (
+            "if (a) { x } else if (b) { y } else { z }",
+            "if a x else if b y else z",
+        ),
+        (
+            "if (a < b) { 1 } else if (a > b) { 2 } else if (a == b) { 3 } else { 4 }",
+            "if (a < b) 1 else if (a > b) 2 else if (a == b) 3 else 4",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected);
+    }
+}
+
 /// Test the parsing of function literals.
 #[test]
 fn test_function_literals() {
@@ -546,6 +655,79 @@ fn test_function_literals() {
     test_infix(&body.expression, &"x", "+", &"y");
 }
 
+/// Tests the parsing of named function declarations.
+#[test]
+fn test_function_statements() {
+    let input = "fn add(x, y) { x + y; } fn(x) { x };";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    assert_eq!(
+        program.statements.len(),
+        2,
+        "Expected 2 statements, but got {} statements",
+        program.statements.len()
+    );
+
+    let Statement::Function(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not a FunctionStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    assert_eq!(stmt.token_literal(), "fn");
+    assert_eq!(stmt.name.value, "add");
+    assert_eq!(stmt.function.name.as_deref(), Some("add"));
+    assert_eq!(stmt.function.parameters.len(), 2);
+    assert_eq!(stmt.to_string(), "fn add(x, y) (x + y)");
+
+    // A `fn` that isn't followed by a name is still a function literal.
+    let Statement::Expression(stmt) = &program.statements[1] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            program.statements[1].token_literal()
+        );
+    };
+
+    let Expression::Function(function) = &stmt.expression else {
+        panic!(
+            "Expression is not a FunctionLiteral expression, got {}",
+            stmt.expression.token_literal()
+        );
+    };
+
+    assert!(function.name.is_none());
+}
+
+/// Tests that function literals bound by `let` are named after the binding.
+#[test]
+fn test_let_function_names() {
+    let input = "let fib = fn(n) { n }; let x = 5;";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Let(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not a LetStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::Function(function) = stmt.value.as_ref() else {
+        panic!(
+            "Expression is not a FunctionLiteral expression, got {}",
+            stmt.value.token_literal()
+        );
+    };
+
+    assert_eq!(function.name.as_deref(), Some("fib"));
+}
+
 /// Tests the parsing of function parameters.
 #[test]
 fn test_function_parameter_parsing() {
@@ -590,6 +772,98 @@ fn test_function_parameter_parsing() {
     }
 }
 
+/// Tests the parsing of default and rest parameters.
+#[test]
+fn test_function_default_and_rest_parameters() {
+    let tests = vec![
+        ("fn(x, y = 10) {};", "fn(x, y = 10) "),
+        ("fn(x = 1 + 2, y = x) {};", "fn(x = (1 + 2), y = x) "),
+        ("fn(...rest) {};", "fn(...rest) "),
+        ("fn(x, y = 2, ...rest) {};", "fn(x, y = 2, ...rest) "),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected);
+    }
+
+    let lexer = Lexer::new("fn(x, y = 10, ...rest) {};");
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Expression(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::Function(function) = &stmt.expression else {
+        panic!(
+            "Expression is not a FunctionLiteral expression, got {}",
+            stmt.expression.token_literal()
+        );
+    };
+
+    let [Parameter::Required(x), Parameter::Default { name: y, value }, Parameter::Rest { name: rest, .. }] =
+        function.parameters.as_slice()
+    else {
+        panic!("Unexpected parameters, got {:?}", function.parameters);
+    };
+
+    assert_eq!(x.value, "x");
+    assert_eq!(y.value, "y");
+    test_integer(value, 10);
+    assert_eq!(rest.value, "rest");
+}
+
+/// Tests that parameters have to be in the right order, and have different names.
+#[test]
+fn test_function_parameter_ordering_errors() {
+    let tests = vec![
+        ("fn(a, a) { a };", "duplicate parameter a"),
+        ("fn(a, b, a = 5) { a };", "duplicate parameter a"),
+        ("fn(a, ...a) { a };", "duplicate parameter a"),
+        ("fn f(a, a) { a }", "duplicate parameter a"),
+        ("|a, a| a", "duplicate parameter a"),
+        ("|a = 1, ...a| a", "duplicate parameter a"),
+        (
+            "fn(x = 1, y) {};",
+            "required parameter y cannot come after a parameter with a default value",
+        ),
+        (
+            "fn(...rest, x) {};",
+            "rest parameter ...rest must be the last parameter",
+        ),
+        (
+            "fn(...rest, ...more) {};",
+            "rest parameter ...rest must be the last parameter",
+        ),
+        (
+            "fn(...rest = 1) {};",
+            "rest parameter ...rest cannot have a default value",
+        ),
+        (
+            "fn(1) {};",
+            "Expected an identifier in function literal parameters",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors[0].message, expected, "input: {}", input);
+    }
+}
+
 /// Tests the parsing of call expressions.
 #[test]
 fn test_call_expressions() {
@@ -630,3 +904,1160 @@ fn test_call_expressions() {
 
     test_infix(&call.arguments[2], &4, "+", &5);
 }
+
+/// Tests the parsing of spread arguments in call expressions.
+#[test]
+fn test_call_spread_arguments() {
+    let input = "add(1, ...xs);";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Expression(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::Call(call) = &stmt.expression else {
+        panic!(
+            "Expression is not a CallExpression expression, got {}",
+            stmt.expression.token_literal()
+        );
+    };
+
+    assert_eq!(call.arguments.len(), 2);
+
+    test_integer(&call.arguments[0], 1);
+
+    let Expression::Spread(spread) = &call.arguments[1] else {
+        panic!(
+            "Expression is not a SpreadExpression expression, got {}",
+            call.arguments[1].token_literal()
+        );
+    };
+
+    assert_eq!(spread.token_literal(), "...");
+
+    test_identifier(&spread.value, "xs");
+}
+
+/// Tests the parsing of array literals.
+#[test]
+fn test_array_literals() {
+    let input = "[1, 2 * 2, 3 + 3]";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Expression(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::Array(array) = &stmt.expression else {
+        panic!(
+            "Expression is not an ArrayLiteral expression, got {}",
+            stmt.expression.token_literal()
+        );
+    };
+
+    assert_eq!(array.elements.len(), 3);
+
+    test_integer(&array.elements[0], 1);
+
+    test_infix(&array.elements[1], &2, "*", &2);
+
+    test_infix(&array.elements[2], &3, "+", &3);
+}
+
+/// Tests the parsing of index expressions.
+#[test]
+fn test_index_expressions() {
+    let input = "myArray[1 + 1]";
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Expression(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::Index(index) = &stmt.expression else {
+        panic!(
+            "Expression is not an IndexExpression expression, got {}",
+            stmt.expression.token_literal()
+        );
+    };
+
+    test_identifier(&index.left, "myArray");
+
+    test_infix(&index.index, &1, "+", &1);
+}
+
+/// Tests the parsing of string literals.
+#[test]
+fn test_string_literal_expression() {
+    let input = r#""hello world";"#;
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Expression(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::String(string) = &stmt.expression else {
+        panic!(
+            "Expression is not a StringLiteral expression, got {}",
+            stmt.expression.token_literal()
+        );
+    };
+
+    assert_eq!(string.value, "hello world");
+}
+
+/// Tests the parsing of hash literals.
+#[test]
+fn test_hash_literals() {
+    let tests = vec![
+        ("{}", "{}"),
+        (
+            r#"{"one": 1, "two": 2, "three": 3}"#,
+            r#"{"one": 1, "two": 2, "three": 3}"#,
+        ),
+        (
+            r#"{"one": 0 + 1, "two": 10 - 8, "three": 15 / 5}"#,
+            r#"{"one": (0 + 1), "two": (10 - 8), "three": (15 / 5)}"#,
+        ),
+        (r#"{1: true, true: "one"}"#, r#"{1: true, true: "one"}"#),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        let Statement::Expression(stmt) = &program.statements[0] else {
+            panic!(
+                "Statement is not an ExpressionStatement statement, got {}",
+                program.statements[0].token_literal()
+            );
+        };
+
+        let Expression::Hash(hash) = &stmt.expression else {
+            panic!(
+                "Expression is not a HashLiteral expression, got {}",
+                stmt.expression.token_literal()
+            );
+        };
+
+        assert_eq!(hash.to_string(), expected);
+    }
+}
+
+/// Tests the parsing of destructuring patterns in let statements.
+#[test]
+fn test_let_patterns() {
+    let tests = vec![
+        ("let [a, b] = xs;", "let [a, b] = xs;"),
+        ("let [a, b, ...rest] = xs;", "let [a, b, ...rest] = xs;"),
+        ("let [...all] = xs;", "let [...all] = xs;"),
+        ("let [] = xs;", "let [] = xs;"),
+        (
+            r#"let {"name": n, "age": a} = person;"#,
+            r#"let {"name": n, "age": a} = person;"#,
+        ),
+        (
+            r#"let [a, [b, c], {"d": [e, ...f]}] = xs;"#,
+            r#"let [a, [b, c], {"d": [e, ...f]}] = xs;"#,
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected);
+    }
+
+    let lexer = Lexer::new(r#"let [a, {"b": c}, ...rest] = xs;"#);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Let(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not a LetStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Pattern::Array(array) = &stmt.pattern else {
+        panic!("Pattern is not an Array pattern, got {}", stmt.pattern);
+    };
+
+    assert_eq!(array.elements.len(), 2);
+    assert!(matches!(&array.elements[0], Pattern::Identifier(a) if a.value == "a"));
+    assert_eq!(array.rest.as_ref().unwrap().value, "rest");
+
+    let Pattern::Hash(hash) = &array.elements[1] else {
+        panic!("Pattern is not a Hash pattern, got {}", array.elements[1]);
+    };
+
+    assert_eq!(hash.pairs[0].0.to_string(), r#""b""#);
+    assert!(matches!(&hash.pairs[0].1, Pattern::Identifier(c) if c.value == "c"));
+}
+
+/// Tests the errors from parsing invalid destructuring patterns.
+#[test]
+fn test_let_pattern_errors() {
+    let tests = vec![
+        (
+            "let [...rest, a] = xs;",
+            "rest element ...rest must be the last element of an array pattern",
+        ),
+        (
+            "let * = xs;",
+            "expected an identifier, literal, array pattern or hash pattern, got * instead",
+        ),
+        (
+            "let [...5] = xs;",
+            "expected an identifier in pattern, got INT instead",
+        ),
+        (
+            r#"let {"a" b} = xs;"#,
+            "expected next token to be :, got IDENT instead",
+        ),
+        ("let [a, a] = [1, 2];", "duplicate name a in pattern"),
+        (
+            r#"let {"a": [b, c], "d": [...b]} = xs;"#,
+            "duplicate name b in pattern",
+        ),
+        ("for ([x, x] in xs) { x }", "duplicate name x in pattern"),
+        (
+            "match (xs) { [x, [y, x]] => x }",
+            "duplicate name x in pattern",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors[0].message, expected, "input: {}", input);
+    }
+}
+
+/// Tests that parse errors point at the token that the parser got stuck at
+#[test]
+fn test_parse_error_positions() {
+    let tests = vec![
+        ("let x = ;", "1:9: no prefix parse function for ; found"),
+        ("let x = 1;\nfn(a, a) { a }", "2:7: duplicate parameter a"),
+        (
+            "let x = (1;",
+            "1:11: expected next token to be ), got ; instead",
+        ),
+        (
+            "try { 1 }\nlet x = 1;",
+            "2:1: expected catch or finally after try block, got LET instead",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors[0].to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests the parsing of match expressions.
+#[test]
+fn test_match_expressions() {
+    let tests = vec![
+        (
+            r#"match (x) { 0 => "zero", -1 => "minus one", _ => "other" }"#,
+            r#"match (x) { 0 => "zero", (-1) => "minus one", _ => "other" }"#,
+        ),
+        (
+            "match (x) { n if n < 0 => -n, n => n, }",
+            "match (x) { n if (n < 0) => (-n), n => n }",
+        ),
+        (
+            r#"match (xs) { [] => 0, [x, ...rest] => x + 1, {"a": [b]} => b }"#,
+            r#"match (xs) { [] => 0, [x, ...rest] => (x + 1), {"a": [b]} => b }"#,
+        ),
+        (
+            "match (a + b) { true => fn(x) { x }, false => 1 }",
+            "match ((a + b)) { true => fn(x) x, false => 1 }",
+        ),
+        ("match (x) {}", "match (x) {  }"),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected, "input: {}", input);
+        assert!(parser.warnings().is_empty(), "input: {}", input);
+    }
+
+    let lexer = Lexer::new("match (x) { [a, ...b] if a > 1 => a, _ => 0 }");
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Expression(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::Match(match_expression) = &stmt.expression else {
+        panic!(
+            "Expression is not a MatchExpression expression, got {}",
+            stmt.expression.token_literal()
+        );
+    };
+
+    assert_eq!(match_expression.subject.to_string(), "x");
+    assert_eq!(match_expression.arms.len(), 2);
+    assert!(matches!(
+        match_expression.arms[0].pattern,
+        Pattern::Array(_)
+    ));
+    assert_eq!(
+        match_expression.arms[0].guard.as_ref().unwrap().to_string(),
+        "(a > 1)"
+    );
+    assert!(matches!(
+        match_expression.arms[1].pattern,
+        Pattern::Wildcard(_)
+    ));
+    assert!(match_expression.arms[1].guard.is_none());
+}
+
+/// Tests the warnings for match arms that can never match.
+#[test]
+fn test_match_unreachable_arms() {
+    let tests = vec![
+        (
+            "match (x) { _ => 1, 2 => 2 }",
+            vec!["1:21: unreachable match arm: 2 is already matched by the earlier arm _"],
+        ),
+        (
+            "match (x) {\n  n => 1,\n  [a] => 2,\n  m => 3\n}",
+            vec![
+                "3:3: unreachable match arm: [a] is already matched by the earlier arm n",
+                "4:3: unreachable match arm: m is already matched by the earlier arm n",
+            ],
+        ),
+        (
+            "match (x) { 1 => 1, 1 => 2 }",
+            vec!["1:21: unreachable match arm: 1 is already matched by the earlier arm 1"],
+        ),
+        (
+            "match (x) { [a, ...r] => 1, [1, 2] => 2, [b, c, ...d] => 3 }",
+            vec![
+                "1:29: unreachable match arm: [1, 2] is already matched by the earlier arm [a, ...r]",
+                "1:42: unreachable match arm: [b, c, ...d] is already matched by the earlier arm [a, ...r]",
+            ],
+        ),
+        (
+            r#"match (x) { {"a": a} => 1, {"b": 2, "a": 1} => 2 }"#,
+            vec![r#"1:28: unreachable match arm: {"b": 2, "a": 1} is already matched by the earlier arm {"a": a}"#],
+        ),
+        // Arms with guards, and arms that only overlap, can still be reached.
+        ("match (x) { n if n > 1 => 1, n => 2 }", vec![]),
+        ("match (x) { [a] => 1, [a, b] => 2, [] => 3, [...a] => 4 }", vec![]),
+        ("match (x) { 1 => 1, 2 => 2, \"1\" => 3, true => 4 }", vec![]),
+        (r#"match (x) { {"a": 1} => 1, {"a": a} => 2, {"b": b} => 3 }"#, vec![]),
+        ("match (x) { {k: 1} => 1, {k: 1} => 2 }", vec![]),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        parser.parse_program().unwrap();
+
+        let warnings = parser
+            .warnings()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(warnings, expected, "input: {}", input);
+    }
+}
+
+/// Tests the errors from parsing invalid match expressions.
+#[test]
+fn test_match_errors() {
+    let tests = vec![
+        (
+            "match x { _ => 1 }",
+            "expected next token to be (, got IDENT instead",
+        ),
+        (
+            "match (x) { _ 1 }",
+            "expected next token to be =>, got INT instead",
+        ),
+        (
+            "match (x) { _ => 1 _ => 2 }",
+            "expected next token to be ,, got IDENT instead",
+        ),
+        (
+            "match (x) { x + 1 => 2 }",
+            "expected next token to be =>, got + instead",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors[0].message, expected, "input: {}", input);
+    }
+}
+
+/// Tests that pipes are parsed into the calls that they stand for.
+#[test]
+fn test_pipe_expressions() {
+    let tests = vec![
+        ("x |> f", "f(x)"),
+        ("x |> f(y)", "f(x, y)"),
+        ("x |> f(y, z)", "f(x, y, z)"),
+        ("x |> f |> g(y)", "g(f(x), y)"),
+        ("x |> f(y) |> g |> h(z)", "h(g(f(x, y)), z)"),
+        ("a + b |> f", "f((a + b))"),
+        ("a == b |> f", "f((a == b))"),
+        ("a |> f == b", "(f == b)(a)"),
+        ("a < b |> f(c * d)", "f((a < b), (c * d))"),
+        ("xs[0] |> fs[1]", "(fs[1])((xs[0]))"),
+        ("x |> f(y)(z)", "f(y)(x, z)"),
+        ("f(x |> g)", "f(g(x))"),
+        ("x |> (|y| y * 2)", "fn(y) (y * 2)(x)"),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected, "input: {}", input);
+    }
+}
+
+/// Tests that calls and function literals remember how they are written, as pipes and short lambdas
+#[test]
+fn test_surface_forms() {
+    let expression = |input: &str| {
+        let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+
+        match program.statements.as_slice() {
+            [Statement::Expression(statement)] => statement.expression.clone(),
+            _ => panic!("expected an expression statement, got {}", program),
+        }
+    };
+
+    let calls = vec![
+        ("f(x)", false),
+        ("x |> f", true),
+        ("x |> f(y)", true),
+        ("x |> f |> g(y)", true),
+        ("f(x |> g)", false),
+    ];
+
+    for (input, piped) in calls {
+        let Expression::Call(call) = expression(input) else {
+            panic!("expected a call for {}", input);
+        };
+
+        assert_eq!(call.piped, piped, "input: {}", input);
+    }
+
+    let functions = vec![
+        ("fn(x) { x }", FunctionForm::Fn),
+        ("|x| x", FunctionForm::Bar),
+        ("x => x", FunctionForm::Arrow),
+    ];
+
+    for (input, form) in functions {
+        let Expression::Function(function) = expression(input) else {
+            panic!("expected a function literal for {}", input);
+        };
+
+        assert_eq!(function.form, form, "input: {}", input);
+    }
+}
+
+/// Tests the parsing of short lambdas into function literals.
+#[test]
+fn test_short_lambdas() {
+    let tests = vec![
+        ("|x| x * 2", "fn(x) (x * 2)"),
+        ("|x, y| x + y", "fn(x, y) (x + y)"),
+        ("|| 1", "fn() 1"),
+        ("|x, y = 1, ...rest| x", "fn(x, y = 1, ...rest) x"),
+        ("x => x * 2", "fn(x) (x * 2)"),
+        ("x => y => x + y", "fn(x) fn(y) (x + y)"),
+        ("|x| |y| x + y", "fn(x) fn(y) (x + y)"),
+        ("map(xs, |x| x * 2)", "map(xs, fn(x) (x * 2))"),
+        ("map(xs, x => x * 2)", "map(xs, fn(x) (x * 2))"),
+        (
+            "xs |> map(x => x + 1) |> filter(|x| x > 2)",
+            "filter(map(xs, fn(x) (x + 1)), fn(x) (x > 2))",
+        ),
+        ("let f = x => x;", "let f = fn(x) x;"),
+        (
+            "match (x) { n if n => n => n }",
+            "match (x) { n if n => fn(n) n }",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected, "input: {}", input);
+    }
+
+    let lexer = Lexer::new("let double = |x| x * 2;");
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Let(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not a LetStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::Function(function) = stmt.value.as_ref() else {
+        panic!(
+            "Expression is not a FunctionLiteral expression, got {}",
+            stmt.value.token_literal()
+        );
+    };
+
+    assert_eq!(function.name.as_deref(), Some("double"));
+    assert_eq!(function.parameters.len(), 1);
+    assert_eq!(function.body.statements.len(), 1);
+
+    let tests = vec![
+        ("1 => 2", "expected an identifier before =>, got 1 instead"),
+        (
+            "a + b => 2",
+            "expected an identifier before =>, got (a + b) instead",
+        ),
+        ("|x 1", "expected next token to be |, got INT instead"),
+        (
+            "|1| 1",
+            "Expected an identifier in function literal parameters",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors[0].message, expected, "input: {}", input);
+    }
+}
+
+/// Tests the parsing of macro literals.
+#[test]
+fn test_macro_literal_parsing() {
+    let input = "macro(x, y) { x + y; }";
+
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    assert_eq!(program.statements.len(), 1);
+
+    let Statement::Expression(stmt) = &program.statements[0] else {
+        panic!(
+            "Statement is not an ExpressionStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    let Expression::Macro(macro_literal) = &stmt.expression else {
+        panic!(
+            "Expression is not a MacroLiteral expression, got {}",
+            stmt.expression.token_literal()
+        );
+    };
+
+    assert_eq!(macro_literal.parameters.len(), 2);
+    assert_eq!(macro_literal.parameters[0].to_string(), "x");
+    assert_eq!(macro_literal.parameters[1].to_string(), "y");
+    assert_eq!(macro_literal.body.statements.len(), 1);
+    assert_eq!(macro_literal.body.to_string(), "(x + y)");
+    assert_eq!(program.to_string(), "macro(x, y) (x + y)");
+}
+
+/// Tests the parsing of import statements, export declarations and member access.
+#[test]
+fn test_modules() {
+    let tests = vec![
+        (
+            r#"import "lib/math.monkey" as math;"#,
+            r#"import "lib/math.monkey" as math;"#,
+        ),
+        ("export let pi = 3;", "export let pi = 3;"),
+        ("export let [a, b] = [1, 2];", "export let [a, b] = [1, 2];"),
+        (
+            "export fn add(x, y) { x + y }",
+            "export fn add(x, y) (x + y)",
+        ),
+        ("math.pi", "(math.pi)"),
+        ("math.add(1, 2)", "(math.add)(1, 2)"),
+        ("a.b.c", "((a.b).c)"),
+        ("-a.b", "(-(a.b))"),
+        ("a.b[0].c", "(((a.b)[0]).c)"),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected, "input: {}", input);
+    }
+
+    let lexer = Lexer::new("import \"lib/math.monkey\" as math;\nexport let [a, {\"b\": b}] = x;");
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Import(import) = &program.statements[0] else {
+        panic!(
+            "Statement is not an ImportStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    assert_eq!(import.path.value, "lib/math.monkey");
+    assert_eq!(import.alias.value, "math");
+
+    let Statement::Export(export) = &program.statements[1] else {
+        panic!(
+            "Statement is not an ExportStatement statement, got {}",
+            program.statements[1].token_literal()
+        );
+    };
+
+    let names = export
+        .names()
+        .into_iter()
+        .map(|name| name.value.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(names, vec!["a", "b"]);
+
+    let tests =
+        vec![
+        ("import math;", "expected next token to be STRING, got IDENT instead"),
+        (r#"import "math";"#, "expected next token to be AS, got ; instead"),
+        (r#"import "math" as 1;"#, "expected next token to be IDENT, got INT instead"),
+        (r#"import "math" as m"#, "expected next token to be ;, got EOF instead"),
+        (
+            "export 1;",
+            "expected a let statement, function or struct declaration after export, got INT instead",
+        ),
+        (
+            "export fn(x) { x };",
+            "expected a let statement, function or struct declaration after export, got FUNCTION instead",
+        ),
+        (
+            "fn f() { export let x = 1; }",
+            "export is only allowed at the top level of a module",
+        ),
+        (
+            "if (true) { export let x = 1; }",
+            "export is only allowed at the top level of a module",
+        ),
+        (
+            "for (x in xs) { export fn g() { x } }",
+            "export is only allowed at the top level of a module",
+        ),
+        ("math.1", "expected next token to be IDENT, got INT instead"),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors[0].message, expected, "input: {}", input);
+    }
+}
+
+/// Tests parsing struct declarations and record literals
+#[test]
+fn test_records() {
+    let input = "struct Point { x, y } Point { x: 1 + 2, y: f(3) }.x";
+
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+
+    let program = parser.parse_program().unwrap();
+
+    let Statement::Struct(declaration) = &program.statements[0] else {
+        panic!(
+            "Statement is not a StructStatement statement, got {}",
+            program.statements[0].token_literal()
+        );
+    };
+
+    assert_eq!(declaration.name.value, "Point");
+    assert_eq!(
+        declaration
+            .fields
+            .iter()
+            .map(|field| field.value.as_str())
+            .collect::<Vec<_>>(),
+        vec!["x", "y"]
+    );
+
+    assert_eq!(
+        program.to_string(),
+        "struct Point { x, y }(Point { x: (1 + 2), y: f(3) }.x)"
+    );
+
+    let tests = vec![
+        ("struct P {}", "struct P {  }"),
+        ("struct P { x, };", "struct P { x }"),
+        ("P {}", "P {  }"),
+        ("P { x: 1, }", "P { x: 1 }"),
+        ("-P { x: 1 }.x", "(-(P { x: 1 }.x))"),
+        ("if (a) { P { x: a } }", "if a P { x: a }"),
+        ("export struct P { x }", "export struct P { x }"),
+        // Only a name right before a `{` starts a record, so a hash can follow a block.
+        ("if (a) { b } {\"k\": 1}", "if a b{\"k\": 1}"),
+        (
+            "for (x in xs) { x }\n{\"k\": 1}",
+            "for (x in xs) x{\"k\": 1}",
+        ),
+        (
+            "fn config(debug) { if (debug) { puts(\"debug\") } {\"level\": 1} }",
+            "fn config(debug) if debug puts(\"debug\"){\"level\": 1}",
+        ),
+        ("f() { x: 1 }", "f(){x: 1}"),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected, "input: {}", input);
+    }
+
+    let tests = vec![
+        (
+            "struct { x }",
+            "expected next token to be IDENT, got { instead",
+        ),
+        (
+            "struct P { 1 }",
+            "expected next token to be IDENT, got INT instead",
+        ),
+        (
+            "struct P { x y }",
+            "expected next token to be ,, got IDENT instead",
+        ),
+        (
+            "struct P { x, x }",
+            "field x is declared more than once in struct P",
+        ),
+        ("P { x }", "expected next token to be :, got } instead"),
+        (
+            "P { x: 1, x: 2 }",
+            "field x is given more than once in P literal",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors[0].message, expected, "input: {}", input);
+    }
+}
+
+/// Tests parsing throw statements and try expressions
+#[test]
+fn test_exceptions() {
+    let tests = vec![
+        ("throw 1;", "throw 1;"),
+        ("throw f(x)", "throw f(x);"),
+        ("try { f() } catch (e) { e }", "try f() catch (e) e"),
+        ("try { f() } finally { g() }", "try f() finally g()"),
+        (
+            "let x = try { f() } catch (e) { 0 } finally { g() };",
+            "let x = try f() catch (e) 0 finally g();",
+        ),
+        (
+            "try { throw 1; } catch (e) { throw e; }",
+            "try throw 1; catch (e) throw e;",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected, "input: {}", input);
+    }
+
+    let tests = vec![
+        (
+            "try { 1 }",
+            "expected catch or finally after try block, got EOF instead",
+        ),
+        (
+            "try { 1 } else { 2 }",
+            "expected catch or finally after try block, got ELSE instead",
+        ),
+        (
+            "try 1 catch (e) { 2 }",
+            "expected next token to be {, got INT instead",
+        ),
+        (
+            "try { 1 } catch e { 2 }",
+            "expected next token to be (, got IDENT instead",
+        ),
+        (
+            "try { 1 } catch (1) { 2 }",
+            "expected next token to be IDENT, got INT instead",
+        ),
+        (
+            "try { 1 } catch (e) 2",
+            "expected next token to be {, got INT instead",
+        ),
+        (
+            "try { 1 } finally 2",
+            "expected next token to be {, got INT instead",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors[0].message, expected, "input: {}", input);
+    }
+}
+
+/// Tests parsing for loops, yield statements and generators
+#[test]
+fn test_generators() {
+    let tests = vec![
+        ("for (x in xs) { f(x) }", "for (x in xs) f(x)"),
+        (
+            "for ([key, value] in pairs) { f(key, value); }",
+            "for ([key, value] in pairs) f(key, value)",
+        ),
+        ("for (i in 0..10) { i }", "for (i in (0 .. 10)) i"),
+        ("fn() { yield 1; yield 2 }", "fn() yield 1;yield 2;"),
+        (
+            "fn(xs) { for (x in xs) { yield x * 2; } }",
+            "fn(xs) for (x in xs) yield (x * 2);",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        assert_eq!(program.to_string(), expected, "input: {}", input);
+    }
+
+    // A function is a generator if it has a `yield` in it, but not if the `yield` is in a function nested
+    // inside of it.
+    let tests = vec![
+        ("fn() { yield 1; }", true),
+        ("fn() { if (x) { for (y in x) { yield y; } } }", true),
+        ("|x| if (x) { yield x; }", true),
+        ("fn() { try { yield 1; } finally { 2 } }", true),
+        ("fn() { try { 1 } catch (e) { yield e; } }", true),
+        ("fn() { match (y) { _ => if (y) { yield 1; } } }", true),
+        (
+            "fn() { match (y) { [x] => match (x) { _ => try { yield x; } finally {} } } }",
+            true,
+        ),
+        ("fn() { 1 }", false),
+        ("fn() { fn() { yield 1; } }", false),
+        ("fn() { let f = fn() { yield 1; }; f }", false),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program().unwrap();
+
+        let Statement::Expression(ExpressionStatement {
+            expression: Expression::Function(function),
+            ..
+        }) = &program.statements[0]
+        else {
+            panic!("not a function literal: {}", input);
+        };
+
+        assert_eq!(function.generator, expected, "input: {}", input);
+    }
+
+    let tests = vec![
+        ("yield 1;", "yield is only allowed inside of a function"),
+        (
+            "if (x) { yield 1; }",
+            "yield is only allowed inside of a function",
+        ),
+        (
+            "macro() { yield 1; }",
+            "yield is only allowed inside of a function",
+        ),
+        (
+            "for x in xs { x }",
+            "expected next token to be (, got IDENT instead",
+        ),
+        (
+            "for (x of xs) { x }",
+            "expected next token to be IN, got IDENT instead",
+        ),
+        (
+            "for (x in xs) x",
+            "expected next token to be {, got IDENT instead",
+        ),
+        // A generator can only suspend at a `yield` that is a statement of its body, or of the blocks and
+        // arms of the `if`, `for`, `try` and `match` statements in it.
+        (
+            "fn() { let x = if (y) { yield 1; }; }",
+            "yield is only allowed in a generator's body, and the if, for, try and match statements in it",
+        ),
+        (
+            "fn() { if (y) { yield 1; } + 1 }",
+            "yield is only allowed in a generator's body, and the if, for, try and match statements in it",
+        ),
+        (
+            "fn() { let x = match (y) { _ => if (y) { yield 1; } }; }",
+            "yield is only allowed in a generator's body, and the if, for, try and match statements in it",
+        ),
+        (
+            "fn() { match (y) { _ if (if (y) { yield 1; }) => 1 } }",
+            "yield is only allowed in a generator's body, and the if, for, try and match statements in it",
+        ),
+        (
+            "fn() { f(fn() { 1 }, if (y) { yield 1; }) }",
+            "yield is only allowed in a generator's body, and the if, for, try and match statements in it",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(errors[0].message, expected, "input: {}", input);
+    }
+}
+
+/// Tests that deeply nested input is a parse error instead of a stack overflow
+#[test]
+fn test_max_depth() {
+    let tests = vec![
+        "(".repeat(100_000),
+        format!("{}1", "!".repeat(100_000)),
+        format!("{}1{}", "[".repeat(100_000), "]".repeat(100_000)),
+        format!("let {}a{} = 1;", "[".repeat(100_000), "]".repeat(100_000)),
+        format!("{}1", "fn() { ".repeat(100_000)),
+        format!("{}1", "if (x) { ".repeat(100_000)),
+        format!("{}1", "try { ".repeat(100_000)),
+        format!("{}1", "match (x) { _ => ".repeat(100_000)),
+        format!("{}1{}", "if (x) { ".repeat(3_000), " }".repeat(3_000)),
+    ];
+
+    for input in tests {
+        let lexer = Lexer::new(&input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        // Parsing stops at the limit, instead of running into it again for each level on the way out.
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>(),
+            ["maximum nesting depth of 1000 exceeded"]
+        );
+    }
+
+    let tests = vec![
+        ("((1))", Ok("1")),
+        ("(((1)))", Err("maximum nesting depth of 3 exceeded")),
+        // Chains aren't nesting, however long they are.
+        ("1 + 2 + 3 + 4 + 5", Ok("((((1 + 2) + 3) + 4) + 5)")),
+        ("x[0][1][2][3]", Ok("((((x[0])[1])[2])[3])")),
+        ("-(1)", Ok("(-1)")),
+        ("-(-1)", Err("maximum nesting depth of 3 exceeded")),
+        ("fn() { 1 }", Ok("fn() 1")),
+        (
+            "fn() { fn() { 1 } }",
+            Err("maximum nesting depth of 3 exceeded"),
+        ),
+        ("let [[a]] = x;", Ok("let [[a]] = x;")),
+        (
+            "let [[[a]]] = x;",
+            Err("maximum nesting depth of 3 exceeded"),
+        ),
+        // The depth goes back down after an error, so later statements can still be parsed.
+        ("(((1))); ((2))", Err("maximum nesting depth of 3 exceeded")),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        parser.set_max_depth(3);
+
+        let result = parser
+            .parse_program()
+            .map(|program| program.to_string())
+            .map_err(|errors| errors[0].message.clone());
+
+        assert_eq!(
+            result.as_deref().map_err(String::as_str),
+            expected,
+            "input: {}",
+            input
+        );
+    }
+}
+
+/// Tests that long chains of operators, calls and indexes are a parse error instead of a tree too deep
+/// for the passes after parsing
+#[test]
+fn test_max_chain() {
+    let tests = vec![
+        format!("1{}", " + 1".repeat(100_000)),
+        format!("x{}", "[0]".repeat(100_000)),
+        format!("f{}", "()".repeat(100_000)),
+        format!("x{}", ".a".repeat(100_000)),
+        format!("x{}", " |> f".repeat(100_000)),
+    ];
+
+    for input in tests {
+        let lexer = Lexer::new(&input);
+        let mut parser = Parser::new(lexer);
+
+        let errors = parser.parse_program().unwrap_err();
+
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.message.as_str())
+                .collect::<Vec<_>>(),
+            ["maximum chain length of 5000 exceeded"]
+        );
+    }
+
+    // Flat code is nowhere near the default.
+    let tests = vec![
+        format!("1{}", " + 1".repeat(1_000)),
+        format!("\"a\"{}", " + \"a\"".repeat(150)),
+        format!("x{}", " |> f".repeat(1_000)),
+    ];
+
+    for input in tests {
+        let lexer = Lexer::new(&input);
+        let mut parser = Parser::new(lexer);
+
+        assert!(parser.parse_program().is_ok(), "input: {}", input);
+    }
+
+    let tests = vec![
+        ("1 + 2 + 3", Ok("((1 + 2) + 3)")),
+        ("1 + 2 + 3 + 4", Err("maximum chain length of 2 exceeded")),
+        ("x[0][1]", Ok("((x[0])[1])")),
+        ("x[0][1][2]", Err("maximum chain length of 2 exceeded")),
+        ("x |> f |> g", Ok("g(f(x))")),
+        // The links of chains that are nested in each other add up.
+        ("f(x + 1)", Ok("f((x + 1))")),
+        ("f(x + 1 + 2)", Err("maximum chain length of 2 exceeded")),
+        ("(1 + 2) + 3", Ok("((1 + 2) + 3)")),
+        // Chains side by side don't add up, as they don't wrap each other.
+        (
+            "[1 + 2 + 3, 4 + 5 + 6]",
+            Ok("[((1 + 2) + 3), ((4 + 5) + 6)]"),
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        parser.set_max_chain(2);
+
+        let result = parser
+            .parse_program()
+            .map(|program| program.to_string())
+            .map_err(|errors| errors[0].message.clone());
+
+        assert_eq!(
+            result.as_deref().map_err(String::as_str),
+            expected,
+            "input: {}",
+            input
+        );
+    }
+}
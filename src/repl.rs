@@ -1,5 +1,20 @@
-use crate::{evaluator::eval, lexer::Lexer, parser::Parser};
-use std::io::{BufRead, BufReader, Read, Result, Write};
+use crate::{
+    ast::Program,
+    evaluator::{errors::Error, interrupt::InterruptHandle, EvalResult, Evaluator},
+    lexer::Lexer,
+    object::Object,
+    optimizer::OptLevel,
+    parser::{Limits, Parser},
+    symbol::Symbol,
+    vm::Vm,
+};
+use signal_hook::{consts::SIGINT, flag, low_level};
+use std::{
+    io::{BufRead, BufReader, Read, Result, Write},
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
 
 const PROMPT: &str = ">> ";
 const MONKEY_FACE: &str = r#"
@@ -16,9 +31,147 @@ const MONKEY_FACE: &str = r#"
            '-----'
 "#;
 
-/// Runs the REPL. Reads input from `inp`, and writes output to `out`.
-pub fn start<I: Read, O: Write>(inp: I, mut out: O) -> Result<()> {
+/// The kinds of engine that code can be run on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EngineKind {
+    /// The tree-walking `Evaluator`.
+    #[default]
+    Eval,
+
+    /// The bytecode compiler and `Vm`.
+    Vm,
+}
+
+impl FromStr for EngineKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        match name {
+            "eval" => Ok(EngineKind::Eval),
+            "vm" => Ok(EngineKind::Vm),
+            _ => Err(format!("unknown engine: {:?}, expected eval or vm", name)),
+        }
+    }
+}
+
+/// An engine that the REPL runs code on.
+pub enum Engine {
+    /// Evaluates the syntax tree directly.
+    Eval(Box<Evaluator>),
+
+    /// Compiles each line to bytecode, and runs it.
+    Vm(Box<Vm>),
+}
+
+impl Engine {
+    /// Returns a new engine of the given kind.
+    pub fn new(kind: EngineKind) -> Self {
+        match kind {
+            EngineKind::Eval => Engine::Eval(Box::default()),
+            EngineKind::Vm => Engine::Vm(Box::default()),
+        }
+    }
+
+    /// Sets how much programs are optimized before they are run.
+    pub fn set_opt_level(&mut self, opt_level: OptLevel) {
+        match self {
+            Engine::Eval(evaluator) => evaluator.set_opt_level(opt_level),
+            Engine::Vm(vm) => vm.set_opt_level(opt_level),
+        }
+    }
+
+    /// Sets the most function calls that are allowed to run at once.
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        match self {
+            Engine::Eval(evaluator) => evaluator.set_max_call_depth(max_call_depth),
+            Engine::Vm(vm) => vm.set_max_call_depth(max_call_depth),
+        }
+    }
+
+    /// Sets the deepest that expressions are allowed to be nested while they are evaluated, which on
+    /// the `Vm` is only while macros are expanded.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        match self {
+            Engine::Eval(evaluator) => evaluator.set_max_depth(max_depth),
+            Engine::Vm(vm) => vm.set_max_depth(max_depth),
+        }
+    }
+
+    /// Sets the limits that code is parsed with, both by the REPL and when it is imported.
+    pub fn set_parse_limits(&mut self, limits: Limits) {
+        match self {
+            Engine::Eval(evaluator) => evaluator.set_parse_limits(limits),
+            Engine::Vm(vm) => vm.set_parse_limits(limits),
+        }
+    }
+
+    /// Returns the limits that code is parsed with.
+    pub fn parse_limits(&self) -> Limits {
+        match self {
+            Engine::Eval(evaluator) => evaluator.parse_limits(),
+            Engine::Vm(vm) => vm.parse_limits(),
+        }
+    }
+
+    /// Sets the path of the file that is being run, which relative imports are resolved against.
+    pub fn set_file<P: AsRef<Path>>(&mut self, path: P) {
+        match self {
+            Engine::Eval(evaluator) => evaluator.set_file(path),
+            Engine::Vm(vm) => vm.set_file(path),
+        }
+    }
+
+    /// Binds `name` to `value` in the global environment.
+    pub fn define(&mut self, name: Symbol, value: Object) {
+        match self {
+            Engine::Eval(evaluator) => evaluator.define(name, value),
+            Engine::Vm(vm) => vm.define(name, value),
+        }
+    }
+
+    /// Expands the macros in `program`, and runs it.
+    pub fn run(&mut self, program: Program) -> EvalResult<Option<Object>> {
+        match self {
+            Engine::Eval(evaluator) => evaluator.run(program),
+            Engine::Vm(vm) => vm.run(program),
+        }
+    }
+
+    /// Returns the warnings that the resolver found in the last program that was run.
+    pub fn warnings(&self) -> &[Error] {
+        match self {
+            Engine::Eval(evaluator) => evaluator.warnings(),
+            Engine::Vm(vm) => vm.warnings(),
+        }
+    }
+
+    /// Returns a handle that can interrupt the engine.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        match self {
+            Engine::Eval(evaluator) => evaluator.interrupt_handle(),
+            Engine::Vm(vm) => vm.interrupt_handle(),
+        }
+    }
+}
+
+/// Runs the REPL on `engine` until the end of the input. Reads input from `inp`, and writes output to
+/// `out`. While it runs, Ctrl-C (`SIGINT`) interrupts whatever is being evaluated instead of killing the
+/// process, so that the session and its bindings are kept.
+pub fn start<I: Read, O: Write>(mut engine: Engine, inp: I, out: O) -> Result<()> {
+    // The engine is kept around between lines, so that bindings from previous lines can be used.
+    let interrupt = engine.interrupt_handle();
+
+    let signal = flag::register(SIGINT, Arc::clone(interrupt.flag()))?;
+    let result = run(&mut engine, inp, out);
+    low_level::unregister(signal);
+
+    result
+}
+
+/// Runs the REPL with `engine` until the end of the input.
+fn run<I: Read, O: Write>(engine: &mut Engine, inp: I, mut out: O) -> Result<()> {
     let mut reader = BufReader::new(inp);
+    let interrupt = engine.interrupt_handle();
 
     loop {
         // Print the prompt.
@@ -27,20 +180,38 @@ pub fn start<I: Read, O: Write>(inp: I, mut out: O) -> Result<()> {
 
         // Read a line of input.
         let mut line = String::new();
-        reader.read_line(&mut line)?;
+
+        if reader.read_line(&mut line)? == 0 {
+            writeln!(out)?;
+            return Ok(());
+        }
+
+        // A Ctrl-C while waiting for input shouldn't interrupt the line that was then typed in.
+        interrupt.clear();
 
         // Lex the line
         let lexer = Lexer::new(&line);
         let mut parser = Parser::new(lexer);
+        parser.set_limits(engine.parse_limits());
 
         let parsed = parser.parse_program();
 
+        for warning in parser.warnings() {
+            writeln!(out, "WARNING: {}", warning)?;
+        }
+
         match parsed {
             Ok(program) => {
-                let evaluated = eval(program);
+                let result = engine.run(program);
+
+                for warning in engine.warnings() {
+                    writeln!(out, "WARNING: {}", warning)?;
+                }
 
-                if let Some(object) = evaluated {
-                    writeln!(out, "{}", object)?;
+                match result {
+                    Ok(Some(object)) => writeln!(out, "{}", object)?,
+                    Ok(None) => {}
+                    Err(error) => writeln!(out, "ERROR: {}", error)?,
                 }
             }
             Err(errors) => {
@@ -0,0 +1,264 @@
+use super::{declare, declare_expression, declare_statements, Resolver};
+use crate::{
+    ast::{
+        expressions::{CallExpression, Expression, FunctionLiteral, MatchExpression, Parameter},
+        modify::modify_expression,
+        patterns::Pattern,
+    },
+    evaluator::{expressions::is_quote, macros::is_unquote},
+};
+use std::{convert::Infallible, mem};
+
+impl Resolver<'_> {
+    /// Resolves the identifiers in an `Expression`.
+    pub(super) fn resolve_expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::Identifier(identifier) => self.resolve_use(identifier),
+
+            Expression::Integer(_) | Expression::Boolean(_) | Expression::String(_) => {}
+
+            Expression::Function(function) => self.resolve_function(function),
+
+            // Macros are only evaluated while expanding them, before the program is resolved, and so
+            // their names are looked up by name.
+            Expression::Macro(_) => {}
+
+            Expression::Prefix(prefix) => self.resolve_expression(&mut prefix.right),
+
+            Expression::Infix(infix) => {
+                self.resolve_expression(&mut infix.left);
+                self.resolve_expression(&mut infix.right);
+            }
+
+            Expression::If(if_expression) => {
+                self.resolve_expression(&mut if_expression.condition);
+                self.resolve_block(&mut if_expression.consequence.statements);
+
+                for else_if in &mut if_expression.else_ifs {
+                    self.resolve_expression(&mut else_if.condition);
+                    self.resolve_block(&mut else_if.consequence.statements);
+                }
+
+                if let Some(alternative) = &mut if_expression.alternative {
+                    self.resolve_block(&mut alternative.statements);
+                }
+            }
+
+            Expression::Try(try_expression) => {
+                self.resolve_block(&mut try_expression.body.statements);
+
+                if let Some(catch) = &mut try_expression.catch {
+                    let mut names = vec![catch.parameter.value.clone()];
+                    declare_statements(&mut names, &catch.body.statements);
+
+                    self.scoped(names, false, |resolver| {
+                        resolver.resolve_binding(&mut catch.parameter);
+                        resolver.resolve_block(&mut catch.body.statements);
+                    });
+                }
+
+                if let Some(finally) = &mut try_expression.finally {
+                    self.resolve_block(&mut finally.statements);
+                }
+            }
+
+            Expression::For(for_expression) => {
+                self.resolve_expression(&mut for_expression.iterable);
+
+                let mut names = Vec::new();
+
+                for identifier in for_expression.pattern.identifiers() {
+                    declare(&mut names, identifier.value.clone());
+                }
+
+                declare_statements(&mut names, &for_expression.body.statements);
+
+                self.scoped(names, false, |resolver| {
+                    resolver.resolve_pattern_values(&mut for_expression.pattern);
+                    resolver.resolve_pattern_bindings(&mut for_expression.pattern);
+                    resolver.resolve_block(&mut for_expression.body.statements);
+                });
+            }
+
+            Expression::Match(match_expression) => self.resolve_match(match_expression),
+
+            Expression::Call(call) if is_quote(call) => self.resolve_quote(call),
+
+            Expression::Call(call) => {
+                self.resolve_expression(&mut call.function);
+
+                for argument in &mut call.arguments {
+                    self.resolve_expression(argument);
+                }
+            }
+
+            Expression::Array(array) => {
+                for element in &mut array.elements {
+                    self.resolve_expression(element);
+                }
+            }
+
+            Expression::Index(index) => {
+                self.resolve_expression(&mut index.left);
+                self.resolve_expression(&mut index.index);
+            }
+
+            // The member is the name of a field or export, not a variable.
+            Expression::Member(member) => self.resolve_expression(&mut member.object),
+
+            Expression::Spread(spread) => self.resolve_expression(&mut spread.value),
+
+            Expression::Hash(hash) => {
+                for (key, value) in &mut hash.pairs {
+                    self.resolve_expression(key);
+                    self.resolve_expression(value);
+                }
+            }
+
+            Expression::Record(record) => {
+                self.resolve_use(&mut record.name);
+
+                for (_, value) in &mut record.fields {
+                    self.resolve_expression(value);
+                }
+            }
+        }
+    }
+
+    /// Resolves a `FunctionLiteral`, whose body is in a scope of its own, which starts with a slot for
+    /// each of its parameters. The default values of the parameters are evaluated in that scope too.
+    pub(super) fn resolve_function(&mut self, function: &mut FunctionLiteral) {
+        let mut names = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name().value.clone())
+            .collect::<Vec<_>>();
+
+        for parameter in &function.parameters {
+            if let Parameter::Default { value, .. } = parameter {
+                declare_expression(&mut names, value);
+            }
+        }
+
+        declare_statements(&mut names, &function.body.statements);
+
+        self.scoped(names, true, |resolver| {
+            for parameter in &mut function.parameters {
+                resolver.resolve_binding(parameter.name_mut());
+            }
+
+            for parameter in &mut function.parameters {
+                if let Parameter::Default { value, .. } = parameter {
+                    resolver.resolve_expression(value);
+                }
+            }
+
+            resolver.resolve_block(&mut function.body.statements);
+        });
+    }
+
+    /// Resolves a `MatchExpression`. Each arm is in a scope of its own, with its pattern's bindings.
+    /// The values in the patterns are evaluated before an arm's scope is entered, like
+    /// `Evaluator::eval_match`.
+    fn resolve_match(&mut self, match_expression: &mut MatchExpression) {
+        self.resolve_expression(&mut match_expression.subject);
+
+        for arm in &mut match_expression.arms {
+            self.resolve_pattern_values(&mut arm.pattern);
+
+            let mut names = Vec::new();
+
+            for identifier in arm.pattern.identifiers() {
+                declare(&mut names, identifier.value.clone());
+            }
+
+            if let Some(guard) = &arm.guard {
+                declare_expression(&mut names, guard);
+            }
+
+            declare_expression(&mut names, &arm.body);
+
+            self.scoped(names, false, |resolver| {
+                resolver.resolve_pattern_bindings(&mut arm.pattern);
+
+                if let Some(guard) = &mut arm.guard {
+                    resolver.resolve_expression(guard);
+                }
+
+                resolver.resolve_expression(&mut arm.body);
+            });
+        }
+    }
+
+    /// Resolves a call to `quote`. Its argument isn't evaluated, apart from the arguments of the calls
+    /// to `unquote` inside of it.
+    fn resolve_quote(&mut self, call: &mut CallExpression) {
+        let arguments = mem::take(&mut call.arguments);
+
+        call.arguments = arguments
+            .into_iter()
+            .map(|argument| {
+                let Ok(argument) = modify_expression(argument, &mut |mut expression| {
+                    if let Expression::Call(call) = &mut expression {
+                        if is_unquote(call) {
+                            for argument in &mut call.arguments {
+                                self.resolve_expression(argument);
+                            }
+                        }
+                    }
+
+                    Ok::<_, Infallible>(expression)
+                });
+
+                argument
+            })
+            .collect();
+    }
+
+    /// Resolves the values in `pattern` that are compared to (or looked up in) the value being matched.
+    pub(super) fn resolve_pattern_values(&mut self, pattern: &mut Pattern) {
+        match pattern {
+            Pattern::Identifier(_) | Pattern::Wildcard(_) => {}
+
+            Pattern::Literal(literal) => self.resolve_expression(literal),
+
+            Pattern::Array(array) => {
+                for element in &mut array.elements {
+                    self.resolve_pattern_values(element);
+                }
+            }
+
+            Pattern::Hash(hash) => {
+                for (key, value) in &mut hash.pairs {
+                    self.resolve_expression(key);
+                    self.resolve_pattern_values(value);
+                }
+            }
+        }
+    }
+
+    /// Resolves the identifiers that `pattern` binds in the innermost scope.
+    pub(super) fn resolve_pattern_bindings(&mut self, pattern: &mut Pattern) {
+        match pattern {
+            Pattern::Identifier(identifier) => self.resolve_binding(identifier),
+
+            Pattern::Wildcard(_) | Pattern::Literal(_) => {}
+
+            Pattern::Array(array) => {
+                for element in &mut array.elements {
+                    self.resolve_pattern_bindings(element);
+                }
+
+                if let Some(rest) = &mut array.rest {
+                    self.resolve_binding(rest);
+                }
+            }
+
+            Pattern::Hash(hash) => {
+                for (_, value) in &mut hash.pairs {
+                    self.resolve_pattern_bindings(value);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,146 @@
+mod expressions;
+mod statements;
+pub mod tests;
+
+pub(crate) use self::statements::{declare, declare_expression, declare_statements};
+use crate::{
+    ast::{
+        expressions::{Identifier, Slot},
+        Program,
+    },
+    evaluator::errors::{Error, ErrorKind},
+    symbol::Symbol,
+};
+use std::collections::HashSet;
+
+/// Resolves every identifier in `program`, recording the slots of the variables that it can refer to
+/// in the identifier (see `Identifier::slots`), so that the evaluator can look them up by index instead
+/// of by name. `is_defined` is whether a name is already bound where the program is going to run (e.g.
+/// by the prelude, a builtin or an earlier line of the REPL).
+///
+/// The scopes are the same as the evaluator's environments: names bound at the top level of the
+/// program are globals, and every function call, `for` loop iteration, `match` arm and `catch` clause
+/// has a scope of its own, with a slot for each name that is bound in it.
+///
+/// Returns the names that are used before they are defined, or that aren't defined at all. These aren't
+/// errors, as the code that uses them might never run, so they are meant to be reported as warnings.
+pub fn resolve(program: &mut Program, is_defined: impl Fn(&Symbol) -> bool) -> Vec<Error> {
+    let mut names = Vec::new();
+    declare_statements(&mut names, &program.statements);
+
+    let mut resolver = Resolver {
+        scopes: vec![Scope::new(names, true)],
+        is_defined: &is_defined,
+        errors: Vec::new(),
+    };
+
+    resolver.resolve_block(&mut program.statements);
+    resolver.errors
+}
+
+/// The resolver. It keeps track of the scopes that the code being resolved is in.
+struct Resolver<'a> {
+    /// The scopes that the code being resolved is in, from the top level of the program (whose names
+    /// are globals, without slots) to the innermost one.
+    scopes: Vec<Scope>,
+
+    /// Whether a name is already bound where the program is going to run.
+    is_defined: &'a dyn Fn(&Symbol) -> bool,
+
+    /// The names that have been found to be used before they are defined, or not to be defined at all.
+    errors: Vec<Error>,
+}
+
+/// A scope that is being resolved.
+struct Scope {
+    /// The names that are bound in the scope, which are the names of its slots.
+    names: Vec<Symbol>,
+
+    /// The names that have been bound so far, going through the scope's code in order.
+    defined: HashSet<Symbol>,
+
+    /// Whether the scope is the top level of the program or a function's own scope. The code in a
+    /// function runs whenever the function is called, so it can use any name that the scopes around the
+    /// function bind, even if they bind it further down.
+    function: bool,
+}
+
+impl Resolver<'_> {
+    /// Runs `resolve` in a new scope with slots for `names`.
+    fn scoped(&mut self, names: Vec<Symbol>, function: bool, resolve: impl FnOnce(&mut Self)) {
+        self.scopes.push(Scope::new(names, function));
+        resolve(self);
+        self.scopes.pop();
+    }
+
+    /// Resolves a use of `identifier`, as any of the slots that bind its name in the scopes that it is
+    /// in, and otherwise a global. A name that the code might run before it is bound is recorded.
+    fn resolve_use(&mut self, identifier: &mut Identifier) {
+        let name = &identifier.value;
+        let mut slots = Vec::new();
+        let mut defined = false;
+        let mut later = false;
+        let mut in_function = false;
+
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(index) = scope.names.iter().position(|slot| slot == name) {
+                // The top level of the program has no slots, as its names are globals.
+                if depth < self.scopes.len() - 1 {
+                    slots.push(Slot { depth, index });
+                }
+
+                if in_function || scope.defined.contains(name) {
+                    defined = true;
+                } else {
+                    later = true;
+                }
+            }
+
+            in_function |= scope.function;
+        }
+
+        if !defined && !(self.is_defined)(name) {
+            let message = match later {
+                true => format!("{} is used before it is defined", name),
+                false => format!("identifier not found: {}", name),
+            };
+
+            self.errors
+                .push(Error::new(ErrorKind::Name, message).at(identifier.token.position));
+        }
+
+        identifier.slots = Some(slots);
+    }
+
+    /// Resolves `identifier` being bound in the innermost scope, giving it a slot there if it doesn't
+    /// have one yet.
+    fn resolve_binding(&mut self, identifier: &mut Identifier) {
+        let top_level = self.scopes.len() == 1;
+        let scope = self.scopes.last_mut().expect("the program has a scope");
+
+        declare(&mut scope.names, identifier.value.clone());
+        scope.defined.insert(identifier.value.clone());
+
+        let index = scope
+            .names
+            .iter()
+            .position(|slot| *slot == identifier.value)
+            .expect("the name has just been declared");
+
+        identifier.slots = Some(match top_level {
+            true => Vec::new(),
+            false => vec![Slot { depth: 0, index }],
+        });
+    }
+}
+
+impl Scope {
+    /// Creates a scope with slots for `names`, none of which have been bound yet.
+    fn new(names: Vec<Symbol>, function: bool) -> Self {
+        Self {
+            names,
+            defined: HashSet::new(),
+            function,
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use super::Resolver;
+use crate::{
+    ast::{
+        expressions::Expression,
+        statements::{ExportStatement, Statement},
+    },
+    symbol::Symbol,
+};
+use std::slice;
+
+impl Resolver<'_> {
+    /// Resolves the statements of a block, in the scope that it is in. The functions and structs that
+    /// the block declares are bound as soon as it is entered, like `Evaluator::hoist_declarations`.
+    pub(super) fn resolve_block(&mut self, statements: &mut [Statement]) {
+        for statement in statements.iter_mut() {
+            let statement = match statement {
+                Statement::Export(export) => export.declaration.as_mut(),
+                statement => statement,
+            };
+
+            match statement {
+                Statement::Function(declaration) => self.resolve_binding(&mut declaration.name),
+                Statement::Struct(declaration) => self.resolve_binding(&mut declaration.name),
+                _ => {}
+            }
+        }
+
+        for statement in statements {
+            self.resolve_statement(statement);
+        }
+    }
+
+    /// Resolves a single statement.
+    fn resolve_statement(&mut self, statement: &mut Statement) {
+        match statement {
+            Statement::Let(let_statement) => {
+                self.resolve_expression(&mut let_statement.value);
+                self.resolve_pattern_values(&mut let_statement.pattern);
+                self.resolve_pattern_bindings(&mut let_statement.pattern);
+            }
+
+            Statement::Return(return_statement) => {
+                self.resolve_expression(&mut return_statement.return_value)
+            }
+            Statement::Throw(throw) => self.resolve_expression(&mut throw.value),
+            Statement::Yield(yield_statement) => {
+                self.resolve_expression(&mut yield_statement.value)
+            }
+            Statement::Expression(expression) => {
+                self.resolve_expression(&mut expression.expression)
+            }
+
+            // Declarations have already been bound when their block was entered.
+            Statement::Function(declaration) => self.resolve_function(&mut declaration.function),
+            Statement::Struct(_) => {}
+
+            Statement::Import(import) => self.resolve_binding(&mut import.alias),
+            Statement::Export(export) => self.resolve_statement(&mut export.declaration),
+        }
+    }
+}
+
+/// Adds the names that `statements` bind in the scope that they are in to `names`, without any
+/// duplicates. This doesn't look inside of anything that has a scope of its own (function literals,
+/// macros, `for` loop bodies, `match` arms and `catch` clauses), but does look inside of every other
+/// block, as blocks share the scope that they are in.
+///
+/// A scope has a slot for each of these names from the start, so that a closure created in the scope
+/// refers to a name's slot even if it is created before the name is bound.
+pub(crate) fn declare_statements(names: &mut Vec<Symbol>, statements: &[Statement]) {
+    for statement in statements {
+        match statement {
+            Statement::Let(let_statement) => {
+                for identifier in let_statement.pattern.identifiers() {
+                    declare(names, identifier.value.clone());
+                }
+
+                declare_expression(names, &let_statement.value);
+            }
+
+            Statement::Return(return_statement) => {
+                declare_expression(names, &return_statement.return_value)
+            }
+            Statement::Throw(throw) => declare_expression(names, &throw.value),
+            Statement::Yield(yield_statement) => declare_expression(names, &yield_statement.value),
+            Statement::Expression(expression) => declare_expression(names, &expression.expression),
+            Statement::Function(declaration) => declare(names, declaration.name.value.clone()),
+            Statement::Struct(declaration) => declare(names, declaration.name.value.clone()),
+            Statement::Import(import) => declare(names, import.alias.value.clone()),
+            Statement::Export(ExportStatement { declaration, .. }) => {
+                declare_statements(names, slice::from_ref(declaration.as_ref()))
+            }
+        }
+    }
+}
+
+/// Adds the names that the blocks in `expression` bind in the scope that it is in to `names`, like
+/// `declare_statements`.
+pub(crate) fn declare_expression(names: &mut Vec<Symbol>, expression: &Expression) {
+    match expression {
+        Expression::Identifier(_)
+        | Expression::Integer(_)
+        | Expression::Boolean(_)
+        | Expression::String(_)
+        | Expression::Function(_)
+        | Expression::Macro(_) => {}
+
+        Expression::Prefix(prefix) => declare_expression(names, &prefix.right),
+
+        Expression::Infix(infix) => {
+            declare_expression(names, &infix.left);
+            declare_expression(names, &infix.right);
+        }
+
+        Expression::If(if_expression) => {
+            declare_expression(names, &if_expression.condition);
+            declare_statements(names, &if_expression.consequence.statements);
+
+            for else_if in &if_expression.else_ifs {
+                declare_expression(names, &else_if.condition);
+                declare_statements(names, &else_if.consequence.statements);
+            }
+
+            if let Some(alternative) = &if_expression.alternative {
+                declare_statements(names, &alternative.statements);
+            }
+        }
+
+        Expression::Try(try_expression) => {
+            declare_statements(names, &try_expression.body.statements);
+
+            if let Some(finally) = &try_expression.finally {
+                declare_statements(names, &finally.statements);
+            }
+        }
+
+        Expression::For(for_expression) => declare_expression(names, &for_expression.iterable),
+
+        Expression::Match(match_expression) => declare_expression(names, &match_expression.subject),
+
+        Expression::Call(call) => {
+            declare_expression(names, &call.function);
+
+            for argument in &call.arguments {
+                declare_expression(names, argument);
+            }
+        }
+
+        Expression::Array(array) => {
+            for element in &array.elements {
+                declare_expression(names, element);
+            }
+        }
+
+        Expression::Index(index) => {
+            declare_expression(names, &index.left);
+            declare_expression(names, &index.index);
+        }
+
+        Expression::Member(member) => declare_expression(names, &member.object),
+
+        Expression::Spread(spread) => declare_expression(names, &spread.value),
+
+        Expression::Hash(hash) => {
+            for (key, value) in &hash.pairs {
+                declare_expression(names, key);
+                declare_expression(names, value);
+            }
+        }
+
+        Expression::Record(record) => {
+            for (_, value) in &record.fields {
+                declare_expression(names, value);
+            }
+        }
+    }
+}
+
+/// Adds `name` to `names`, unless it is already in it.
+pub(crate) fn declare(names: &mut Vec<Symbol>, name: Symbol) {
+    if !names.contains(&name) {
+        names.push(name);
+    }
+}
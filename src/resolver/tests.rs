@@ -0,0 +1,134 @@
+#![cfg(test)]
+
+use super::resolve;
+use crate::{
+    ast::{
+        expressions::{Expression, Slot},
+        statements::Statement,
+        Program,
+    },
+    lexer::Lexer,
+    parser::Parser,
+};
+
+/// Parses the input source code
+fn parse(input: &str) -> Program {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    parser.parse_program().unwrap()
+}
+
+/// Returns the slots of the identifier that `statements` end with, looking inside of the function
+/// literals, `for` loops and (first) `match` arms that they end with
+fn last_slots(statements: &[Statement]) -> Option<Vec<Slot>> {
+    match statements.last() {
+        Some(Statement::Expression(statement)) => expression_slots(&statement.expression),
+        statement => panic!("Statement is not an expression, found {:?}", statement),
+    }
+}
+
+/// Returns the slots of the identifier that `expression` is or ends with, like `last_slots`
+fn expression_slots(expression: &Expression) -> Option<Vec<Slot>> {
+    match expression {
+        Expression::Identifier(identifier) => identifier.slots.clone(),
+        Expression::Function(function) => last_slots(&function.body.statements),
+        Expression::For(for_expression) => last_slots(&for_expression.body.statements),
+        Expression::Match(match_expression) => expression_slots(&match_expression.arms[0].body),
+        expression => panic!("Expression is not an identifier, found {}", expression),
+    }
+}
+
+/// Tests resolving identifiers to the slots of the scopes that bind them
+#[test]
+fn test_resolve_slots() {
+    let slot = |depth, index| Slot { depth, index };
+
+    let tests = vec![
+        ("let x = 1; x", vec![]),
+        ("fn(a, b) { b }", vec![slot(0, 1)]),
+        ("fn(a) { let b = a; b }", vec![slot(0, 1)]),
+        ("fn(a) { fn(b) { a } }", vec![slot(1, 0)]),
+        (
+            "fn(a) { fn(b) { let a = b; a } }",
+            vec![slot(0, 1), slot(1, 0)],
+        ),
+        // A name that is only bound in some of the scopes' code might not be bound yet, so the scopes
+        // further out are looked in too.
+        (
+            "fn(x) { fn() { if (true) { let x = 2; } x } }",
+            vec![slot(0, 0), slot(1, 0)],
+        ),
+        ("fn(f) { for (x in f) { x } }", vec![slot(0, 0)]),
+        (
+            "fn() { let x = 1; fn() { match (1) { y => x } } }",
+            vec![slot(2, 0)],
+        ),
+        ("fn() { puts }", vec![]),
+    ];
+
+    for (input, expected) in tests {
+        let mut program = parse(input);
+        let errors = resolve(&mut program, |_| true);
+
+        assert!(errors.is_empty(), "input: {}, errors: {:?}", input, errors);
+        assert_eq!(
+            last_slots(&program.statements),
+            Some(expected),
+            "input: {}",
+            input
+        );
+    }
+
+    // Unresolved identifiers are looked up by name.
+    assert_eq!(last_slots(&parse("fn(a) { a }").statements), None);
+}
+
+/// Tests finding names that are used before they are defined, or that aren't defined at all
+#[test]
+fn test_resolve_warnings() {
+    let tests = vec![
+        ("let x = 1; x", vec![]),
+        ("x", vec!["1:1: identifier not found: x"]),
+        ("x; let x = 1;", vec!["1:1: x is used before it is defined"]),
+        ("let x = x;", vec!["1:9: x is used before it is defined"]),
+        (
+            "fn f() { y; let y = 1; }",
+            vec!["1:10: y is used before it is defined"],
+        ),
+        // Functions run when they are called, so they can use names that are defined further down.
+        ("let f = fn() { g() }; let g = fn() { 1 }; f()", vec![]),
+        ("fn f() { g() } fn g() { 1 }", vec![]),
+        (
+            "fn() { for (i in [1]) { last } let last = 1; }",
+            vec!["1:25: last is used before it is defined"],
+        ),
+        (
+            "fn(x) { match (x) { [a, ...rest] => rest, _ => a } }",
+            vec!["1:48: identifier not found: a"],
+        ),
+        ("try { 1 } catch (e) { e.message }", vec![]),
+        (
+            "struct Point { x, y } Point { x: 1, y: missing }",
+            vec!["1:40: identifier not found: missing"],
+        ),
+        ("defined", vec![]),
+        // Only the arguments of `unquote`s are evaluated in a `quote`.
+        (
+            "quote(a + unquote(b))",
+            vec!["1:19: identifier not found: b"],
+        ),
+    ];
+
+    for (input, expected) in tests {
+        let mut program = parse(input);
+
+        let errors = resolve(&mut program, |name| {
+            ["defined", "puts"].contains(&name.as_str())
+        })
+        .into_iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>();
+
+        assert_eq!(errors, expected, "input: {}", input);
+    }
+}
@@ -0,0 +1,220 @@
+pub mod tests;
+
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::{Arc, LazyLock, RwLock},
+};
+
+// Names come up over and over again: every use of an identifier repeats its name, and so does every
+// token of a keyword. Instead of keeping a `String` for each of them, names are interned: each
+// distinct name is stored once, in the interner, and everything else refers to it by a `Symbol`,
+// which is cheap to clone, compare and hash.
+//
+// The interner is global, so that symbols mean the same thing on every thread and in every engine.
+// String constants and the literals of integers are interned too, and they can be anything at all,
+// so a long-running program that parses code (e.g. the REPL) would run out of memory if the interner
+// kept every name forever. Instead, each symbol counts as a reference to its name, and the interner
+// forgets the names that nothing refers to anymore, once enough of them have been interned.
+
+/// The interner that all symbols refer to.
+static INTERNER: LazyLock<RwLock<Interner>> = LazyLock::new(|| RwLock::new(Interner::new()));
+
+/// The fewest names that the interner holds before it forgets the ones that are no longer used.
+const MIN_PRUNE: usize = 1024;
+
+/// An interned name, which refers to the one copy of the name in the interner. Two symbols are equal
+/// if and only if their names are, and the name of a symbol can be looked up with `as_str` (or
+/// through `Deref`, so a symbol can be used as a `&str` in most places). Symbols are hashed by where
+/// their name is, so a map keyed by symbols can't be looked up by name directly; `Symbol::lookup`
+/// finds the key for it.
+///
+/// The default symbol is for the empty name.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    /// Returns the symbol for `name`, interning it if it hasn't been interned yet.
+    pub fn intern(name: &str) -> Self {
+        if let Some(symbol) = Self::lookup(name) {
+            return symbol;
+        }
+
+        INTERNER
+            .write()
+            .unwrap_or_else(|error| error.into_inner())
+            .intern(name)
+    }
+
+    /// Returns the symbol for `name`, if it is interned. Nothing can be bound to a name that isn't, so
+    /// this is how names are looked up without interning them.
+    pub fn lookup(name: &str) -> Option<Self> {
+        INTERNER
+            .read()
+            .unwrap_or_else(|error| error.into_inner())
+            .get(name)
+    }
+
+    /// Returns the name that this symbol refers to.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Symbol {
+    fn default() -> Self {
+        Self::intern("")
+    }
+}
+
+// Each name is only interned once for as long as any symbol refers to it, so two symbols have the
+// same name if and only if they refer to the same copy of it.
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Arc::as_ptr(&self.0).cast::<u8>().hash(state);
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::intern(name)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Self::intern(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Self::intern(&name)
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+// Symbols are ordered by their names, not by when they were interned, so that sorting them doesn't
+// depend on what happened to be interned first.
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self == other {
+            true => Ordering::Equal,
+            false => self.as_str().cmp(other.as_str()),
+        }
+    }
+}
+
+impl Debug for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// The names that have been interned.
+struct Interner {
+    /// The names. A name that only the interner refers to isn't used anymore, and is forgotten the
+    /// next time the interner prunes its names.
+    names: HashSet<Arc<str>>,
+
+    /// How many names the interner can hold before it next prunes its names.
+    limit: usize,
+}
+
+impl Interner {
+    /// Creates an empty interner.
+    fn new() -> Self {
+        Self {
+            names: HashSet::new(),
+            limit: MIN_PRUNE,
+        }
+    }
+
+    /// Returns the symbol for `name`, if it is interned and still used.
+    fn get(&self, name: &str) -> Option<Symbol> {
+        self.names
+            .get(name)
+            .filter(|name| Arc::strong_count(name) > 1)
+            .map(|name| Symbol(name.clone()))
+    }
+
+    /// Returns the symbol for `name`, interning it if it hasn't been interned yet. Once the interner
+    /// is full, the names that aren't used anymore are forgotten first, and the interner can then hold
+    /// twice as many names as are still used, so that pruning takes amortized constant time.
+    fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(name) = self.names.get(name) {
+            return Symbol(name.clone());
+        }
+
+        if self.names.len() >= self.limit {
+            self.names.retain(|name| Arc::strong_count(name) > 1);
+            self.limit = (self.names.len() * 2).max(MIN_PRUNE);
+        }
+
+        let name: Arc<str> = name.into();
+        self.names.insert(name.clone());
+
+        Symbol(name)
+    }
+
+    /// Returns how many names the interner holds, including the ones that aren't used anymore but
+    /// haven't been forgotten yet.
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.names.len()
+    }
+}
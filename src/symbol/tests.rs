@@ -0,0 +1,128 @@
+#![cfg(test)]
+
+use super::{Symbol, INTERNER};
+use crate::{
+    ast::{
+        expressions::Expression,
+        statements::{LetStatement, Statement},
+    },
+    lexer::Lexer,
+    parser::Parser,
+    token::TokenType,
+};
+
+/// Tests interning names and looking them back up
+#[test]
+fn test_intern() {
+    let names = vec!["x", "foo", "fooBar", "let", "", "snow ☃ man", "a\nb"];
+
+    for name in names {
+        let symbol = Symbol::intern(name);
+
+        assert_eq!(symbol, Symbol::intern(name));
+        assert_eq!(symbol, Symbol::from(name.to_string()));
+        assert_eq!(Symbol::lookup(name), Some(symbol.clone()));
+        assert_eq!(symbol.as_str(), name);
+        assert_eq!(symbol.to_string(), name);
+        assert_eq!(symbol, name);
+    }
+
+    assert_ne!(Symbol::intern("foo"), Symbol::intern("bar"));
+    assert_ne!(Symbol::intern("foo"), Symbol::intern("Foo"));
+    assert_eq!(Symbol::default(), Symbol::intern(""));
+
+    // Looking a name up doesn't intern it.
+    assert_eq!(Symbol::lookup("a name that is never interned"), None);
+    assert_eq!(Symbol::lookup("a name that is never interned"), None);
+}
+
+/// Tests that symbols are ordered by their names
+#[test]
+fn test_symbol_order() {
+    // Interned in the opposite order that they sort in.
+    let mut symbols = ["zeta", "mu", "alpha", "Zeta", "m"].map(Symbol::intern);
+    symbols.sort();
+
+    assert_eq!(
+        symbols.each_ref().map(Symbol::as_str),
+        ["Zeta", "alpha", "m", "mu", "zeta"]
+    );
+}
+
+/// Tests that the tokens and identifiers that the lexer and parser produce share the symbols of their
+/// names
+#[test]
+fn test_symbol_sharing() {
+    let input = "let total = total + total; let x = \"total\";";
+
+    let mut lexer = Lexer::new(input);
+    let tokens = std::iter::from_fn(|| Some(lexer.next_token()))
+        .take_while(|token| token.r#type != TokenType::EOF)
+        .collect::<Vec<_>>();
+
+    let count = |symbol: &Symbol| {
+        tokens
+            .iter()
+            .filter(|token| token.literal == *symbol)
+            .count()
+    };
+
+    // The string's token has the same literal as the identifiers, as the quotes aren't part of it.
+    let total = Symbol::intern("total");
+    assert_eq!(count(&total), 4);
+    assert_eq!(count(&Symbol::intern("let")), 2);
+
+    let program = Parser::new(Lexer::new(input)).parse_program().unwrap();
+
+    let [Statement::Let(first), Statement::Let(second)] = program.statements.as_slice() else {
+        panic!("expected two let statements, got {}", program);
+    };
+
+    let LetStatement { value, .. } = first;
+    let Expression::Infix(infix) = value.as_ref() else {
+        panic!("expected an infix expression, got {}", value);
+    };
+
+    for operand in [&infix.left, &infix.right] {
+        let Expression::Identifier(identifier) = operand.as_ref() else {
+            panic!("expected an identifier, got {}", operand);
+        };
+
+        assert_eq!(identifier.value, total);
+        assert_eq!(identifier.token.literal, total);
+    }
+
+    let Expression::String(string) = second.value.as_ref() else {
+        panic!("expected a string literal, got {}", second.value);
+    };
+
+    assert_eq!(string.value, total);
+}
+
+/// Tests that the interner forgets the names that no symbol refers to anymore, so that interning ever
+/// more names (e.g. the string constants of each line entered into the REPL) doesn't keep all of them
+#[test]
+fn test_reclaim() {
+    let kept = Symbol::intern("a name that is kept");
+
+    for i in 0..100_000 {
+        Symbol::intern(&format!("a name that is dropped {}", i));
+    }
+
+    // Other tests intern names at the same time, but none of them keep anywhere near this many.
+    assert!(INTERNER.read().unwrap().len() < 50_000);
+    assert_eq!(Symbol::lookup("a name that is dropped 0"), None);
+    assert_eq!(Symbol::lookup("a name that is kept"), Some(kept));
+
+    let name = "a string constant that is dropped with its program";
+    let program = Parser::new(Lexer::new(&format!("let x = \"{}\";", name)))
+        .parse_program()
+        .unwrap();
+
+    assert!(Symbol::lookup(name).is_some());
+    drop(program);
+    assert_eq!(Symbol::lookup(name), None);
+
+    let symbol = Symbol::intern(name);
+    assert_eq!(symbol, name);
+}
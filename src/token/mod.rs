@@ -1,15 +1,20 @@
+use crate::symbol::Symbol;
 use core::fmt::Display;
+use std::hash::{Hash, Hasher};
 
 /// The token type that is used in the lexer. This contains both the type of the
 /// token (as `TokenType`), as well as the string literal value that the token was
-/// created from.
-#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+/// created from (interned as a `Symbol`), and where in the source code it was found.
+#[derive(Clone, Debug, Default)]
 pub struct Token {
     /// The token type that this token is.
     pub r#type: TokenType,
 
     /// The literal value of the token.
-    pub literal: String,
+    pub literal: Symbol,
+
+    /// The position of the first character of the token in the source code.
+    pub position: Position,
 }
 
 /// Public API for the `Token` struct.
@@ -19,13 +24,21 @@ impl Token {
         Self {
             r#type,
 
-            // Why do this? Because 1) we don't want to allocate a new `String` for
-            // every token, and 2) it makes it easier when defining a token, as defining
-            // a `&str` literal is easier than defining a `String` literal.
-            literal: literal.to_string(),
+            // Why do this? Because it makes it easier when defining a token, as a
+            // `char` or `&str` literal can be given as well as a `String`. Either way,
+            // the literal is interned, so tokens with the same literal share it.
+            literal: Symbol::intern(&literal.to_string()),
+
+            position: Position::default(),
         }
     }
 
+    /// Sets the position of the token in the source code.
+    pub fn at(mut self, position: Position) -> Self {
+        self.position = position;
+        self
+    }
+
     /// Creates a new `Token` from a given identifier `ident`. This is used when
     /// lexing an identifier, as we don't know if it is a keyword or not until
     /// we have lexed the entire identifier. Defaults to `TokenType::Ident`.
@@ -39,6 +52,19 @@ impl Token {
                 "true" => TokenType::True,
                 "false" => TokenType::False,
                 "return" => TokenType::Return,
+                "match" => TokenType::Match,
+                "macro" => TokenType::Macro,
+                "import" => TokenType::Import,
+                "export" => TokenType::Export,
+                "as" => TokenType::As,
+                "struct" => TokenType::Struct,
+                "throw" => TokenType::Throw,
+                "try" => TokenType::Try,
+                "catch" => TokenType::Catch,
+                "finally" => TokenType::Finally,
+                "for" => TokenType::For,
+                "in" => TokenType::In,
+                "yield" => TokenType::Yield,
                 _ => TokenType::Ident,
             },
             ident,
@@ -46,6 +72,65 @@ impl Token {
     }
 }
 
+// Two tokens are the same if they have the same type and literal value, no matter where they are in
+// the source code. This means that the same code parses to the same AST wherever it is written, which
+// makes comparing tokens and ASTs (e.g. in tests) a lot easier.
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.r#type == other.r#type && self.literal == other.literal
+    }
+}
+
+impl Eq for Token {}
+
+impl Hash for Token {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.r#type.hash(state);
+        self.literal.hash(state);
+    }
+}
+
+/// A comment in the source code, which starts with `//` and runs to the end of the line. The lexer skips
+/// comments like whitespace, but remembers them, so that they can be kept when the code is formatted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comment {
+    /// The text of the comment, including the `//`, but not the newline after it.
+    pub text: String,
+
+    /// The position of the first `/` of the comment in the source code.
+    pub position: Position,
+}
+
+/// A position in the source code. Both the line and the column start at 1, and the column is counted
+/// in characters, not bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    /// The line that the position is on.
+    pub line: usize,
+
+    /// The column that the position is at.
+    pub column: usize,
+}
+
+impl Position {
+    /// Creates a new `Position` from the given line and column.
+    pub fn new(line: usize, column: usize) -> Self {
+        Self { line, column }
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new(1, 1)
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
 /// The token type that is used in the lexer. These are markers for the type of
 /// token that is being used. Note that they do not contain the actual value of
 /// the token, only the type.
@@ -58,6 +143,7 @@ pub enum TokenType {
     // Identifiers + literals
     Ident,
     Int,
+    String,
 
     // Operators
     Assign,
@@ -75,14 +161,31 @@ pub enum TokenType {
     Eq,
     NotEq,
 
+    // `|>`, which passes the value on its left as the first argument of the call on its right.
+    Pipe,
+
     // Delimiters
     Comma,
     Semicolon,
+    Colon,
+    // `.`, used to access the exports of a module, e.g. `math.add`.
+    Dot,
+    // `..`, used for ranges, e.g. `0..10`.
+    DotDot,
+    // `...`, used for rest parameters and spread arguments.
+    Ellipsis,
+    // `=>`, used to separate the pattern of a match arm from its body, and the parameter of a short
+    // lambda from its body.
+    Arrow,
+    // `|`, used around the parameters of a short lambda, e.g. `|x, y| x + y`.
+    Bar,
 
     LParen,
     RParen,
     LBrace,
     RBrace,
+    LBracket,
+    RBracket,
 
     // Keywords
     Function,
@@ -92,6 +195,19 @@ pub enum TokenType {
     Return,
     True,
     False,
+    Match,
+    Macro,
+    Import,
+    Export,
+    As,
+    Struct,
+    Throw,
+    Try,
+    Catch,
+    Finally,
+    For,
+    In,
+    Yield,
 }
 
 impl Display for TokenType {
@@ -104,6 +220,7 @@ impl Display for TokenType {
                 TokenType::EOF => "EOF",
                 TokenType::Ident => "IDENT",
                 TokenType::Int => "INT",
+                TokenType::String => "STRING",
                 TokenType::Assign => "=",
                 TokenType::Plus => "+",
                 TokenType::Minus => "-",
@@ -114,12 +231,21 @@ impl Display for TokenType {
                 TokenType::Gt => ">",
                 TokenType::Eq => "==",
                 TokenType::NotEq => "!=",
+                TokenType::Pipe => "|>",
                 TokenType::Comma => ",",
                 TokenType::Semicolon => ";",
+                TokenType::Colon => ":",
+                TokenType::Dot => ".",
+                TokenType::DotDot => "..",
+                TokenType::Ellipsis => "...",
+                TokenType::Arrow => "=>",
+                TokenType::Bar => "|",
                 TokenType::LParen => "(",
                 TokenType::RParen => ")",
                 TokenType::LBrace => "{",
                 TokenType::RBrace => "}",
+                TokenType::LBracket => "[",
+                TokenType::RBracket => "]",
                 TokenType::Function => "FUNCTION",
                 TokenType::Let => "LET",
                 TokenType::If => "IF",
@@ -127,6 +253,19 @@ impl Display for TokenType {
                 TokenType::Return => "RETURN",
                 TokenType::True => "TRUE",
                 TokenType::False => "FALSE",
+                TokenType::Match => "MATCH",
+                TokenType::Macro => "MACRO",
+                TokenType::Import => "IMPORT",
+                TokenType::Export => "EXPORT",
+                TokenType::As => "AS",
+                TokenType::Struct => "STRUCT",
+                TokenType::Throw => "THROW",
+                TokenType::Try => "TRY",
+                TokenType::Catch => "CATCH",
+                TokenType::Finally => "FINALLY",
+                TokenType::For => "FOR",
+                TokenType::In => "IN",
+                TokenType::Yield => "YIELD",
             }
         )
     }
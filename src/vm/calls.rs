@@ -0,0 +1,317 @@
+use super::{
+    frame::{Frame, FrameKind},
+    locals::Local,
+    EvalResult, Exit, Vm,
+};
+use crate::{
+    ast::expressions::Parameter,
+    evaluator::{
+        errors::{Error, ErrorKind},
+        expressions::{check_arity, not_a_function},
+    },
+    object::{
+        array::Array,
+        builtin::{Builtin, Runtime},
+        closure::Closure,
+        iterator::{Coroutine, CoroutineState, Iter, IterState},
+        null::Null,
+        Object,
+    },
+    token::Position,
+};
+use std::{mem, rc::Rc};
+
+impl Vm {
+    /// Pops the function and arguments of a call off of the stack. If `spread` is set, the arguments
+    /// are in an array, and otherwise there are `arguments` of them.
+    pub(super) fn pop_call(&mut self, arguments: usize, spread: bool) -> (Object, Vec<Object>) {
+        let frame = self.frame();
+
+        let arguments = match spread {
+            true => match frame.pop() {
+                Object::Array(array) => array.elements,
+                _ => unreachable!("spread arguments are in an array"),
+            },
+            false => frame.stack.split_off(frame.stack.len() - arguments),
+        };
+
+        (frame.pop(), arguments)
+    }
+
+    /// Calls `callee` with the given `arguments`. A closure is called by pushing a frame for it, which
+    /// hands its value to the caller's stack once it returns. A builtin is called right away, pushing
+    /// its value. If the call is a tail call, `position` is where it was made.
+    pub(super) fn call(
+        &mut self,
+        callee: Object,
+        arguments: Vec<Object>,
+        position: Option<Position>,
+    ) -> EvalResult<()> {
+        match callee {
+            Object::Closure(closure) => self.call_closure(closure, arguments, position),
+
+            Object::Builtin(builtin) => {
+                let value = self.call_builtin(builtin, arguments)?;
+                self.frame().stack.push(value);
+                Ok(())
+            }
+
+            callee => Err(not_a_function(&callee)),
+        }
+    }
+
+    /// Calls `callee` from Rust, e.g. for a builtin, and returns its value. A closure's frame is run on
+    /// its own until it returns, like a resumed generator's.
+    pub(super) fn call_value(
+        &mut self,
+        callee: Object,
+        arguments: Vec<Object>,
+    ) -> EvalResult<Object> {
+        let Object::Closure(closure) = callee else {
+            return match callee {
+                Object::Builtin(builtin) => self.call_builtin(builtin, arguments),
+                callee => Err(not_a_function(&callee)),
+            };
+        };
+
+        let base = self.frames.len();
+
+        self.call_closure(closure, arguments, None)?;
+
+        match self.execute(base)? {
+            Exit::Return(value) => Ok(value.unwrap_or(Object::Null(Null))),
+            Exit::Yield(_) => unreachable!("only generators yield"),
+        }
+    }
+
+    /// Calls `callee` in place of the innermost frame, which is a function call whose value is the
+    /// value of this call. Only closures need a frame, so builtins are just called as usual.
+    pub(super) fn tail_call(
+        &mut self,
+        callee: Object,
+        arguments: Vec<Object>,
+        base: usize,
+    ) -> EvalResult<Option<Exit>> {
+        let Object::Closure(_) = callee else {
+            self.call(callee, arguments, None)?;
+            return Ok(None);
+        };
+
+        let frame = self.frames.pop().expect("a frame is running");
+        let position = frame.position();
+
+        if frame.kind.is_call() {
+            self.call_depth -= 1;
+        }
+
+        self.recycle(frame);
+
+        // The frame that made the tail call is gone, so errors in the call are raised in its caller,
+        // at the tail call.
+        self.call(callee, arguments, position)?;
+
+        debug_assert!(self.frames.len() > base, "tail calls are made from calls");
+
+        Ok(None)
+    }
+
+    /// Calls `closure`, like `Evaluator::call_function`. The arguments are bound to the parameters,
+    /// which are the first locals of a new frame, and the function's own code fills in the default
+    /// values of any parameters that weren't given.
+    fn call_closure(
+        &mut self,
+        closure: Closure,
+        mut arguments: Vec<Object>,
+        position: Option<Position>,
+    ) -> EvalResult<()> {
+        self.check_call_depth()?;
+
+        let Closure {
+            function,
+            captures,
+            globals,
+            file,
+        } = closure;
+
+        let parameters = &function
+            .literal
+            .as_ref()
+            .expect("closures are of function literals")
+            .parameters;
+
+        let in_call = |mut error: Error| {
+            error.trace.push(function.name().to_string());
+
+            match position {
+                Some(position) => error.at(position),
+                None => error,
+            }
+        };
+
+        check_arity(parameters, arguments.len()).map_err(in_call)?;
+
+        let rest = match parameters.last() {
+            Some(Parameter::Rest { .. }) => {
+                let rest = arguments.split_off(arguments.len().min(parameters.len() - 1));
+                self.budget.allocate_array(rest.len()).map_err(in_call)?;
+
+                Some(Object::Array(Array::new(rest)))
+            }
+            _ => None,
+        };
+
+        let (mut locals, stack) = self.spare.pop().unwrap_or_default();
+
+        locals.extend(arguments.into_iter().map(Local::Value));
+        locals.resize(function.locals, Local::Unbound);
+
+        if let Some(rest) = rest {
+            locals[parameters.len() - 1] = Local::Value(rest);
+        }
+
+        let frame = Frame {
+            stack,
+            call_position: position,
+            ..Frame::new(
+                Rc::clone(&function),
+                globals,
+                captures,
+                locals,
+                file,
+                FrameKind::Function,
+            )
+        };
+
+        self.call_depth += 1;
+        self.frames.push(frame);
+
+        Ok(())
+    }
+
+    /// Calls `builtin` with the given `arguments`, like `Evaluator::call_builtin`.
+    fn call_builtin(&mut self, builtin: Builtin, arguments: Vec<Object>) -> EvalResult<Object> {
+        (builtin.function)(self, arguments).map_err(|mut error| {
+            error.trace.push(builtin.name.to_string());
+            error
+        })
+    }
+
+    /// Turns the innermost frame, a call to a generator function whose arguments have just been bound,
+    /// into a generator, which is handed to the caller. The frame is put aside until the generator is
+    /// resumed.
+    pub(super) fn generate(&mut self, base: usize) -> Option<Exit> {
+        let mut frame = self.frames.pop().expect("a frame is running");
+        self.call_depth -= 1;
+
+        frame.kind = FrameKind::Generator;
+        frame.call_position = None;
+
+        let generator = Object::Iterator(Iter::new(IterState::Coroutine(Coroutine {
+            function: Rc::clone(&frame.function),
+            state: CoroutineState::Suspended(Box::new(frame)),
+            returned: None,
+        })));
+
+        if self.frames.len() <= base {
+            return Some(Exit::Return(Some(generator)));
+        }
+
+        self.frame().stack.push(generator);
+
+        None
+    }
+
+    /// Takes the next value from `iterator`, or returns `None` if it is done, like
+    /// `Evaluator::next_value`.
+    pub(super) fn next_value(&mut self, iterator: &Iter) -> EvalResult<Option<Object>> {
+        self.interrupt.check()?;
+        self.step()?;
+
+        if let Some(value) = iterator.state.borrow_mut().next_known() {
+            return Ok(value);
+        }
+
+        self.resume(iterator)
+    }
+
+    /// Resumes the generator that `iterator` is iterating over, running its frame up to the next
+    /// `yield`, and returning the yielded value, like `Evaluator::resume_generator`. Once the frame is
+    /// left, by reaching its end, a `return` or an error, the generator is done, and keeps the value
+    /// that it returned.
+    fn resume(&mut self, iterator: &Iter) -> EvalResult<Option<Object>> {
+        let frame = {
+            let mut state = iterator.state.borrow_mut();
+
+            let IterState::Coroutine(coroutine) = &mut *state else {
+                unreachable!("only generators are resumed");
+            };
+
+            if let CoroutineState::Running = coroutine.state {
+                return Err(Error::new(
+                    ErrorKind::Type,
+                    format!("generator {} is already running", coroutine.function.name()),
+                ));
+            }
+
+            self.check_call_depth()?;
+
+            match mem::replace(&mut coroutine.state, CoroutineState::Running) {
+                CoroutineState::Suspended(frame) => frame,
+                _ => {
+                    coroutine.state = CoroutineState::Done;
+                    return Ok(None);
+                }
+            }
+        };
+
+        // The generator's frame is run on its own, so that running it stops at a `yield`, with the
+        // frame still there to be put aside.
+        let base = self.frames.len();
+
+        self.call_depth += 1;
+        self.frames.push(*frame);
+
+        let result = self.execute(base);
+
+        let state = match result {
+            Ok(Exit::Yield(_)) => {
+                self.call_depth -= 1;
+
+                let frame = self.frames.pop().expect("the generator yielded");
+                CoroutineState::Suspended(Box::new(frame))
+            }
+            _ => CoroutineState::Done,
+        };
+
+        if let IterState::Coroutine(coroutine) = &mut *iterator.state.borrow_mut() {
+            coroutine.state = state;
+
+            if let Ok(Exit::Return(returned)) = &result {
+                coroutine.returned = returned.clone();
+            }
+        }
+
+        match result? {
+            Exit::Yield(value) => Ok(Some(value)),
+            Exit::Return(_) => Ok(None),
+        }
+    }
+}
+
+impl Runtime for Vm {
+    fn next_value(&mut self, iterator: &Iter) -> EvalResult<Option<Object>> {
+        Vm::next_value(self, iterator)
+    }
+
+    fn allocate_array(&mut self, length: usize) -> EvalResult<()> {
+        self.budget.allocate_array(length)
+    }
+
+    fn allocate_hash(&mut self, length: usize) -> EvalResult<()> {
+        self.budget.allocate_hash(length)
+    }
+
+    fn call(&mut self, function: Object, arguments: Vec<Object>) -> EvalResult<Object> {
+        self.call_value(function, arguments)
+    }
+}
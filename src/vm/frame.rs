@@ -0,0 +1,182 @@
+use super::locals::{Cell, Local};
+use crate::{
+    compiler::code::CompiledFunction,
+    evaluator::errors::Error,
+    gc::{Trace, Tracer},
+    object::{environment::Environment, Object},
+    token::Position,
+};
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+/// The call frame of a program, function call or generator that the `Vm` is running. Each frame has
+/// its own value stack, so that a generator's frame can be put aside at a `yield` with everything that
+/// it is in the middle of, and picked up again later.
+pub struct Frame {
+    /// The function that is being run.
+    pub(crate) function: Rc<CompiledFunction>,
+
+    /// The index of the next instruction to run.
+    pub(crate) ip: usize,
+
+    /// The global environment that the code's globals are looked up in.
+    pub(crate) globals: Rc<RefCell<Environment>>,
+
+    /// The variables that the closure being run captured, or none for a program.
+    pub(crate) captures: Rc<[Cell]>,
+
+    /// The slots of the variables in the function's scopes (see `CompiledFunction::locals`).
+    pub(crate) locals: Vec<Local>,
+
+    /// The path of the file that the code being run is from, if it is from one.
+    pub(crate) file: Option<Rc<Path>>,
+
+    /// The values that the instructions work on.
+    pub(crate) stack: Vec<Object>,
+
+    /// The `try`s that the code is currently inside of, from the outermost to the innermost.
+    pub(crate) handlers: Vec<Handler>,
+
+    /// How the `try`s whose `finally` blocks are currently running were left, from the outermost to the
+    /// innermost.
+    pub(crate) completions: Vec<Completion>,
+
+    /// Where the tail call that made this frame was, if it was made by one. As the frame that made the
+    /// tail call is gone, errors that leave this frame happened there.
+    pub(crate) call_position: Option<Position>,
+
+    /// What kind of code is being run.
+    pub(crate) kind: FrameKind,
+}
+
+impl Frame {
+    /// Creates a new frame that runs `function` from the start, with the given `globals`, `captures`
+    /// and `locals`, for code from `file`.
+    pub(crate) fn new(
+        function: Rc<CompiledFunction>,
+        globals: Rc<RefCell<Environment>>,
+        captures: Rc<[Cell]>,
+        locals: Vec<Local>,
+        file: Option<Rc<Path>>,
+        kind: FrameKind,
+    ) -> Self {
+        Self {
+            locals,
+            function,
+            ip: 0,
+            globals,
+            captures,
+            file,
+            stack: Vec::new(),
+            handlers: Vec::new(),
+            completions: Vec::new(),
+            call_position: None,
+            kind,
+        }
+    }
+
+    /// Pops the top value off of the stack.
+    pub(crate) fn pop(&mut self) -> Object {
+        self.stack.pop().expect("the stack has a value")
+    }
+
+    /// Returns the top value of the stack.
+    pub(crate) fn top(&self) -> &Object {
+        self.stack.last().expect("the stack has a value")
+    }
+
+    /// Returns the position of the instruction that was run last, which is where any error that
+    /// happens in this frame happened.
+    pub(crate) fn position(&self) -> Option<Position> {
+        self.ip
+            .checked_sub(1)
+            .and_then(|ip| self.function.positions[ip])
+    }
+}
+
+// We can't derive this, as the locals usually contain the function itself.
+impl std::fmt::Debug for Frame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Frame")
+            .field("function", &self.function.name())
+            .field("ip", &self.ip)
+            .field("kind", &self.kind)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What kind of code a `Frame` is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    /// A program or module.
+    Program,
+
+    /// A function call.
+    Function,
+
+    /// A generator that has been resumed.
+    Generator,
+}
+
+impl FrameKind {
+    /// Returns whether the frame counts towards the call depth, and shows up in stack traces.
+    pub(crate) fn is_call(self) -> bool {
+        self != FrameKind::Program
+    }
+}
+
+/// A `try` that the code of a `Frame` is inside of.
+#[derive(Debug, Clone)]
+pub(crate) struct Handler {
+    /// Where errors are caught, or `None` if they aren't (anymore).
+    pub(crate) catch: Option<usize>,
+
+    /// Where the `finally` block starts, if there is one.
+    pub(crate) finally: Option<usize>,
+
+    /// How many values were on the stack when the `try` was entered.
+    pub(crate) stack_len: usize,
+
+    /// How many completions were pending when the `try` was entered.
+    pub(crate) completions_len: usize,
+}
+
+/// How a `try` was left, which is carried on with once its `finally` block is done.
+#[derive(Debug)]
+pub(crate) enum Completion {
+    /// The `try` (or its `catch` block) was left normally.
+    Normal,
+
+    /// An error wasn't caught.
+    Error(Error),
+
+    /// A `return` was made with the value.
+    Return(Object),
+}
+
+impl Trace for Frame {
+    fn trace(&self, tracer: &mut Tracer) {
+        tracer.node(&self.globals);
+
+        tracer.shared(&self.captures, |captures, tracer| {
+            for cell in captures {
+                tracer.node(cell);
+            }
+        });
+
+        for local in &self.locals {
+            local.trace(tracer);
+        }
+
+        for value in &self.stack {
+            value.trace(tracer);
+        }
+
+        for completion in &self.completions {
+            match completion {
+                Completion::Normal => {}
+                Completion::Error(error) => error.trace(tracer),
+                Completion::Return(value) => value.trace(tracer),
+            }
+        }
+    }
+}
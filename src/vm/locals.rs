@@ -0,0 +1,98 @@
+use crate::{
+    gc::{self, Node, Trace, Tracer},
+    object::Object,
+};
+use std::{any::Any, cell::RefCell, mem, rc::Rc};
+
+/// A variable that closures have captured. It is shared by the closures and the frame that it is a
+/// local of, so that they all see it being bound, which is why it is on the heap.
+pub type Cell = Rc<RefCell<Option<Object>>>;
+
+/// A local of a frame, which is the slot of a variable in one of the scopes of the code that the frame
+/// is running (see `compiler::compile`).
+///
+/// Locals are kept in the frame, by value, until a closure captures them. Only then are they moved
+/// into a `Cell` on the heap, so that calls and loop iterations that don't create closures don't
+/// allocate anything for their variables.
+#[derive(Clone, Default)]
+pub enum Local {
+    /// A variable that hasn't been bound yet (or captured).
+    #[default]
+    Unbound,
+
+    /// A variable that only the frame uses, which has been bound.
+    Value(Object),
+
+    /// A variable that closures have captured.
+    Captured(Cell),
+}
+
+impl Local {
+    /// Returns the value of the variable, or `None` if it hasn't been bound yet.
+    pub(crate) fn get(&self) -> Option<Object> {
+        match self {
+            Local::Unbound => None,
+            Local::Value(value) => Some(value.clone()),
+            Local::Captured(cell) => cell.borrow().clone(),
+        }
+    }
+
+    /// Binds the variable to `value`.
+    pub(crate) fn set(&mut self, value: Object) {
+        match self {
+            Local::Captured(cell) => *cell.borrow_mut() = Some(value),
+            local => *local = Local::Value(value),
+        }
+    }
+
+    /// Returns whether the variable has been bound.
+    pub(crate) fn is_bound(&self) -> bool {
+        match self {
+            Local::Unbound => false,
+            Local::Value(_) => true,
+            Local::Captured(cell) => cell.borrow().is_some(),
+        }
+    }
+
+    /// Captures the variable, moving it into a `Cell` if it isn't in one yet.
+    pub(crate) fn capture(&mut self) -> Cell {
+        if let Local::Captured(cell) = self {
+            return Rc::clone(cell);
+        }
+
+        let value = match mem::take(self) {
+            Local::Value(value) => Some(value),
+            _ => None,
+        };
+
+        let cell = gc::alloc(value);
+        *self = Local::Captured(Rc::clone(&cell));
+        cell
+    }
+}
+
+impl Trace for Local {
+    fn trace(&self, tracer: &mut Tracer) {
+        match self {
+            Local::Unbound => {}
+            Local::Value(value) => value.trace(tracer),
+            Local::Captured(cell) => tracer.node(cell),
+        }
+    }
+}
+
+impl Trace for Option<Object> {
+    fn trace(&self, tracer: &mut Tracer) {
+        if let Some(value) = self {
+            value.trace(tracer);
+        }
+    }
+}
+
+impl Node for Option<Object> {
+    // A captured variable usually holds a closure that captured it, so taking its value breaks the
+    // cycle.
+    fn clear(&mut self) -> Box<dyn Any> {
+        Box::new(self.take())
+    }
+}
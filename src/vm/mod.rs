@@ -0,0 +1,512 @@
+mod calls;
+pub mod frame;
+pub mod locals;
+mod modules;
+mod ops;
+pub mod tests;
+
+use self::{
+    frame::{Completion, Frame, FrameKind},
+    locals::Local,
+};
+use crate::{
+    ast::Program,
+    compiler::{code::CompiledFunction, compile},
+    evaluator::{
+        budget::Budget,
+        builtins::get_builtin,
+        errors::{Error, ErrorKind},
+        interrupt::InterruptHandle,
+        modules::{normalize, FileLoader, Loader},
+        prelude::{PRELUDE, PRELUDE_FILE},
+        EvalResult, Evaluator, DEFAULT_MAX_CALL_DEPTH, DEFAULT_MAX_DEPTH,
+    },
+    gc,
+    lexer::Lexer,
+    object::{environment::Environment, error::ErrorValue, module::Module, null::Null, Object},
+    optimizer::{optimize_at, OptLevel},
+    parser::{Limits, Parser},
+    resolver::resolve,
+    symbol::Symbol,
+};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+/// The most left frames whose locals and value stacks are kept for the next calls.
+const MAX_SPARE_FRAMES: usize = 64;
+
+/// The stack-based virtual machine for the Monkey programming language, which runs the bytecode that
+/// the `compiler` compiles programs into. It gives the same results and errors as the `Evaluator`,
+/// but instead of recursing on the Rust stack for every expression, it keeps a stack of call frames
+/// of its own, so only generators and imported modules are run recursively.
+///
+/// Like the `Evaluator`, it keeps its global environment between calls to `eval`, so that bindings
+/// persist (which is what the REPL needs).
+pub struct Vm {
+    /// The call frames that are currently running, from the outermost to the innermost.
+    frames: Vec<Frame>,
+
+    /// The locals and value stacks of frames that have been left, emptied out, to be used by the next
+    /// calls, so that most calls don't have to allocate them.
+    spare: Vec<(Vec<Local>, Vec<Object>)>,
+
+    /// The global environment that programs are run in.
+    globals: Rc<RefCell<Environment>>,
+
+    /// The environment that the prelude was loaded into, if it was. Imported modules are run in an
+    /// environment enclosed by this one, so that they can use the prelude too.
+    prelude: Option<Rc<RefCell<Environment>>>,
+
+    /// The evaluator that macros are defined in and expanded with, as they are run on the AST before
+    /// the program is compiled.
+    expander: Evaluator,
+
+    /// The loader that the source code of imported modules is loaded with.
+    loader: Rc<dyn Loader>,
+
+    /// The modules that have already been imported, by their path, so that each module is only run
+    /// once.
+    modules: HashMap<PathBuf, Module>,
+
+    /// The paths of the modules that are currently being loaded, from the outermost import to the
+    /// innermost. This is used to detect import cycles.
+    loading: Vec<PathBuf>,
+
+    /// The path of the file that is currently being run, if there is one.
+    file: Option<Rc<Path>>,
+
+    /// How many function calls (and resumed generators) are currently running.
+    call_depth: usize,
+
+    /// The most function calls that are allowed to run at once.
+    max_call_depth: usize,
+
+    /// The deepest that expressions are allowed to be nested while macros are expanded.
+    max_depth: usize,
+
+    /// What is left of the resources that running is allowed to use.
+    budget: Budget,
+
+    /// How many steps have been taken since the budget was set.
+    steps: u64,
+
+    /// The flag that is set to interrupt running. This is shared with the `expander`.
+    interrupt: InterruptHandle,
+
+    /// The names that the last program that was run uses before they are defined, or without defining
+    /// them at all, as found by the resolver.
+    warnings: Vec<Error>,
+
+    /// How much programs are optimized before they are compiled.
+    opt_level: OptLevel,
+
+    /// The limits that imported modules are parsed with.
+    parse_limits: Limits,
+}
+
+/// How the frame that `Vm::execute` was running code in was left.
+#[derive(Debug)]
+enum Exit {
+    /// The frame returned, with the value if it has one.
+    Return(Option<Object>),
+
+    /// The frame, which is a generator, yielded the value, and is still on the stack of frames.
+    Yield(Object),
+}
+
+/// Public API for the `Vm` struct.
+impl Vm {
+    /// Creates a new `Vm` with the prelude (see `evaluator::prelude::PRELUDE`) loaded, like
+    /// `Evaluator::new`.
+    pub fn new() -> Self {
+        let program = Parser::new(Lexer::new(PRELUDE))
+            .parse_program()
+            .expect("the prelude should parse");
+
+        let mut loader = Self::without_prelude();
+        loader.file = Some(Rc::from(Path::new(PRELUDE_FILE)));
+
+        loader.run(program).expect("the prelude should run");
+
+        let prelude = loader.globals;
+
+        let mut vm =
+            Self::with_environment(gc::alloc(Environment::new_enclosed(Rc::clone(&prelude))));
+
+        vm.prelude = Some(prelude);
+        vm
+    }
+
+    /// Creates a new `Vm` with an empty global environment, without the prelude.
+    pub fn without_prelude() -> Self {
+        Self::with_environment(gc::alloc(Environment::new()))
+    }
+
+    /// Creates a new `Vm` that runs programs in the given global environment.
+    pub fn with_environment(globals: Rc<RefCell<Environment>>) -> Self {
+        let interrupt = InterruptHandle::new();
+
+        let mut expander = Evaluator::without_prelude();
+        expander.set_interrupt_handle(interrupt.clone());
+
+        Self {
+            frames: Vec::new(),
+            spare: Vec::new(),
+            globals,
+            prelude: None,
+            expander,
+            loader: Rc::new(FileLoader),
+            modules: HashMap::new(),
+            loading: Vec::new(),
+            file: None,
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            max_depth: DEFAULT_MAX_DEPTH,
+            budget: Budget::unlimited(),
+            steps: 0,
+            interrupt,
+            warnings: Vec::new(),
+            opt_level: OptLevel::None,
+            parse_limits: Limits::default(),
+        }
+    }
+
+    /// Sets the most function calls that are allowed to run at once, like
+    /// `Evaluator::set_max_call_depth`. This also limits the calls that macros make while they are
+    /// expanded.
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+        self.expander.set_max_call_depth(max_call_depth);
+    }
+
+    /// Sets the deepest that expressions are allowed to be nested while macros are expanded, like
+    /// `Evaluator::set_max_depth`. The `Vm` doesn't recurse to run expressions, so this is the only
+    /// place that it applies.
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+        self.expander.set_max_depth(max_depth);
+    }
+
+    /// Sets the budget that running is allowed to use from now on, like `Evaluator::set_budget`. A
+    /// step is a single instruction, so the same amount of fuel goes further than it does with the
+    /// `Evaluator`.
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+        self.steps = 0;
+    }
+
+    /// Returns what is left of the budget, like `Evaluator::remaining_budget`.
+    pub fn remaining_budget(&self) -> Budget {
+        self.budget
+    }
+
+    /// Returns a handle that can interrupt this `Vm` from another thread.
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.interrupt.clone()
+    }
+
+    /// Replaces the loader that is used to load the source code of imported modules.
+    pub fn set_loader<L: Loader + 'static>(&mut self, loader: L) {
+        self.loader = Rc::new(loader);
+    }
+
+    /// Sets the path of the file that is being run, which relative imports are resolved against. Like
+    /// `Evaluator::set_file`, the file counts as being loaded, so a module importing it is a cycle.
+    pub fn set_file<P: AsRef<Path>>(&mut self, path: P) {
+        let path = normalize(path.as_ref());
+
+        self.file = Some(Rc::from(path.as_path()));
+        self.loading = vec![path];
+    }
+
+    /// Sets how much programs are optimized before they are compiled, like `Evaluator::set_opt_level`.
+    pub fn set_opt_level(&mut self, opt_level: OptLevel) {
+        self.opt_level = opt_level;
+    }
+
+    /// Sets the limits that imported modules are parsed with, like `Evaluator::set_parse_limits`.
+    pub fn set_parse_limits(&mut self, limits: Limits) {
+        self.parse_limits = limits;
+    }
+
+    /// Returns the limits that imported modules are parsed with.
+    pub fn parse_limits(&self) -> Limits {
+        self.parse_limits
+    }
+
+    /// Binds `name` to `value` in the global environment, like `Evaluator::define`.
+    pub fn define(&mut self, name: Symbol, value: Object) {
+        self.globals.borrow_mut().set(name, value);
+    }
+
+    /// Defines the macros in `program`, expands the calls to them, and then compiles and runs the
+    /// expanded program, like `Evaluator::run`.
+    pub fn run(&mut self, program: Program) -> EvalResult<Option<Object>> {
+        let program = self.expand(program)?;
+
+        self.eval(&program)
+    }
+
+    /// Compiles and runs a `Program`, returning the value of the last statement (if it has one).
+    pub fn eval(&mut self, program: &Program) -> EvalResult<Option<Object>> {
+        let mut program = optimize_at(program.clone(), self.opt_level);
+
+        // The compiler finds the slots of variables itself, so the resolver is only run for its
+        // warnings.
+        let globals = Rc::clone(&self.globals);
+
+        self.warnings = resolve(&mut program, |name| {
+            globals.borrow().get(name).is_some() || get_builtin(name).is_some()
+        });
+
+        let function = Rc::new(compile(&program));
+
+        self.run_program(function, Rc::clone(&self.globals))
+    }
+
+    /// Returns the names that the last program that was run uses before they are defined, or without
+    /// defining them at all, like `Evaluator::warnings`.
+    pub fn warnings(&self) -> &[Error] {
+        &self.warnings
+    }
+}
+
+impl Vm {
+    /// Creates a new evaluator to define and expand macros with, which has the same limits as the `Vm`,
+    /// and is interrupted along with it.
+    fn new_expander(&self) -> Evaluator {
+        let mut expander = Evaluator::without_prelude();
+        expander.set_max_call_depth(self.max_call_depth);
+        expander.set_max_depth(self.max_depth);
+        expander.set_interrupt_handle(self.interrupt.clone());
+
+        expander
+    }
+
+    /// Defines the macros in `program` with the `expander`, and expands the calls to them. The macros
+    /// are run with what is left of the budget, and whatever they use up of it is gone.
+    fn expand(&mut self, mut program: Program) -> EvalResult<Program> {
+        self.expander.set_budget(self.budget);
+        self.expander.define_macros(&mut program);
+
+        let program = self.expander.expand_macros(program);
+        self.budget = self.expander.remaining_budget();
+
+        program
+    }
+
+    /// Runs a compiled program in the global environment `globals`.
+    fn run_program(
+        &mut self,
+        function: Rc<CompiledFunction>,
+        globals: Rc<RefCell<Environment>>,
+    ) -> EvalResult<Option<Object>> {
+        let base = self.frames.len();
+
+        let locals = vec![Local::Unbound; function.locals];
+
+        self.frames.push(Frame::new(
+            function,
+            globals,
+            Rc::from([]),
+            locals,
+            self.file.clone(),
+            FrameKind::Program,
+        ));
+
+        match self.execute(base)? {
+            Exit::Return(value) => Ok(value),
+            Exit::Yield(_) => unreachable!("programs don't yield"),
+        }
+    }
+
+    /// Runs the instructions of the innermost frame, and of the frames that it calls, until the frame
+    /// that is left when there are only `base` frames is left. Errors are unwound through the frames
+    /// (see `unwind`), and returned once they leave that frame.
+    fn execute(&mut self, base: usize) -> EvalResult<Exit> {
+        loop {
+            match self.execute_next(base) {
+                Ok(None) => {}
+                Ok(Some(exit)) => return Ok(exit),
+                Err(error) => self.unwind(error, base)?,
+            }
+        }
+    }
+
+    /// Runs the next instruction of the innermost frame, as a step of running. Returns how the frame
+    /// that was being run was left, if it was left, and there are only `base` frames left.
+    fn execute_next(&mut self, base: usize) -> EvalResult<Option<Exit>> {
+        let frame = self.frame();
+        let op = frame.function.instructions[frame.ip];
+        frame.ip += 1;
+
+        self.interrupt.check()?;
+        self.step()?;
+
+        self.execute_op(op, base)
+    }
+
+    /// Returns the innermost frame.
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("a frame is running")
+    }
+
+    /// Takes a step of running, erroring instead if the budget has no fuel or time left.
+    fn step(&mut self) -> EvalResult<()> {
+        self.steps += 1;
+        self.budget.step(self.steps)
+    }
+
+    /// Unwinds `error` out of the frames, until it is caught by a `try` (which carries on running), or
+    /// it leaves the frame that is left when there are only `base` frames (which returns it).
+    ///
+    /// The error happened at the instruction that each frame was running, unless it already knows where
+    /// it happened, and the name of each function that it leaves is added to its stack trace.
+    fn unwind(&mut self, mut error: Error, base: usize) -> EvalResult<()> {
+        loop {
+            let frame = self.frame();
+
+            if let Some(position) = frame.position() {
+                error = error.at(position);
+            }
+
+            if error.kind.is_catchable() {
+                if let Some(handler) = frame.handlers.pop() {
+                    frame.stack.truncate(handler.stack_len);
+                    frame.completions.truncate(handler.completions_len);
+
+                    match handler.catch {
+                        Some(catch) => {
+                            // The `finally` block still has to run after the `catch` block, however
+                            // that is left.
+                            if handler.finally.is_some() {
+                                frame.handlers.push(frame::Handler {
+                                    catch: None,
+                                    ..handler
+                                });
+                            }
+
+                            frame.stack.push(Object::Error(ErrorValue::new(error)));
+                            frame.ip = catch;
+                        }
+
+                        None => {
+                            let finally = handler.finally.expect("a try has a catch or a finally");
+
+                            frame.stack.push(Object::Null(Null));
+                            frame.completions.push(Completion::Error(error));
+                            frame.ip = finally;
+                        }
+                    }
+
+                    return Ok(());
+                }
+            }
+
+            let frame = self.frames.pop().expect("a frame is running");
+
+            error.locate(frame.file.as_ref());
+
+            if frame.kind.is_call() {
+                self.call_depth -= 1;
+                error.trace.push(frame.function.name().to_string());
+            }
+
+            if let Some(position) = frame.call_position {
+                error = error.at(position);
+            }
+
+            if self.frames.len() <= base {
+                return Err(error);
+            }
+        }
+    }
+
+    /// Returns `value` from the innermost frame. If the `return` is inside of any `try`s with `finally`
+    /// blocks, the innermost of those is run first, and the `return` carries on once it is done.
+    fn return_value(&mut self, value: Object, base: usize) -> Option<Exit> {
+        let frame = self.frame();
+
+        let Some(index) = frame
+            .handlers
+            .iter()
+            .rposition(|handler| handler.finally.is_some())
+        else {
+            return self.leave(Some(value), base);
+        };
+
+        let handler = frame.handlers[index].clone();
+        frame.handlers.truncate(index);
+        frame.stack.truncate(handler.stack_len);
+        frame.completions.truncate(handler.completions_len);
+
+        frame.stack.push(Object::Null(Null));
+        frame.completions.push(Completion::Return(value));
+        frame.ip = handler.finally.expect("the handler has a finally");
+
+        None
+    }
+
+    /// Leaves the innermost frame, handing `value` to the frame that called it. If that leaves only
+    /// `base` frames, the value is returned instead. That is always the case for a generator, as it is
+    /// run on its own by `resume`, which keeps the value for `next`.
+    fn leave(&mut self, value: Option<Object>, base: usize) -> Option<Exit> {
+        let frame = self.frames.pop().expect("a frame is running");
+
+        if frame.kind.is_call() {
+            self.call_depth -= 1;
+        }
+
+        self.recycle(frame);
+
+        if self.frames.len() <= base {
+            return Some(Exit::Return(value));
+        }
+
+        self.frame().stack.push(value.unwrap_or(Object::Null(Null)));
+
+        None
+    }
+
+    /// Keeps the locals and value stack of `frame`, which has been left, for the next call to use.
+    fn recycle(&mut self, frame: Frame) {
+        let Frame {
+            mut locals,
+            mut stack,
+            ..
+        } = frame;
+
+        if self.spare.len() < MAX_SPARE_FRAMES {
+            locals.clear();
+            stack.clear();
+            self.spare.push((locals, stack));
+        }
+    }
+
+    /// Errors if another call would go deeper than the maximum call depth.
+    fn check_call_depth(&self) -> EvalResult<()> {
+        if self.call_depth >= self.max_call_depth {
+            return Err(Error::new(
+                ErrorKind::Limit,
+                format!("maximum call depth of {} exceeded", self.max_call_depth),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compiles and runs a `Program` on a new `Vm`, like `evaluator::eval`.
+pub fn eval(program: &Program) -> EvalResult<Option<Object>> {
+    Vm::new().eval(program)
+}
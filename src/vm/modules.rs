@@ -0,0 +1,75 @@
+use super::{EvalResult, Vm};
+use crate::{
+    ast::Program,
+    compiler::compile,
+    evaluator::modules::{check_cycle, export_names, parse_module, resolve_import},
+    gc,
+    object::{environment::Environment, module::Module},
+    optimizer::optimize_at,
+};
+use std::{cell::RefCell, collections::BTreeMap, mem, rc::Rc};
+
+impl Vm {
+    /// Returns the module at `path` (as written in the `import`), loading and running it first if it
+    /// hasn't been yet, like `Evaluator::load_module`.
+    pub(super) fn import(&mut self, path: &str) -> EvalResult<Module> {
+        let path = resolve_import(self.file.as_deref(), path);
+
+        if let Some(module) = self.modules.get(&path) {
+            return Ok(module.clone());
+        }
+
+        check_cycle(&self.loading, &path)?;
+
+        let program = parse_module(self.loader.as_ref(), &path, self.parse_limits)?;
+        let names = export_names(&program);
+
+        let env = gc::alloc(match &self.prelude {
+            Some(prelude) => Environment::new_enclosed(Rc::clone(prelude)),
+            None => Environment::new(),
+        });
+
+        // Swap in a new expander (with its own macros) and file for the module, and make sure to swap the
+        // importer's back in afterwards, whether or not running the module succeeded.
+        let expander = self.new_expander();
+        let previous_expander = mem::replace(&mut self.expander, expander);
+        let file = Rc::from(path.as_path());
+        let previous_file = self.file.replace(Rc::clone(&file));
+        self.loading.push(path.clone());
+
+        let result = self.run_module(program, Rc::clone(&env));
+
+        self.loading.pop();
+        self.file = previous_file;
+        self.expander = previous_expander;
+
+        if let Err(mut error) = result {
+            error.locate(Some(&file));
+            error.trace.push(format!("module {}", path.display()));
+            return Err(error);
+        }
+
+        let exports = names
+            .into_iter()
+            .filter_map(|name| {
+                let value = env.borrow().get(&name)?;
+                Some((name, value))
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let module = Module::new(path.display(), exports);
+
+        self.modules.insert(path, module.clone());
+
+        Ok(module)
+    }
+
+    /// Expands the macros in a module's `program`, optimizes it, and runs it in `env`.
+    fn run_module(&mut self, program: Program, env: Rc<RefCell<Environment>>) -> EvalResult<()> {
+        let program = optimize_at(self.expand(program)?, self.opt_level);
+
+        self.run_program(Rc::new(compile(&program)), env)?;
+
+        Ok(())
+    }
+}
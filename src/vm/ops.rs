@@ -0,0 +1,616 @@
+use super::{
+    frame::{Completion, Frame, Handler},
+    locals::Local,
+    EvalResult, Exit, Vm,
+};
+use crate::{
+    ast::{expressions::Expression, modify::modify_expression, operators::InfixOperatorType},
+    compiler::code::{Fallback, Op, Variable},
+    evaluator::{
+        builtins::get_builtin,
+        errors::{Error, ErrorKind},
+        exceptions::thrown,
+        expressions::{eval_index, eval_infix, eval_prefix, is_truthy},
+        iterators::iterate,
+        macros::{is_unquote, object_to_expression},
+        modules::get_member,
+        records::{build_record, not_a_struct, unknown_field},
+    },
+    object::{
+        array::Array,
+        boolean::Boolean,
+        closure::Closure,
+        hash::{Hash, HashKey, HashPair},
+        null::Null,
+        quote::Quote,
+        Object, Objective,
+    },
+    symbol::Symbol,
+};
+use std::{collections::BTreeMap, rc::Rc};
+
+impl Vm {
+    /// Runs a single instruction in the innermost frame (see `Op` for what each of them does).
+    pub(super) fn execute_op(&mut self, op: Op, base: usize) -> EvalResult<Option<Exit>> {
+        let function = Rc::clone(&self.frame().function);
+
+        match op {
+            Op::Constant(index) => {
+                let object = function.object(index).clone();
+
+                if let Object::String(string) = &object {
+                    self.budget.allocate_string(string.value.len())?;
+                }
+
+                self.frame().stack.push(object);
+            }
+
+            Op::Null => self.frame().stack.push(Object::Null(Null)),
+
+            Op::True => self.frame().stack.push(Object::Boolean(Boolean::new(true))),
+
+            Op::False => self
+                .frame()
+                .stack
+                .push(Object::Boolean(Boolean::new(false))),
+
+            Op::Pop => {
+                self.frame().pop();
+            }
+
+            Op::Dup => {
+                let frame = self.frame();
+                let top = frame.top().clone();
+                frame.stack.push(top);
+            }
+
+            Op::Nip => {
+                let frame = self.frame();
+                let top = frame.pop();
+                frame.pop();
+                frame.stack.push(top);
+            }
+
+            Op::GetLocal { index, fallback } => {
+                let frame = self.frame();
+
+                let object = match frame.locals[index].get() {
+                    Some(object) => object,
+                    None => fall_back(frame, function.fallback(fallback))?,
+                };
+
+                frame.stack.push(object);
+            }
+
+            Op::GetFree { index, fallback } => {
+                let frame = self.frame();
+
+                let object = match frame.captures[index].borrow().clone() {
+                    Some(object) => object,
+                    None => fall_back(frame, function.fallback(fallback))?,
+                };
+
+                frame.stack.push(object);
+            }
+
+            Op::SetLocal(index) => {
+                let frame = self.frame();
+                let value = frame.pop();
+                frame.locals[index].set(value);
+            }
+
+            Op::JumpIfBound { index, target } => {
+                let frame = self.frame();
+
+                if frame.locals[index].is_bound() {
+                    frame.ip = target;
+                }
+            }
+
+            Op::GetGlobal(index) => {
+                let frame = self.frame();
+                let object = get_global(frame, function.symbol(index))?;
+                frame.stack.push(object);
+            }
+
+            Op::SetGlobal(index) => {
+                let frame = self.frame();
+                let value = frame.pop();
+
+                frame
+                    .globals
+                    .borrow_mut()
+                    .set(function.symbol(index).clone(), value);
+            }
+
+            Op::Prefix(operator) => {
+                let frame = self.frame();
+                let right = frame.pop();
+                frame.stack.push(eval_prefix(operator, right)?);
+            }
+
+            Op::Infix(operator) => {
+                let frame = self.frame();
+                let right = frame.pop();
+                let left = frame.pop();
+
+                // Concatenating two strings allocates a new one.
+                if let (Object::String(left), Object::String(right)) = (&left, &right) {
+                    if operator == InfixOperatorType::Add {
+                        self.budget
+                            .allocate_string(left.value.len() + right.value.len())?;
+                    }
+                }
+
+                self.frame().stack.push(eval_infix(operator, left, right)?);
+            }
+
+            Op::Jump(target) => self.frame().ip = target,
+
+            Op::JumpIfFalse(target) => {
+                let frame = self.frame();
+
+                if !is_truthy(&frame.pop()) {
+                    frame.ip = target;
+                }
+            }
+
+            Op::List(length) => {
+                let frame = self.frame();
+                let elements = frame.stack.split_off(frame.stack.len() - length);
+                frame.stack.push(Object::Array(Array::new(elements)));
+            }
+
+            Op::Append => {
+                let frame = self.frame();
+                let value = frame.pop();
+
+                let Some(Object::Array(array)) = frame.stack.last_mut() else {
+                    unreachable!("values are appended to arrays");
+                };
+
+                array.elements.push(value);
+            }
+
+            Op::Extend => {
+                let frame = self.frame();
+
+                let spread = match frame.pop() {
+                    Object::Array(spread) => spread,
+                    object => {
+                        return Err(Error::new(
+                            ErrorKind::Type,
+                            format!(
+                                "cannot spread {}, only arrays can be spread",
+                                object.object_type()
+                            ),
+                        ))
+                    }
+                };
+
+                let Some(Object::Array(array)) = frame.stack.last_mut() else {
+                    unreachable!("arrays are spread into arrays");
+                };
+
+                array.elements.extend(spread.elements);
+            }
+
+            Op::Allocate => {
+                let Object::Array(array) = self.frame().top() else {
+                    unreachable!("only arrays are allocated");
+                };
+
+                let length = array.elements.len();
+                self.budget.allocate_array(length)?;
+            }
+
+            Op::HashKey => {
+                HashKey::try_from(self.frame().top()).map_err(unusable_as_hash_key)?;
+            }
+
+            Op::Hash(length) => {
+                let frame = self.frame();
+                let values = frame.stack.split_off(frame.stack.len() - length * 2);
+
+                let mut pairs = BTreeMap::new();
+                let mut values = values.into_iter();
+
+                while let (Some(key), Some(value)) = (values.next(), values.next()) {
+                    let hash_key = HashKey::try_from(&key).map_err(unusable_as_hash_key)?;
+                    pairs.insert(hash_key, HashPair { key, value });
+                }
+
+                self.budget.allocate_hash(pairs.len())?;
+                self.frame().stack.push(Object::Hash(Hash::new(pairs)));
+            }
+
+            Op::Index => {
+                let frame = self.frame();
+                let index = frame.pop();
+                let left = frame.pop();
+                frame.stack.push(eval_index(left, index)?);
+            }
+
+            Op::Member(index) => {
+                let frame = self.frame();
+                let object = frame.pop();
+                frame
+                    .stack
+                    .push(get_member(object, function.identifier(index))?);
+            }
+
+            Op::Closure(index) => {
+                let literal = Rc::clone(function.function(index));
+                let frame = self.frame();
+
+                let captures = literal
+                    .captures
+                    .iter()
+                    .map(|variable| match *variable {
+                        Variable::Local(index) => frame.locals[index].capture(),
+                        Variable::Free(index) => Rc::clone(&frame.captures[index]),
+                    })
+                    .collect();
+
+                let closure = Closure::new(
+                    literal,
+                    captures,
+                    Rc::clone(&frame.globals),
+                    frame.file.clone(),
+                );
+                frame.stack.push(Object::Closure(closure));
+            }
+
+            Op::Call { arguments, spread } => {
+                let (callee, arguments) = self.pop_call(arguments, spread);
+                self.call(callee, arguments, None)?;
+            }
+
+            Op::TailCall { arguments, spread } => {
+                let (callee, arguments) = self.pop_call(arguments, spread);
+                return self.tail_call(callee, arguments, base);
+            }
+
+            Op::Return => {
+                let value = self.frame().pop();
+                return Ok(self.return_value(value, base));
+            }
+
+            Op::End => return Ok(self.leave(None, base)),
+
+            Op::Generate => return Ok(self.generate(base)),
+
+            Op::Yield => {
+                let value = self.frame().pop();
+                return Ok(Some(Exit::Yield(value)));
+            }
+
+            Op::Throw => {
+                let value = self.frame().pop();
+                return Err(thrown(value));
+            }
+
+            Op::Fail(index) => return Err(function.error(index).clone()),
+
+            Op::Try { catch, finally } => {
+                let frame = self.frame();
+
+                let handler = Handler {
+                    catch,
+                    finally,
+                    stack_len: frame.stack.len(),
+                    completions_len: frame.completions.len(),
+                };
+
+                frame.handlers.push(handler);
+            }
+
+            Op::EndTry => {
+                self.frame().handlers.pop();
+            }
+
+            Op::EnterFinally => self.frame().completions.push(Completion::Normal),
+
+            Op::EndFinally => {
+                let completion = self
+                    .frame()
+                    .completions
+                    .pop()
+                    .expect("a finally block is running");
+
+                match completion {
+                    Completion::Normal => {}
+                    Completion::Error(error) => return Err(error),
+                    Completion::Return(value) => {
+                        self.frame().pop();
+                        return Ok(self.return_value(value, base));
+                    }
+                }
+            }
+
+            Op::EnterScope { start, end } => self.frame().locals[start..end].fill(Local::Unbound),
+
+            Op::Iterate => {
+                let frame = self.frame();
+                let iterable = frame.pop();
+                frame.stack.push(Object::Iterator(iterate(iterable)?));
+            }
+
+            Op::Next(target) => {
+                let Object::Iterator(iterator) = self.frame().top().clone() else {
+                    unreachable!("only iterators are iterated over");
+                };
+
+                let value = self.next_value(&iterator)?;
+                let frame = self.frame();
+
+                match value {
+                    Some(value) => frame.stack.push(value),
+                    None => {
+                        frame.pop();
+                        frame.ip = target;
+                    }
+                }
+            }
+
+            Op::DestructureArray {
+                elements,
+                rest,
+                fail,
+            } => {
+                let frame = self.frame();
+
+                let error = match frame.top() {
+                    Object::Array(array) => {
+                        let got = array.elements.len();
+
+                        if got < elements {
+                            let want = match rest {
+                                true => format!("at least {}", elements),
+                                false => elements.to_string(),
+                            };
+
+                            Some(Error::new(
+                                ErrorKind::Match,
+                                format!(
+                                    "not enough elements to destructure: want={}, got={}",
+                                    want, got
+                                ),
+                            ))
+                        } else if got > elements && !rest {
+                            Some(Error::new(
+                                ErrorKind::Match,
+                                format!(
+                                    "too many elements to destructure: want={}, got={}",
+                                    elements, got
+                                ),
+                            ))
+                        } else {
+                            None
+                        }
+                    }
+
+                    object => Some(Error::new(
+                        ErrorKind::Type,
+                        format!("cannot destructure {} as an array", object.object_type()),
+                    )),
+                };
+
+                if let Some(error) = error {
+                    fail_pattern(frame, fail, error)?;
+                }
+            }
+
+            Op::Element(index) => {
+                let frame = self.frame();
+
+                let Object::Array(array) = frame.top() else {
+                    unreachable!("elements are taken from arrays");
+                };
+
+                let element = array.elements[index].clone();
+                frame.stack.push(element);
+            }
+
+            Op::Rest(index) => {
+                let Object::Array(array) = self.frame().top() else {
+                    unreachable!("elements are taken from arrays");
+                };
+
+                let elements = array.elements[index..].to_vec();
+                self.budget.allocate_array(elements.len())?;
+                self.frame().stack.push(Object::Array(Array::new(elements)));
+            }
+
+            Op::DestructureHash { fail } => {
+                let frame = self.frame();
+
+                if !matches!(frame.top(), Object::Hash(_)) {
+                    let error = Error::new(
+                        ErrorKind::Type,
+                        format!("cannot destructure {} as a hash", frame.top().object_type()),
+                    );
+
+                    fail_pattern(frame, fail, error)?;
+                }
+            }
+
+            Op::Key { key, fail } => {
+                let frame = self.frame();
+                let hash_key = HashKey::try_from(&frame.pop()).map_err(unusable_as_hash_key)?;
+
+                let Object::Hash(hash) = frame.top() else {
+                    unreachable!("keys are looked up in hashes");
+                };
+
+                match hash.get(&hash_key).cloned() {
+                    Some(value) => frame.stack.push(value),
+                    // The key is already popped, and the hash stays where it is.
+                    None => match fail {
+                        Some(fail) => frame.ip = fail,
+                        None => {
+                            return Err(Error::new(
+                                ErrorKind::Match,
+                                format!("missing key {} in hash", function.expression(key)),
+                            ))
+                        }
+                    },
+                }
+            }
+
+            Op::Literal { literal, fail } => {
+                let frame = self.frame();
+                let expected = frame.pop();
+                let value = frame.pop();
+
+                if value != expected {
+                    let error = Error::new(
+                        ErrorKind::Match,
+                        format!(
+                            "{} does not match the pattern {}",
+                            value,
+                            function.expression(literal)
+                        ),
+                    );
+
+                    match fail {
+                        Some(fail) => frame.ip = fail,
+                        None => return Err(error),
+                    }
+                }
+            }
+
+            Op::NoMatch => {
+                let value = self.frame().pop();
+
+                return Err(Error::new(
+                    ErrorKind::Match,
+                    format!("no match arm matches {}", value),
+                ));
+            }
+
+            Op::Struct(index) => {
+                let name = function.identifier(index);
+
+                match self.frame().top() {
+                    Object::Struct(_) => {}
+                    object => return Err(not_a_struct(&name.value, object)),
+                }
+            }
+
+            Op::Field { field, offset } => {
+                let frame = self.frame();
+                let field = function.identifier(field);
+
+                let Object::Struct(declaration) = &frame.stack[frame.stack.len() - 1 - offset]
+                else {
+                    unreachable!("fields are checked against structs");
+                };
+
+                if !declaration.fields.contains(&field.value) {
+                    return Err(unknown_field(&declaration.name, field));
+                }
+            }
+
+            Op::Record(index) => {
+                let names = function.names(index);
+                let frame = self.frame();
+                let values = frame.stack.split_off(frame.stack.len() - names.len());
+
+                let Object::Struct(declaration) = frame.pop() else {
+                    unreachable!("records are built from structs");
+                };
+
+                let values = names.iter().cloned().zip(values).collect();
+                let record = build_record(&declaration, values)?;
+
+                self.frame().stack.push(record);
+            }
+
+            Op::Import(index) => {
+                let module = self.import(function.symbol(index))?;
+                self.frame().stack.push(Object::Module(module));
+            }
+
+            Op::Quote { quote, unquotes } => {
+                let frame = self.frame();
+                let mut values = frame
+                    .stack
+                    .split_off(frame.stack.len() - unquotes)
+                    .into_iter();
+
+                let node =
+                    modify_expression(function.expression(quote).clone(), &mut |expression| {
+                        let Expression::Call(call) = &expression else {
+                            return Ok(expression);
+                        };
+
+                        if !is_unquote(call) {
+                            return Ok(expression);
+                        }
+
+                        let value = values.next().expect("every unquote has a value");
+                        object_to_expression(value, call.token.position)
+                    })?;
+
+                self.frame().stack.push(Object::Quote(Quote::new(node)));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Looks up a variable whose innermost slot hasn't been bound yet, in its other slots and then as a
+/// global, as `fallback` says.
+fn fall_back(frame: &Frame, fallback: &Fallback) -> EvalResult<Object> {
+    let bound = fallback
+        .variables
+        .iter()
+        .find_map(|variable| match *variable {
+            Variable::Local(index) => frame.locals[index].get(),
+            Variable::Free(index) => frame.captures[index].borrow().clone(),
+        });
+
+    match bound {
+        Some(object) => Ok(object),
+        None => get_global(frame, &fallback.name),
+    }
+}
+
+/// Looks up the global `name` in the global environment of `frame` (and the prelude), and then in the
+/// builtins.
+fn get_global(frame: &Frame, name: &Symbol) -> EvalResult<Object> {
+    frame
+        .globals
+        .borrow()
+        .get(name)
+        .or_else(|| get_builtin(name).map(Object::Builtin))
+        .ok_or_else(|| identifier_not_found(name))
+}
+
+/// Handles a value that doesn't match a pattern, which is on top of the stack. If the pattern is in a
+/// `match` arm, the value is popped and the jump is made to `fail`, and otherwise it is an error.
+fn fail_pattern(frame: &mut Frame, fail: Option<usize>, error: Error) -> EvalResult<()> {
+    match fail {
+        Some(fail) => {
+            frame.pop();
+            frame.ip = fail;
+            Ok(())
+        }
+        None => Err(error),
+    }
+}
+
+/// Creates the error for looking up `name`, which isn't bound to anything.
+fn identifier_not_found(name: &str) -> Error {
+    Error::new(ErrorKind::Name, format!("identifier not found: {}", name))
+}
+
+/// Creates the error for using a value of the type `object_type` as a hash key.
+fn unusable_as_hash_key(object_type: crate::object::ObjectType) -> Error {
+    Error::new(
+        ErrorKind::Type,
+        format!("unusable as hash key: {}", object_type),
+    )
+}
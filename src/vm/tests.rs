@@ -0,0 +1,306 @@
+#![cfg(test)]
+
+use super::Vm;
+use crate::{
+    ast::Program,
+    evaluator::{budget::Budget, errors::ErrorKind, modules::MemoryLoader},
+    lexer::Lexer,
+    parser::Parser,
+};
+use std::{path::Path, thread, time::Duration};
+
+/// Parses the input source code
+fn parse(input: &str) -> Program {
+    let lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    parser.parse_program().unwrap()
+}
+
+/// Runs the input source code on `vm`, expecting it to succeed, and returns its value as a string
+fn run(vm: &mut Vm, input: &str) -> String {
+    match vm.run(parse(input)) {
+        Ok(Some(obj)) => obj.to_string(),
+        Ok(None) => panic!("no value for {}", input),
+        Err(error) => panic!("the vm failed on {}: {}", input, error),
+    }
+}
+
+/// Tests that deep recursion in tail position, and iteration, doesn't grow the Rust stack, which is
+/// all that a program can use on the VM
+#[test]
+fn test_vm_constant_stack() {
+    let tests = vec![
+        (
+            "fn sum(n, acc) { if (n == 0) { return acc; } return sum(n - 1, acc + n); } sum(100000, 0)",
+            "5000050000",
+        ),
+        (
+            "fn f(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } } f(5000)",
+            "5000",
+        ),
+        (
+            "fn xs() { for (x in 0..100000) { yield x; } } collect(xs())[99999]",
+            "99999",
+        ),
+    ];
+
+    // The test thread's stack is small, so a VM that recursed on it for each call would overflow.
+    for (input, expected) in tests {
+        let mut vm = Vm::new();
+        vm.set_max_call_depth(10_000);
+
+        assert_eq!(run(&mut vm, input), expected, "input: {}", input);
+    }
+}
+
+/// Tests the functions in the prelude on arrays with more elements than the default call depth, which
+/// they must not recurse once per element on
+#[test]
+fn test_vm_prelude_large_arrays() {
+    let tests = vec![
+        ("map(range(3000), |x| x * 2)[2999]", "5998"),
+        ("filter(range(3000), |x| x > 2997)", "[2998, 2999]"),
+        ("reduce(range(3000), 0, |sum, x| sum + x)", "4498500"),
+        ("zip(range(3000), range(2000, 5000))[2999]", "[2999, 4999]"),
+        ("sort_by(range(3000), |x| -x)[0]", "2999"),
+        // Whichever part of this array is left to sort, its middle element is the largest, which
+        // is the worst case for a quicksort that picks the middle element as its pivot.
+        (
+            "let s = sort_by([...map(range(600), |x| x * 2), ...map(range(600), |x| 1199 - x * 2)], |x| x); [s[0], s[599], s[1199]]",
+            "[0, 599, 1199]",
+        ),
+        ("contains(range(3000), 2999)", "true"),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(run(&mut Vm::new(), input), expected, "input: {}", input);
+    }
+}
+
+/// Tests the call depth limit
+#[test]
+fn test_vm_depth_limits() {
+    let mut vm = Vm::new();
+    vm.set_max_call_depth(20);
+
+    let error = vm
+        .eval(&parse("fn f(n) { 1 + f(n + 1) } f(1)"))
+        .unwrap_err();
+
+    assert_eq!(error.kind, ErrorKind::Limit);
+    assert_eq!(error.trace.len(), 20);
+    assert_eq!(
+        error.to_string(),
+        "1:16: maximum call depth of 20 exceeded\n    in f (and 19 more times)"
+    );
+
+    let tests = vec![
+        (
+            "let loop = fn(n) { if (n == 0) { 0 } else { loop(n - 1) } }; loop(1000)",
+            "0",
+        ),
+        (
+            "fn f(n) { 1 + f(n) } try { f(1) } catch (e) { [e.kind, e.message] }",
+            "[limit, maximum call depth of 20 exceeded]",
+        ),
+        // The frames of the failed calls are gone, so the VM can still be used afterwards.
+        (
+            "fn f(n) { if (n == 0) { 0 } else { 1 + f(n - 1) } } f(19)",
+            "19",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(run(&mut vm, input), expected, "input: {}", input);
+    }
+}
+
+/// Tests that chains of operators too long for the compiler to recurse over are parse errors, while the
+/// longest chains that parse still compile and run
+#[test]
+fn test_vm_long_chains() {
+    let errors = Parser::new(Lexer::new(&format!("1{}", " + 1".repeat(199_999))))
+        .parse_program()
+        .unwrap_err();
+
+    assert_eq!(errors[0].message, "maximum chain length of 5000 exceeded");
+
+    // Compiling recurses over each link, so the longest chains need a bigger stack than the test
+    // thread's, like the one that `main` runs on.
+    thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(|| {
+            let tests = vec![
+                (format!("1{}", " + 1".repeat(5_000)), "5001"),
+                (format!("fn f() {{ f }} f{}; 1", "()".repeat(5_000)), "1"),
+            ];
+
+            for (input, expected) in tests {
+                assert_eq!(run(&mut Vm::new(), &input), expected, "input: {}", input);
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+/// Tests that running stops once the VM runs out of its budget
+#[test]
+fn test_vm_budgets() {
+    let tests = vec![
+        (
+            Budget::unlimited().with_fuel(10_000),
+            "let f = fn() { f() }; f()",
+            "fuel budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_timeout(Duration::from_millis(10)),
+            "let f = fn() { f() }; f()",
+            "time budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_memory(1_000),
+            "let f = fn(xs) { f([...xs, 1]) }; f([])",
+            "memory budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_fuel(10_000),
+            "let f = fn() { f() }; let g = fn() { try { f() } finally { return 1 } }; g()",
+            "fuel budget exceeded",
+        ),
+        (
+            Budget::unlimited().with_fuel(1_000),
+            "for (x in 0..1000000000) {}",
+            "fuel budget exceeded",
+        ),
+    ];
+
+    let mut vm = Vm::without_prelude();
+
+    for (budget, input, expected) in tests {
+        vm.set_budget(budget);
+
+        let error = vm.eval(&parse(input)).unwrap_err();
+
+        assert_eq!(error.kind, ErrorKind::BudgetExceeded, "input: {}", input);
+        assert_eq!(error.message, expected, "input: {}", input);
+    }
+
+    assert_eq!(vm.remaining_budget().fuel, Some(0));
+    assert!(vm.eval(&parse("1")).is_err());
+
+    vm.set_budget(Budget::unlimited());
+
+    assert_eq!(run(&mut vm, "1"), "1");
+}
+
+/// Tests that running can be interrupted from another thread
+#[test]
+fn test_vm_interrupts() {
+    let mut vm = Vm::without_prelude();
+    let interrupt = vm.interrupt_handle();
+
+    interrupt.interrupt();
+
+    let error = vm.eval(&parse("1")).unwrap_err();
+
+    assert_eq!(error.kind, ErrorKind::Interrupted);
+    assert!(!interrupt.is_interrupted());
+
+    let tests = vec![
+        "let f = fn() { f() }; f()",
+        "let f = fn() { f() }; try { f() } catch (e) { 1 }",
+        "fn g() { let f = fn() { f() }; yield f(); } next(g())",
+    ];
+
+    for input in tests {
+        let interrupter = {
+            let interrupt = interrupt.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                interrupt.interrupt();
+            })
+        };
+
+        let error = vm.eval(&parse(input)).unwrap_err();
+        interrupter.join().unwrap();
+
+        assert_eq!(error.kind, ErrorKind::Interrupted, "input: {}", input);
+    }
+
+    assert_eq!(run(&mut vm, "f"), "fn f() { f() }");
+}
+
+/// Tests expanding and running macros on the VM
+#[test]
+fn test_vm_macros() {
+    let tests = vec![
+        (
+            "let twice = macro(x) { quote(unquote(x) + unquote(x)) }; twice(twice(1))",
+            "4",
+        ),
+        (
+            "let one = macro() { quote(1) }; let f = fn() { one() }; f()",
+            "1",
+        ),
+    ];
+
+    for (input, expected) in tests {
+        assert_eq!(run(&mut Vm::new(), input), expected, "input: {}", input);
+    }
+
+    let error = Vm::new()
+        .run(parse("let bad = macro() { 1 }; bad()"))
+        .unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "1:29: macro bad must return a quote, got INTEGER"
+    );
+}
+
+/// Tests importing modules on the VM
+#[test]
+fn test_vm_modules() {
+    let mut loader = MemoryLoader::new();
+    loader.insert(
+        "lib/math.monkey",
+        r#"import "./util.monkey" as util; export fn square(x) { util.times(x, x) }"#,
+    );
+    loader.insert("lib/util.monkey", "export let times = fn(a, b) { a * b };");
+    loader.insert("fails.monkey", "let x = 1;\nlet y = x + true;");
+    loader.insert("back.monkey", r#"import "main.monkey" as main;"#);
+
+    let mut vm = Vm::new();
+    vm.set_loader(loader);
+    vm.set_file("main.monkey");
+
+    assert_eq!(
+        run(
+            &mut vm,
+            r#"import "lib/math.monkey" as a; import "lib/math.monkey" as b; [a.square(4), a == b]"#
+        ),
+        "[16, true]"
+    );
+
+    let error = vm.run(parse(r#"import "fails.monkey" as f;"#)).unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "2:11: type mismatch: INTEGER + BOOLEAN\n    in module fails.monkey"
+    );
+    assert_eq!(error.file.as_deref(), Some(Path::new("fails.monkey")));
+
+    let error = vm
+        .run(parse(r#"import "lib/math.monkey" as m; m.square("x")"#))
+        .unwrap_err();
+
+    assert_eq!(error.file.as_deref(), Some(Path::new("lib/util.monkey")));
+
+    let error = vm.run(parse(r#"import "back.monkey" as b;"#)).unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "1:1: import cycle: main.monkey -> back.monkey -> main.monkey\n    in module back.monkey"
+    );
+}